@@ -0,0 +1,27 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/// admin is the admin grpc service of the dfdaemon, refer to proto/admin.proto.
+pub mod admin {
+    /// v1 is the generated code of the admin grpc service.
+    #[allow(clippy::all)]
+    pub mod v1 {
+        tonic::include_proto!("admin.v1");
+    }
+}
+
+/// FILE_DESCRIPTOR_SET is the file descriptor set of the admin grpc service for reflection.
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("admin_descriptor");
@@ -14,16 +14,26 @@
  * limitations under the License.
  */
 
-use dragonfly_client_core::{Error, Result};
+use dragonfly_client_core::{
+    error::{BackendError, ErrorType, OrErr},
+    Error, Result,
+};
 use dragonfly_client_util::tls::NoVerifier;
-use futures::TryStreamExt;
+use futures::{StreamExt, TryStreamExt};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use regex::Regex;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use reqwest_tracing::TracingMiddleware;
 use rustls_pki_types::CertificateDer;
+use std::collections::{HashSet, VecDeque};
 use std::io::{Error as IOError, ErrorKind};
+use std::sync::LazyLock;
+use std::time::Duration;
 use tokio_util::io::StreamReader;
-use tracing::{debug, error, instrument};
+use tracing::{debug, error, instrument, warn};
+use url::Url;
 
 /// HTTP_SCHEME is the HTTP scheme.
 pub const HTTP_SCHEME: &str = "http";
@@ -31,6 +41,62 @@ pub const HTTP_SCHEME: &str = "http";
 /// HTTPS_SCHEME is the HTTPS scheme.
 pub const HTTPS_SCHEME: &str = "https";
 
+/// MAX_DIRECTORY_INDEX_DEPTH is the max depth of the subdirectories to list by the directory
+/// index.
+const MAX_DIRECTORY_INDEX_DEPTH: usize = 32;
+
+/// DIRECTORY_INDEX_CONCURRENCY is the concurrency of the requests to get the content length of
+/// the files whose size is not exact in the directory index.
+const DIRECTORY_INDEX_CONCURRENCY: usize = 16;
+
+/// PATH_ENCODE_SET is the set of the characters to encode in the path of the entry url, the
+/// slash is kept to separate the directories.
+const PATH_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// HTML_LINK_REGEX matches the link and the text following it in the same line of the html
+/// directory index, nginx prints the modified time and the size of the file after the link.
+static HTML_LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<a\s[^>]*?href\s*=\s*["']([^"']+)["'][^>]*>.*?</a>([^<\r\n]*)"#).unwrap()
+});
+
+/// S3_CONTENTS_REGEX matches the objects in the ListBucketResult of the S3 compatible listing.
+static S3_CONTENTS_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<Contents>(.*?)</Contents>").unwrap());
+
+/// IndexEntry is the entry parsed from the directory index.
+#[derive(Debug, PartialEq, Eq)]
+struct IndexEntry {
+    /// url is the url of the entry.
+    url: Url,
+
+    /// content_length is the content length of the entry, it is none if the directory index
+    /// does not record the exact size.
+    content_length: Option<u64>,
+
+    /// is_dir is the flag of the entry is a directory.
+    is_dir: bool,
+}
+
+/// DirectoryIndex is the page of the directory index.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct DirectoryIndex {
+    /// entries are the entries in the page.
+    entries: Vec<IndexEntry>,
+
+    /// next is the url of the next page, it is only used by the truncated S3 compatible listing.
+    next: Option<Url>,
+}
+
 /// HTTP is the HTTP backend.
 pub struct HTTP {
     /// scheme is the scheme of the HTTP backend.
@@ -111,6 +177,348 @@ impl HTTP {
             None => Ok(self.client.clone()),
         }
     }
+
+    /// list_entries lists the entries in the directory recursively by parsing the directory
+    /// index, the subdirectories are listed in breadth-first order.
+    #[instrument(skip_all)]
+    async fn list_entries(
+        &self,
+        client: &ClientWithMiddleware,
+        task_id: &str,
+        url: &str,
+        header: &HeaderMap,
+        timeout: Duration,
+    ) -> Result<Vec<super::DirEntry>> {
+        let root: Url = url.parse().or_err(ErrorType::ParseError)?;
+        let mut visited = HashSet::from([root.to_string()]);
+        let mut pages = VecDeque::from([(root, 0)]);
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        while let Some((page, depth)) = pages.pop_front() {
+            let index = self
+                .get_directory_index(client, task_id, &page, header, timeout)
+                .await?;
+
+            if let Some(next) = index.next {
+                if visited.insert(next.to_string()) {
+                    pages.push_back((next, depth));
+                }
+            }
+
+            for entry in index.entries {
+                if !visited.insert(entry.url.to_string()) {
+                    continue;
+                }
+
+                if !entry.is_dir {
+                    files.push(entry);
+                    continue;
+                }
+
+                if depth + 1 >= MAX_DIRECTORY_INDEX_DEPTH {
+                    warn!("skip {}, exceeds the max depth of the directory", entry.url);
+                    continue;
+                }
+
+                pages.push_back((entry.url.clone(), depth + 1));
+                dirs.push(super::DirEntry {
+                    url: entry.url.to_string(),
+                    content_length: 0,
+                    is_dir: true,
+                });
+            }
+        }
+
+        // Get the content length of the files whose size is not exact in the directory index,
+        // e.g. apache prints the size as 1.2K.
+        let files: Vec<super::DirEntry> = futures::stream::iter(files)
+            .map(|entry| async move {
+                let content_length = match entry.content_length {
+                    Some(content_length) => content_length,
+                    None => {
+                        self.get_content_length(client, task_id, &entry.url, header, timeout)
+                            .await?
+                    }
+                };
+
+                Ok::<_, Error>(super::DirEntry {
+                    url: entry.url.to_string(),
+                    content_length: content_length as usize,
+                    is_dir: false,
+                })
+            })
+            .buffered(DIRECTORY_INDEX_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        dirs.extend(files);
+        Ok(dirs)
+    }
+
+    /// get_directory_index gets and parses the page of the directory index.
+    #[instrument(skip_all)]
+    async fn get_directory_index(
+        &self,
+        client: &ClientWithMiddleware,
+        task_id: &str,
+        url: &Url,
+        header: &HeaderMap,
+        timeout: Duration,
+    ) -> Result<DirectoryIndex> {
+        let response = client
+            .get(url.as_str())
+            .headers(header.clone())
+            .timeout(timeout)
+            .send()
+            .await
+            .inspect_err(|err| {
+                error!("list request failed {} {}: {}", task_id, url, err);
+            })?;
+
+        let status_code = response.status();
+        if !status_code.is_success() {
+            error!("list request failed {} {}: {}", task_id, url, status_code);
+            return Err(Error::BackendError(Box::new(BackendError {
+                message: format!("list directory {} failed: {}", url, status_code),
+                status_code: Some(status_code),
+                header: None,
+            })));
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let body = response.text().await?;
+        debug!(
+            "list response {} {}: {} {} bytes",
+            task_id,
+            url,
+            content_type,
+            body.len()
+        );
+
+        parse_directory_index(url, &content_type, &body)
+    }
+
+    /// get_content_length gets the content length of the file by the header of the response.
+    #[instrument(skip_all)]
+    async fn get_content_length(
+        &self,
+        client: &ClientWithMiddleware,
+        task_id: &str,
+        url: &Url,
+        header: &HeaderMap,
+        timeout: Duration,
+    ) -> Result<u64> {
+        let response = client
+            .get(url.as_str())
+            .headers(header.clone())
+            .timeout(timeout)
+            .send()
+            .await
+            .inspect_err(|err| {
+                error!("head request failed {} {}: {}", task_id, url, err);
+            })?;
+
+        let status_code = response.status();
+        if !status_code.is_success() {
+            error!("head request failed {} {}: {}", task_id, url, status_code);
+            return Err(Error::BackendError(Box::new(BackendError {
+                message: format!("get content length of {} failed: {}", url, status_code),
+                status_code: Some(status_code),
+                header: None,
+            })));
+        }
+
+        response
+            .content_length()
+            .ok_or_else(|| Error::Unsupported(format!("content length of {} is unknown", url)))
+    }
+}
+
+/// parse_directory_index parses the page of the directory index, it supports the json format
+/// of nginx autoindex, the ListBucketResult of the S3 compatible listing and the html format of
+/// nginx and apache autoindex.
+fn parse_directory_index(url: &Url, content_type: &str, body: &str) -> Result<DirectoryIndex> {
+    let trimmed_body = body.trim_start();
+    if content_type.contains("json") || trimmed_body.starts_with('[') {
+        return parse_json_directory_index(url, body);
+    }
+
+    if trimmed_body.contains("<ListBucketResult") {
+        return parse_s3_directory_index(url, body);
+    }
+
+    Ok(parse_html_directory_index(url, body))
+}
+
+/// parse_json_directory_index parses the json format of nginx autoindex, e.g.
+/// [{"name":"dir","type":"directory"},{"name":"file","type":"file","size":1024}].
+fn parse_json_directory_index(url: &Url, body: &str) -> Result<DirectoryIndex> {
+    let items: Vec<serde_json::Value> = serde_json::from_str(body).or_err(ErrorType::ParseError)?;
+
+    let mut entries = Vec::with_capacity(items.len());
+    for item in items {
+        let Some(name) = item.get("name").and_then(|name| name.as_str()) else {
+            continue;
+        };
+
+        if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+            continue;
+        }
+
+        let is_dir = item.get("type").and_then(|t| t.as_str()) == Some("directory");
+        let mut path = utf8_percent_encode(name, PATH_ENCODE_SET).to_string();
+        if is_dir {
+            path.push('/');
+        }
+
+        entries.push(IndexEntry {
+            url: url.join(&path).or_err(ErrorType::ParseError)?,
+            content_length: item.get("size").and_then(|size| size.as_u64()),
+            is_dir,
+        });
+    }
+
+    Ok(DirectoryIndex {
+        entries,
+        next: None,
+    })
+}
+
+/// parse_s3_directory_index parses the ListBucketResult of the S3 compatible listing, the keys
+/// are relative to the bucket, so the url of the listing should be the bucket url, e.g.
+/// http://<host>/<bucket>/?prefix=<path>/. The listing is paginated by the continuation token
+/// of ListObjectsV2 or the marker of ListObjects.
+fn parse_s3_directory_index(url: &Url, body: &str) -> Result<DirectoryIndex> {
+    let mut base = url.clone();
+    base.set_query(None);
+    base.set_fragment(None);
+
+    let mut entries = Vec::new();
+    let mut last_key = None;
+    for contents in S3_CONTENTS_REGEX.captures_iter(body) {
+        let contents = &contents[1];
+        let Some(key) = xml_element(contents, "Key") else {
+            continue;
+        };
+
+        last_key = Some(key.clone());
+
+        // The key ending with '/' is the placeholder of the directory.
+        if key.ends_with('/') {
+            continue;
+        }
+
+        entries.push(IndexEntry {
+            url: base
+                .join(&utf8_percent_encode(&key, PATH_ENCODE_SET).to_string())
+                .or_err(ErrorType::ParseError)?,
+            content_length: xml_element(contents, "Size").and_then(|size| size.parse().ok()),
+            is_dir: false,
+        });
+    }
+
+    // Build the url of the next page if the listing is truncated.
+    let mut next = None;
+    if xml_element(body, "IsTruncated").as_deref() == Some("true") {
+        let (name, token) = match xml_element(body, "NextContinuationToken") {
+            Some(token) => ("continuation-token", Some(token)),
+            None => ("marker", xml_element(body, "NextMarker").or(last_key)),
+        };
+
+        if let Some(token) = token {
+            let mut next_url = url.clone();
+            let query: Vec<(String, String)> = url
+                .query_pairs()
+                .filter(|(key, _)| key != name)
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .collect();
+            next_url
+                .query_pairs_mut()
+                .clear()
+                .extend_pairs(query)
+                .append_pair(name, &token);
+            next = Some(next_url);
+        }
+    }
+
+    Ok(DirectoryIndex { entries, next })
+}
+
+/// parse_html_directory_index parses the html format of nginx and apache autoindex, only the
+/// links to the direct children of the directory are the entries, e.g. the links to the parent
+/// directory and the links to sort the index are skipped.
+fn parse_html_directory_index(url: &Url, body: &str) -> DirectoryIndex {
+    let mut entries = Vec::new();
+    for captures in HTML_LINK_REGEX.captures_iter(body) {
+        let href = captures[1].replace("&amp;", "&");
+        if href.starts_with('?') || href.starts_with('#') {
+            continue;
+        }
+
+        let Ok(entry_url) = url.join(&href) else {
+            continue;
+        };
+
+        if entry_url.origin() != url.origin()
+            || entry_url.query().is_some()
+            || entry_url.fragment().is_some()
+        {
+            continue;
+        }
+
+        let Some(name) = entry_url.path().strip_prefix(url.path()) else {
+            continue;
+        };
+
+        let is_dir = name.ends_with('/');
+        let name = name.trim_end_matches('/');
+        if name.is_empty() || name.contains('/') {
+            continue;
+        }
+
+        // The size of nginx autoindex is the last field after the link, it is exact only when
+        // it is printed in bytes.
+        let content_length = if is_dir {
+            None
+        } else {
+            captures[2]
+                .split_whitespace()
+                .last()
+                .and_then(|size| size.parse().ok())
+        };
+
+        entries.push(IndexEntry {
+            url: entry_url,
+            content_length,
+            is_dir,
+        });
+    }
+
+    DirectoryIndex {
+        entries,
+        next: None,
+    }
+}
+
+/// xml_element returns the unescaped text of the first element with the name.
+fn xml_element(xml: &str, name: &str) -> Option<String> {
+    let start_tag = format!("<{}>", name);
+    let end_tag = format!("</{}>", name);
+    let start = xml.find(&start_tag)? + start_tag.len();
+    let end = start + xml[start..].find(&end_tag)?;
+    Some(
+        xml[start..end]
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
 }
 
 /// Backend implements the Backend trait.
@@ -131,16 +539,16 @@ impl super::Backend for HTTP {
         );
 
         // The header of the request is required.
-        let header = request.http_header.ok_or(Error::InvalidParameter)?;
+        let request_header = request.http_header.ok_or(Error::InvalidParameter)?;
 
         // The signature in the signed URL generated by the object storage client will include
         // the request method. Therefore, the signed URL of the GET method cannot be requested
         // through the HEAD method. Use GET request to replace of HEAD request
         // to get header and status code.
-        let response = self
-            .client(request.client_cert)?
+        let client = self.client(request.client_cert)?;
+        let response = client
             .get(&request.url)
-            .headers(header)
+            .headers(request_header.clone())
             .timeout(request.timeout)
             .send()
             .await
@@ -162,13 +570,36 @@ impl super::Backend for HTTP {
         // Drop the response body to avoid reading it.
         drop(response);
 
+        // Get the entries by the directory index if the url points to a directory and the
+        // recursive listing is requested, otherwise the url is a normal page.
+        let entries = if request.recursive
+            && status_code.is_success()
+            && request
+                .url
+                .parse::<Url>()
+                .or_err(ErrorType::ParseError)?
+                .path()
+                .ends_with('/')
+        {
+            self.list_entries(
+                &client,
+                &request.task_id,
+                &request.url,
+                &request_header,
+                request.timeout,
+            )
+            .await?
+        } else {
+            Vec::new()
+        };
+
         Ok(super::HeadResponse {
             success: status_code.is_success(),
             content_length,
             http_header: Some(header),
             http_status_code: Some(status_code),
             error_message: Some(status_code.to_string()),
-            entries: Vec::new(),
+            entries,
         })
     }
 
@@ -222,8 +653,8 @@ impl super::Backend for HTTP {
 #[cfg(test)]
 mod tests {
     use crate::{
-        http::{HTTP, HTTPS_SCHEME, HTTP_SCHEME},
-        Backend, GetRequest, HeadRequest,
+        http::{parse_directory_index, IndexEntry, HTTP, HTTPS_SCHEME, HTTP_SCHEME},
+        Backend, DirEntry, GetRequest, HeadRequest,
     };
     use dragonfly_client_util::tls::{load_certs_from_pem, load_key_from_pem};
     use hyper_util::rt::{TokioExecutor, TokioIo};
@@ -232,6 +663,7 @@ mod tests {
     use tokio::net::TcpListener;
     use tokio_rustls::rustls::ServerConfig;
     use tokio_rustls::TlsAcceptor;
+    use url::Url;
     use wiremock::{
         matchers::{method, path},
         Mock, ResponseTemplate,
@@ -403,6 +835,7 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
                 client_cert: None,
                 object_storage: None,
                 hdfs: None,
+                recursive: false,
             })
             .await
             .unwrap();
@@ -432,6 +865,7 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
                 client_cert: None,
                 object_storage: None,
                 hdfs: None,
+                recursive: false,
             })
             .await;
 
@@ -484,6 +918,7 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
                 client_cert: Some(load_certs_from_pem(CA_CERT).unwrap()),
                 object_storage: None,
                 hdfs: None,
+                recursive: false,
             })
             .await
             .unwrap();
@@ -504,6 +939,7 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
                 client_cert: Some(load_certs_from_pem(WRONG_CA_CERT).unwrap()),
                 object_storage: None,
                 hdfs: None,
+                recursive: false,
             })
             .await;
 
@@ -567,6 +1003,7 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
                 client_cert: None,
                 object_storage: None,
                 hdfs: None,
+                recursive: false,
             })
             .await
             .unwrap();
@@ -597,4 +1034,164 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
         assert_eq!(resp.http_status_code, Some(StatusCode::OK));
         assert_eq!(resp.text().await.unwrap(), "OK");
     }
+
+    #[test]
+    fn should_parse_html_directory_index() {
+        let url: Url = "http://example.com/data/".parse().unwrap();
+
+        // The nginx autoindex prints the exact size in bytes.
+        let nginx = r#"<html><body><h1>Index of /data/</h1><hr><pre><a href="../">../</a>
+<a href="models/">models/</a>                                            17-Oct-2026 10:00                   -
+<a href="a%20b.txt">a b.txt</a>                                           17-Oct-2026 10:00                1024
+</pre><hr></body></html>"#;
+        let index = parse_directory_index(&url, "text/html", nginx).unwrap();
+        assert_eq!(
+            index.entries,
+            vec![
+                IndexEntry {
+                    url: "http://example.com/data/models/".parse().unwrap(),
+                    content_length: None,
+                    is_dir: true,
+                },
+                IndexEntry {
+                    url: "http://example.com/data/a%20b.txt".parse().unwrap(),
+                    content_length: Some(1024),
+                    is_dir: false,
+                },
+            ]
+        );
+
+        // The apache autoindex prints the approximate size and the links to sort the index.
+        let apache = r#"<table><tr><th><a href="?C=N;O=D">Name</a></th></tr>
+<tr><td><a href="/">Parent Directory</a></td></tr>
+<tr><td><a href="file.bin">file.bin</a></td><td align="right">1.2K</td></tr>
+<tr><td><a href="https://other.com/data/x">x</a></td></tr></table>"#;
+        let index = parse_directory_index(&url, "text/html", apache).unwrap();
+        assert_eq!(
+            index.entries,
+            vec![IndexEntry {
+                url: "http://example.com/data/file.bin".parse().unwrap(),
+                content_length: None,
+                is_dir: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn should_parse_json_directory_index() {
+        let url: Url = "http://example.com/data/".parse().unwrap();
+        let body = r#"[{"name":"models","type":"directory","mtime":"Sat, 17 Oct 2026 10:00:00 GMT"},
+{"name":"a#b.txt","type":"file","mtime":"Sat, 17 Oct 2026 10:00:00 GMT","size":12}]"#;
+        let index = parse_directory_index(&url, "application/json", body).unwrap();
+        assert_eq!(
+            index.entries,
+            vec![
+                IndexEntry {
+                    url: "http://example.com/data/models/".parse().unwrap(),
+                    content_length: None,
+                    is_dir: true,
+                },
+                IndexEntry {
+                    url: "http://example.com/data/a%23b.txt".parse().unwrap(),
+                    content_length: Some(12),
+                    is_dir: false,
+                },
+            ]
+        );
+
+        assert!(parse_directory_index(&url, "application/json", "[").is_err());
+    }
+
+    #[test]
+    fn should_parse_s3_directory_index() {
+        let url: Url = "http://example.com/bucket/?prefix=data/".parse().unwrap();
+        let body = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult><Name>bucket</Name><Prefix>data/</Prefix><IsTruncated>true</IsTruncated>
+<Contents><Key>data/</Key><Size>0</Size></Contents>
+<Contents><Key>data/a&amp;b.txt</Key><Size>42</Size></Contents>
+</ListBucketResult>"#;
+        let index = parse_directory_index(&url, "application/xml", body).unwrap();
+        assert_eq!(
+            index.entries,
+            vec![IndexEntry {
+                url: "http://example.com/bucket/data/a&b.txt".parse().unwrap(),
+                content_length: Some(42),
+                is_dir: false,
+            }]
+        );
+        assert_eq!(
+            index.next,
+            Some(
+                "http://example.com/bucket/?prefix=data%2F&marker=data%2Fa%26b.txt"
+                    .parse()
+                    .unwrap()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn should_get_head_response_with_recursive_entries() {
+        let server = wiremock::MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/data/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<a href="../">../</a>
+<a href="sub/">sub/</a>      17-Oct-2026 10:00       -
+<a href="a.txt">a.txt</a>    17-Oct-2026 10:00       5"#,
+            ))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/data/sub/"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", "application/json")
+                    .set_body_string(r#"[{"name":"b.txt","type":"file"}]"#),
+            )
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/data/sub/b.txt"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("hello world"))
+            .mount(&server)
+            .await;
+
+        let resp = HTTP::new(HTTP_SCHEME)
+            .unwrap()
+            .head(HeadRequest {
+                task_id: "test".to_string(),
+                url: format!("{}/data/", server.uri()),
+                http_header: Some(HeaderMap::new()),
+                timeout: std::time::Duration::from_secs(5),
+                client_cert: None,
+                object_storage: None,
+                hdfs: None,
+                recursive: true,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            resp.entries,
+            vec![
+                DirEntry {
+                    url: format!("{}/data/sub/", server.uri()),
+                    content_length: 0,
+                    is_dir: true,
+                },
+                DirEntry {
+                    url: format!("{}/data/a.txt", server.uri()),
+                    content_length: 5,
+                    is_dir: false,
+                },
+                DirEntry {
+                    url: format!("{}/data/sub/b.txt", server.uri()),
+                    content_length: 11,
+                    is_dir: false,
+                },
+            ]
+        );
+    }
 }
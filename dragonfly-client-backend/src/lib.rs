@@ -77,6 +77,11 @@ pub struct HeadRequest {
 
     /// hdfs is the hdfs related information.
     pub hdfs: Option<Hdfs>,
+
+    /// recursive is whether to list the entries of the directory recursively by parsing the
+    /// directory index, e.g. nginx autoindex. It only works for the http backend, because the
+    /// url ending with '/' is a normal page of the http server by default.
+    pub recursive: bool,
 }
 
 /// HeadResponse is the head response for backend.
@@ -242,10 +247,15 @@ impl BackendFactory {
         Ok(backend_factory)
     }
 
-    /// supported_download_directory returns whether the scheme supports directory download.
+    /// supported_download_directory returns whether the scheme supports directory download,
+    /// the directory of the http backend is listed by the directory index only when the
+    /// head request is recursive.
     #[instrument(skip_all)]
     pub fn supported_download_directory(scheme: &str) -> bool {
-        object_storage::Scheme::from_str(scheme).is_ok() || scheme == hdfs::HDFS_SCHEME
+        object_storage::Scheme::from_str(scheme).is_ok()
+            || scheme == hdfs::HDFS_SCHEME
+            || scheme == http::HTTP_SCHEME
+            || scheme == http::HTTPS_SCHEME
     }
 
     /// build returns the backend by the scheme of the url.
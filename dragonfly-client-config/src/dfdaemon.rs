@@ -17,9 +17,10 @@
 use bytesize::ByteSize;
 use dragonfly_client_core::{
     error::{ErrorType, OrErr},
-    Result,
+    Error, Result,
 };
 use dragonfly_client_util::{
+    digest,
     http::basic_auth,
     tls::{generate_ca_cert_from_pem, generate_cert_from_pem},
 };
@@ -27,22 +28,38 @@ use local_ip_address::{local_ip, local_ipv6};
 use rcgen::Certificate;
 use regex::Regex;
 use rustls_pki_types::CertificateDer;
-use serde::Deserialize;
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_yaml::{Mapping, Value};
 use std::collections::HashSet;
 use std::fmt;
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Duration;
 use tokio::fs;
 use tonic::transport::{
     Certificate as TonicCertificate, ClientTlsConfig, Identity, ServerTlsConfig,
 };
-use tracing::{error, instrument};
-use validator::Validate;
+use tracing::{error, instrument, Level};
+use validator::{Validate, ValidationError};
 
 /// NAME is the name of dfdaemon.
 pub const NAME: &str = "dfdaemon";
 
+/// ENV_PREFIX is the prefix of the environment variables to override the configuration,
+/// such as DFDAEMON_UPLOAD__RATE_LIMIT overrides upload.rateLimit.
+pub const ENV_PREFIX: &str = "DFDAEMON_";
+
+/// ENV_SEPARATOR is the separator of the nested keys in the environment variables.
+pub const ENV_SEPARATOR: &str = "__";
+
+/// REDACTED_SECRET is the placeholder of the redacted secrets.
+const REDACTED_SECRET: &str = "******";
+
+/// SECRET_KEYWORDS are the keywords of the keys whose values are redacted.
+const SECRET_KEYWORDS: &[&str] = &["password", "secret", "token"];
+
 /// default_dfdaemon_config_path is the default config path for dfdaemon.
 #[inline]
 pub fn default_dfdaemon_config_path() -> PathBuf {
@@ -66,7 +83,7 @@ fn default_parent_selector_sync_interval() -> Duration {
     Duration::from_secs(3)
 }
 
-/// default_parent_selector_capacity is the default capacity of the parent selector's gRPC connections.
+/// default_parent_selector_capacity is the default max number of the pooled gRPC connections.
 #[inline]
 pub fn default_parent_selector_capacity() -> usize {
     20
@@ -140,6 +157,13 @@ fn default_download_concurrent_piece_count() -> u32 {
     16
 }
 
+/// default_priority_scheduler_weights is the default weights of the priorities from LEVEL0 to
+/// LEVEL6, the weight is doubled for each level from LEVEL2.
+#[inline]
+fn default_priority_scheduler_weights() -> Vec<u32> {
+    vec![1, 1, 2, 4, 8, 16, 32]
+}
+
 /// default_download_max_schedule_count is the default max count of schedule.
 #[inline]
 fn default_download_max_schedule_count() -> u32 {
@@ -164,6 +188,18 @@ fn default_dynconfig_refresh_interval() -> Duration {
     Duration::from_secs(300)
 }
 
+/// default_network_connection_pool_idle_timeout is the default timeout of the idle pooled gRPC connections.
+#[inline]
+fn default_network_connection_pool_idle_timeout() -> Duration {
+    Duration::from_secs(300)
+}
+
+/// default_reload_watch_interval is the default interval to check the configuration file changes.
+#[inline]
+fn default_reload_watch_interval() -> Duration {
+    Duration::from_secs(10)
+}
+
 /// default_storage_server_protocol is the default protocol of the storage server.
 #[inline]
 fn default_storage_server_protocol() -> String {
@@ -195,6 +231,25 @@ fn default_storage_cache_capacity() -> usize {
     100
 }
 
+/// default_storage_memory_cache_capacity is the default capacity of the memory cache of the hot
+/// pieces, default is 0, which disables the memory cache.
+#[inline]
+fn default_storage_memory_cache_capacity() -> ByteSize {
+    ByteSize::b(0)
+}
+
+/// default_storage_compression_level is the default zstd compression level of the stored content.
+#[inline]
+fn default_storage_compression_level() -> i32 {
+    3
+}
+
+/// default_storage_io_uring_entries is the default size of the submission queue of io_uring.
+#[inline]
+fn default_storage_io_uring_entries() -> u32 {
+    256
+}
+
 /// default_seed_peer_cluster_id is the default cluster id of seed peer.
 #[inline]
 fn default_seed_peer_cluster_id() -> u64 {
@@ -367,7 +422,7 @@ fn default_proxy_registry_mirror_addr() -> String {
 }
 
 /// Host is the host configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Host {
     /// idc is the idc of the host.
@@ -397,7 +452,7 @@ impl Default for Host {
 }
 
 /// Server is the server configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Server {
     /// plugin_dir is the directory to store plugins.
@@ -420,7 +475,7 @@ impl Default for Server {
 }
 
 /// DownloadServer is the download server configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct DownloadServer {
     /// socket_path is the unix socket path for dfdaemon gRPC service.
@@ -438,27 +493,40 @@ impl Default for DownloadServer {
 }
 
 /// Download is the download configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Download {
     /// server is the download server configuration for dfdaemon.
     pub server: DownloadServer,
 
     /// parent_selector is the download parent selector configuration for dfdaemon.
+    #[validate]
     pub parent_selector: ParentSelector,
 
     /// rate_limit is the rate limit of the download speed in GiB/Mib/Kib per second.
     #[serde(with = "bytesize_serde", default = "default_download_rate_limit")]
+    #[schemars(with = "String")]
     pub rate_limit: ByteSize,
 
     /// piece_timeout is the timeout for downloading a piece from source.
     #[serde(default = "default_download_piece_timeout", with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub piece_timeout: Duration,
 
     /// concurrent_piece_count is the number of concurrent pieces to download.
     #[serde(default = "default_download_concurrent_piece_count")]
     #[validate(range(min = 1))]
     pub concurrent_piece_count: u32,
+
+    /// resume_unfinished_tasks indicates whether resume the unfinished tasks when the dfdaemon
+    /// starts. The download request is persisted in the task metadata without the sensitive
+    /// headers, and the finished pieces will be reused. It only works when `storage.keep` is true.
+    pub resume_unfinished_tasks: bool,
+
+    /// priority_scheduler is the configuration of scheduling the piece downloads across the
+    /// tasks by the priority.
+    #[validate]
+    pub priority_scheduler: PriorityScheduler,
 }
 
 /// Download implements Default.
@@ -470,13 +538,53 @@ impl Default for Download {
             rate_limit: default_download_rate_limit(),
             piece_timeout: default_download_piece_timeout(),
             concurrent_piece_count: default_download_concurrent_piece_count(),
+            resume_unfinished_tasks: false,
+            priority_scheduler: PriorityScheduler::default(),
+        }
+    }
+}
+
+/// PriorityScheduler is the configuration of scheduling the piece downloads across the tasks by
+/// the priority of the download, e.g. `--priority` of dfget and `X-Dragonfly-Priority` of the
+/// proxy. The pieces acquire the download rate limiter by weighted fair queuing, so the task
+/// with the higher priority gets the larger share of the download bandwidth.
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PriorityScheduler {
+    /// enable indicates whether to schedule the piece downloads by the priority. If it is false,
+    /// the pieces acquire the download rate limiter in the order of arrival. It is disabled by
+    /// default, because the tasks without the priority are LEVEL0 and get the smallest share
+    /// of the download bandwidth when it is enabled.
+    pub enable: bool,
+
+    /// weights are the weights of the priorities from LEVEL0 to LEVEL6, the share of the
+    /// download bandwidth of the task is proportional to the weight of its priority.
+    #[validate(length(equal = 7))]
+    pub weights: Vec<u32>,
+
+    /// preemption_priority is the priority to preempt the download bandwidth. If it is set, the
+    /// pieces of the prefetch tasks and the tasks with the lower priority wait until the pieces
+    /// of the tasks with the priority higher than or equal to it are downloaded, e.g. the
+    /// interactive image pulls preempt the background model downloads.
+    #[validate(range(max = 6))]
+    pub preemption_priority: Option<u8>,
+}
+
+/// PriorityScheduler implements Default.
+impl Default for PriorityScheduler {
+    fn default() -> Self {
+        PriorityScheduler {
+            enable: false,
+            weights: default_priority_scheduler_weights(),
+            preemption_priority: None,
         }
     }
 }
 
 /// UploadServer is the upload server configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
+#[validate(schema(function = "validate_upload_server"))]
 pub struct UploadServer {
     /// ip is the listen ip of the gRPC server.
     pub ip: Option<IpAddr>,
@@ -537,8 +645,9 @@ impl UploadServer {
 }
 
 /// UploadClient is the upload client configuration for dfdaemon.
-#[derive(Debug, Clone, Default, Validate, Deserialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
+#[validate(schema(function = "validate_upload_client"))]
 pub struct UploadClient {
     /// ca_cert is the root CA cert path with PEM format for the upload client, and it is used
     /// for mutual TLS.
@@ -583,6 +692,64 @@ impl UploadClient {
     }
 }
 
+/// BandwidthClass is a named bandwidth class for dfdaemon. The task is in the class if its
+/// application or tag of the download matches, e.g. `--application` of dfget,
+/// `X-Dragonfly-Application` of the proxy and the application of the proxy rule. The
+/// persistent cache task is in the class by the application and tag of `dfcache import`. The rate
+/// limits of the class nest within the global rate limits of the download and upload, so the
+/// pieces of the task in the class acquire both of them. If the task matches multiple classes,
+/// the first one is used.
+#[derive(Debug, Clone, PartialEq, Validate, Deserialize, Serialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BandwidthClass {
+    /// name is the name of the bandwidth class.
+    #[validate(length(min = 1))]
+    pub name: String,
+
+    /// applications are the applications of the tasks in the class.
+    pub applications: Vec<String>,
+
+    /// tags are the tags of the tasks in the class.
+    pub tags: Vec<String>,
+
+    /// download_rate_limit is the rate limit of the download speed of the tasks in the class in
+    /// GiB/Mib/Kib per second.
+    #[serde(with = "bytesize_serde", default = "default_download_rate_limit")]
+    #[schemars(with = "String")]
+    pub download_rate_limit: ByteSize,
+
+    /// upload_rate_limit is the rate limit of the upload speed of the tasks in the class in
+    /// GiB/Mib/Kib per second.
+    #[serde(with = "bytesize_serde", default = "default_upload_rate_limit")]
+    #[schemars(with = "String")]
+    pub upload_rate_limit: ByteSize,
+}
+
+/// BandwidthClass implements Default.
+impl Default for BandwidthClass {
+    fn default() -> Self {
+        BandwidthClass {
+            name: String::new(),
+            applications: Vec::new(),
+            tags: Vec::new(),
+            download_rate_limit: default_download_rate_limit(),
+            upload_rate_limit: default_upload_rate_limit(),
+        }
+    }
+}
+
+/// BandwidthClass implements the matching of the tasks.
+impl BandwidthClass {
+    /// matches returns whether the task with the application and tag is in the class.
+    pub fn matches(&self, application: Option<&str>, tag: Option<&str>) -> bool {
+        application.is_some_and(|application| {
+            self.applications
+                .iter()
+                .any(|candidate| candidate == application)
+        }) || tag.is_some_and(|tag| self.tags.iter().any(|candidate| candidate == tag))
+    }
+}
+
 /// ParentSelector is the download parent selector configuration for dfdaemon. It will synchronize
 /// the host info in real-time from the parents and then select the parents for downloading.
 ///
@@ -608,7 +775,7 @@ impl UploadClient {
 /// |                                                +------------+     |
 /// +-------------------------------------------------------------------+
 /// ```
-#[derive(Debug, Clone, Default, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct ParentSelector {
     /// enable indicates whether enable parent selector for downloading.
@@ -623,16 +790,30 @@ pub struct ParentSelector {
         default = "default_parent_selector_sync_interval",
         with = "humantime_serde"
     )]
+    #[schemars(with = "String")]
     pub sync_interval: Duration,
 
-    /// capacity is the maximum number of gRPC connections that `DfdaemonUpload.SyncHost` maintains
-    /// in the `ParentSelector`, the default value is 20.
+    /// capacity is the maximum number of the pooled gRPC connections to the schedulers and the
+    /// parents, the default value is 20. The connections are reused by the requests to the same
+    /// address, and the least recently used connection is evicted if the pool is full.
     #[serde(default = "default_parent_selector_capacity")]
+    #[validate(range(min = 1))]
     pub capacity: usize,
 }
 
+/// ParentSelector implements Default.
+impl Default for ParentSelector {
+    fn default() -> Self {
+        ParentSelector {
+            enable: false,
+            sync_interval: default_parent_selector_sync_interval(),
+            capacity: default_parent_selector_capacity(),
+        }
+    }
+}
+
 /// Upload is the upload configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Upload {
     /// server is the upload server configuration for dfdaemon.
@@ -646,6 +827,7 @@ pub struct Upload {
 
     /// rate_limit is the rate limit of the upload speed in GiB/Mib/Kib per second.
     #[serde(with = "bytesize_serde", default = "default_upload_rate_limit")]
+    #[schemars(with = "String")]
     pub rate_limit: ByteSize,
 }
 
@@ -662,12 +844,18 @@ impl Default for Upload {
 }
 
 /// Manager is the manager configuration for dfdaemon.
-#[derive(Debug, Clone, Default, Validate, Deserialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
+#[validate(schema(function = "validate_manager"))]
 pub struct Manager {
     /// addr is the manager address.
     pub addr: String,
 
+    /// addrs is the addresses of the managers, e.g. http://manager-a:65003. The dfdaemon connects
+    /// to the first healthy manager, and fails over to the next healthy one if the active manager
+    /// is unavailable. The addr is the first address if both are set.
+    pub addrs: Vec<String>,
+
     /// ca_cert is the root CA cert path with PEM format for the manager, and it is used
     /// for mutual TLS.
     pub ca_cert: Option<PathBuf>,
@@ -683,6 +871,18 @@ pub struct Manager {
 
 /// Manager is the implementation of Manager.
 impl Manager {
+    /// get_addrs returns the deduplicated addresses of the managers, the addr is the first one.
+    pub fn get_addrs(&self) -> Vec<String> {
+        let mut addrs: Vec<String> = Vec::with_capacity(self.addrs.len() + 1);
+        for addr in std::iter::once(&self.addr).chain(self.addrs.iter()) {
+            if !addr.is_empty() && !addrs.contains(addr) {
+                addrs.push(addr.clone());
+            }
+        }
+
+        addrs
+    }
+
     /// load_client_tls_config loads the client tls config.
     pub async fn load_client_tls_config(
         &self,
@@ -712,8 +912,9 @@ impl Manager {
 }
 
 /// Scheduler is the scheduler configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
+#[validate(schema(function = "validate_scheduler"))]
 pub struct Scheduler {
     /// announce_interval is the interval to announce peer to the scheduler.
     /// Announcer will provide the scheduler with peer information for scheduling,
@@ -722,6 +923,7 @@ pub struct Scheduler {
         default = "default_scheduler_announce_interval",
         with = "humantime_serde"
     )]
+    #[schemars(with = "String")]
     pub announce_interval: Duration,
 
     /// schedule_timeout is the timeout for scheduling. If the scheduling timeout, dfdaemon will back-to-source
@@ -730,6 +932,7 @@ pub struct Scheduler {
         default = "default_scheduler_schedule_timeout",
         with = "humantime_serde"
     )]
+    #[schemars(with = "String")]
     pub schedule_timeout: Duration,
 
     /// max_schedule_count is the max count of schedule.
@@ -737,6 +940,11 @@ pub struct Scheduler {
     #[validate(range(min = 1))]
     pub max_schedule_count: u32,
 
+    /// addrs is the static addresses of the schedulers, e.g. 192.168.1.1:8002. They are
+    /// used as the fallback when the schedulers can not be listed from the manager and
+    /// the cached schedulers of the last refresh are not found.
+    pub addrs: Vec<SocketAddr>,
+
     /// ca_cert is the root CA cert path with PEM format for the scheduler, and it is used
     /// for mutual TLS.
     pub ca_cert: Option<PathBuf>,
@@ -757,6 +965,7 @@ impl Default for Scheduler {
             announce_interval: default_scheduler_announce_interval(),
             schedule_timeout: default_scheduler_schedule_timeout(),
             max_schedule_count: default_download_max_schedule_count(),
+            addrs: Vec::new(),
             ca_cert: None,
             cert: None,
             key: None,
@@ -795,7 +1004,7 @@ impl Scheduler {
 }
 
 /// HostType is the type of the host.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, JsonSchema)]
 pub enum HostType {
     /// Normal indicates the peer is normal peer.
     #[serde(rename = "normal")]
@@ -828,7 +1037,7 @@ impl fmt::Display for HostType {
 }
 
 /// SeedPeer is the seed peer configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct SeedPeer {
     /// enable indicates whether enable seed peer.
@@ -848,6 +1057,7 @@ pub struct SeedPeer {
         default = "default_seed_peer_keepalive_interval",
         with = "humantime_serde"
     )]
+    #[schemars(with = "String")]
     pub keepalive_interval: Duration,
 }
 
@@ -864,7 +1074,7 @@ impl Default for SeedPeer {
 }
 
 /// Dynconfig is the dynconfig configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Dynconfig {
     /// refresh_interval is the interval to refresh dynamic configuration from manager.
@@ -872,6 +1082,7 @@ pub struct Dynconfig {
         default = "default_dynconfig_refresh_interval",
         with = "humantime_serde"
     )]
+    #[schemars(with = "String")]
     pub refresh_interval: Duration,
 }
 
@@ -884,8 +1095,23 @@ impl Default for Dynconfig {
     }
 }
 
+/// Standalone is the standalone configuration for dfdaemon. In standalone mode, dfdaemon
+/// runs without the manager and the scheduler, the tasks are downloaded from the static
+/// peers if they are configured, otherwise they are downloaded back-to-source.
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Standalone {
+    /// enable indicates whether enable standalone mode.
+    pub enable: bool,
+
+    /// peers is the upload server addresses of the static peers, e.g. 192.168.1.1:4000.
+    /// The pieces are synced from the static peers before downloading back-to-source,
+    /// and the static peers are expected to run in standalone mode too.
+    pub peers: Vec<SocketAddr>,
+}
+
 /// StorageServer is the storage server configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct StorageServer {
     /// protocol is the protocol of the storage server. The protocol used for downloading pieces
@@ -906,8 +1132,106 @@ impl Default for StorageServer {
     }
 }
 
+/// StorageCompression is the compression configuration of the stored content.
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StorageCompression {
+    /// enable indicates whether compress the pieces of the task by zstd when they are written
+    /// to the disk. The pieces are decompressed when they are read, and the piece that can not
+    /// be compressed to a smaller size is stored raw.
+    pub enable: bool,
+
+    /// level is the zstd compression level, default is 3.
+    #[serde(default = "default_storage_compression_level")]
+    #[validate(range(min = 1, max = 22))]
+    pub level: i32,
+
+    /// applications is the applications of the tasks to be compressed.
+    pub applications: Vec<String>,
+
+    /// rules is the regexes of the download urls of the tasks to be compressed. If both
+    /// applications and rules are empty, all tasks are compressed.
+    #[serde(with = "serde_regex")]
+    #[schemars(with = "Vec<String>")]
+    pub rules: Vec<Regex>,
+}
+
+/// StorageCompression implements Default.
+impl Default for StorageCompression {
+    fn default() -> Self {
+        StorageCompression {
+            enable: false,
+            level: default_storage_compression_level(),
+            applications: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// StorageCompression implements the compression configuration.
+impl StorageCompression {
+    /// is_matched returns whether the task of the application and url needs to be compressed.
+    pub fn is_matched(&self, application: Option<&str>, url: &str) -> bool {
+        if !self.enable {
+            return false;
+        }
+
+        if self.applications.is_empty() && self.rules.is_empty() {
+            return true;
+        }
+
+        application.is_some_and(|application| {
+            self.applications
+                .iter()
+                .any(|expected| expected == application)
+        }) || self.rules.iter().any(|rule| rule.is_match(url))
+    }
+}
+
+/// StorageDeduplication is the deduplication configuration of the stored content.
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StorageDeduplication {
+    /// enable indicates whether deduplicate the pieces across the tasks. The piece is keyed by
+    /// the sha256 digest of its content and stored once in the block store, the tasks with the
+    /// identical pieces reference the same block. The block is deleted when it is not referenced
+    /// by any task.
+    pub enable: bool,
+}
+
+/// StorageIoUring is the io_uring configuration of the storage.
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StorageIoUring {
+    /// enable indicates whether read and write the pieces of the task by io_uring. It only works
+    /// on linux when the dfdaemon is built with the `io-uring` feature, otherwise the pieces are
+    /// read and written by the thread pool of tokio.
+    pub enable: bool,
+
+    /// direct_io indicates whether read and write the pieces with O_DIRECT to bypass the page
+    /// cache. The piece whose offset and length are not aligned is written without O_DIRECT.
+    #[serde(rename = "directIO")]
+    pub direct_io: bool,
+
+    /// entries is the size of the submission queue of io_uring.
+    #[serde(default = "default_storage_io_uring_entries")]
+    #[validate(range(min = 1))]
+    pub entries: u32,
+}
+
+/// StorageIoUring implements Default.
+impl Default for StorageIoUring {
+    fn default() -> Self {
+        StorageIoUring {
+            enable: false,
+            direct_io: false,
+            entries: default_storage_io_uring_entries(),
+        }
+    }
+}
+
 /// Storage is the storage configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Storage {
     /// server is the storage server configuration for dfdaemon.
@@ -959,6 +1283,29 @@ pub struct Storage {
     /// ```
     #[serde(default = "default_storage_cache_capacity")]
     pub cache_capacity: usize,
+
+    /// memory_cache_capacity is the capacity of the memory cache of the hot pieces, such as
+    /// 128MiB, default is 0, which disables the memory cache. The pieces recently downloaded from
+    /// the parents or uploaded to other peers are cached, so the hot piece uploaded to many peers
+    /// is read from the disk once, and the least recently used pieces are evicted when the
+    /// capacity is exceeded.
+    #[serde(
+        with = "bytesize_serde",
+        default = "default_storage_memory_cache_capacity"
+    )]
+    #[schemars(with = "String")]
+    pub memory_cache_capacity: ByteSize,
+
+    /// compression is the compression configuration of the stored content.
+    #[validate]
+    pub compression: StorageCompression,
+
+    /// deduplication is the deduplication configuration of the stored content.
+    pub deduplication: StorageDeduplication,
+
+    /// io_uring is the io_uring configuration of the storage.
+    #[validate]
+    pub io_uring: StorageIoUring,
 }
 
 /// Storage implements Default.
@@ -971,13 +1318,60 @@ impl Default for Storage {
             write_buffer_size: default_storage_write_buffer_size(),
             read_buffer_size: default_storage_read_buffer_size(),
             cache_capacity: default_storage_cache_capacity(),
+            memory_cache_capacity: default_storage_memory_cache_capacity(),
+            compression: StorageCompression::default(),
+            deduplication: StorageDeduplication::default(),
+            io_uring: StorageIoUring::default(),
+        }
+    }
+}
+
+/// PersistentCacheTaskIDAlgorithm is the algorithm to generate the id of the imported persistent
+/// cache task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, JsonSchema)]
+pub enum PersistentCacheTaskIDAlgorithm {
+    /// Wyhash generates the id by the wyhash of the content, tag and application.
+    #[default]
+    #[serde(rename = "wyhash")]
+    Wyhash,
+
+    /// Sha256 uses the hex encoded sha256 digest of the content as the id.
+    #[serde(rename = "sha256")]
+    Sha256,
+
+    /// Blake3 uses the hex encoded blake3 digest of the content as the id.
+    #[serde(rename = "blake3")]
+    Blake3,
+}
+
+/// PersistentCacheTaskIDAlgorithm implements the conversion to the digest algorithm.
+impl PersistentCacheTaskIDAlgorithm {
+    /// digest_algorithm returns the digest algorithm of the content, it is none for wyhash.
+    pub fn digest_algorithm(&self) -> Option<digest::Algorithm> {
+        match self {
+            PersistentCacheTaskIDAlgorithm::Wyhash => None,
+            PersistentCacheTaskIDAlgorithm::Sha256 => Some(digest::Algorithm::Sha256),
+            PersistentCacheTaskIDAlgorithm::Blake3 => Some(digest::Algorithm::Blake3),
         }
     }
 }
 
+/// PersistentCacheTask is the persistent cache task configuration for dfdaemon.
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PersistentCacheTask {
+    /// id_algorithm is the algorithm to generate the id of the imported persistent cache task
+    /// when the id is not specified, default is wyhash. If it is sha256 or blake3, the id is the
+    /// hex encoded content digest of the file and the digest is recorded in the task metadata,
+    /// so the same file imported with different tags or applications is deduplicated to one
+    /// persistent cache task, and it can be exported by the digest.
+    pub id_algorithm: PersistentCacheTaskIDAlgorithm,
+}
+
 /// Policy is the policy configuration for gc.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
+#[validate(schema(function = "validate_gc_policy"))]
 pub struct Policy {
     /// task_ttl is the ttl of the task.
     #[serde(
@@ -985,6 +1379,7 @@ pub struct Policy {
         rename = "taskTTL",
         with = "humantime_serde"
     )]
+    #[schemars(with = "String")]
     pub task_ttl: Duration,
 
     /// dist_high_threshold_percent is the high threshold percent of the disk usage.
@@ -1012,11 +1407,12 @@ impl Default for Policy {
 }
 
 /// GC is the gc configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct GC {
     /// interval is the interval to do gc.
     #[serde(default = "default_gc_interval", with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub interval: Duration,
 
     /// policy is the gc policy.
@@ -1034,7 +1430,7 @@ impl Default for GC {
 }
 
 /// BasicAuth is the basic auth configuration for HTTP proxy in dfdaemon.
-#[derive(Default, Debug, Clone, Validate, Deserialize)]
+#[derive(Default, Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct BasicAuth {
     /// username is the username of the basic auth.
@@ -1054,8 +1450,9 @@ impl BasicAuth {
 }
 
 /// ProxyServer is the proxy server configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
+#[validate(schema(function = "validate_proxy_server"))]
 pub struct ProxyServer {
     /// ip is the listen ip of the proxy server.
     pub ip: Option<IpAddr>,
@@ -1131,11 +1528,12 @@ impl ProxyServer {
 }
 
 /// Rule is the proxy rule configuration.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Rule {
     /// regex is the regex of the request url.
     #[serde(with = "serde_regex")]
+    #[schemars(with = "String")]
     pub regex: Regex,
 
     /// use_tls indicates whether use tls for the proxy backend.
@@ -1145,6 +1543,10 @@ pub struct Rule {
     /// redirect is the redirect url.
     pub redirect: Option<String>,
 
+    /// application is the application of the download tasks proxied by the rule, it is used
+    /// if the `X-Dragonfly-Application` header is not set, e.g. to select the bandwidth class.
+    pub application: Option<String>,
+
     /// filtered_query_params is the filtered query params to generate the task id.
     /// When filter is ["Signature", "Expires", "ns"], for example:
     /// http://example.com/xyz?Expires=e1&Signature=s1&ns=docker.io and http://example.com/xyz?Expires=e2&Signature=s2&ns=docker.io
@@ -1161,13 +1563,14 @@ impl Default for Rule {
             regex: Regex::new(r".*").unwrap(),
             use_tls: false,
             redirect: None,
+            application: None,
             filtered_query_params: default_proxy_rule_filtered_query_params(),
         }
     }
 }
 
 /// RegistryMirror is the registry mirror configuration.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct RegistryMirror {
     /// addr is the default address of the registry mirror. Proxy will start a registry mirror service for the
@@ -1212,7 +1615,7 @@ impl RegistryMirror {
 }
 
 /// Proxy is the proxy configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Proxy {
     /// server is the proxy server configuration for dfdaemon.
@@ -1234,6 +1637,7 @@ pub struct Proxy {
     /// prefetch_rate_limit is the rate limit of the prefetch speed in GiB/Mib/Kib per second. The prefetch request
     /// has lower priority so limit the rate to avoid occupying the bandwidth impact other download tasks.
     #[serde(with = "bytesize_serde", default = "default_prefetch_rate_limit")]
+    #[schemars(with = "String")]
     pub prefetch_rate_limit: ByteSize,
 
     /// cache_capacity is the capacity of the cache by LRU algorithm for HTTP proxy, default is 150.
@@ -1265,7 +1669,7 @@ impl Default for Proxy {
 }
 
 /// Security is the security configuration for dfdaemon.
-#[derive(Debug, Clone, Default, Validate, Deserialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Security {
     /// enable indicates whether enable security.
@@ -1273,15 +1677,45 @@ pub struct Security {
 }
 
 /// Network is the network configuration for dfdaemon.
-#[derive(Debug, Clone, Default, Validate, Deserialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Network {
     /// enable_ipv6 indicates whether enable ipv6.
     pub enable_ipv6: bool,
+
+    /// connection_pool is the pool configuration of the gRPC connections to the schedulers
+    /// and the parents.
+    #[validate]
+    pub connection_pool: ConnectionPool,
+}
+
+/// ConnectionPool is the pool configuration of the gRPC connections, the connections are
+/// reused by the requests to the same address. The capacity of the pool is limited by
+/// `download.parentSelector.capacity`.
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ConnectionPool {
+    /// idle_timeout is the timeout of the idle connection, the connection is evicted
+    /// if it is not used in the timeout.
+    #[serde(
+        default = "default_network_connection_pool_idle_timeout",
+        with = "humantime_serde"
+    )]
+    #[schemars(with = "String")]
+    pub idle_timeout: Duration,
+}
+
+/// ConnectionPool implements Default.
+impl Default for ConnectionPool {
+    fn default() -> Self {
+        ConnectionPool {
+            idle_timeout: default_network_connection_pool_idle_timeout(),
+        }
+    }
 }
 
 /// HealthServer is the health server configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct HealthServer {
     /// ip is the listen ip of the health server.
@@ -1303,7 +1737,7 @@ impl Default for HealthServer {
 }
 
 /// Health is the health configuration for dfdaemon.
-#[derive(Debug, Clone, Default, Validate, Deserialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Health {
     /// server is the health server configuration for dfdaemon.
@@ -1311,7 +1745,7 @@ pub struct Health {
 }
 
 /// MetricsServer is the metrics server configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct MetricsServer {
     /// ip is the listen ip of the metrics server.
@@ -1333,7 +1767,7 @@ impl Default for MetricsServer {
 }
 
 /// Metrics is the metrics configuration for dfdaemon.
-#[derive(Debug, Clone, Default, Validate, Deserialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Metrics {
     /// server is the metrics server configuration for dfdaemon.
@@ -1341,7 +1775,7 @@ pub struct Metrics {
 }
 
 /// StatsServer is the stats server configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct StatsServer {
     /// ip is the listen ip of the stats server.
@@ -1363,7 +1797,7 @@ impl Default for StatsServer {
 }
 
 /// Stats is the stats configuration for dfdaemon.
-#[derive(Debug, Clone, Default, Validate, Deserialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Stats {
     /// server is the stats server configuration for dfdaemon.
@@ -1371,7 +1805,7 @@ pub struct Stats {
 }
 
 /// Tracing is the tracing configuration for dfdaemon.
-#[derive(Debug, Clone, Default, Validate, Deserialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Tracing {
     /// addr is the address to report tracing log.
@@ -1381,9 +1815,73 @@ pub struct Tracing {
     pub flamegraph: bool,
 }
 
+/// Log is the log configuration for dfdaemon.
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Log {
+    /// level is the log level, it overrides the log level of the command line if it is set,
+    /// and it can be changed by reloading the configuration.
+    #[serde(
+        deserialize_with = "deserialize_log_level",
+        serialize_with = "serialize_log_level"
+    )]
+    #[schemars(with = "Option<String>")]
+    pub level: Option<Level>,
+}
+
+/// deserialize_log_level deserializes the log level from the string, such as info, debug.
+fn deserialize_log_level<'de, D>(deserializer: D) -> std::result::Result<Option<Level>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|level| Level::from_str(&level).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// serialize_log_level serializes the log level to the lowercase string, such as info, debug.
+fn serialize_log_level<S>(
+    level: &Option<Level>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    level
+        .map(|level| level.as_str().to_lowercase())
+        .serialize(serializer)
+}
+
+/// Reload is the reload configuration for dfdaemon. The configuration is reloaded when
+/// dfdaemon receives the SIGHUP signal or the configuration file is changed if the watch
+/// is enabled. Only the rate limits, proxy rules, gc policy, log level and scheduler
+/// timeouts are reloaded, the other changes require restarting dfdaemon.
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Reload {
+    /// watch indicates whether watch the configuration file and reload it when it is changed.
+    pub watch: bool,
+
+    /// watch_interval is the interval to check the configuration file changes.
+    #[serde(default = "default_reload_watch_interval", with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub watch_interval: Duration,
+}
+
+/// Reload implements Default.
+impl Default for Reload {
+    fn default() -> Self {
+        Reload {
+            watch: false,
+            watch_interval: default_reload_watch_interval(),
+        }
+    }
+}
+
 /// Config is the configuration for dfdaemon.
-#[derive(Debug, Clone, Default, Validate, Deserialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
+#[validate(schema(function = "validate_server_ports"))]
 pub struct Config {
     /// host is the host configuration for dfdaemon.
     #[validate]
@@ -1401,6 +1899,10 @@ pub struct Config {
     #[validate]
     pub upload: Upload,
 
+    /// bandwidth_classes are the named bandwidth classes for dfdaemon.
+    #[validate]
+    pub bandwidth_classes: Vec<BandwidthClass>,
+
     /// manager is the manager configuration for dfdaemon.
     #[validate]
     pub manager: Manager,
@@ -1417,10 +1919,18 @@ pub struct Config {
     #[validate]
     pub dynconfig: Dynconfig,
 
+    /// standalone is the standalone configuration for dfdaemon.
+    #[validate]
+    pub standalone: Standalone,
+
     /// storage is the storage configuration for dfdaemon.
     #[validate]
     pub storage: Storage,
 
+    /// persistent_cache_task is the persistent cache task configuration for dfdaemon.
+    #[validate]
+    pub persistent_cache_task: PersistentCacheTask,
+
     /// gc is the gc configuration for dfdaemon.
     #[validate]
     pub gc: GC,
@@ -1452,16 +1962,71 @@ pub struct Config {
     /// network is the network configuration for dfdaemon.
     #[validate]
     pub network: Network,
+
+    /// log is the log configuration for dfdaemon.
+    #[validate]
+    pub log: Log,
+
+    /// reload is the reload configuration for dfdaemon.
+    #[validate]
+    pub reload: Reload,
 }
 
 /// Config implements the config operation of dfdaemon.
 impl Config {
-    /// load loads configuration from file.
+    /// load loads configuration from file, and overrides it by the environment variables.
     #[instrument(skip_all)]
     pub async fn load(path: &PathBuf) -> Result<Config> {
+        Self::load_with_overrides(path, &[]).await
+    }
+
+    /// load_with_overrides loads configuration by layers, the defaults are overridden by the
+    /// configuration file, then by the environment variables prefixed with DFDAEMON_, and
+    /// then by the overrides in the form of key=value, such as upload.rateLimit=1GiB.
+    #[instrument(skip_all)]
+    pub async fn load_with_overrides(path: &PathBuf, overrides: &[String]) -> Result<Config> {
+        Self::load_by_layers(path, std::env::vars_os(), overrides).await
+    }
+
+    /// load_without_envs loads configuration from file and overrides it by the overrides in
+    /// the form of key=value, the environment variables are ignored. It is used to validate
+    /// the configuration file as written, the environment of the runner does not leak into it.
+    #[instrument(skip_all)]
+    pub async fn load_without_envs(path: &PathBuf, overrides: &[String]) -> Result<Config> {
+        Self::load_by_layers(path, std::iter::empty(), overrides).await
+    }
+
+    /// load_by_layers loads configuration from file, and overrides it by the environment
+    /// variables and then by the overrides in the form of key=value.
+    async fn load_by_layers<I>(path: &PathBuf, envs: I, overrides: &[String]) -> Result<Config>
+    where
+        I: IntoIterator<Item = (std::ffi::OsString, std::ffi::OsString)>,
+    {
         // Load configuration from file.
         let content = fs::read_to_string(path).await?;
-        let mut config: Config = serde_yaml::from_str(&content).or_err(ErrorType::ConfigError)?;
+        let mut value: Value = serde_yaml::from_str(&content).or_err(ErrorType::ConfigError)?;
+        if value.is_null() {
+            value = Value::Mapping(Mapping::new());
+        }
+
+        // Override configuration by the environment variables.
+        let schema = OverrideSchema::new()?;
+        override_by_envs(&mut value, &schema, envs)?;
+
+        // Override configuration by the key=value overrides.
+        for override_ in overrides {
+            let (key, raw) = override_.split_once('=').ok_or_else(|| {
+                Error::ValidationError(format!(
+                    "invalid override {}, it should be key=value",
+                    override_
+                ))
+            })?;
+
+            let keys = key.split('.').collect::<Vec<_>>();
+            set_value(&mut value, &schema, &keys, raw)?;
+        }
+
+        let mut config: Config = serde_yaml::from_value(value).or_err(ErrorType::ConfigError)?;
 
         // Convert configuration.
         config.convert();
@@ -1471,6 +2036,45 @@ impl Config {
         Ok(config)
     }
 
+    /// schema returns the JSON schema of the configuration.
+    pub fn schema() -> RootSchema {
+        schema_for!(Config)
+    }
+
+    /// to_redacted_yaml returns the configuration in yaml, the values of the secrets such as
+    /// passwords and tokens are redacted.
+    pub fn to_redacted_yaml(&self) -> Result<String> {
+        let mut value = serde_yaml::to_value(self).or_err(ErrorType::ConfigError)?;
+        redact_secrets(&mut value);
+        serde_yaml::to_string(&value).or_err(ErrorType::ConfigError)
+    }
+
+    /// merge_reloadable returns the current configuration with the reloadable settings of the
+    /// new configuration, the other settings of the new configuration are ignored.
+    pub fn merge_reloadable(&self, new: &Config) -> Config {
+        let mut config = self.clone();
+
+        // Reload the rate limits.
+        config.download.rate_limit = new.download.rate_limit;
+        config.upload.rate_limit = new.upload.rate_limit;
+        config.proxy.prefetch_rate_limit = new.proxy.prefetch_rate_limit;
+        config.bandwidth_classes = new.bandwidth_classes.clone();
+
+        // Reload the proxy rules.
+        config.proxy.rules = new.proxy.rules.clone();
+
+        // Reload the gc policy.
+        config.gc.policy = new.gc.policy.clone();
+
+        // Reload the log level.
+        config.log.level = new.log.level;
+
+        // Reload the scheduler timeouts.
+        config.scheduler.schedule_timeout = new.scheduler.schedule_timeout;
+        config.scheduler.max_schedule_count = new.scheduler.max_schedule_count;
+        config
+    }
+
     /// convert converts the configuration.
     #[instrument(skip_all)]
     fn convert(&mut self) {
@@ -1529,3 +2133,603 @@ impl Config {
         }
     }
 }
+
+/// override_by_envs overrides the configuration value by the environment variables prefixed
+/// with DFDAEMON_, the nested keys are separated by the double underscores and resolved by the
+/// schema, such as DFDAEMON_UPLOAD__RATE_LIMIT overrides upload.rateLimit. The environment
+/// variables of the unknown sections are ignored, e.g. DFDAEMON_PORT injected by Kubernetes
+/// for the service named dfdaemon, but the unknown keys of the known sections are rejected.
+fn override_by_envs<I>(value: &mut Value, schema: &OverrideSchema, envs: I) -> Result<()>
+where
+    I: IntoIterator<Item = (std::ffi::OsString, std::ffi::OsString)>,
+{
+    for (name, raw) in envs {
+        let (Some(name), Some(raw)) = (name.to_str(), raw.to_str()) else {
+            continue;
+        };
+
+        let Some(name) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+
+        let keys = name.split(ENV_SEPARATOR).collect::<Vec<_>>();
+        if schema.resolve(&keys[..1]).is_err() {
+            continue;
+        }
+
+        set_value(value, schema, &keys, raw)?;
+    }
+
+    Ok(())
+}
+
+/// set_value sets the raw value to the nested keys of the configuration value, the keys are
+/// resolved by the schema, the missing keys are created and the numeric keys index the
+/// sequences. The raw value of the string field is kept as it is, and the others are parsed
+/// as yaml, such as true, 10 and [a, b].
+fn set_value(value: &mut Value, schema: &OverrideSchema, keys: &[&str], raw: &str) -> Result<()> {
+    let (keys, is_string) = schema.resolve(keys)?;
+    let mut current = value;
+    for key in keys.iter() {
+        if !current.is_mapping() && !current.is_sequence() {
+            *current = if key.parse::<usize>().is_ok() {
+                Value::Sequence(Vec::new())
+            } else {
+                Value::Mapping(Mapping::new())
+            };
+        }
+
+        current = match current {
+            Value::Sequence(sequence) => {
+                let index = key
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|index| *index <= sequence.len())
+                    .ok_or_else(|| {
+                        Error::ValidationError(format!(
+                            "invalid index {} of {}",
+                            key,
+                            keys.join(".")
+                        ))
+                    })?;
+
+                if index == sequence.len() {
+                    sequence.push(Value::Null);
+                }
+
+                &mut sequence[index]
+            }
+            Value::Mapping(mapping) => mapping
+                .entry(Value::String(key.clone()))
+                .or_insert(Value::Null),
+            _ => unreachable!(),
+        };
+    }
+
+    *current = if is_string {
+        Value::String(raw.to_string())
+    } else {
+        serde_yaml::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+    };
+
+    Ok(())
+}
+
+/// OverrideSchema resolves the keys and the types of the overrides by the JSON schema of the
+/// configuration, because the keys can not be derived from the field names mechanically, such
+/// as gc.policy.taskTTL and storage.ioUring.directIO.
+struct OverrideSchema {
+    /// root is the JSON schema of the configuration.
+    root: Value,
+}
+
+/// OverrideSchema implements the schema of the overrides.
+impl OverrideSchema {
+    /// new creates a new OverrideSchema by the JSON schema of the configuration.
+    fn new() -> Result<Self> {
+        let root = serde_yaml::to_value(Config::schema()).or_err(ErrorType::ConfigError)?;
+        Ok(Self { root })
+    }
+
+    /// resolve resolves the keys in snake case or camel case to the keys of the configuration,
+    /// and returns whether the value of the keys is a string. It fails if the keys are unknown.
+    fn resolve(&self, keys: &[&str]) -> Result<(Vec<String>, bool)> {
+        let mut schemas = self.expand(&self.root);
+        let mut resolved = Vec::with_capacity(keys.len());
+        for key in keys {
+            let (name, child) = schemas
+                .iter()
+                .copied()
+                .find_map(|schema| Self::child(schema, key))
+                .ok_or_else(|| {
+                    Error::ValidationError(format!("unknown configuration key {}", keys.join(".")))
+                })?;
+
+            resolved.push(name);
+            schemas = self.expand(child);
+        }
+
+        let types = schemas
+            .iter()
+            .filter_map(|schema| schema.get("type"))
+            .flat_map(|kind| match kind {
+                Value::Sequence(kinds) => kinds.iter().filter_map(Value::as_str).collect(),
+                kind => kind.as_str().into_iter().collect::<Vec<_>>(),
+            })
+            .filter(|kind| *kind != "null")
+            .collect::<Vec<_>>();
+        let is_string = !types.is_empty() && types.iter().all(|kind| *kind == "string");
+        Ok((resolved, is_string))
+    }
+
+    /// expand returns the schema with the schemas referenced by $ref, allOf, anyOf and oneOf.
+    fn expand<'a>(&'a self, schema: &'a Value) -> Vec<&'a Value> {
+        let mut schemas = vec![schema];
+        if let Some(definition) = schema
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| reference.strip_prefix("#/definitions/"))
+            .and_then(|name| self.root.get("definitions")?.get(name))
+        {
+            schemas.extend(self.expand(definition));
+        }
+
+        for keyword in ["allOf", "anyOf", "oneOf"] {
+            if let Some(Value::Sequence(subschemas)) = schema.get(keyword) {
+                for subschema in subschemas {
+                    schemas.extend(self.expand(subschema));
+                }
+            }
+        }
+
+        schemas
+    }
+
+    /// child returns the resolved key and the schema of the child by the key, the key of the
+    /// property is matched regardless of the case and the underscores.
+    fn child<'a>(schema: &'a Value, key: &str) -> Option<(String, &'a Value)> {
+        if let Some(Value::Mapping(properties)) = schema.get("properties") {
+            let normalized = normalize_key(key);
+            if let Some((name, child)) = properties.iter().find(|(name, _)| {
+                name.as_str()
+                    .is_some_and(|name| normalize_key(name) == normalized)
+            }) {
+                return Some((name.as_str()?.to_string(), child));
+            }
+        }
+
+        if let Some(items) = schema.get("items") {
+            if key.parse::<usize>().is_ok() {
+                return Some((key.to_string(), items));
+            }
+        }
+
+        match schema.get("additionalProperties") {
+            Some(child) if child.is_mapping() => Some((key.to_string(), child)),
+            _ => None,
+        }
+    }
+}
+
+/// normalize_key normalizes the key to lowercase without the underscores, so the snake case
+/// key, such as task_ttl, matches the camel case key, such as taskTTL.
+fn normalize_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// redact_secrets replaces the values of the secret keys with the placeholder.
+fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Mapping(mapping) => {
+            for (key, value) in mapping.iter_mut() {
+                let is_secret = key.as_str().is_some_and(|key| {
+                    let key = key.to_lowercase();
+                    SECRET_KEYWORDS.iter().any(|keyword| key.contains(keyword))
+                });
+
+                if is_secret && !value.is_null() {
+                    *value = Value::String(REDACTED_SECRET.to_string());
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        }
+        Value::Sequence(sequence) => sequence.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// validate_upload_server validates the TLS files of the upload server are set together.
+fn validate_upload_server(server: &UploadServer) -> std::result::Result<(), ValidationError> {
+    validate_tls_files(&server.ca_cert, &server.cert, &server.key)
+}
+
+/// validate_upload_client validates the TLS files of the upload client are set together.
+fn validate_upload_client(client: &UploadClient) -> std::result::Result<(), ValidationError> {
+    validate_tls_files(&client.ca_cert, &client.cert, &client.key)
+}
+
+/// validate_manager validates the TLS files of the manager client are set together.
+fn validate_manager(manager: &Manager) -> std::result::Result<(), ValidationError> {
+    validate_tls_files(&manager.ca_cert, &manager.cert, &manager.key)
+}
+
+/// validate_scheduler validates the TLS files of the scheduler client are set together.
+fn validate_scheduler(scheduler: &Scheduler) -> std::result::Result<(), ValidationError> {
+    validate_tls_files(&scheduler.ca_cert, &scheduler.cert, &scheduler.key)
+}
+
+/// validate_tls_files validates the ca cert, cert and key are set together, the mutual TLS
+/// is enabled only if all of them are set, so setting part of them is a misconfiguration.
+fn validate_tls_files(
+    ca_cert: &Option<PathBuf>,
+    cert: &Option<PathBuf>,
+    key: &Option<PathBuf>,
+) -> std::result::Result<(), ValidationError> {
+    let count = [ca_cert, cert, key].iter().filter(|f| f.is_some()).count();
+    if count != 0 && count != 3 {
+        return Err(new_validation_error(
+            "tls_files",
+            "caCert, cert and key must be set together",
+        ));
+    }
+
+    Ok(())
+}
+
+/// validate_proxy_server validates the CA cert and key of the proxy server are set together.
+fn validate_proxy_server(server: &ProxyServer) -> std::result::Result<(), ValidationError> {
+    if server.ca_cert.is_some() != server.ca_key.is_some() {
+        return Err(new_validation_error(
+            "tls_files",
+            "caCert and caKey must be set together",
+        ));
+    }
+
+    Ok(())
+}
+
+/// validate_gc_policy validates the low threshold of the disk usage is less than the high
+/// threshold, otherwise the gc can not stop.
+fn validate_gc_policy(policy: &Policy) -> std::result::Result<(), ValidationError> {
+    if policy.dist_low_threshold_percent >= policy.dist_high_threshold_percent {
+        return Err(new_validation_error(
+            "threshold",
+            "distLowThresholdPercent must be less than distHighThresholdPercent",
+        ));
+    }
+
+    Ok(())
+}
+
+/// validate_server_ports validates the servers of dfdaemon do not listen on the same port,
+/// the servers listening on the different ips can share the port unless one of the ips is
+/// unspecified.
+fn validate_server_ports(config: &Config) -> std::result::Result<(), ValidationError> {
+    let servers = [
+        (
+            "upload.server",
+            config.upload.server.ip,
+            config.upload.server.port,
+        ),
+        (
+            "proxy.server",
+            config.proxy.server.ip,
+            config.proxy.server.port,
+        ),
+        (
+            "health.server",
+            config.health.server.ip,
+            config.health.server.port,
+        ),
+        (
+            "metrics.server",
+            config.metrics.server.ip,
+            config.metrics.server.port,
+        ),
+        (
+            "stats.server",
+            config.stats.server.ip,
+            config.stats.server.port,
+        ),
+    ];
+
+    for (i, (name, ip, port)) in servers.iter().enumerate() {
+        // The port 0 is assigned by the operating system.
+        if *port == 0 {
+            continue;
+        }
+
+        for (other_name, other_ip, other_port) in servers.iter().skip(i + 1) {
+            let ip_collided = match (ip, other_ip) {
+                (Some(ip), Some(other_ip)) => {
+                    ip == other_ip || ip.is_unspecified() || other_ip.is_unspecified()
+                }
+                _ => true,
+            };
+
+            if port == other_port && ip_collided {
+                return Err(new_validation_error(
+                    "port",
+                    format!(
+                        "{} and {} listen on the same port {}",
+                        name, other_name, port
+                    ),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// new_validation_error creates a new validation error with the message.
+fn new_validation_error(
+    code: &'static str,
+    message: impl Into<std::borrow::Cow<'static, str>>,
+) -> ValidationError {
+    let mut err = ValidationError::new(code);
+    err.message = Some(message.into());
+    err
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_deserialize_storage_cache_capacity() {
+        // The legacy cache capacity is still parsed as the count.
+        let storage: Storage = serde_yaml::from_str("cacheCapacity: 100").unwrap();
+        assert_eq!(storage.cache_capacity, 100);
+        assert_eq!(storage.memory_cache_capacity, ByteSize::b(0));
+
+        let storage: Storage = serde_yaml::from_str("memoryCacheCapacity: 128MiB").unwrap();
+        assert_eq!(storage.cache_capacity, 100);
+        assert_eq!(storage.memory_cache_capacity, ByteSize::mib(128));
+
+        let storage: Storage =
+            serde_yaml::from_str("cacheCapacity: 50\nmemoryCacheCapacity: 64MB").unwrap();
+        assert_eq!(storage.cache_capacity, 50);
+        assert_eq!(storage.memory_cache_capacity, ByteSize::mb(64));
+    }
+
+    #[test]
+    fn should_normalize_key() {
+        assert_eq!(normalize_key("task_ttl"), "taskttl");
+        assert_eq!(normalize_key("taskTTL"), "taskttl");
+        assert_eq!(normalize_key("RATE_LIMIT"), "ratelimit");
+        assert_eq!(normalize_key("rateLimit"), "ratelimit");
+    }
+
+    #[test]
+    fn should_resolve_override_keys() {
+        let schema = OverrideSchema::new().unwrap();
+
+        // Test the keys renamed by serde.
+        let (keys, is_string) = schema.resolve(&["gc", "policy", "task_ttl"]).unwrap();
+        assert_eq!(keys, vec!["gc", "policy", "taskTTL"]);
+        assert!(is_string);
+
+        let (keys, is_string) = schema
+            .resolve(&["STORAGE", "IO_URING", "DIRECT_IO"])
+            .unwrap();
+        assert_eq!(keys, vec!["storage", "ioUring", "directIO"]);
+        assert!(!is_string);
+
+        let (keys, _) = schema.resolve(&["seedPeer", "clusterID"]).unwrap();
+        assert_eq!(keys, vec!["seedPeer", "clusterID"]);
+
+        // Test the keys of the items in the sequence.
+        let (keys, is_string) = schema.resolve(&["proxy", "rules", "0", "use_tls"]).unwrap();
+        assert_eq!(keys, vec!["proxy", "rules", "0", "useTLS"]);
+        assert!(!is_string);
+
+        // Test the optional string.
+        let (_, is_string) = schema.resolve(&["host", "idc"]).unwrap();
+        assert!(is_string);
+
+        // Test the unknown keys.
+        assert!(schema.resolve(&["gc", "policy", "unknown"]).is_err());
+        assert!(schema.resolve(&["unknown"]).is_err());
+    }
+
+    #[test]
+    fn should_set_value() {
+        let schema = OverrideSchema::new().unwrap();
+        let mut value = Value::Mapping(Mapping::new());
+
+        // Test the string field is not parsed as yaml.
+        set_value(&mut value, &schema, &["host", "hostname"], "123").unwrap();
+        assert_eq!(value["host"]["hostname"], Value::String("123".to_string()));
+
+        // Test the other fields are parsed as yaml.
+        set_value(
+            &mut value,
+            &schema,
+            &["storage", "io_uring", "direct_io"],
+            "true",
+        )
+        .unwrap();
+        assert_eq!(value["storage"]["ioUring"]["directIO"], Value::Bool(true));
+
+        set_value(&mut value, &schema, &["manager", "addrs"], "[a, b]").unwrap();
+        assert_eq!(value["manager"]["addrs"].as_sequence().unwrap().len(), 2);
+
+        // Test the sequence is created by the index.
+        set_value(
+            &mut value,
+            &schema,
+            &["proxy", "rules", "0", "regex"],
+            "blobs/sha256.*",
+        )
+        .unwrap();
+        set_value(
+            &mut value,
+            &schema,
+            &["proxy", "rules", "0", "use_tls"],
+            "true",
+        )
+        .unwrap();
+        assert!(set_value(&mut value, &schema, &["proxy", "rules", "2", "regex"], ".*").is_err());
+
+        // Test the unknown keys are rejected.
+        assert!(set_value(&mut value, &schema, &["gc", "policy", "unknown"], "1h").is_err());
+
+        let config: Config = serde_yaml::from_value(value).unwrap();
+        assert_eq!(config.host.hostname, "123");
+        assert!(config.storage.io_uring.direct_io);
+        assert_eq!(config.manager.addrs, vec!["a", "b"]);
+
+        let rules = config.proxy.rules.unwrap();
+        assert_eq!(rules[0].regex.as_str(), "blobs/sha256.*");
+        assert!(rules[0].use_tls);
+    }
+
+    #[test]
+    fn should_override_by_envs() {
+        let schema = OverrideSchema::new().unwrap();
+        let mut value = serde_yaml::from_str::<Value>("gc:\n  policy:\n    taskTTL: 1h").unwrap();
+        let envs = |envs: &[(&str, &str)]| {
+            envs.iter()
+                .map(|(name, value)| ((*name).into(), (*value).into()))
+                .collect::<Vec<(std::ffi::OsString, std::ffi::OsString)>>()
+        };
+
+        override_by_envs(
+            &mut value,
+            &schema,
+            envs(&[
+                ("DFDAEMON_GC__POLICY__TASK_TTL", "2h"),
+                ("DFDAEMON_UPLOAD__RATE_LIMIT", "1GiB"),
+                ("DFDAEMON_PORT", "tcp://10.0.0.1:4000"),
+                ("UPLOAD__RATE_LIMIT", "2GiB"),
+            ]),
+        )
+        .unwrap();
+
+        let config: Config = serde_yaml::from_value(value.clone()).unwrap();
+        assert_eq!(config.gc.policy.task_ttl, Duration::from_secs(7200));
+        assert_eq!(config.upload.rate_limit, ByteSize::gib(1));
+        assert!(value.get("port").is_none());
+
+        // Test the unknown keys of the known sections are rejected.
+        assert!(override_by_envs(
+            &mut value,
+            &schema,
+            envs(&[("DFDAEMON_GC__POLICY__TASK_TIMEOUT", "1h")]),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn should_redact_secrets() {
+        let mut value = serde_yaml::from_str::<Value>(
+            r#"
+manager:
+  addr: http://manager:65003
+  password: foo
+backend:
+  - accessToken: bar
+    secretKey: ~
+"#,
+        )
+        .unwrap();
+
+        redact_secrets(&mut value);
+        assert_eq!(
+            value["manager"]["addr"],
+            Value::String("http://manager:65003".to_string())
+        );
+        assert_eq!(
+            value["manager"]["password"],
+            Value::String(REDACTED_SECRET.to_string())
+        );
+        assert_eq!(
+            value["backend"][0]["accessToken"],
+            Value::String(REDACTED_SECRET.to_string())
+        );
+        assert!(value["backend"][0]["secretKey"].is_null());
+    }
+
+    #[test]
+    fn should_validate_tls_files() {
+        let path = Some(PathBuf::from("/etc/ssl/file"));
+        assert!(validate_tls_files(&None, &None, &None).is_ok());
+        assert!(validate_tls_files(&path, &path, &path).is_ok());
+        assert!(validate_tls_files(&None, &path, &path).is_err());
+        assert!(validate_tls_files(&path, &None, &None).is_err());
+
+        let manager = Manager {
+            cert: path.clone(),
+            ..Default::default()
+        };
+        assert!(manager.validate().is_err());
+    }
+
+    #[test]
+    fn should_validate_proxy_server() {
+        let path = Some(PathBuf::from("/etc/ssl/file"));
+        assert!(validate_proxy_server(&ProxyServer::default()).is_ok());
+        assert!(validate_proxy_server(&ProxyServer {
+            ca_cert: path.clone(),
+            ca_key: path.clone(),
+            ..Default::default()
+        })
+        .is_ok());
+        assert!(validate_proxy_server(&ProxyServer {
+            ca_cert: path.clone(),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_proxy_server(&ProxyServer {
+            ca_key: path,
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn should_validate_gc_policy() {
+        assert!(validate_gc_policy(&Policy::default()).is_ok());
+        assert!(validate_gc_policy(&Policy {
+            dist_high_threshold_percent: 80,
+            dist_low_threshold_percent: 80,
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_gc_policy(&Policy {
+            dist_high_threshold_percent: 60,
+            dist_low_threshold_percent: 80,
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn should_validate_server_ports() {
+        assert!(validate_server_ports(&Config::default()).is_ok());
+
+        // Test the servers listen on the same port.
+        let mut config = Config::default();
+        config.proxy.server.port = config.upload.server.port;
+        assert!(validate_server_ports(&config).is_err());
+
+        // Test the servers listen on the same port with the different ips.
+        config.upload.server.ip = Some(IpAddr::from([127, 0, 0, 1]));
+        config.proxy.server.ip = Some(IpAddr::from([127, 0, 0, 2]));
+        assert!(validate_server_ports(&config).is_ok());
+
+        // Test the servers listen on the same port with the unspecified ip.
+        config.proxy.server.ip = Some(Ipv4Addr::UNSPECIFIED.into());
+        assert!(validate_server_ports(&config).is_err());
+
+        // Test the port 0 is assigned by the operating system.
+        config.upload.server.port = 0;
+        config.proxy.server.port = 0;
+        assert!(validate_server_ports(&config).is_ok());
+    }
+}
@@ -17,12 +17,14 @@
 use crate::dfdaemon::default_proxy_server_port;
 use dragonfly_client_core::error::{ErrorType, OrErr};
 use dragonfly_client_core::Result;
+use schemars::{gen::SchemaGenerator, schema::RootSchema, schema::Schema, schema_for, JsonSchema};
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
 use std::net::Ipv4Addr;
 use std::path::PathBuf;
 use tracing::{info, instrument};
-use validator::Validate;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 /// NAME is the name of dfinit.
 pub const NAME: &str = "dfinit";
@@ -102,7 +104,7 @@ fn default_container_runtime_containerd_registry_capabilities() -> Vec<String> {
 }
 
 /// Registry is the registry configuration for containerd.
-#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct ContainerdRegistry {
     /// host_namespace is the location where container images and artifacts are sourced,
@@ -131,8 +133,9 @@ pub struct ContainerdRegistry {
 }
 
 /// Containerd is the containerd configuration for dfinit.
-#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
+#[validate(schema(function = "validate_containerd"))]
 pub struct Containerd {
     /// config_path is the path of containerd configuration file.
     #[serde(default = "default_container_runtime_containerd_config_path")]
@@ -143,7 +146,7 @@ pub struct Containerd {
 }
 
 /// CRIORegistry is the registry configuration for cri-o.
-#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct CRIORegistry {
     /// prefix is the prefix of the user-specified image name, refer to
@@ -156,7 +159,7 @@ pub struct CRIORegistry {
 }
 
 /// CRIO is the cri-o configuration for dfinit.
-#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct CRIO {
     /// config_path is the path of cri-o registries's configuration file.
@@ -174,7 +177,7 @@ pub struct CRIO {
 }
 
 /// CRIORegistry is the registry configuration for cri-o.
-#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct PodmanRegistry {
     /// prefix is the prefix of the user-specified image name, refer to
@@ -187,7 +190,7 @@ pub struct PodmanRegistry {
 }
 
 /// Podman is the podman configuration for dfinit.
-#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Podman {
     /// config_path is the path of cri-o registries's configuration file.
@@ -205,7 +208,7 @@ pub struct Podman {
 }
 
 /// Docker is the docker configuration for dfinit.
-#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Docker {
     /// config_path is the path of docker configuration file.
@@ -214,10 +217,11 @@ pub struct Docker {
 }
 
 /// ContainerRuntime is the container runtime configuration for dfinit.
-#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct ContainerRuntime {
     #[serde(flatten)]
+    #[validate]
     pub config: Option<ContainerRuntimeConfig>,
 }
 
@@ -261,6 +265,38 @@ impl Serialize for ContainerRuntimeConfig {
     }
 }
 
+/// Validate is the implementation of the Validate trait for ContainerRuntimeConfig.
+impl Validate for ContainerRuntimeConfig {
+    fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        match self {
+            ContainerRuntimeConfig::Containerd(cfg) => cfg.validate(),
+            ContainerRuntimeConfig::Docker(cfg) => cfg.validate(),
+            ContainerRuntimeConfig::CRIO(cfg) => cfg.validate(),
+            ContainerRuntimeConfig::Podman(cfg) => cfg.validate(),
+        }
+    }
+}
+
+/// JsonSchema is the implementation of the JsonSchema trait for ContainerRuntimeConfig.
+impl JsonSchema for ContainerRuntimeConfig {
+    fn schema_name() -> String {
+        "ContainerRuntimeConfig".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        #[allow(dead_code)]
+        #[derive(JsonSchema)]
+        struct ContainerRuntimeHelper {
+            containerd: Option<Containerd>,
+            docker: Option<Docker>,
+            crio: Option<CRIO>,
+            podman: Option<Podman>,
+        }
+
+        ContainerRuntimeHelper::json_schema(gen)
+    }
+}
+
 /// Deserialize is the implementation of the Deserialize trait for ContainerRuntimeConfig.
 impl<'de> Deserialize<'de> for ContainerRuntimeConfig {
     fn deserialize<D>(deserializer: D) -> std::prelude::v1::Result<Self, D::Error>
@@ -303,7 +339,7 @@ impl<'de> Deserialize<'de> for ContainerRuntimeConfig {
 }
 
 /// Proxy is the proxy server configuration for dfdaemon.
-#[derive(Debug, Clone, Validate, Deserialize, Serialize)]
+#[derive(Debug, Clone, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Proxy {
     // addr is the proxy server address of dfdaemon.
@@ -321,7 +357,7 @@ impl Default for Proxy {
 }
 
 /// Config is the configuration for dfinit.
-#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Validate, Deserialize, Serialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Config {
     /// proxy is the configuration of the dfdaemon's HTTP/HTTPS proxy.
@@ -347,6 +383,32 @@ impl Config {
         config.validate().or_err(ErrorType::ValidationError)?;
         Ok(config)
     }
+
+    /// schema returns the JSON schema of the configuration.
+    pub fn schema() -> RootSchema {
+        schema_for!(Config)
+    }
+}
+
+/// validate_containerd validates the host namespaces of the containerd registries are unique,
+/// otherwise the hosts.toml of the registries overwrite each other.
+fn validate_containerd(containerd: &Containerd) -> std::result::Result<(), ValidationError> {
+    let mut host_namespaces = HashSet::new();
+    for registry in &containerd.registries {
+        if !host_namespaces.insert(registry.host_namespace.as_str()) {
+            let mut err = ValidationError::new("host_namespace");
+            err.message = Some(
+                format!(
+                    "hostNamespace {} is duplicated in containerd registries",
+                    registry.host_namespace
+                )
+                .into(),
+            );
+            return Err(err);
+        }
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -473,4 +535,30 @@ containerRuntime:
             panic!("failed to deserialize");
         }
     }
+
+    #[test]
+    fn validate_duplicated_containerd_registries() {
+        let registry = ContainerdRegistry {
+            host_namespace: "docker.io".to_string(),
+            server_addr: "https://registry-1.docker.io".to_string(),
+            ..Default::default()
+        };
+
+        let mut config = Config {
+            container_runtime: ContainerRuntime {
+                config: Some(ContainerRuntimeConfig::Containerd(Containerd {
+                    registries: vec![registry.clone()],
+                    ..Default::default()
+                })),
+            },
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.container_runtime.config = Some(ContainerRuntimeConfig::Containerd(Containerd {
+            registries: vec![registry.clone(), registry],
+            ..Default::default()
+        }));
+        assert!(config.validate().is_err());
+    }
 }
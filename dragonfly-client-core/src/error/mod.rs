@@ -210,6 +210,10 @@ pub enum DFError {
     #[error("max number of files to download exceeded: {0}")]
     MaxDownloadFilesExceeded(usize),
 
+    /// DownloadFilesFailed is the error for some files failed to download in batch.
+    #[error("failed to download {0} of {1} files")]
+    DownloadFilesFailed(usize, usize),
+
     /// Unsupported is the error for unsupported.
     #[error("unsupported {0}")]
     Unsupported(String),
@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use dragonfly_client::tracing::init_tracing;
 use dragonfly_client_config::dfinit;
 use dragonfly_client_config::VersionValueParser;
@@ -37,6 +37,7 @@ struct Args {
     #[arg(
         short = 'c',
         long = "config",
+        global = true,
         default_value_os_t = dfinit::default_dfinit_config_path(),
         help = "Specify config file to use")
     ]
@@ -80,6 +81,44 @@ struct Args {
         value_parser = VersionValueParser
     )]
     version: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum Command {
+    #[command(
+        name = "config",
+        about = "Validate the config of dfinit",
+        long_about = "Validate the config of dfinit and print its JSON schema without changing the configuration of the container runtime."
+    )]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum ConfigCommand {
+    #[command(
+        name = "validate",
+        about = "Validate the config file",
+        long_about = "Validate the config file without running dfinit, it checks the fields and the cross-field constraints, such as the duplicated registries. It exits with non-zero code if the config is invalid."
+    )]
+    Validate {
+        #[arg(
+            help = "Specify the config file to validate, it is the config file of --config if not set"
+        )]
+        path: Option<PathBuf>,
+    },
+
+    #[command(
+        name = "schema",
+        about = "Print the JSON schema of the config",
+        long_about = "Print the JSON schema of the config, which is derived from the config types and can be used to lint the config files."
+    )]
+    Schema,
 }
 
 #[tokio::main]
@@ -87,6 +126,29 @@ async fn main() -> Result<(), anyhow::Error> {
     // Parse command line arguments.
     let args = Args::parse();
 
+    // Execute the config subcommands.
+    if let Some(Command::Config { command }) = &args.command {
+        match command {
+            ConfigCommand::Validate { path } => {
+                let path = path.as_ref().unwrap_or(&args.config);
+                if let Err(err) = dfinit::Config::load(path) {
+                    println!("Config {} is invalid: {}", path.display(), err);
+                    std::process::exit(1);
+                }
+
+                println!("Config {} is valid", path.display());
+            }
+            ConfigCommand::Schema => {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&dfinit::Config::schema())?
+                );
+            }
+        }
+
+        return Ok(());
+    }
+
     // Initialize tracing.
     let _guards = init_tracing(
         dfinit::NAME,
@@ -0,0 +1,177 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use bytes::Bytes;
+use lru::LruCache;
+use std::sync::Mutex;
+use tracing::debug;
+
+/// Pieces is the pieces in the cache with the total size.
+struct Pieces {
+    /// lru stores the piece content with piece id by LRU algorithm.
+    lru: LruCache<String, Bytes>,
+
+    /// size is the total size of the piece content in the cache.
+    size: u64,
+}
+
+/// Cache is the memory cache for storing the hot piece content by LRU algorithm, the cache
+/// is bounded by the total size of the piece content.
+pub struct Cache {
+    /// capacity is the capacity of the cache in bytes.
+    capacity: u64,
+
+    /// pieces stores the piece content.
+    pieces: Mutex<Pieces>,
+}
+
+/// Cache implements the memory cache of the pieces.
+impl Cache {
+    /// new creates a new cache with the capacity in bytes.
+    pub fn new(capacity: u64) -> Self {
+        Cache {
+            capacity,
+            pieces: Mutex::new(Pieces {
+                lru: LruCache::unbounded(),
+                size: 0,
+            }),
+        }
+    }
+
+    /// is_enabled returns whether the cache is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// is_cacheable returns whether the piece with the length can be cached.
+    pub fn is_cacheable(&self, length: u64) -> bool {
+        self.is_enabled() && length <= self.capacity
+    }
+
+    /// get_piece gets the piece content from the cache.
+    pub fn get_piece(&self, id: &str) -> Option<Bytes> {
+        let mut pieces = self.pieces.lock().unwrap();
+        pieces.lru.get(id).cloned()
+    }
+
+    /// add_piece adds the piece content into the cache, the least recently used pieces are
+    /// evicted if the cache is full. If the piece already exists, it is replaced.
+    pub fn add_piece(&self, id: &str, content: Bytes) {
+        let length = content.len() as u64;
+        if !self.is_cacheable(length) {
+            return;
+        }
+
+        let mut pieces = self.pieces.lock().unwrap();
+        if let Some(old) = pieces.lru.put(id.to_string(), content) {
+            pieces.size -= old.len() as u64;
+        }
+        pieces.size += length;
+
+        while pieces.size > self.capacity {
+            match pieces.lru.pop_lru() {
+                Some((id, content)) => {
+                    debug!("evict piece {} from cache", id);
+                    pieces.size -= content.len() as u64;
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// remove_piece removes the piece content from the cache.
+    pub fn remove_piece(&self, id: &str) {
+        let mut pieces = self.pieces.lock().unwrap();
+        if let Some(content) = pieces.lru.pop(id) {
+            pieces.size -= content.len() as u64;
+        }
+    }
+
+    /// remove_task_pieces removes the piece contents of the task from the cache.
+    pub fn remove_task_pieces(&self, task_id: &str) {
+        let mut pieces = self.pieces.lock().unwrap();
+        let ids = pieces
+            .lru
+            .iter()
+            .filter(|(id, _)| id.starts_with(task_id))
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            if let Some(content) = pieces.lru.pop(&id) {
+                pieces.size -= content.len() as u64;
+            }
+        }
+    }
+
+    /// size returns the total size of the piece content in the cache.
+    pub fn size(&self) -> u64 {
+        self.pieces.lock().unwrap().size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_evict_by_size() {
+        let cache = Cache::new(10);
+        cache.add_piece("task-0", Bytes::from_static(b"0123"));
+        cache.add_piece("task-1", Bytes::from_static(b"4567"));
+        assert_eq!(cache.size(), 8);
+
+        // Test the least recently used piece is evicted.
+        assert!(cache.get_piece("task-0").is_some());
+        cache.add_piece("task-2", Bytes::from_static(b"89ab"));
+        assert_eq!(cache.size(), 8);
+        assert!(cache.get_piece("task-1").is_none());
+        assert!(cache.get_piece("task-0").is_some());
+
+        // Test the piece larger than the capacity is not cached.
+        cache.add_piece("task-3", Bytes::from(vec![0; 11]));
+        assert!(cache.get_piece("task-3").is_none());
+
+        // Test the replaced piece is counted once.
+        cache.add_piece("task-2", Bytes::from_static(b"89"));
+        assert_eq!(cache.size(), 6);
+    }
+
+    #[test]
+    fn should_remove_pieces() {
+        let cache = Cache::new(1024);
+        cache.add_piece("task-0", Bytes::from_static(b"0123"));
+        cache.add_piece("task-1", Bytes::from_static(b"4567"));
+        cache.add_piece("other-0", Bytes::from_static(b"89ab"));
+
+        cache.remove_piece("task-0");
+        assert!(cache.get_piece("task-0").is_none());
+        assert_eq!(cache.size(), 8);
+
+        cache.remove_task_pieces("task");
+        assert!(cache.get_piece("task-1").is_none());
+        assert!(cache.get_piece("other-0").is_some());
+        assert_eq!(cache.size(), 4);
+    }
+
+    #[test]
+    fn should_disable_cache() {
+        let cache = Cache::new(0);
+        assert!(!cache.is_enabled());
+        cache.add_piece("task-0", Bytes::from_static(b"0123"));
+        assert!(cache.get_piece("task-0").is_none());
+    }
+}
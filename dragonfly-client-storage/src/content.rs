@@ -14,17 +14,21 @@
  * limitations under the License.
  */
 
+use bytes::Bytes;
 use crc::*;
 use dragonfly_api::common::v2::Range;
 use dragonfly_client_config::dfdaemon::Config;
-use dragonfly_client_core::Result;
+use dragonfly_client_core::{Error, Result};
+use sha2::{Digest, Sha256};
 use std::cmp::{max, min};
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::fs::{self, File, OpenOptions};
 use tokio::io::{
     self, AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader, BufWriter, SeekFrom,
 };
+use tokio_util::either::Either;
 use tokio_util::io::InspectReader;
 use tracing::{error, info, instrument, warn};
 
@@ -37,6 +41,12 @@ pub const DEFAULT_TASK_DIR: &str = "tasks";
 /// DEFAULT_PERSISTENT_CACHE_TASK_DIR is the default directory for store persistent cache task.
 pub const DEFAULT_PERSISTENT_CACHE_TASK_DIR: &str = "persistent-cache-tasks";
 
+/// DEFAULT_BLOCK_DIR is the default directory for store deduplicated blocks.
+pub const DEFAULT_BLOCK_DIR: &str = "blocks";
+
+/// DEFAULT_BLOCK_TEMP_DIR is the default directory for store the temporary files of blocks.
+pub const DEFAULT_BLOCK_TEMP_DIR: &str = "temp";
+
 /// Content is the content of a piece.
 pub struct Content {
     /// config is the configuration of the dfdaemon.
@@ -44,6 +54,10 @@ pub struct Content {
 
     /// dir is the directory to store content.
     dir: PathBuf,
+
+    /// uring reads and writes the pieces by io_uring if it is enabled.
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    uring: Option<crate::uring::Uring>,
 }
 
 /// WritePieceResponse is the response of writing a piece.
@@ -53,6 +67,29 @@ pub struct WritePieceResponse {
 
     /// hash is the hash of the piece.
     pub hash: String,
+
+    /// compressed_length is the length of the compressed piece written to the content,
+    /// it is none if the piece is written raw.
+    pub compressed_length: Option<u64>,
+}
+
+/// WriteBlockResponse is the response of writing a block.
+pub struct WriteBlockResponse {
+    /// length is the length of the piece.
+    pub length: u64,
+
+    /// hash is the crc32 castagnoli hash of the piece.
+    pub hash: String,
+
+    /// digest is the sha256 digest of the piece, it is the key of the block.
+    pub digest: String,
+
+    /// compressed_length is the length of the compressed block, it is none if
+    /// the block is written raw.
+    pub compressed_length: Option<u64>,
+
+    /// temp_path is the path of the temporary file of the block.
+    pub temp_path: PathBuf,
 }
 
 /// WritePersistentCacheTaskResponse is the response of writing a persistent cache task.
@@ -80,8 +117,45 @@ impl Content {
 
         fs::create_dir_all(&dir.join(DEFAULT_TASK_DIR)).await?;
         fs::create_dir_all(&dir.join(DEFAULT_PERSISTENT_CACHE_TASK_DIR)).await?;
+
+        // The temporary files of blocks are left by the interrupted writes, remove them.
+        let block_temp_dir = dir.join(DEFAULT_BLOCK_DIR).join(DEFAULT_BLOCK_TEMP_DIR);
+        fs::remove_dir_all(&block_temp_dir)
+            .await
+            .unwrap_or_else(|err| {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("remove {:?} failed: {}", block_temp_dir, err);
+                }
+            });
+        fs::create_dir_all(&block_temp_dir).await?;
         info!("content initialized directory: {:?}", dir);
-        Ok(Content { config, dir })
+
+        // Initialize io_uring, fallback to the thread pool of tokio if it is not available.
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        let uring = if config.storage.io_uring.enable {
+            crate::uring::Uring::new(
+                config.storage.io_uring.entries,
+                config.storage.io_uring.direct_io,
+            )
+            .inspect_err(|err| {
+                warn!("initialize io_uring failed, fallback: {}", err);
+            })
+            .ok()
+        } else {
+            None
+        };
+
+        #[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+        if config.storage.io_uring.enable {
+            warn!("io_uring is not supported, dfdaemon needs to be built with io-uring feature on linux");
+        }
+
+        Ok(Content {
+            config,
+            dir,
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            uring,
+        })
     }
 
     /// available_space returns the available space of the disk.
@@ -232,46 +306,133 @@ impl Content {
         Ok(())
     }
 
-    /// delete_task deletes the task content.
+    /// copy_task_by_pieces copies the task content to the destination piece by piece, it is
+    /// used for the task whose pieces are compressed or deduplicated. If the range is specified,
+    /// only the pieces in the range are copied.
     #[instrument(skip_all)]
-    pub async fn delete_task(&self, task_id: &str) -> Result<()> {
-        info!("delete task content: {}", task_id);
-        let task_path = self.get_task_path(task_id);
-        fs::remove_file(task_path.as_path())
+    pub async fn copy_task_by_pieces(
+        &self,
+        task_id: &str,
+        mut pieces: Vec<crate::metadata::Piece>,
+        to: &Path,
+        range: Option<Range>,
+    ) -> Result<()> {
+        // Ensure the parent directory of the destination exists.
+        if let Some(parent) = to.parent() {
+            if !parent.exists() {
+                fs::create_dir_all(parent).await.inspect_err(|err| {
+                    error!("failed to create directory {:?}: {}", parent, err);
+                })?;
+            }
+        }
+
+        let mut to_f = OpenOptions::new()
+            .create(true)
+            .truncate(range.is_none())
+            .write(true)
+            .open(to.as_os_str())
             .await
             .inspect_err(|err| {
-                error!("remove {:?} failed: {}", task_path, err);
+                error!("open {:?} failed: {}", to, err);
             })?;
+
+        // Copy the pieces in order, the pieces out of the range are skipped.
+        pieces.sort_by_key(|piece| piece.number);
+        for piece in pieces {
+            if let Some(range) = range {
+                if piece.length == 0
+                    || piece.offset >= range.start + range.length
+                    || piece.offset + piece.length <= range.start
+                {
+                    continue;
+                }
+            }
+
+            let mut reader = self.read_piece(task_id, &piece, range).await?;
+            io::copy(&mut reader, &mut to_f).await?;
+        }
+
+        to_f.flush().await?;
         Ok(())
     }
 
-    /// read_piece reads the piece from the content.
+    /// delete_task deletes the task content. The task content does not exist if all
+    /// pieces of the task are deduplicated, so it is ignored.
+    #[instrument(skip_all)]
+    pub async fn delete_task(&self, task_id: &str) -> Result<()> {
+        info!("delete task content: {}", task_id);
+        let task_path = self.get_task_path(task_id);
+        if let Err(err) = fs::remove_file(task_path.as_path()).await {
+            if err.kind() == io::ErrorKind::NotFound {
+                return Ok(());
+            }
+
+            error!("remove {:?} failed: {}", task_path, err);
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+
+    /// read_piece reads the piece from the content. If the piece is deduplicated, it is read
+    /// from the block. If the piece is compressed, it is decompressed in memory before it is read.
     #[instrument(skip_all)]
     pub async fn read_piece(
         &self,
         task_id: &str,
-        offset: u64,
-        length: u64,
+        piece: &crate::metadata::Piece,
         range: Option<Range>,
     ) -> Result<impl AsyncRead> {
-        let task_path = self.get_task_path(task_id);
+        let (path, offset) = self.get_piece_path(task_id, piece);
 
         // Calculate the target offset and length based on the range.
-        let (target_offset, target_length) = calculate_piece_range(offset, length, range);
+        let (target_offset, target_length) =
+            calculate_piece_range(piece.offset, piece.length, range);
+
+        // Read the range of the decompressed piece if the piece is compressed.
+        if let Some(compressed_length) = piece.compressed_length {
+            let content = self
+                .read_compressed_piece(path.as_path(), offset, piece.length, compressed_length)
+                .await?;
+
+            return Ok(Either::Left(slice_piece(
+                content.into(),
+                target_offset - piece.offset,
+                target_length,
+            )));
+        }
 
-        let f = File::open(task_path.as_path()).await.inspect_err(|err| {
-            error!("open {:?} failed: {}", task_path, err);
+        // Read the range of the piece by io_uring if it is enabled.
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(uring) = &self.uring {
+            let (buffer, head) = uring
+                .read_exact_at(
+                    path.as_path(),
+                    offset + target_offset - piece.offset,
+                    target_length,
+                )
+                .await?;
+
+            return Ok(Either::Left(slice_piece(
+                Bytes::from_owner(buffer),
+                head as u64,
+                target_length,
+            )));
+        }
+
+        let f = File::open(path.as_path()).await.inspect_err(|err| {
+            error!("open {:?} failed: {}", path, err);
         })?;
         let mut f_reader = BufReader::with_capacity(self.config.storage.read_buffer_size, f);
 
         f_reader
-            .seek(SeekFrom::Start(target_offset))
+            .seek(SeekFrom::Start(offset + target_offset - piece.offset))
             .await
             .inspect_err(|err| {
-                error!("seek {:?} failed: {}", task_path, err);
+                error!("seek {:?} failed: {}", path, err);
             })?;
 
-        Ok(f_reader.take(target_length))
+        Ok(Either::Right(f_reader.take(target_length)))
     }
 
     /// read_piece_with_dual_read return two readers, one is the range reader, and the other is the
@@ -280,31 +441,67 @@ impl Content {
     pub async fn read_piece_with_dual_read(
         &self,
         task_id: &str,
-        offset: u64,
-        length: u64,
+        piece: &crate::metadata::Piece,
         range: Option<Range>,
     ) -> Result<(impl AsyncRead, impl AsyncRead)> {
-        let task_path = self.get_task_path(task_id);
+        let (path, offset) = self.get_piece_path(task_id, piece);
 
         // Calculate the target offset and length based on the range.
-        let (target_offset, target_length) = calculate_piece_range(offset, length, range);
+        let (target_offset, target_length) =
+            calculate_piece_range(piece.offset, piece.length, range);
+
+        // Read the range and the full of the decompressed piece if the piece is compressed.
+        if let Some(compressed_length) = piece.compressed_length {
+            let content = self
+                .read_compressed_piece(path.as_path(), offset, piece.length, compressed_length)
+                .await?;
+
+            let content = Bytes::from(content);
+            return Ok((
+                Either::Left(slice_piece(
+                    content.clone(),
+                    target_offset - piece.offset,
+                    target_length,
+                )),
+                Either::Left(slice_piece(content, 0, piece.length)),
+            ));
+        }
 
-        let f = File::open(task_path.as_path()).await.inspect_err(|err| {
-            error!("open {:?} failed: {}", task_path, err);
+        // Read the full piece by io_uring once if it is enabled, and the range reader
+        // shares the same buffer.
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(uring) = &self.uring {
+            let (buffer, head) = uring
+                .read_exact_at(path.as_path(), offset, piece.length)
+                .await?;
+            let content = Bytes::from_owner(buffer).slice(head..);
+
+            return Ok((
+                Either::Left(slice_piece(
+                    content.clone(),
+                    target_offset - piece.offset,
+                    target_length,
+                )),
+                Either::Left(slice_piece(content, 0, piece.length)),
+            ));
+        }
+
+        let f = File::open(path.as_path()).await.inspect_err(|err| {
+            error!("open {:?} failed: {}", path, err);
         })?;
         let mut f_range_reader = BufReader::with_capacity(self.config.storage.read_buffer_size, f);
 
         f_range_reader
-            .seek(SeekFrom::Start(target_offset))
+            .seek(SeekFrom::Start(offset + target_offset - piece.offset))
             .await
             .inspect_err(|err| {
-                error!("seek {:?} failed: {}", task_path, err);
+                error!("seek {:?} failed: {}", path, err);
             })?;
         let range_reader = f_range_reader.take(target_length);
 
         // Create full reader of the piece.
-        let f = File::open(task_path.as_path()).await.inspect_err(|err| {
-            error!("open {:?} failed: {}", task_path, err);
+        let f = File::open(path.as_path()).await.inspect_err(|err| {
+            error!("open {:?} failed: {}", path, err);
         })?;
         let mut f_reader = BufReader::with_capacity(self.config.storage.read_buffer_size, f);
 
@@ -312,11 +509,249 @@ impl Content {
             .seek(SeekFrom::Start(offset))
             .await
             .inspect_err(|err| {
-                error!("seek {:?} failed: {}", task_path, err);
+                error!("seek {:?} failed: {}", path, err);
             })?;
-        let reader = f_reader.take(length);
+        let reader = f_reader.take(piece.length);
 
-        Ok((range_reader, reader))
+        Ok((Either::Right(range_reader), Either::Right(reader)))
+    }
+
+    /// read_compressed_piece reads the compressed piece from the file and decompresses it.
+    #[instrument(skip_all)]
+    async fn read_compressed_piece(
+        &self,
+        path: &Path,
+        offset: u64,
+        length: u64,
+        compressed_length: u64,
+    ) -> Result<Vec<u8>> {
+        let mut f = File::open(path).await.inspect_err(|err| {
+            error!("open {:?} failed: {}", path, err);
+        })?;
+
+        f.seek(SeekFrom::Start(offset)).await.inspect_err(|err| {
+            error!("seek {:?} failed: {}", path, err);
+        })?;
+
+        let mut compressed = vec![0; compressed_length as usize];
+        f.read_exact(&mut compressed).await.inspect_err(|err| {
+            error!("read {:?} failed: {}", path, err);
+        })?;
+
+        // Decompress the piece in the blocking thread, because the decompression is cpu bound.
+        let piece = tokio::task::spawn_blocking(move || {
+            zstd::bulk::decompress(&compressed, length as usize)
+        })
+        .await
+        .map_err(Error::TokioJoinError)?
+        .inspect_err(|err| {
+            error!("decompress {:?} failed: {}", path, err);
+        })?;
+
+        Ok(piece)
+    }
+
+    /// write_compressed_piece_with_crc32_castagnoli writes the piece compressed by zstd to the
+    /// content with crc32 castagnoli. The hash is calculated by the raw piece. If the compressed
+    /// piece is not smaller than the raw piece, the raw piece is written.
+    #[instrument(skip_all)]
+    pub async fn write_compressed_piece_with_crc32_castagnoli<R: AsyncRead + Unpin + ?Sized>(
+        &self,
+        task_id: &str,
+        offset: u64,
+        reader: &mut R,
+    ) -> Result<WritePieceResponse> {
+        let (piece, hash) = self.read_to_end_with_crc32_castagnoli(reader).await?;
+        let length = piece.len() as u64;
+        let (content, compressed_length) = self.compress_piece(piece).await?;
+
+        // Open the file and seek to the offset.
+        let task_path = self.create_or_get_task_path(task_id).await?;
+        let mut f = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(task_path.as_path())
+            .await
+            .inspect_err(|err| {
+                error!("open {:?} failed: {}", task_path, err);
+            })?;
+
+        f.seek(SeekFrom::Start(offset)).await.inspect_err(|err| {
+            error!("seek {:?} failed: {}", task_path, err);
+        })?;
+
+        f.write_all(&content).await.inspect_err(|err| {
+            error!("write {:?} failed: {}", task_path, err);
+        })?;
+
+        f.flush().await.inspect_err(|err| {
+            error!("flush {:?} failed: {}", task_path, err);
+        })?;
+
+        Ok(WritePieceResponse {
+            length,
+            hash,
+            compressed_length,
+        })
+    }
+
+    /// write_block_with_crc32_castagnoli writes the piece to the temporary file of the block store
+    /// with crc32 castagnoli, and calculates the sha256 digest of the piece as the block digest.
+    /// The temporary file needs to be committed by [Content::commit_block] or removed by
+    /// [Content::remove_block_temp].
+    #[instrument(skip_all)]
+    pub async fn write_block_with_crc32_castagnoli<R: AsyncRead + Unpin + ?Sized>(
+        &self,
+        task_id: &str,
+        offset: u64,
+        reader: &mut R,
+        compressed: bool,
+    ) -> Result<WriteBlockResponse> {
+        let (piece, hash) = self.read_to_end_with_crc32_castagnoli(reader).await?;
+        let length = piece.len() as u64;
+
+        // Calculate the block digest in the blocking thread, because the hashing is cpu bound.
+        let (piece, digest) = tokio::task::spawn_blocking(move || {
+            let digest = base16ct::lower::encode_string(&Sha256::digest(&piece));
+            (piece, digest)
+        })
+        .await
+        .map_err(Error::TokioJoinError)?;
+
+        let (content, compressed_length) = if compressed {
+            self.compress_piece(piece).await?
+        } else {
+            (piece, None)
+        };
+
+        // Write the block to the temporary file, the temporary file is unique for the piece
+        // of the task, so the concurrent writes of the identical blocks do not conflict.
+        let temp_path = self
+            .dir
+            .join(DEFAULT_BLOCK_DIR)
+            .join(DEFAULT_BLOCK_TEMP_DIR)
+            .join(format!("{}-{}", task_id, offset));
+        fs::write(temp_path.as_path(), &content)
+            .await
+            .inspect_err(|err| {
+                error!("write {:?} failed: {}", temp_path, err);
+            })?;
+
+        Ok(WriteBlockResponse {
+            length,
+            hash,
+            digest,
+            compressed_length,
+            temp_path,
+        })
+    }
+
+    /// commit_block moves the temporary file of the block to the block store.
+    #[instrument(skip_all)]
+    pub async fn commit_block(&self, temp_path: &Path, digest: &str) -> Result<()> {
+        let block_dir = self.dir.join(DEFAULT_BLOCK_DIR).join(&digest[..3]);
+        fs::create_dir_all(&block_dir).await.inspect_err(|err| {
+            error!("create {:?} failed: {}", block_dir, err);
+        })?;
+
+        let block_path = block_dir.join(digest);
+        fs::rename(temp_path, block_path.as_path())
+            .await
+            .inspect_err(|err| {
+                error!("rename {:?} to {:?} failed: {}", temp_path, block_path, err);
+            })?;
+        Ok(())
+    }
+
+    /// remove_block_temp removes the temporary file of the block, it is used when the
+    /// block already exists in the block store.
+    #[instrument(skip_all)]
+    pub async fn remove_block_temp(&self, temp_path: &Path) -> Result<()> {
+        fs::remove_file(temp_path).await.inspect_err(|err| {
+            error!("remove {:?} failed: {}", temp_path, err);
+        })?;
+        Ok(())
+    }
+
+    /// delete_block deletes the block content.
+    #[instrument(skip_all)]
+    pub async fn delete_block(&self, digest: &str) -> Result<()> {
+        info!("delete block content: {}", digest);
+        let block_path = self.get_block_path(digest);
+        fs::remove_file(block_path.as_path())
+            .await
+            .inspect_err(|err| {
+                error!("remove {:?} failed: {}", block_path, err);
+            })?;
+        Ok(())
+    }
+
+    /// get_block_digests returns the digests of the blocks in the block store.
+    #[instrument(skip_all)]
+    pub async fn get_block_digests(&self) -> Result<Vec<String>> {
+        let mut digests = Vec::new();
+        let mut sub_dirs = fs::read_dir(self.dir.join(DEFAULT_BLOCK_DIR)).await?;
+        while let Some(sub_dir) = sub_dirs.next_entry().await? {
+            if sub_dir.file_name() == DEFAULT_BLOCK_TEMP_DIR || !sub_dir.file_type().await?.is_dir()
+            {
+                continue;
+            }
+
+            let mut entries = fs::read_dir(sub_dir.path()).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                if let Some(digest) = entry.file_name().to_str() {
+                    digests.push(digest.to_string());
+                }
+            }
+        }
+
+        Ok(digests)
+    }
+
+    /// read_to_end_with_crc32_castagnoli reads the full piece into memory and calculates the
+    /// hash of the piece by crc32 castagnoli.
+    #[instrument(skip_all)]
+    async fn read_to_end_with_crc32_castagnoli<R: AsyncRead + Unpin + ?Sized>(
+        &self,
+        reader: &mut R,
+    ) -> Result<(Vec<u8>, String)> {
+        let reader = BufReader::with_capacity(self.config.storage.write_buffer_size, reader);
+        let crc = Crc::<u32, Table<16>>::new(&CRC_32_ISCSI);
+        let mut digest = crc.digest();
+
+        let mut tee = InspectReader::new(reader, |bytes| {
+            digest.update(bytes);
+        });
+
+        let mut piece = Vec::new();
+        tee.read_to_end(&mut piece).await.inspect_err(|err| {
+            error!("read piece failed: {}", err);
+        })?;
+        drop(tee);
+
+        Ok((piece, digest.finalize().to_string()))
+    }
+
+    /// compress_piece compresses the piece by zstd. If the compressed piece is not smaller
+    /// than the raw piece, the raw piece is returned without the compressed length.
+    #[instrument(skip_all)]
+    async fn compress_piece(&self, piece: Vec<u8>) -> Result<(Vec<u8>, Option<u64>)> {
+        // Compress the piece in the blocking thread, because the compression is cpu bound.
+        let level = self.config.storage.compression.level;
+        let (content, compressed_length) = tokio::task::spawn_blocking(move || {
+            let compressed = zstd::bulk::compress(&piece, level)?;
+            if compressed.len() < piece.len() {
+                let compressed_length = compressed.len() as u64;
+                return Ok::<_, io::Error>((compressed, Some(compressed_length)));
+            }
+
+            Ok((piece, None))
+        })
+        .await
+        .map_err(Error::TokioJoinError)??;
+
+        Ok((content, compressed_length))
     }
 
     /// write_piece_with_crc32_castagnoli writes the piece to the content with crc32 castagnoli.
@@ -328,8 +763,24 @@ impl Content {
         offset: u64,
         reader: &mut R,
     ) -> Result<WritePieceResponse> {
-        // Open the file and seek to the offset.
         let task_path = self.create_or_get_task_path(task_id).await?;
+
+        // Write the piece by io_uring if it is enabled.
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(uring) = &self.uring {
+            let (piece, hash) = self.read_to_end_with_crc32_castagnoli(reader).await?;
+            uring
+                .write_all_at(task_path.as_path(), offset, &piece)
+                .await?;
+
+            return Ok(WritePieceResponse {
+                length: piece.len() as u64,
+                hash,
+                compressed_length: None,
+            });
+        }
+
+        // Open the file and seek to the offset.
         let mut f = OpenOptions::new()
             .create(true)
             .truncate(false)
@@ -366,6 +817,7 @@ impl Content {
         Ok(WritePieceResponse {
             length,
             hash: digest.finalize().to_string(),
+            compressed_length: None,
         })
     }
 
@@ -378,6 +830,27 @@ impl Content {
         self.dir.join(DEFAULT_TASK_DIR).join(sub_dir).join(task_id)
     }
 
+    /// get_block_path returns the block path by block digest.
+    #[instrument(skip_all)]
+    fn get_block_path(&self, digest: &str) -> PathBuf {
+        // The block needs split by the first 3 characters of digest(sha256) to
+        // avoid too many files in one directory.
+        self.dir
+            .join(DEFAULT_BLOCK_DIR)
+            .join(&digest[..3])
+            .join(digest)
+    }
+
+    /// get_piece_path returns the path of the file storing the piece and the offset of
+    /// the piece in the file.
+    #[instrument(skip_all)]
+    fn get_piece_path(&self, task_id: &str, piece: &crate::metadata::Piece) -> (PathBuf, u64) {
+        match piece.block_digest.as_deref() {
+            Some(digest) => (self.get_block_path(digest), 0),
+            None => (self.get_task_path(task_id), piece.offset),
+        }
+    }
+
     /// create_or_get_task_path creates parent directories or returns the task path by task id.
     #[instrument(skip_all)]
     async fn create_or_get_task_path(&self, task_id: &str) -> Result<PathBuf> {
@@ -569,6 +1042,7 @@ impl Content {
         Ok(WritePieceResponse {
             length,
             hash: digest.finalize().to_string(),
+            compressed_length: None,
         })
     }
 
@@ -635,6 +1109,13 @@ impl Content {
     }
 }
 
+/// slice_piece returns the reader of the piece in memory from the offset with the length.
+pub(crate) fn slice_piece(piece: Bytes, offset: u64, length: u64) -> impl AsyncRead {
+    let mut reader = Cursor::new(piece);
+    reader.set_position(offset);
+    reader.take(length)
+}
+
 /// calculate_piece_range calculates the target offset and length based on the piece range and
 /// request range.
 pub fn calculate_piece_range(offset: u64, length: u64, range: Option<Range>) -> (u64, u64) {
@@ -651,6 +1132,130 @@ pub fn calculate_piece_range(offset: u64, length: u64, range: Option<Range>) ->
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metadata::Piece;
+    use tempdir::TempDir;
+
+    #[tokio::test]
+    async fn should_write_and_read_compressed_piece() {
+        let dir = TempDir::new("content").unwrap();
+        let content = Content::new(Arc::new(Config::default()), dir.path())
+            .await
+            .unwrap();
+        let task_id = "60409bd0ec44160f44c53c39b3fe1c5fdfb23faded0228c68bee83bc15a200e3";
+
+        // Test the compressible piece is compressed.
+        let data = vec![b'a'; 4096];
+        let response = content
+            .write_compressed_piece_with_crc32_castagnoli(task_id, 0, &mut data.as_slice())
+            .await
+            .unwrap();
+        assert_eq!(response.length, 4096);
+        assert!(response.compressed_length.unwrap() < 4096);
+
+        let piece = Piece {
+            offset: 0,
+            length: 4096,
+            compressed_length: response.compressed_length,
+            ..Default::default()
+        };
+        let mut reader = content.read_piece(task_id, &piece, None).await.unwrap();
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer).await.unwrap();
+        assert_eq!(buffer, data);
+
+        let mut reader = content
+            .read_piece(
+                task_id,
+                &piece,
+                Some(Range {
+                    start: 1024,
+                    length: 16,
+                }),
+            )
+            .await
+            .unwrap();
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer).await.unwrap();
+        assert_eq!(buffer, vec![b'a'; 16]);
+
+        // Test the incompressible piece is written raw.
+        let data: Vec<u8> = (0..=255).collect();
+        let response = content
+            .write_compressed_piece_with_crc32_castagnoli(task_id, 4096, &mut data.as_slice())
+            .await
+            .unwrap();
+        assert_eq!(response.length, 256);
+        assert!(response.compressed_length.is_none());
+
+        let piece = Piece {
+            offset: 4096,
+            length: 256,
+            ..Default::default()
+        };
+        let mut reader = content.read_piece(task_id, &piece, None).await.unwrap();
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer).await.unwrap();
+        assert_eq!(buffer, data);
+    }
+
+    #[tokio::test]
+    async fn should_write_and_read_block() {
+        let dir = TempDir::new("content").unwrap();
+        let content = Content::new(Arc::new(Config::default()), dir.path())
+            .await
+            .unwrap();
+        let task_id = "a535b115f18d96870f0422ac891f91dd162f2f391e4778fb84279701fcd02dd1";
+
+        let data: Vec<u8> = (0..=255).collect();
+        let response = content
+            .write_block_with_crc32_castagnoli(task_id, 256, &mut data.as_slice(), false)
+            .await
+            .unwrap();
+        assert_eq!(response.length, 256);
+        assert_eq!(
+            response.digest,
+            "40aff2e9d2d8922e47afd4648e6967497158785fbd1da870e7110266bf944880"
+        );
+        assert!(response.compressed_length.is_none());
+
+        content
+            .commit_block(response.temp_path.as_path(), response.digest.as_str())
+            .await
+            .unwrap();
+        assert!(!response.temp_path.exists());
+        assert_eq!(
+            content.get_block_digests().await.unwrap(),
+            vec![response.digest.clone()]
+        );
+
+        // Test the piece is read from the block with the range.
+        let piece = Piece {
+            offset: 256,
+            length: 256,
+            block_digest: Some(response.digest.clone()),
+            ..Default::default()
+        };
+        let mut reader = content
+            .read_piece(
+                task_id,
+                &piece,
+                Some(Range {
+                    start: 300,
+                    length: 4,
+                }),
+            )
+            .await
+            .unwrap();
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer).await.unwrap();
+        assert_eq!(buffer, vec![44, 45, 46, 47]);
+
+        content
+            .delete_block(response.digest.as_str())
+            .await
+            .unwrap();
+        assert!(content.read_piece(task_id, &piece, None).await.is_err());
+    }
 
     #[tokio::test]
     async fn should_calculate_piece_range() {
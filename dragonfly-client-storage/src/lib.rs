@@ -14,26 +14,64 @@
  * limitations under the License.
  */
 
+use bytes::Bytes;
 use chrono::NaiveDateTime;
 use dragonfly_api::common::v2::Range;
 use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::{Error, Result};
 use dragonfly_client_util::digest::{Algorithm, Digest};
 use reqwest::header::HeaderMap;
+use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::AsyncRead;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::Mutex;
+use tokio_util::either::Either;
+use tokio_util::io::InspectReader;
 use tracing::{debug, error, instrument, warn};
 
+pub mod cache;
 pub mod content;
 pub mod metadata;
 pub mod storage_engine;
 
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub mod uring;
+
 /// DEFAULT_WAIT_FOR_PIECE_FINISHED_INTERVAL is the default interval for waiting for the piece to be finished.
 pub const DEFAULT_WAIT_FOR_PIECE_FINISHED_INTERVAL: Duration = Duration::from_millis(100);
 
+/// WrittenPiece is the piece written to the content or the block store.
+enum WrittenPiece {
+    /// Content is the piece written to the content of the task.
+    Content(content::WritePieceResponse),
+
+    /// Block is the piece written to the temporary file of the block store, the temporary
+    /// file is committed when the piece is finished.
+    Block(content::WriteBlockResponse),
+}
+
+/// WrittenPiece implements the written piece.
+impl WrittenPiece {
+    /// length returns the length of the piece.
+    fn length(&self) -> u64 {
+        match self {
+            WrittenPiece::Content(response) => response.length,
+            WrittenPiece::Block(response) => response.length,
+        }
+    }
+
+    /// hash returns the crc32 castagnoli hash of the piece.
+    fn hash(&self) -> String {
+        match self {
+            WrittenPiece::Content(response) => response.hash.clone(),
+            WrittenPiece::Block(response) => response.hash.clone(),
+        }
+    }
+}
+
 /// Storage is the storage of the task.
 pub struct Storage {
     /// config is the configuration of the dfdaemon.
@@ -44,6 +82,13 @@ pub struct Storage {
 
     /// content implements the content storage.
     content: content::Content,
+
+    /// block_mutex guarantees the block content and the reference count of the block
+    /// metadata are updated consistently.
+    block_mutex: Mutex<()>,
+
+    /// cache is the memory cache of the hot pieces.
+    cache: cache::Cache,
 }
 
 /// Storage implements the storage.
@@ -53,10 +98,26 @@ impl Storage {
     pub async fn new(config: Arc<Config>, dir: &Path, log_dir: PathBuf) -> Result<Self> {
         let metadata = metadata::Metadata::new(config.clone(), dir, &log_dir)?;
         let content = content::Content::new(config.clone(), dir).await?;
+
+        // The blocks committed to the block store by the interrupted writes are not referenced
+        // by any piece, so recount the references of the blocks and delete the unreferenced ones.
+        let referenced_block_digests = metadata.recount_blocks()?;
+        for digest in content.get_block_digests().await? {
+            if !referenced_block_digests.contains(&digest) {
+                content
+                    .delete_block(digest.as_str())
+                    .await
+                    .unwrap_or_else(|err| error!("delete block failed: {}", err));
+            }
+        }
+
+        let cache = cache::Cache::new(config.storage.memory_cache_capacity.as_u64());
         Ok(Storage {
             config,
             metadata,
             content,
+            block_mutex: Mutex::new(()),
+            cache,
         })
     }
 
@@ -83,10 +144,26 @@ impl Storage {
         to: &Path,
         range: Option<Range>,
     ) -> Result<()> {
+        // The content of the task can not be hard linked if the pieces are compressed
+        // or deduplicated, so copy the pieces to the destination. It is decided by the
+        // stored pieces instead of the current config, because the deduplication may be
+        // disabled after the pieces are written to the block store.
+        let pieces = self.metadata.get_pieces(task.id.as_str())?;
+        if pieces
+            .iter()
+            .any(|piece| piece.compressed_length.is_some() || piece.block_digest.is_some())
+        {
+            return self
+                .content
+                .copy_task_by_pieces(task.id.as_str(), pieces, to, range)
+                .await;
+        }
+
         self.content.hard_link_or_copy_task(task, to, range).await
     }
 
     /// download_task_started updates the metadata of the task when the task downloads started.
+    #[allow(clippy::too_many_arguments)]
     #[instrument(skip_all)]
     pub fn download_task_started(
         &self,
@@ -94,9 +171,19 @@ impl Storage {
         piece_length: Option<u64>,
         content_length: Option<u64>,
         response_header: Option<HeaderMap>,
+        download_request: Option<metadata::DownloadRequest>,
+        compressed: bool,
+        bandwidth_class: Option<String>,
     ) -> Result<metadata::Task> {
-        self.metadata
-            .download_task_started(id, piece_length, content_length, response_header)
+        self.metadata.download_task_started(
+            id,
+            piece_length,
+            content_length,
+            response_header,
+            download_request,
+            compressed,
+            bandwidth_class,
+        )
     }
 
     /// download_task_finished updates the metadata of the task when the task downloads finished.
@@ -147,17 +234,33 @@ impl Storage {
         self.metadata.get_tasks()
     }
 
-    /// delete_task deletes the task metadatas, task content and piece metadatas.
+    /// delete_task deletes the task metadatas, task content and piece metadatas. The blocks
+    /// referenced by the pieces are released, and they are deleted if no longer referenced.
     #[instrument(skip_all)]
     pub async fn delete_task(&self, id: &str) {
         self.metadata
             .delete_task(id)
             .unwrap_or_else(|err| error!("delete task metadata failed: {}", err));
 
-        self.metadata.delete_pieces(id).unwrap_or_else(|err| {
-            error!("delete piece metadatas failed: {}", err);
-        });
+        // The pieces and the references of the blocks are deleted in one batch, and the
+        // unreferenced blocks are deleted under the lock, otherwise the block may be referenced
+        // by the new piece before it is deleted.
+        {
+            let _guard = self.block_mutex.lock().await;
+            match self.metadata.delete_pieces(id) {
+                Ok(unreferenced_block_digests) => {
+                    for digest in unreferenced_block_digests {
+                        self.content
+                            .delete_block(digest.as_str())
+                            .await
+                            .unwrap_or_else(|err| error!("delete block failed: {}", err));
+                    }
+                }
+                Err(err) => error!("delete piece metadatas failed: {}", err),
+            }
+        }
 
+        self.cache.remove_task_pieces(id);
         self.content.delete_task(id).await.unwrap_or_else(|err| {
             error!("delete task content failed: {}", err);
         });
@@ -183,9 +286,17 @@ impl Storage {
         ttl: Duration,
         piece_length: u64,
         content_length: u64,
+        digest: Option<String>,
+        bandwidth_class: Option<String>,
     ) -> Result<metadata::PersistentCacheTask> {
-        self.metadata
-            .create_persistent_cache_task_started(id, ttl, piece_length, content_length)
+        self.metadata.create_persistent_cache_task_started(
+            id,
+            ttl,
+            piece_length,
+            content_length,
+            digest,
+            bandwidth_class,
+        )
     }
 
     /// create_persistent_cache_task_finished updates the metadata of the persistent cache task
@@ -206,7 +317,9 @@ impl Storage {
     }
 
     /// download_persistent_cache_task_started updates the metadata of the persistent cache task
-    /// when the persistent cache task downloads started.
+    /// when the persistent cache task downloads started, the digest is recorded if the id of
+    /// the persistent cache task is generated by the content digest.
+    #[allow(clippy::too_many_arguments)]
     #[instrument(skip_all)]
     pub fn download_persistent_cache_task_started(
         &self,
@@ -216,6 +329,8 @@ impl Storage {
         piece_length: u64,
         content_length: u64,
         created_at: NaiveDateTime,
+        digest: Option<String>,
+        bandwidth_class: Option<String>,
     ) -> Result<metadata::PersistentCacheTask> {
         self.metadata.download_persistent_cache_task_started(
             id,
@@ -224,6 +339,8 @@ impl Storage {
             piece_length,
             content_length,
             created_at,
+            digest,
+            bandwidth_class,
         )
     }
 
@@ -290,9 +407,9 @@ impl Storage {
                 error!("delete persistent cache task metadata failed: {}", err);
             });
 
-        self.metadata.delete_pieces(id).unwrap_or_else(|err| {
+        if let Err(err) = self.metadata.delete_pieces(id) {
             error!("delete persistent cache piece metadatas failed: {}", err);
-        });
+        }
 
         self.content
             .delete_persistent_cache_task(id)
@@ -354,19 +471,18 @@ impl Storage {
         length: u64,
         reader: &mut R,
     ) -> Result<metadata::Piece> {
-        let response = self
-            .content
-            .write_piece_with_crc32_castagnoli(task_id, offset, reader)
-            .await?;
-        let digest = Digest::new(Algorithm::Crc32, response.hash);
+        let response = self.write_piece(task_id, offset, reader).await?;
+        let digest = Digest::new(Algorithm::Crc32, response.hash());
 
-        self.metadata.download_piece_finished(
+        self.finish_piece(
             piece_id,
             offset,
             length,
             digest.to_string().as_str(),
             None,
+            response,
         )
+        .await
     }
 
     /// download_piece_from_parent_finished is used for downloading piece from parent.
@@ -380,37 +496,326 @@ impl Storage {
         parent_id: &str,
         reader: &mut R,
     ) -> Result<metadata::Piece> {
-        let response = self
-            .content
-            .write_piece_with_crc32_castagnoli(task_id, offset, reader)
-            .await?;
+        // Collect the content of the piece while writing if the memory cache is enabled,
+        // the hot pieces downloaded from the parent are likely to be uploaded to others.
+        let mut content = Vec::new();
+        let response = if self.cache.is_enabled() {
+            let mut reader = InspectReader::new(reader, |bytes: &[u8]| {
+                content.extend_from_slice(bytes);
+            });
 
-        let length = response.length;
-        let digest = Digest::new(Algorithm::Crc32, response.hash);
+            self.write_piece(task_id, offset, &mut reader).await?
+        } else {
+            self.write_piece(task_id, offset, reader).await?
+        };
 
-        // Check the digest of the piece.
+        let length = response.length();
+        let digest = Digest::new(Algorithm::Crc32, response.hash());
+
+        // Check the digest of the piece, the temporary file of the block written by the
+        // mismatched piece needs to be removed.
         if expected_digest != digest.to_string() {
+            if let WrittenPiece::Block(response) = response {
+                self.content
+                    .remove_block_temp(response.temp_path.as_path())
+                    .await
+                    .unwrap_or_else(|err| warn!("remove block temp failed: {}", err));
+            }
+
             return Err(Error::DigestMismatch(
                 expected_digest.to_string(),
                 digest.to_string(),
             ));
         }
 
-        self.metadata.download_piece_finished(
+        let piece = self
+            .finish_piece(
+                piece_id,
+                offset,
+                length,
+                digest.to_string().as_str(),
+                Some(parent_id.to_string()),
+                response,
+            )
+            .await?;
+
+        if content.len() as u64 == length {
+            self.cache.add_piece(piece_id, content.into());
+        }
+
+        Ok(piece)
+    }
+
+    /// write_piece writes the piece to the content, the piece is compressed if the
+    /// task is compressed, and it is written to the temporary file of the block store if
+    /// the deduplication is enabled.
+    #[instrument(skip_all)]
+    async fn write_piece<R: AsyncRead + Unpin + ?Sized>(
+        &self,
+        task_id: &str,
+        offset: u64,
+        reader: &mut R,
+    ) -> Result<WrittenPiece> {
+        let compressed = self
+            .metadata
+            .get_task(task_id)?
+            .is_some_and(|task| task.is_compressed());
+
+        if self.config.storage.deduplication.enable {
+            return Ok(WrittenPiece::Block(
+                self.content
+                    .write_block_with_crc32_castagnoli(task_id, offset, reader, compressed)
+                    .await?,
+            ));
+        }
+
+        if compressed {
+            return Ok(WrittenPiece::Content(
+                self.content
+                    .write_compressed_piece_with_crc32_castagnoli(task_id, offset, reader)
+                    .await?,
+            ));
+        }
+
+        Ok(WrittenPiece::Content(
+            self.content
+                .write_piece_with_crc32_castagnoli(task_id, offset, reader)
+                .await?,
+        ))
+    }
+
+    /// finish_piece updates the metadata of the finished piece. If the piece is written to
+    /// the block store, the temporary file of the block is committed if the identical block
+    /// does not exist, and the block is referenced with the piece metadata in one batch.
+    #[instrument(skip_all)]
+    async fn finish_piece(
+        &self,
+        piece_id: &str,
+        offset: u64,
+        length: u64,
+        digest: &str,
+        parent_id: Option<String>,
+        response: WrittenPiece,
+    ) -> Result<metadata::Piece> {
+        let response = match response {
+            WrittenPiece::Content(response) => {
+                return self.metadata.download_piece_finished(
+                    piece_id,
+                    offset,
+                    length,
+                    digest,
+                    parent_id,
+                    response.compressed_length,
+                    None,
+                );
+            }
+            WrittenPiece::Block(response) => response,
+        };
+
+        // The block content and the block metadata are updated under the lock, otherwise the
+        // block may be deleted by the released pieces between the commit and the reference.
+        let _guard = self.block_mutex.lock().await;
+        let old_block_digest = self
+            .metadata
+            .get_piece(piece_id)?
+            .and_then(|piece| piece.block_digest);
+
+        let compressed_length = match self.metadata.get_block(response.digest.as_str())? {
+            Some(block) => {
+                debug!("block {} is deduplicated", block.digest);
+                self.content
+                    .remove_block_temp(response.temp_path.as_path())
+                    .await
+                    .unwrap_or_else(|err| warn!("remove block temp failed: {}", err));
+                block.compressed_length
+            }
+            None => {
+                if let Err(err) = self
+                    .content
+                    .commit_block(response.temp_path.as_path(), response.digest.as_str())
+                    .await
+                {
+                    self.content
+                        .remove_block_temp(response.temp_path.as_path())
+                        .await
+                        .unwrap_or_else(|err| warn!("remove block temp failed: {}", err));
+                    return Err(err);
+                }
+
+                response.compressed_length
+            }
+        };
+
+        let piece = match self.metadata.download_piece_finished(
             piece_id,
             offset,
             length,
-            digest.to_string().as_str(),
-            Some(parent_id.to_string()),
-        )
+            digest,
+            parent_id,
+            compressed_length,
+            Some(response.digest.clone()),
+        ) {
+            Ok(piece) => piece,
+            Err(err) => {
+                self.delete_unreferenced_block(response.digest.as_str())
+                    .await;
+                return Err(err);
+            }
+        };
+
+        // The old block of the overwritten piece is released with the piece metadata, so delete
+        // it if it is no longer referenced.
+        if let Some(old_block_digest) = old_block_digest {
+            if old_block_digest != response.digest {
+                self.delete_unreferenced_block(old_block_digest.as_str())
+                    .await;
+            }
+        }
+
+        Ok(piece)
+    }
+
+    /// delete_unreferenced_block deletes the block content if the block metadata does not
+    /// exist, it needs to be called under the lock of the block.
+    #[instrument(skip_all)]
+    async fn delete_unreferenced_block(&self, digest: &str) {
+        match self.metadata.get_block(digest) {
+            Ok(Some(_)) => {}
+            Ok(None) => self
+                .content
+                .delete_block(digest)
+                .await
+                .unwrap_or_else(|err| error!("delete block failed: {}", err)),
+            Err(err) => error!("get block metadata failed: {}", err),
+        }
+    }
+
+    /// get_task_stored_length returns the length of the task stored in the content, the
+    /// compressed pieces are counted by the compressed length, and the blocks referenced by
+    /// the pieces of other tasks are not counted, because they will not be freed when the
+    /// task is deleted. It is decided by the stored pieces instead of the current config.
+    #[instrument(skip_all)]
+    pub fn get_task_stored_length(&self, task: &metadata::Task) -> Result<u64> {
+        let mut length = 0;
+        let mut block_references: HashMap<String, (u64, u64)> = HashMap::new();
+        for piece in self.metadata.get_pieces(task.id.as_str())? {
+            let stored_length = piece.stored_length();
+            match piece.block_digest {
+                Some(digest) => {
+                    block_references
+                        .entry(digest)
+                        .or_insert((0, stored_length))
+                        .0 += 1
+                }
+                None => length += stored_length,
+            }
+        }
+
+        for (digest, (reference_count, stored_length)) in block_references {
+            if self
+                .metadata
+                .get_block(digest.as_str())?
+                .is_some_and(|block| block.reference_count <= reference_count)
+            {
+                length += stored_length;
+            }
+        }
+
+        Ok(length)
     }
 
     /// download_piece_failed updates the metadata of the piece when the piece downloads failed.
     #[instrument(skip_all)]
     pub fn download_piece_failed(&self, piece_id: &str) -> Result<()> {
+        self.cache.remove_piece(piece_id);
         self.metadata.download_piece_failed(piece_id)
     }
 
+    /// read_piece returns the reader of the piece, the piece is served from the memory
+    /// cache if it is hot, otherwise it is read from the content.
+    #[instrument(skip_all)]
+    async fn read_piece(
+        &self,
+        task_id: &str,
+        piece: &metadata::Piece,
+        range: Option<Range>,
+    ) -> Result<impl AsyncRead> {
+        if let Some(content) = self.get_cached_piece(task_id, piece).await? {
+            let (target_offset, target_length) =
+                content::calculate_piece_range(piece.offset, piece.length, range);
+
+            return Ok(Either::Left(content::slice_piece(
+                content,
+                target_offset - piece.offset,
+                target_length,
+            )));
+        }
+
+        Ok(Either::Right(
+            self.content.read_piece(task_id, piece, range).await?,
+        ))
+    }
+
+    /// read_piece_with_dual_read returns the range reader and the full reader of the piece,
+    /// the piece is served from the memory cache if it is hot, otherwise it is read from the content.
+    #[instrument(skip_all)]
+    async fn read_piece_with_dual_read(
+        &self,
+        task_id: &str,
+        piece: &metadata::Piece,
+        range: Option<Range>,
+    ) -> Result<(impl AsyncRead, impl AsyncRead)> {
+        if let Some(content) = self.get_cached_piece(task_id, piece).await? {
+            let (target_offset, target_length) =
+                content::calculate_piece_range(piece.offset, piece.length, range);
+
+            return Ok((
+                Either::Left(content::slice_piece(
+                    content.clone(),
+                    target_offset - piece.offset,
+                    target_length,
+                )),
+                Either::Left(content::slice_piece(content, 0, piece.length)),
+            ));
+        }
+
+        let (range_reader, reader) = self
+            .content
+            .read_piece_with_dual_read(task_id, piece, range)
+            .await?;
+        Ok((Either::Right(range_reader), Either::Right(reader)))
+    }
+
+    /// get_cached_piece returns the content of the piece from the memory cache. If the piece
+    /// is missed, the full piece is read from the content and added to the memory cache. It
+    /// returns none if the piece can not be cached.
+    #[instrument(skip_all)]
+    async fn get_cached_piece(
+        &self,
+        task_id: &str,
+        piece: &metadata::Piece,
+    ) -> Result<Option<Bytes>> {
+        if !self.cache.is_cacheable(piece.length) {
+            return Ok(None);
+        }
+
+        let piece_id = self.metadata.piece_id(task_id, piece.number);
+        if let Some(content) = self.cache.get_piece(piece_id.as_str()) {
+            debug!("hit piece {} in memory cache", piece_id);
+            return Ok(Some(content));
+        }
+
+        let mut reader = self.content.read_piece(task_id, piece, None).await?;
+        let mut content = Vec::with_capacity(piece.length as usize);
+        reader.read_to_end(&mut content).await.inspect_err(|err| {
+            error!("read piece {} failed: {}", piece_id, err);
+        })?;
+
+        let content = Bytes::from(content);
+        self.cache.add_piece(piece_id.as_str(), content.clone());
+        Ok(Some(content))
+    }
+
     /// upload_piece updates the metadata of the piece and
     /// returns the data of the piece.
     #[instrument(skip_all)]
@@ -429,11 +834,7 @@ impl Storage {
         // Get the piece metadata and return the content of the piece.
         match self.metadata.get_piece(piece_id) {
             Ok(Some(piece)) => {
-                match self
-                    .content
-                    .read_piece(task_id, piece.offset, piece.length, range)
-                    .await
-                {
+                match self.read_piece(task_id, &piece, range).await {
                     Ok(reader) => {
                         // Finish uploading the task.
                         self.metadata.upload_task_finished(task_id)?;
@@ -477,11 +878,7 @@ impl Storage {
         // Get the piece metadata and return the content of the piece.
         match self.metadata.get_piece(piece_id) {
             Ok(Some(piece)) => {
-                match self
-                    .content
-                    .read_piece_with_dual_read(task_id, piece.offset, piece.length, range)
-                    .await
-                {
+                match self.read_piece_with_dual_read(task_id, &piece, range).await {
                     Ok(dual_reader) => {
                         // Finish uploading the task.
                         self.metadata.upload_task_finished(task_id)?;
@@ -585,6 +982,8 @@ impl Storage {
             length,
             digest.to_string().as_str(),
             Some(parent_id.to_string()),
+            None,
+            None,
         )
     }
 
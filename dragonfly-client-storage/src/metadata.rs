@@ -16,19 +16,83 @@
 
 use chrono::{NaiveDateTime, Utc};
 use dragonfly_client_config::dfdaemon::Config;
-use dragonfly_client_core::{Error, Result};
+use dragonfly_client_core::{
+    error::{ErrorType, OrErr},
+    Error, Result,
+};
 use dragonfly_client_util::http::headermap_to_hashmap;
 use rayon::prelude::*;
 use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::{error, info, instrument};
+use tracing::{error, info, instrument, warn};
 
-use crate::storage_engine::{rocksdb::RocksdbStorageEngine, DatabaseObject, StorageEngineOwned};
+use crate::storage_engine::{
+    rocksdb::RocksdbStorageEngine, DatabaseObject, StorageEngineOwned, WriteBatch,
+};
+
+/// DownloadRequest is the persisted download request of the task, it is used to
+/// resume the unfinished task when the dfdaemon restarts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadRequest {
+    /// url is the download url of the task.
+    pub url: String,
+
+    /// digest is the digest of the task.
+    pub digest: Option<String>,
+
+    /// range is the range of the download request, the first element is the start
+    /// and the second element is the length.
+    pub range: Option<(u64, u64)>,
+
+    /// r#type is the type of the task.
+    pub r#type: i32,
+
+    /// tag is the tag of the task.
+    pub tag: Option<String>,
+
+    /// application is the application of the task.
+    pub application: Option<String>,
+
+    /// priority is the priority of the peer.
+    pub priority: i32,
+
+    /// filtered_query_params is the filtered query params to generate the task id.
+    pub filtered_query_params: Vec<String>,
+
+    /// request_header is the header of the download request, the sensitive
+    /// headers are removed before persisted.
+    pub request_header: HashMap<String, String>,
+
+    /// piece_length is the piece length of the download request.
+    pub piece_length: Option<u64>,
+
+    /// disable_back_to_source indicates whether the task can not download from the source.
+    pub disable_back_to_source: bool,
+
+    /// need_back_to_source indicates whether the task needs to download from the source.
+    pub need_back_to_source: bool,
+
+    /// is_prefetch indicates whether the download request is a prefetch request.
+    pub is_prefetch: bool,
+}
+
+/// DownloadRequest implements the persisted download request.
+impl DownloadRequest {
+    /// is_range_request returns whether the download request only downloads a range of the task,
+    /// the range is set by the range field or the range header.
+    pub fn is_range_request(&self) -> bool {
+        self.range.is_some()
+            || self
+                .request_header
+                .keys()
+                .any(|key| key.eq_ignore_ascii_case(reqwest::header::RANGE.as_str()))
+    }
+}
 
 /// Task is the metadata of the task.
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -66,12 +130,117 @@ pub struct Task {
 
     /// finished_at is the time when the task downloads finished.
     pub finished_at: Option<NaiveDateTime>,
+
+    /// download_request is the persisted download request of the task.
+    pub download_request: Option<DownloadRequest>,
+
+    /// compressed indicates whether the pieces of the task are compressed by zstd in the content.
+    pub compressed: bool,
+
+    /// bandwidth_class is the name of the bandwidth class which limits the traffic of the task.
+    pub bandwidth_class: Option<String>,
 }
 
 /// Task implements the task database object.
 impl DatabaseObject for Task {
     /// NAMESPACE is the namespace of [Task] objects.
     const NAMESPACE: &'static str = "task";
+
+    /// deserialize_from deserializes the task from bytes. Bincode encodes the fields by
+    /// position, so the new fields are only appended to the end of [Task], and the task
+    /// stored by the legacy dfdaemon is decoded by the [UnclassifiedTask] or [LegacyTask]
+    /// layout.
+    fn deserialize_from(bytes: &[u8]) -> Result<Self> {
+        if let Ok(task) = bincode::deserialize::<Self>(bytes) {
+            return Ok(task);
+        }
+
+        if let Ok(task) = bincode::deserialize::<UnclassifiedTask>(bytes) {
+            return Ok(task.into());
+        }
+
+        Ok(bincode::deserialize::<LegacyTask>(bytes)
+            .or_err(ErrorType::SerializeError)?
+            .into())
+    }
+}
+
+/// UnclassifiedTask is the layout of the task metadata stored by the dfdaemon without the
+/// bandwidth class of [Task]. It must not be changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct UnclassifiedTask {
+    id: String,
+    piece_length: Option<u64>,
+    content_length: Option<u64>,
+    response_header: HashMap<String, String>,
+    uploading_count: i64,
+    uploaded_count: u64,
+    updated_at: NaiveDateTime,
+    created_at: NaiveDateTime,
+    prefetched_at: Option<NaiveDateTime>,
+    failed_at: Option<NaiveDateTime>,
+    finished_at: Option<NaiveDateTime>,
+    download_request: Option<DownloadRequest>,
+    compressed: bool,
+}
+
+/// UnclassifiedTask implements the conversion to the task metadata.
+impl From<UnclassifiedTask> for Task {
+    fn from(task: UnclassifiedTask) -> Self {
+        Task {
+            id: task.id,
+            piece_length: task.piece_length,
+            content_length: task.content_length,
+            response_header: task.response_header,
+            uploading_count: task.uploading_count,
+            uploaded_count: task.uploaded_count,
+            updated_at: task.updated_at,
+            created_at: task.created_at,
+            prefetched_at: task.prefetched_at,
+            failed_at: task.failed_at,
+            finished_at: task.finished_at,
+            download_request: task.download_request,
+            compressed: task.compressed,
+            bandwidth_class: None,
+        }
+    }
+}
+
+/// LegacyTask is the layout of the task metadata stored by the legacy dfdaemon, which has
+/// no trailing fields of [Task]. It must not be changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct LegacyTask {
+    id: String,
+    piece_length: Option<u64>,
+    content_length: Option<u64>,
+    response_header: HashMap<String, String>,
+    uploading_count: i64,
+    uploaded_count: u64,
+    updated_at: NaiveDateTime,
+    created_at: NaiveDateTime,
+    prefetched_at: Option<NaiveDateTime>,
+    failed_at: Option<NaiveDateTime>,
+    finished_at: Option<NaiveDateTime>,
+}
+
+/// LegacyTask implements the conversion to the task metadata.
+impl From<LegacyTask> for Task {
+    fn from(task: LegacyTask) -> Self {
+        Task {
+            id: task.id,
+            piece_length: task.piece_length,
+            content_length: task.content_length,
+            response_header: task.response_header,
+            uploading_count: task.uploading_count,
+            uploaded_count: task.uploaded_count,
+            updated_at: task.updated_at,
+            created_at: task.created_at,
+            prefetched_at: task.prefetched_at,
+            failed_at: task.failed_at,
+            finished_at: task.finished_at,
+            ..Default::default()
+        }
+    }
 }
 
 /// Task implements the task metadata.
@@ -106,6 +275,16 @@ impl Task {
         self.finished_at.is_some()
     }
 
+    /// is_resumable returns whether the task can be resumed by the persisted download request.
+    pub fn is_resumable(&self) -> bool {
+        !self.is_finished() && !self.is_failed() && self.download_request.is_some()
+    }
+
+    /// is_compressed returns whether the pieces of the task are compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
     /// is_empty returns whether the task is empty.
     pub fn is_empty(&self) -> bool {
         match self.content_length() {
@@ -163,12 +342,114 @@ pub struct PersistentCacheTask {
 
     /// finished_at is the time when the task downloads finished.
     pub finished_at: Option<NaiveDateTime>,
+
+    /// digest is the content digest of the persistent cache task, e.g. sha256:xxx. It is set
+    /// when the id of the persistent cache task is generated by the content digest.
+    pub digest: Option<String>,
+
+    /// bandwidth_class is the name of the bandwidth class which limits the traffic of the
+    /// persistent cache task.
+    pub bandwidth_class: Option<String>,
 }
 
 /// PersistentCacheTask implements the persistent cache task database object.
 impl DatabaseObject for PersistentCacheTask {
     /// NAMESPACE is the namespace of [PersistentCacheTask] objects.
     const NAMESPACE: &'static str = "persistent_cache_task";
+
+    /// deserialize_from deserializes the persistent cache task from bytes, the persistent
+    /// cache task stored by the legacy dfdaemon is decoded by the
+    /// [UnclassifiedPersistentCacheTask] or [LegacyPersistentCacheTask] layout.
+    fn deserialize_from(bytes: &[u8]) -> Result<Self> {
+        if let Ok(task) = bincode::deserialize::<Self>(bytes) {
+            return Ok(task);
+        }
+
+        if let Ok(task) = bincode::deserialize::<UnclassifiedPersistentCacheTask>(bytes) {
+            return Ok(task.into());
+        }
+
+        Ok(bincode::deserialize::<LegacyPersistentCacheTask>(bytes)
+            .or_err(ErrorType::SerializeError)?
+            .into())
+    }
+}
+
+/// UnclassifiedPersistentCacheTask is the layout of the persistent cache task metadata stored
+/// by the dfdaemon without the bandwidth class of [PersistentCacheTask]. It must not be changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct UnclassifiedPersistentCacheTask {
+    id: String,
+    persistent: bool,
+    ttl: Duration,
+    piece_length: u64,
+    content_length: u64,
+    uploading_count: u64,
+    uploaded_count: u64,
+    updated_at: NaiveDateTime,
+    created_at: NaiveDateTime,
+    failed_at: Option<NaiveDateTime>,
+    finished_at: Option<NaiveDateTime>,
+    digest: Option<String>,
+}
+
+/// UnclassifiedPersistentCacheTask implements the conversion to the persistent cache task
+/// metadata.
+impl From<UnclassifiedPersistentCacheTask> for PersistentCacheTask {
+    fn from(task: UnclassifiedPersistentCacheTask) -> Self {
+        PersistentCacheTask {
+            id: task.id,
+            persistent: task.persistent,
+            ttl: task.ttl,
+            piece_length: task.piece_length,
+            content_length: task.content_length,
+            uploading_count: task.uploading_count,
+            uploaded_count: task.uploaded_count,
+            updated_at: task.updated_at,
+            created_at: task.created_at,
+            failed_at: task.failed_at,
+            finished_at: task.finished_at,
+            digest: task.digest,
+            bandwidth_class: None,
+        }
+    }
+}
+
+/// LegacyPersistentCacheTask is the layout of the persistent cache task metadata stored by
+/// the legacy dfdaemon, which has no digest. It must not be changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct LegacyPersistentCacheTask {
+    id: String,
+    persistent: bool,
+    ttl: Duration,
+    piece_length: u64,
+    content_length: u64,
+    uploading_count: u64,
+    uploaded_count: u64,
+    updated_at: NaiveDateTime,
+    created_at: NaiveDateTime,
+    failed_at: Option<NaiveDateTime>,
+    finished_at: Option<NaiveDateTime>,
+}
+
+/// LegacyPersistentCacheTask implements the conversion to the persistent cache task metadata.
+impl From<LegacyPersistentCacheTask> for PersistentCacheTask {
+    fn from(task: LegacyPersistentCacheTask) -> Self {
+        PersistentCacheTask {
+            id: task.id,
+            persistent: task.persistent,
+            ttl: task.ttl,
+            piece_length: task.piece_length,
+            content_length: task.content_length,
+            uploading_count: task.uploading_count,
+            uploaded_count: task.uploaded_count,
+            updated_at: task.updated_at,
+            created_at: task.created_at,
+            failed_at: task.failed_at,
+            finished_at: task.finished_at,
+            ..Default::default()
+        }
+    }
 }
 
 /// PersistentCacheTask implements the persistent cache task metadata.
@@ -251,12 +532,67 @@ pub struct Piece {
 
     /// finished_at is the time when the piece downloads finished.
     pub finished_at: Option<NaiveDateTime>,
+
+    /// compressed_length is the length of the compressed piece in the content. It is none
+    /// if the piece is stored raw.
+    pub compressed_length: Option<u64>,
+
+    /// block_digest is the digest of the block storing the piece content when the piece
+    /// is deduplicated. It is none if the piece is stored in the task content.
+    pub block_digest: Option<String>,
 }
 
 /// Piece implements the piece database object.
 impl DatabaseObject for Piece {
     /// NAMESPACE is the namespace of [Piece] objects.
     const NAMESPACE: &'static str = "piece";
+
+    /// deserialize_from deserializes the piece from bytes. Bincode encodes the fields by
+    /// position, so the new fields are only appended to the end of [Piece], and the piece
+    /// stored by the legacy dfdaemon is decoded by the [LegacyPiece] layout.
+    fn deserialize_from(bytes: &[u8]) -> Result<Self> {
+        match bincode::deserialize::<Self>(bytes) {
+            Ok(piece) => Ok(piece),
+            Err(_) => Ok(bincode::deserialize::<LegacyPiece>(bytes)
+                .or_err(ErrorType::SerializeError)?
+                .into()),
+        }
+    }
+}
+
+/// LegacyPiece is the layout of the piece metadata stored by the legacy dfdaemon, which has
+/// no trailing fields of [Piece]. It must not be changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct LegacyPiece {
+    number: u32,
+    offset: u64,
+    length: u64,
+    digest: String,
+    parent_id: Option<String>,
+    uploading_count: i64,
+    uploaded_count: u64,
+    updated_at: NaiveDateTime,
+    created_at: NaiveDateTime,
+    finished_at: Option<NaiveDateTime>,
+}
+
+/// LegacyPiece implements the conversion to the piece metadata.
+impl From<LegacyPiece> for Piece {
+    fn from(piece: LegacyPiece) -> Self {
+        Piece {
+            number: piece.number,
+            offset: piece.offset,
+            length: piece.length,
+            digest: piece.digest,
+            parent_id: piece.parent_id,
+            uploading_count: piece.uploading_count,
+            uploaded_count: piece.uploaded_count,
+            updated_at: piece.updated_at,
+            created_at: piece.created_at,
+            finished_at: piece.finished_at,
+            ..Default::default()
+        }
+    }
 }
 
 /// Piece implements the piece metadata.
@@ -271,6 +607,11 @@ impl Piece {
         self.finished_at.is_some()
     }
 
+    /// stored_length returns the length of the piece stored in the content.
+    pub fn stored_length(&self) -> u64 {
+        self.compressed_length.unwrap_or(self.length)
+    }
+
     /// cost returns the cost of the piece downloaded.
     pub fn cost(&self) -> Option<Duration> {
         match self
@@ -303,7 +644,45 @@ impl Piece {
     }
 }
 
-/// Metadata manages the metadata of [Task], [Piece] and [PersistentCacheTask].
+/// Block is the metadata of the deduplicated piece content, the pieces with the identical
+/// content across the tasks share the same block.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Block {
+    /// digest is the sha256 digest of the block content.
+    pub digest: String,
+
+    /// length is the length of the block content.
+    pub length: u64,
+
+    /// compressed_length is the length of the compressed block in the content. It is none
+    /// if the block is stored raw.
+    pub compressed_length: Option<u64>,
+
+    /// reference_count is the count of the pieces referencing the block.
+    pub reference_count: u64,
+
+    /// updated_at is the time when the block metadata is updated.
+    pub updated_at: NaiveDateTime,
+
+    /// created_at is the time when the block metadata is created.
+    pub created_at: NaiveDateTime,
+}
+
+/// Block implements the block database object.
+impl DatabaseObject for Block {
+    /// NAMESPACE is the namespace of [Block] objects.
+    const NAMESPACE: &'static str = "block";
+}
+
+/// Block implements the block metadata.
+impl Block {
+    /// is_shared returns whether the block is referenced by more than one piece.
+    pub fn is_shared(&self) -> bool {
+        self.reference_count > 1
+    }
+}
+
+/// Metadata manages the metadata of [Task], [Piece], [PersistentCacheTask] and [Block].
 pub struct Metadata<E = RocksdbStorageEngine>
 where
     E: StorageEngineOwned,
@@ -314,6 +693,7 @@ where
 
 impl<E: StorageEngineOwned> Metadata<E> {
     /// download_task_started updates the metadata of the task when the task downloads started.
+    #[allow(clippy::too_many_arguments)]
     #[instrument(skip_all)]
     pub fn download_task_started(
         &self,
@@ -321,6 +701,9 @@ impl<E: StorageEngineOwned> Metadata<E> {
         piece_length: Option<u64>,
         content_length: Option<u64>,
         response_header: Option<HeaderMap>,
+        download_request: Option<DownloadRequest>,
+        compressed: bool,
+        bandwidth_class: Option<String>,
     ) -> Result<Task> {
         // Convert the response header to hashmap.
         let response_header = response_header
@@ -344,12 +727,25 @@ impl<E: StorageEngineOwned> Metadata<E> {
                     task.piece_length = piece_length;
                 }
 
+                // Protect bandwidth class to be overwritten by None.
+                if bandwidth_class.is_some() {
+                    task.bandwidth_class = bandwidth_class;
+                }
+
                 // If the task has the response header, the response header
                 // will not be covered.
                 if task.response_header.is_empty() {
                     task.response_header = response_header;
                 }
 
+                // Protect the download request of the full task to be overwritten
+                // by the range request.
+                if let Some(download_request) = download_request {
+                    if task.download_request.is_none() || !download_request.is_range_request() {
+                        task.download_request = Some(download_request);
+                    }
+                }
+
                 task
             }
             None => Task {
@@ -357,6 +753,9 @@ impl<E: StorageEngineOwned> Metadata<E> {
                 piece_length,
                 content_length,
                 response_header,
+                download_request,
+                compressed,
+                bandwidth_class,
                 updated_at: Utc::now().naive_utc(),
                 created_at: Utc::now().naive_utc(),
                 ..Default::default()
@@ -533,6 +932,8 @@ impl<E: StorageEngineOwned> Metadata<E> {
         ttl: Duration,
         piece_length: u64,
         content_length: u64,
+        digest: Option<String>,
+        bandwidth_class: Option<String>,
     ) -> Result<PersistentCacheTask> {
         let task = PersistentCacheTask {
             id: id.to_string(),
@@ -540,6 +941,8 @@ impl<E: StorageEngineOwned> Metadata<E> {
             ttl,
             piece_length,
             content_length,
+            digest,
+            bandwidth_class,
             updated_at: Utc::now().naive_utc(),
             created_at: Utc::now().naive_utc(),
             ..Default::default()
@@ -576,6 +979,7 @@ impl<E: StorageEngineOwned> Metadata<E> {
     /// the persistent cache task downloads started. If the persistent cache task downloaded by scheduler
     /// to create persistent cache task, the persistent should be set to true.
     #[instrument(skip_all)]
+    #[allow(clippy::too_many_arguments)]
     pub fn download_persistent_cache_task_started(
         &self,
         id: &str,
@@ -584,6 +988,8 @@ impl<E: StorageEngineOwned> Metadata<E> {
         piece_length: u64,
         content_length: u64,
         created_at: NaiveDateTime,
+        digest: Option<String>,
+        bandwidth_class: Option<String>,
     ) -> Result<PersistentCacheTask> {
         let task = match self.db.get::<PersistentCacheTask>(id.as_bytes())? {
             Some(mut task) => {
@@ -593,6 +999,14 @@ impl<E: StorageEngineOwned> Metadata<E> {
                 task.piece_length = piece_length;
                 task.updated_at = Utc::now().naive_utc();
                 task.failed_at = None;
+                if digest.is_some() {
+                    task.digest = digest;
+                }
+
+                if bandwidth_class.is_some() {
+                    task.bandwidth_class = bandwidth_class;
+                }
+
                 task
             }
             None => PersistentCacheTask {
@@ -603,6 +1017,8 @@ impl<E: StorageEngineOwned> Metadata<E> {
                 content_length,
                 updated_at: Utc::now().naive_utc(),
                 created_at,
+                digest,
+                bandwidth_class,
                 ..Default::default()
             },
         };
@@ -788,6 +1204,10 @@ impl<E: StorageEngineOwned> Metadata<E> {
     }
 
     /// download_piece_finished updates the metadata of the piece when the piece downloads finished.
+    /// If the piece references a different block, the new block is referenced and the old block
+    /// is released in the same batch as the piece, so the reference count of the block always
+    /// matches the pieces referencing it.
+    #[allow(clippy::too_many_arguments)]
     #[instrument(skip_all)]
     pub fn download_piece_finished(
         &self,
@@ -796,21 +1216,36 @@ impl<E: StorageEngineOwned> Metadata<E> {
         length: u64,
         digest: &str,
         parent_id: Option<String>,
+        compressed_length: Option<u64>,
+        block_digest: Option<String>,
     ) -> Result<Piece> {
-        let piece = match self.db.get::<Piece>(piece_id.as_bytes())? {
-            Some(mut piece) => {
-                piece.offset = offset;
-                piece.length = length;
-                piece.digest = digest.to_string();
-                piece.parent_id = parent_id;
-                piece.updated_at = Utc::now().naive_utc();
-                piece.finished_at = Some(Utc::now().naive_utc());
-                piece
-            }
+        let mut piece = match self.db.get::<Piece>(piece_id.as_bytes())? {
+            Some(piece) => piece,
             None => return Err(Error::PieceNotFound(piece_id.to_string())),
         };
 
-        self.db.put(piece_id.as_bytes(), &piece)?;
+        let mut batch = WriteBatch::default();
+        if piece.block_digest != block_digest {
+            if let Some(block_digest) = block_digest.as_deref() {
+                self.reference_block(&mut batch, block_digest, length, compressed_length)?;
+            }
+
+            if let Some(old_block_digest) = piece.block_digest.as_deref() {
+                self.release_block(&mut batch, old_block_digest, 1)?;
+            }
+        }
+
+        piece.offset = offset;
+        piece.length = length;
+        piece.digest = digest.to_string();
+        piece.parent_id = parent_id;
+        piece.compressed_length = compressed_length;
+        piece.block_digest = block_digest;
+        piece.updated_at = Utc::now().naive_utc();
+        piece.finished_at = Some(Utc::now().naive_utc());
+
+        batch.put(piece_id.as_bytes(), &piece)?;
+        self.db.write(batch)?;
         Ok(piece)
     }
 
@@ -862,33 +1297,40 @@ impl<E: StorageEngineOwned> Metadata<E> {
         self.db.delete::<Piece>(piece_id.as_bytes())
     }
 
-    /// delete_pieces deletes the piece metadatas.
+    /// delete_pieces deletes the piece metadatas, and releases the blocks referenced by the pieces
+    /// in the same batch. It returns the digests of the blocks which are no longer referenced.
     #[instrument(skip_all)]
-    pub fn delete_pieces(&self, task_id: &str) -> Result<()> {
-        let piece_ids = self
+    pub fn delete_pieces(&self, task_id: &str) -> Result<Vec<String>> {
+        let pieces = self
             .db
-            .prefix_iter_raw::<Piece>(task_id.as_bytes())?
-            .map(|ele| {
-                let (key, _) = ele?;
-                Ok(key)
-            })
-            .collect::<Result<Vec<Box<[u8]>>>>()?;
+            .prefix_iter::<Piece>(task_id.as_bytes())?
+            .collect::<Result<Vec<(Box<[u8]>, Piece)>>>()?;
+
+        // The identical pieces of the task reference the same block, so count the references
+        // of the block before releasing it.
+        let mut block_references: HashMap<&str, u64> = HashMap::new();
+        let mut batch = WriteBatch::default();
+        for (key, piece) in pieces.iter() {
+            info!(
+                "delete piece metadata {} in batch",
+                std::str::from_utf8(key).unwrap_or_default(),
+            );
+
+            batch.delete::<Piece>(key);
+            if let Some(block_digest) = piece.block_digest.as_deref() {
+                *block_references.entry(block_digest).or_default() += 1;
+            }
+        }
 
-        let piece_ids_refs = piece_ids
-            .par_iter()
-            .map(|id| {
-                let id_ref = id.as_ref();
-                info!(
-                    "delete piece metadata {} in batch",
-                    std::str::from_utf8(id_ref).unwrap_or_default(),
-                );
-
-                id_ref
-            })
-            .collect::<Vec<&[u8]>>();
+        let mut unreferenced_block_digests = Vec::new();
+        for (block_digest, count) in block_references {
+            if self.release_block(&mut batch, block_digest, count)? {
+                unreferenced_block_digests.push(block_digest.to_string());
+            }
+        }
 
-        self.db.batch_delete::<Piece>(piece_ids_refs)?;
-        Ok(())
+        self.db.write(batch)?;
+        Ok(unreferenced_block_digests)
     }
 
     /// piece_id returns the piece id.
@@ -897,6 +1339,103 @@ impl<E: StorageEngineOwned> Metadata<E> {
     pub fn piece_id(&self, task_id: &str, number: u32) -> String {
         format!("{}-{}", task_id, number)
     }
+
+    /// reference_block increases the reference count of the block in the batch, the block
+    /// metadata is created if it does not exist.
+    fn reference_block(
+        &self,
+        batch: &mut WriteBatch,
+        digest: &str,
+        length: u64,
+        compressed_length: Option<u64>,
+    ) -> Result<()> {
+        let block = match self.db.get::<Block>(digest.as_bytes())? {
+            Some(mut block) => {
+                block.reference_count += 1;
+                block.updated_at = Utc::now().naive_utc();
+                block
+            }
+            None => Block {
+                digest: digest.to_string(),
+                length,
+                compressed_length,
+                reference_count: 1,
+                updated_at: Utc::now().naive_utc(),
+                created_at: Utc::now().naive_utc(),
+            },
+        };
+
+        batch.put(digest.as_bytes(), &block)
+    }
+
+    /// release_block decreases the reference count of the block by the count in the batch, the
+    /// block metadata is deleted when it is not referenced. It returns whether the block is no
+    /// longer referenced.
+    fn release_block(&self, batch: &mut WriteBatch, digest: &str, count: u64) -> Result<bool> {
+        let mut block = match self.db.get::<Block>(digest.as_bytes())? {
+            Some(block) => block,
+            None => {
+                warn!("block metadata {} not found", digest);
+                return Ok(false);
+            }
+        };
+
+        block.reference_count = block.reference_count.saturating_sub(count);
+        if block.reference_count == 0 {
+            info!("delete block metadata {}", digest);
+            batch.delete::<Block>(digest.as_bytes());
+            return Ok(true);
+        }
+
+        block.updated_at = Utc::now().naive_utc();
+        batch.put(digest.as_bytes(), &block)?;
+        Ok(false)
+    }
+
+    /// recount_blocks rebuilds the reference counts of the blocks by the pieces referencing them,
+    /// and deletes the block metadatas not referenced by any piece. It returns the digests of the
+    /// referenced blocks.
+    #[instrument(skip_all)]
+    pub fn recount_blocks(&self) -> Result<HashSet<String>> {
+        let mut block_references: HashMap<String, u64> = HashMap::new();
+        for ele in self.db.iter::<Piece>()? {
+            let (_, piece) = ele?;
+            if let Some(block_digest) = piece.block_digest {
+                *block_references.entry(block_digest).or_default() += 1;
+            }
+        }
+
+        let mut batch = WriteBatch::default();
+        for ele in self.db.iter::<Block>()? {
+            let (key, mut block) = ele?;
+            match block_references.get(&block.digest) {
+                Some(&reference_count) if reference_count == block.reference_count => {}
+                Some(&reference_count) => {
+                    warn!(
+                        "block {} reference count {} is recounted to {}",
+                        block.digest, block.reference_count, reference_count
+                    );
+
+                    block.reference_count = reference_count;
+                    block.updated_at = Utc::now().naive_utc();
+                    batch.put(&key, &block)?;
+                }
+                None => {
+                    info!("delete unreferenced block metadata {}", block.digest);
+                    batch.delete::<Block>(&key);
+                }
+            }
+        }
+
+        self.db.write(batch)?;
+        Ok(block_references.into_keys().collect())
+    }
+
+    /// get_block gets the block metadata.
+    #[instrument(skip_all)]
+    pub fn get_block(&self, digest: &str) -> Result<Option<Block>> {
+        self.db.get(digest.as_bytes())
+    }
 }
 
 /// Metadata implements the metadata of the storage engine.
@@ -915,6 +1454,7 @@ impl Metadata<RocksdbStorageEngine> {
                 Task::NAMESPACE,
                 Piece::NAMESPACE,
                 PersistentCacheTask::NAMESPACE,
+                Block::NAMESPACE,
             ],
             config.storage.keep,
         )?;
@@ -949,7 +1489,7 @@ mod tests {
 
         // Test download_task_started.
         metadata
-            .download_task_started(task_id, Some(1024), Some(1024), None)
+            .download_task_started(task_id, Some(1024), Some(1024), None, None, false, None)
             .unwrap();
         let task = metadata
             .get_task(task_id)
@@ -989,7 +1529,7 @@ mod tests {
         // Test get_tasks.
         let task_id = "a535b115f18d96870f0422ac891f91dd162f2f391e4778fb84279701fcd02dd1";
         metadata
-            .download_task_started(task_id, Some(1024), None, None)
+            .download_task_started(task_id, Some(1024), None, None, None, false, None)
             .unwrap();
         let tasks = metadata.get_tasks().unwrap();
         assert_eq!(tasks.len(), 2);
@@ -1000,6 +1540,357 @@ mod tests {
         assert!(task.is_none());
     }
 
+    #[test]
+    fn test_task_download_request() {
+        let dir = TempDir::new("metadata").unwrap();
+        let log_dir = dir.path().join("log");
+        let metadata = Metadata::new(Arc::new(Config::default()), dir.path(), &log_dir).unwrap();
+        let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
+
+        // Test download_task_started with the range request.
+        let range_request = DownloadRequest {
+            url: "http://example.com/file".to_string(),
+            range: Some((0, 1024)),
+            ..Default::default()
+        };
+        let task = metadata
+            .download_task_started(
+                task_id,
+                None,
+                None,
+                None,
+                Some(range_request.clone()),
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(task.download_request, Some(range_request));
+        assert!(task.is_resumable());
+
+        // Test download_task_started with the full request overwrites the range request.
+        let full_request = DownloadRequest {
+            url: "http://example.com/file".to_string(),
+            ..Default::default()
+        };
+        let task = metadata
+            .download_task_started(
+                task_id,
+                None,
+                None,
+                None,
+                Some(full_request.clone()),
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(task.download_request, Some(full_request.clone()));
+
+        // Test download_task_started with the range request does not overwrite the full request.
+        let task = metadata
+            .download_task_started(
+                task_id,
+                None,
+                None,
+                None,
+                Some(DownloadRequest {
+                    url: "http://example.com/file".to_string(),
+                    range: Some((1024, 1024)),
+                    ..Default::default()
+                }),
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(task.download_request, Some(full_request.clone()));
+
+        // Test download_task_started with the range header does not overwrite the full request.
+        let task = metadata
+            .download_task_started(
+                task_id,
+                None,
+                None,
+                None,
+                Some(DownloadRequest {
+                    url: "http://example.com/file".to_string(),
+                    request_header: HashMap::from([(
+                        "Range".to_string(),
+                        "bytes=0-1023".to_string(),
+                    )]),
+                    ..Default::default()
+                }),
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(task.download_request, Some(full_request.clone()));
+
+        // Test download_task_started without the request keeps the persisted request.
+        let task = metadata
+            .download_task_started(task_id, Some(1024), Some(2048), None, None, false, None)
+            .unwrap();
+        assert_eq!(task.download_request, Some(full_request));
+
+        // Test finished task is not resumable.
+        let task = metadata.download_task_finished(task_id).unwrap();
+        assert!(!task.is_resumable());
+    }
+
+    #[test]
+    fn test_legacy_task_deserialize() {
+        let legacy_task = LegacyTask {
+            id: "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c".to_string(),
+            piece_length: Some(1024),
+            content_length: Some(2048),
+            updated_at: Utc::now().naive_utc(),
+            created_at: Utc::now().naive_utc(),
+            finished_at: Some(Utc::now().naive_utc()),
+            ..Default::default()
+        };
+
+        // Test the task stored by the legacy dfdaemon.
+        let task = Task::deserialize_from(&bincode::serialize(&legacy_task).unwrap()).unwrap();
+        assert_eq!(task, Task::from(legacy_task));
+        assert!(task.download_request.is_none());
+        assert!(task.is_finished());
+
+        // Test the task stored by the current dfdaemon.
+        let task = Task {
+            download_request: Some(DownloadRequest {
+                url: "http://example.com/file".to_string(),
+                ..Default::default()
+            }),
+            ..task
+        };
+        assert_eq!(
+            Task::deserialize_from(&task.serialized().unwrap()).unwrap(),
+            task
+        );
+    }
+
+    #[test]
+    fn test_unclassified_task_deserialize() {
+        let unclassified_task = UnclassifiedTask {
+            id: "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c".to_string(),
+            piece_length: Some(1024),
+            content_length: Some(2048),
+            updated_at: Utc::now().naive_utc(),
+            created_at: Utc::now().naive_utc(),
+            download_request: Some(DownloadRequest {
+                url: "http://example.com/file".to_string(),
+                ..Default::default()
+            }),
+            compressed: true,
+            ..Default::default()
+        };
+
+        // Test the task stored by the dfdaemon without the bandwidth class.
+        let task =
+            Task::deserialize_from(&bincode::serialize(&unclassified_task).unwrap()).unwrap();
+        assert_eq!(task, Task::from(unclassified_task));
+        assert!(task.is_compressed());
+        assert!(task.bandwidth_class.is_none());
+
+        // Test the task stored by the current dfdaemon.
+        let task = Task {
+            bandwidth_class: Some("ci".to_string()),
+            ..task
+        };
+        assert_eq!(
+            Task::deserialize_from(&task.serialized().unwrap()).unwrap(),
+            task
+        );
+    }
+
+    #[test]
+    fn test_legacy_persistent_cache_task_deserialize() {
+        let legacy_task = LegacyPersistentCacheTask {
+            id: "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c".to_string(),
+            persistent: true,
+            ttl: Duration::from_secs(3600),
+            piece_length: 1024,
+            content_length: 2048,
+            updated_at: Utc::now().naive_utc(),
+            created_at: Utc::now().naive_utc(),
+            finished_at: Some(Utc::now().naive_utc()),
+            ..Default::default()
+        };
+
+        // Test the persistent cache task stored by the legacy dfdaemon.
+        let task =
+            PersistentCacheTask::deserialize_from(&bincode::serialize(&legacy_task).unwrap())
+                .unwrap();
+        assert_eq!(task, PersistentCacheTask::from(legacy_task));
+        assert!(task.digest.is_none());
+        assert!(task.bandwidth_class.is_none());
+
+        // Test the persistent cache task stored by the current dfdaemon.
+        let task = PersistentCacheTask {
+            digest: Some(
+                "sha256:a6c2c1c4c4a8d4e6e3d6d9b0d0f0a1b2c3d4e5f6a7b8c9d0e1f2a3b4c5d6e7f8"
+                    .to_string(),
+            ),
+            ..task
+        };
+        assert_eq!(
+            PersistentCacheTask::deserialize_from(&task.serialized().unwrap()).unwrap(),
+            task
+        );
+    }
+
+    #[test]
+    fn test_unclassified_persistent_cache_task_deserialize() {
+        let unclassified_task = UnclassifiedPersistentCacheTask {
+            id: "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c".to_string(),
+            persistent: true,
+            ttl: Duration::from_secs(3600),
+            piece_length: 1024,
+            content_length: 2048,
+            updated_at: Utc::now().naive_utc(),
+            created_at: Utc::now().naive_utc(),
+            finished_at: Some(Utc::now().naive_utc()),
+            digest: Some(
+                "sha256:a6c2c1c4c4a8d4e6e3d6d9b0d0f0a1b2c3d4e5f6a7b8c9d0e1f2a3b4c5d6e7f8"
+                    .to_string(),
+            ),
+            ..Default::default()
+        };
+
+        // Test the persistent cache task stored by the dfdaemon without the bandwidth class.
+        let task =
+            PersistentCacheTask::deserialize_from(&bincode::serialize(&unclassified_task).unwrap())
+                .unwrap();
+        assert_eq!(task, PersistentCacheTask::from(unclassified_task));
+        assert!(task.digest.is_some());
+        assert!(task.bandwidth_class.is_none());
+
+        // Test the persistent cache task stored by the current dfdaemon.
+        let task = PersistentCacheTask {
+            bandwidth_class: Some("ci".to_string()),
+            ..task
+        };
+        assert_eq!(
+            PersistentCacheTask::deserialize_from(&task.serialized().unwrap()).unwrap(),
+            task
+        );
+    }
+
+    #[test]
+    fn test_legacy_piece_deserialize() {
+        let legacy_piece = LegacyPiece {
+            number: 1,
+            offset: 1024,
+            length: 1024,
+            digest: "crc32:3b9e1bd6".to_string(),
+            updated_at: Utc::now().naive_utc(),
+            created_at: Utc::now().naive_utc(),
+            finished_at: Some(Utc::now().naive_utc()),
+            ..Default::default()
+        };
+
+        // Test the piece stored by the legacy dfdaemon.
+        let piece = Piece::deserialize_from(&bincode::serialize(&legacy_piece).unwrap()).unwrap();
+        assert_eq!(piece, Piece::from(legacy_piece));
+        assert_eq!(piece.stored_length(), 1024);
+
+        // Test the piece stored by the current dfdaemon.
+        let piece = Piece {
+            compressed_length: Some(512),
+            block_digest: Some(
+                "a535b115f18d96870f0422ac891f91dd162f2f391e4778fb84279701fcd02dd1".to_string(),
+            ),
+            ..piece
+        };
+        assert_eq!(
+            Piece::deserialize_from(&piece.serialized().unwrap()).unwrap(),
+            piece
+        );
+    }
+
+    #[test]
+    fn test_block_lifecycle() {
+        let dir = TempDir::new("metadata").unwrap();
+        let log_dir = dir.path().join("log");
+        let metadata = Metadata::new(Arc::new(Config::default()), dir.path(), &log_dir).unwrap();
+        let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
+        let digest = "40aff2e9d2d8922e47afd4648e6967497158785fbd1da870e7110266bf944880";
+        let other_digest = "a535b115f18d96870f0422ac891f91dd162f2f391e4778fb84279701fcd02dd1";
+
+        // Test the block is created by the finished piece.
+        for number in 0..2 {
+            let piece_id = metadata.piece_id(task_id, number);
+            metadata
+                .download_piece_started(piece_id.as_str(), number)
+                .unwrap();
+            metadata
+                .download_piece_finished(
+                    piece_id.as_str(),
+                    number as u64 * 256,
+                    256,
+                    "digest1",
+                    None,
+                    Some(128),
+                    Some(digest.to_string()),
+                )
+                .unwrap();
+        }
+
+        let block = metadata.get_block(digest).unwrap().unwrap();
+        assert_eq!(block.reference_count, 2);
+        assert_eq!(block.compressed_length, Some(128));
+        assert!(block.is_shared());
+
+        // Test the old block is released when the piece is overwritten.
+        metadata
+            .download_piece_finished(
+                metadata.piece_id(task_id, 1).as_str(),
+                256,
+                256,
+                "digest2",
+                None,
+                None,
+                Some(other_digest.to_string()),
+            )
+            .unwrap();
+        assert_eq!(
+            metadata.get_block(digest).unwrap().unwrap().reference_count,
+            1
+        );
+        assert_eq!(
+            metadata
+                .get_block(other_digest)
+                .unwrap()
+                .unwrap()
+                .reference_count,
+            1
+        );
+
+        // Test recount_blocks rebuilds the reference count.
+        let mut batch = WriteBatch::default();
+        metadata
+            .reference_block(&mut batch, digest, 256, None)
+            .unwrap();
+        metadata.db.write(batch).unwrap();
+        assert_eq!(
+            metadata.recount_blocks().unwrap(),
+            HashSet::from([digest.to_string(), other_digest.to_string()])
+        );
+        assert_eq!(
+            metadata.get_block(digest).unwrap().unwrap().reference_count,
+            1
+        );
+
+        // Test delete_pieces releases the blocks.
+        let mut unreferenced_block_digests = metadata.delete_pieces(task_id).unwrap();
+        unreferenced_block_digests.sort();
+        assert_eq!(
+            unreferenced_block_digests,
+            vec![digest.to_string(), other_digest.to_string()]
+        );
+        assert!(metadata.get_block(digest).unwrap().is_none());
+        assert!(metadata.get_block(other_digest).unwrap().is_none());
+        assert!(metadata.recount_blocks().unwrap().is_empty());
+    }
+
     #[test]
     fn test_piece_lifecycle() {
         let dir = TempDir::new("metadata").unwrap();
@@ -1017,7 +1908,7 @@ mod tests {
 
         // Test download_piece_finished.
         metadata
-            .download_piece_finished(piece_id.as_str(), 0, 1024, "digest1", None)
+            .download_piece_finished(piece_id.as_str(), 0, 1024, "digest1", None, None, None)
             .unwrap();
         let piece = metadata.get_piece(piece_id.as_str()).unwrap().unwrap();
         assert_eq!(piece.length, 1024);
@@ -40,6 +40,57 @@ pub trait DatabaseObject: Serialize + DeserializeOwned {
     }
 }
 
+/// WriteBatch collects the puts and deletes of the objects in different namespaces, which are
+/// written atomically by [Operations::write].
+#[derive(Default)]
+pub struct WriteBatch {
+    /// operations are the collected operations of the batch.
+    operations: Vec<BatchOperation>,
+}
+
+/// BatchOperation is the operation of the object in the batch.
+pub enum BatchOperation {
+    /// Put puts the serialized object by key in the namespace.
+    Put {
+        namespace: &'static str,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+
+    /// Delete deletes the object by key in the namespace.
+    Delete {
+        namespace: &'static str,
+        key: Vec<u8>,
+    },
+}
+
+/// WriteBatch implements the write batch.
+impl WriteBatch {
+    /// put puts the object by key.
+    pub fn put<O: DatabaseObject>(&mut self, key: &[u8], value: &O) -> Result<()> {
+        self.operations.push(BatchOperation::Put {
+            namespace: O::NAMESPACE,
+            key: key.to_vec(),
+            value: value.serialized()?,
+        });
+
+        Ok(())
+    }
+
+    /// delete deletes the object by key.
+    pub fn delete<O: DatabaseObject>(&mut self, key: &[u8]) {
+        self.operations.push(BatchOperation::Delete {
+            namespace: O::NAMESPACE,
+            key: key.to_vec(),
+        });
+    }
+
+    /// into_operations returns the collected operations of the batch.
+    pub fn into_operations(self) -> Vec<BatchOperation> {
+        self.operations
+    }
+}
+
 /// StorageEngine defines basic storage engine operations.
 pub trait StorageEngine<'db>: Operations {}
 
@@ -83,4 +134,7 @@ pub trait Operations {
 
     // batch_delete deletes objects by keys.
     fn batch_delete<O: DatabaseObject>(&self, keys: Vec<&[u8]>) -> Result<()>;
+
+    /// write writes the batch atomically.
+    fn write(&self, batch: WriteBatch) -> Result<()>;
 }
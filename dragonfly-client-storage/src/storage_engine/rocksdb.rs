@@ -14,7 +14,9 @@
  * limitations under the License.
  */
 
-use crate::storage_engine::{DatabaseObject, Operations, StorageEngine};
+use crate::storage_engine::{
+    BatchOperation, DatabaseObject, Operations, StorageEngine, WriteBatch,
+};
 use dragonfly_client_core::{
     error::{ErrorType, OrErr},
     Error, Result,
@@ -243,6 +245,30 @@ impl Operations for RocksdbStorageEngine {
             .write_opt(batch, &options)
             .or_err(ErrorType::StorageError)?)
     }
+
+    /// write writes the batch atomically.
+    #[instrument(skip_all)]
+    fn write(&self, batch: WriteBatch) -> Result<()> {
+        let mut inner = rocksdb::WriteBatch::default();
+        for operation in batch.into_operations() {
+            match operation {
+                BatchOperation::Put {
+                    namespace,
+                    key,
+                    value,
+                } => inner.put_cf(cf_handle_by_name(self, namespace)?, key, value),
+                BatchOperation::Delete { namespace, key } => {
+                    inner.delete_cf(cf_handle_by_name(self, namespace)?, key)
+                }
+            }
+        }
+
+        let mut options = WriteOptions::default();
+        options.set_sync(true);
+        Ok(self
+            .write_opt(inner, &options)
+            .or_err(ErrorType::StorageError)?)
+    }
 }
 
 /// RocksdbStorageEngine implements the rocksdb of the storage engine.
@@ -253,7 +279,14 @@ fn cf_handle<T>(db: &rocksdb::DB) -> Result<&rocksdb::ColumnFamily>
 where
     T: DatabaseObject,
 {
-    let cf_name = T::NAMESPACE;
+    cf_handle_by_name(db, T::NAMESPACE)
+}
+
+/// cf_handle_by_name returns the column family handle for the given namespace.
+fn cf_handle_by_name<'db>(
+    db: &'db rocksdb::DB,
+    cf_name: &str,
+) -> Result<&'db rocksdb::ColumnFamily> {
     db.cf_handle(cf_name)
         .ok_or_else(|| Error::ColumnFamilyNotFound(cf_name.to_string()))
 }
@@ -0,0 +1,539 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_core::{Error, Result};
+use io_uring::{opcode, types, IoUring};
+use std::alloc::{self, Layout};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr::NonNull;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{error, info, instrument, warn};
+
+/// DEFAULT_ALIGNMENT is the alignment of the buffer, offset and length for direct io.
+pub const DEFAULT_ALIGNMENT: usize = 4096;
+
+/// DEFAULT_SUBMIT_RETRY_INTERVAL is the interval for retrying the failed submission of io_uring.
+const DEFAULT_SUBMIT_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// AlignedBuffer is the buffer aligned by [DEFAULT_ALIGNMENT], it can be used for direct io.
+pub struct AlignedBuffer {
+    /// ptr is the pointer of the allocated memory.
+    ptr: NonNull<u8>,
+
+    /// len is the length of the valid data in the buffer.
+    len: usize,
+
+    /// capacity is the size of the allocated memory.
+    capacity: usize,
+}
+
+/// AlignedBuffer owns the allocated memory exclusively, so it can be sent and shared
+/// between threads.
+unsafe impl Send for AlignedBuffer {}
+unsafe impl Sync for AlignedBuffer {}
+
+/// AlignedBuffer implements the aligned buffer.
+impl AlignedBuffer {
+    /// new allocates a zeroed buffer, the capacity is aligned up by [DEFAULT_ALIGNMENT].
+    pub fn new(capacity: usize) -> Self {
+        let capacity = align_up(capacity.max(1) as u64) as usize;
+        let layout = Self::layout(capacity);
+
+        // SAFETY: The layout has a non-zero size.
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        AlignedBuffer {
+            ptr,
+            len: capacity,
+            capacity,
+        }
+    }
+
+    /// from_slice allocates a buffer and copies the data to it.
+    pub fn from_slice(data: &[u8]) -> Self {
+        let mut buffer = Self::new(data.len());
+        buffer[..data.len()].copy_from_slice(data);
+        buffer.truncate(data.len());
+        buffer
+    }
+
+    /// capacity returns the size of the allocated memory.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// truncate shortens the valid data of the buffer.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = len.min(self.capacity);
+    }
+
+    /// set_len sets the length of the valid data of the buffer, the memory is always
+    /// initialized, because it is allocated zeroed.
+    fn set_len(&mut self, len: usize) {
+        self.len = len.min(self.capacity);
+    }
+
+    /// layout returns the memory layout of the buffer.
+    fn layout(capacity: usize) -> Layout {
+        Layout::from_size_align(capacity, DEFAULT_ALIGNMENT).expect("invalid aligned layout")
+    }
+}
+
+/// AlignedBuffer implements Deref.
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: The memory is allocated and initialized with the capacity.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+/// AlignedBuffer implements DerefMut.
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: The memory is allocated and initialized with the capacity.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+/// AlignedBuffer implements AsRef.
+impl AsRef<[u8]> for AlignedBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// AlignedBuffer implements Drop.
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        // SAFETY: The memory is allocated by the same layout.
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), Self::layout(self.capacity)) };
+    }
+}
+
+/// Operation is the operation of the io_uring request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    /// Read reads the file to the buffer.
+    Read,
+
+    /// Write writes the buffer to the file.
+    Write,
+}
+
+/// Request is the request submitted to io_uring.
+struct Request {
+    /// operation is the operation of the request.
+    operation: Operation,
+
+    /// file is the file to read or write, it is kept alive until the request completes.
+    file: Arc<File>,
+
+    /// buffer is the buffer to read into or write from, it is kept alive until the
+    /// request completes.
+    buffer: AlignedBuffer,
+
+    /// position is the position of the buffer to read into or write from.
+    position: usize,
+
+    /// length is the length to read or write.
+    length: usize,
+
+    /// offset is the offset of the file.
+    offset: u64,
+
+    /// sender sends the buffer and the result of the request.
+    sender: oneshot::Sender<(AlignedBuffer, io::Result<usize>)>,
+}
+
+/// Uring reads and writes the files by io_uring. The requests are submitted to a dedicated
+/// thread which owns the ring, so the tokio runtime is not blocked.
+pub struct Uring {
+    /// sender sends the requests to the io_uring thread.
+    sender: Sender<Request>,
+
+    /// direct_io indicates whether open the files with O_DIRECT.
+    direct_io: bool,
+}
+
+/// Uring implements the io_uring reader and writer.
+impl Uring {
+    /// new creates a new io_uring with the size of the submission queue.
+    #[instrument(skip_all)]
+    pub fn new(entries: u32, direct_io: bool) -> Result<Uring> {
+        let ring = IoUring::new(entries)?;
+        let (sender, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("io-uring".to_string())
+            .spawn(move || run(ring, receiver, entries as usize))?;
+
+        info!(
+            "io_uring initialized with entries {}, direct io {}",
+            entries, direct_io
+        );
+        Ok(Uring { sender, direct_io })
+    }
+
+    /// read_exact_at reads the file from the offset with the length. The read is aligned by
+    /// [DEFAULT_ALIGNMENT] for direct io, and the returned buffer contains the range of the
+    /// aligned read, the second element is the position of the offset in the buffer. The
+    /// length of the data may be shorter than the expected length if the file reaches EOF.
+    #[instrument(skip_all)]
+    pub async fn read_exact_at(
+        &self,
+        path: &Path,
+        offset: u64,
+        length: u64,
+    ) -> Result<(AlignedBuffer, usize)> {
+        let aligned_offset = align_down(offset);
+        let head = (offset - aligned_offset) as usize;
+        let aligned_length = align_up(head as u64 + length) as usize;
+
+        let mut file = self.open(path, false, self.direct_io).await?;
+        let mut direct_io = self.direct_io;
+        let mut buffer = AlignedBuffer::new(aligned_length);
+        let mut position = 0;
+        while position < aligned_length {
+            // The short read moves the next read to the unaligned offset, which is rejected
+            // by direct io with EINVAL, so the rest of the read falls back to buffered io.
+            if direct_io && !is_aligned(position as u64) {
+                file = self.open(path, false, false).await?;
+                direct_io = false;
+            }
+
+            let (returned, result) = self
+                .submit(
+                    Operation::Read,
+                    file.clone(),
+                    buffer,
+                    position,
+                    aligned_length - position,
+                    aligned_offset + position as u64,
+                )
+                .await?;
+            buffer = returned;
+
+            let n = result.inspect_err(|err| {
+                error!("read {:?} by io_uring failed: {}", path, err);
+            })?;
+
+            // The file reaches EOF.
+            if n == 0 {
+                break;
+            }
+
+            position += n;
+        }
+
+        buffer.set_len(position.min(head + length as usize).max(head));
+        Ok((buffer, head))
+    }
+
+    /// write_all_at writes the data to the file at the offset. The data is written with direct
+    /// io only if the offset and the length are aligned by [DEFAULT_ALIGNMENT].
+    #[instrument(skip_all)]
+    pub async fn write_all_at(&self, path: &Path, offset: u64, data: &[u8]) -> Result<()> {
+        let mut direct_io = self.direct_io && is_aligned(offset) && is_aligned(data.len() as u64);
+        let mut file = self.open(path, true, direct_io).await?;
+
+        let mut buffer = AlignedBuffer::from_slice(data);
+        let mut position = 0;
+        while position < data.len() {
+            // The short write moves the next write to the unaligned offset, which is rejected
+            // by direct io with EINVAL, so the rest of the write falls back to buffered io.
+            if direct_io && !is_aligned(position as u64) {
+                file = self.open(path, true, false).await?;
+                direct_io = false;
+            }
+
+            let (returned, result) = self
+                .submit(
+                    Operation::Write,
+                    file.clone(),
+                    buffer,
+                    position,
+                    data.len() - position,
+                    offset + position as u64,
+                )
+                .await?;
+            buffer = returned;
+
+            let n = result.inspect_err(|err| {
+                error!("write {:?} by io_uring failed: {}", path, err);
+            })?;
+
+            if n == 0 {
+                return Err(Error::IO(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                )));
+            }
+
+            position += n;
+        }
+
+        Ok(())
+    }
+
+    /// open opens the file in the blocking thread. If the file system does not support
+    /// direct io, the file is opened without O_DIRECT.
+    async fn open(&self, path: &Path, write: bool, direct_io: bool) -> Result<Arc<File>> {
+        let path = path.to_path_buf();
+        let file = tokio::task::spawn_blocking(move || {
+            let open = |direct_io: bool| {
+                let mut options = OpenOptions::new();
+                if write {
+                    options.create(true).truncate(false).write(true);
+                } else {
+                    options.read(true);
+                }
+
+                if direct_io {
+                    options.custom_flags(libc::O_DIRECT);
+                }
+
+                options.open(path.as_path())
+            };
+
+            match open(direct_io) {
+                Err(err) if direct_io && err.raw_os_error() == Some(libc::EINVAL) => {
+                    warn!("{:?} does not support direct io, fallback", path);
+                    open(false)
+                }
+                result => result,
+            }
+        })
+        .await
+        .map_err(Error::TokioJoinError)??;
+
+        Ok(Arc::new(file))
+    }
+
+    /// submit submits the request to the io_uring thread and waits for the completion.
+    async fn submit(
+        &self,
+        operation: Operation,
+        file: Arc<File>,
+        buffer: AlignedBuffer,
+        position: usize,
+        length: usize,
+        offset: u64,
+    ) -> Result<(AlignedBuffer, io::Result<usize>)> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Request {
+                operation,
+                file,
+                buffer,
+                position,
+                length,
+                offset,
+                sender,
+            })
+            .map_err(|_| Error::Unknown("io_uring thread exited".to_string()))?;
+
+        receiver
+            .await
+            .map_err(|_| Error::Unknown("io_uring request canceled".to_string()))
+    }
+}
+
+/// run submits the requests to the ring and sends back the completions until the
+/// sender of the requests is dropped.
+fn run(mut ring: IoUring, receiver: Receiver<Request>, entries: usize) {
+    let mut in_flight: HashMap<u64, Request> = HashMap::new();
+    let mut next_id: u64 = 0;
+    let mut disconnected = false;
+
+    loop {
+        // Block for the next request if there is no request in flight.
+        if in_flight.is_empty() {
+            if disconnected {
+                return;
+            }
+
+            match receiver.recv() {
+                Ok(request) => push(&mut ring, &mut in_flight, &mut next_id, request),
+                Err(_) => return,
+            }
+        }
+
+        // Push the pending requests to the submission queue as many as possible.
+        while !disconnected && in_flight.len() < entries {
+            match receiver.try_recv() {
+                Ok(request) => push(&mut ring, &mut in_flight, &mut next_id, request),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => disconnected = true,
+            }
+        }
+
+        if in_flight.is_empty() {
+            continue;
+        }
+
+        // The kernel may still own the buffers of the submitted requests when the submission
+        // fails, so the requests are kept in flight until their completions are reaped, and
+        // the submission is retried after the backoff if nothing completes.
+        let submitted = match ring.submit_and_wait(1) {
+            Ok(_) => true,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                error!("submit io_uring requests failed: {}", err);
+                false
+            }
+        };
+
+        // Send back the results of the completed requests.
+        let mut completed = 0;
+        for cqe in ring.completion() {
+            completed += 1;
+            if let Some(request) = in_flight.remove(&cqe.user_data()) {
+                let result = if cqe.result() < 0 {
+                    Err(io::Error::from_raw_os_error(-cqe.result()))
+                } else {
+                    Ok(cqe.result() as usize)
+                };
+
+                let _ = request.sender.send((request.buffer, result));
+            }
+        }
+
+        if !submitted && completed == 0 {
+            std::thread::sleep(DEFAULT_SUBMIT_RETRY_INTERVAL);
+        }
+    }
+}
+
+/// push pushes the request to the submission queue, the request is kept in flight
+/// until it completes.
+fn push(
+    ring: &mut IoUring,
+    in_flight: &mut HashMap<u64, Request>,
+    next_id: &mut u64,
+    mut request: Request,
+) {
+    let id = *next_id;
+    *next_id = next_id.wrapping_add(1);
+
+    let fd = types::Fd(request.file.as_raw_fd());
+    // SAFETY: The position and length are in the range of the buffer.
+    let ptr = unsafe { request.buffer.ptr.as_ptr().add(request.position) };
+    let length = request
+        .length
+        .min(request.buffer.capacity() - request.position) as u32;
+    let entry = match request.operation {
+        Operation::Read => opcode::Read::new(fd, ptr, length)
+            .offset(request.offset)
+            .build(),
+        Operation::Write => opcode::Write::new(fd, ptr, length)
+            .offset(request.offset)
+            .build(),
+    }
+    .user_data(id);
+
+    // SAFETY: The file and the buffer are kept alive in the in flight requests
+    // until the request completes.
+    if unsafe { ring.submission().push(&entry) }.is_err() {
+        // The submission queue is full, submit the queued requests and retry.
+        if let Err(err) = ring.submit() {
+            let _ = request.sender.send((request.buffer, Err(err)));
+            return;
+        }
+
+        if unsafe { ring.submission().push(&entry) }.is_err() {
+            let _ = request.sender.send((
+                request.buffer,
+                Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "io_uring submission queue is full",
+                )),
+            ));
+            return;
+        }
+    }
+
+    // The buffer pointer is still valid after the request moved, because the memory
+    // is allocated in the heap.
+    request.buffer.set_len(request.buffer.capacity());
+    in_flight.insert(id, request);
+}
+
+/// align_down aligns down the value by [DEFAULT_ALIGNMENT].
+#[inline]
+pub fn align_down(value: u64) -> u64 {
+    value & !(DEFAULT_ALIGNMENT as u64 - 1)
+}
+
+/// align_up aligns up the value by [DEFAULT_ALIGNMENT].
+#[inline]
+pub fn align_up(value: u64) -> u64 {
+    align_down(value + DEFAULT_ALIGNMENT as u64 - 1)
+}
+
+/// is_aligned returns whether the value is aligned by [DEFAULT_ALIGNMENT].
+#[inline]
+pub fn is_aligned(value: u64) -> bool {
+    value % DEFAULT_ALIGNMENT as u64 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn should_align() {
+        assert_eq!(align_down(0), 0);
+        assert_eq!(align_down(4097), 4096);
+        assert_eq!(align_up(1), 4096);
+        assert_eq!(align_up(4096), 4096);
+        assert!(is_aligned(8192));
+        assert!(!is_aligned(8193));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires io_uring enabled in the kernel"]
+    async fn should_write_and_read_by_uring() {
+        let uring = Uring::new(8, true).unwrap();
+
+        let dir = TempDir::new("uring").unwrap();
+        let path = dir.path().join("content");
+        let data: Vec<u8> = (0..8192).map(|i| (i % 251) as u8).collect();
+        uring.write_all_at(&path, 0, &data).await.unwrap();
+        uring.write_all_at(&path, 8192, &data[..100]).await.unwrap();
+
+        let (buffer, head) = uring.read_exact_at(&path, 4100, 16).await.unwrap();
+        assert_eq!(&buffer[head..], &data[4100..4116]);
+
+        // Test the read is truncated by EOF.
+        let (buffer, head) = uring.read_exact_at(&path, 8190, 100).await.unwrap();
+        assert_eq!(buffer.len() - head, 100);
+        assert_eq!(
+            &buffer[head..head + 4],
+            &[data[8190], data[8191], data[0], data[1]][..]
+        );
+    }
+}
@@ -14,15 +14,16 @@
  * limitations under the License.
  */
 
+use crate::digest::{calculate_file_hash, Algorithm, Digest};
 use dragonfly_api::common::v2::TaskType;
 use dragonfly_client_core::{
     error::{ErrorType, OrErr},
-    Result,
+    Error, Result,
 };
-use sha2::{Digest, Sha256};
+use sha2::{Digest as Sha2Digest, Sha256};
 use std::hash::Hasher;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::instrument;
 use url::Url;
 use uuid::Uuid;
@@ -34,6 +35,9 @@ const SEED_PEER_SUFFIX: &str = "seed";
 /// PERSISTENT_CACHE_TASK_SUFFIX is the suffix of the persistent cache task.
 const PERSISTENT_CACHE_TASK_SUFFIX: &str = "persistent-cache-task";
 
+/// PERSISTENT_CACHE_TASK_ID_LENGTH is the length of the persistent cache task id.
+const PERSISTENT_CACHE_TASK_ID_LENGTH: usize = 64;
+
 /// IDGenerator is used to generate the id for the resources.
 #[derive(Debug)]
 pub struct IDGenerator {
@@ -164,6 +168,26 @@ impl IDGenerator {
         Ok(hex::encode(hasher.finalize()))
     }
 
+    /// persistent_cache_task_digest calculates the content digest of the file, which is used as
+    /// the persistent cache task id by [persistent_cache_task_id_from_digest]. Unlike the id
+    /// generated by [IDGenerator::persistent_cache_task_id], the tag and application are not
+    /// included, so the same file is the same persistent cache task.
+    #[inline]
+    #[instrument(skip_all)]
+    pub fn persistent_cache_task_digest(
+        &self,
+        path: &Path,
+        algorithm: Algorithm,
+    ) -> Result<Digest> {
+        match algorithm {
+            Algorithm::Sha256 | Algorithm::Blake3 => calculate_file_hash(algorithm, path),
+            _ => Err(Error::Unsupported(format!(
+                "persistent cache task id algorithm {}",
+                algorithm
+            ))),
+        }
+    }
+
     /// peer_id generates the peer id.
     #[inline]
     #[instrument(skip_all)]
@@ -192,6 +216,34 @@ impl IDGenerator {
     }
 }
 
+/// persistent_cache_task_id_from_digest returns the persistent cache task id of the content
+/// digest, the id is the hex encoded digest, e.g. the id of
+/// `sha256:6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b` is
+/// `6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b`.
+pub fn persistent_cache_task_id_from_digest(digest: &Digest) -> Result<String> {
+    if !matches!(digest.algorithm(), Algorithm::Sha256 | Algorithm::Blake3) {
+        return Err(Error::Unsupported(format!(
+            "persistent cache task id algorithm {}",
+            digest.algorithm()
+        )));
+    }
+
+    let encoded = digest.encoded();
+    if encoded.len() != PERSISTENT_CACHE_TASK_ID_LENGTH
+        || !encoded
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+    {
+        return Err(Error::ValidationError(format!(
+            "invalid {} digest {}",
+            digest.algorithm(),
+            encoded
+        )));
+    }
+
+    Ok(encoded.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,6 +353,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_generate_persistent_cache_task_id_from_digest() {
+        let generator = IDGenerator::new("127.0.0.1".to_string(), "localhost".to_string(), false);
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("testfile");
+        let mut f = File::create(&file_path).unwrap();
+        f.write_all("1".as_bytes()).unwrap();
+
+        let digest = generator
+            .persistent_cache_task_digest(&file_path, Algorithm::Sha256)
+            .unwrap();
+        assert_eq!(
+            digest.to_string(),
+            "sha256:6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b"
+        );
+        assert_eq!(
+            persistent_cache_task_id_from_digest(&digest).unwrap(),
+            "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b"
+        );
+
+        let digest = generator
+            .persistent_cache_task_digest(&file_path, Algorithm::Blake3)
+            .unwrap();
+        assert_eq!(
+            persistent_cache_task_id_from_digest(&digest).unwrap().len(),
+            64
+        );
+
+        assert!(generator
+            .persistent_cache_task_digest(&file_path, Algorithm::Crc32)
+            .is_err());
+
+        for digest in [
+            "md5:86d3f3a95c324c9479bd8986968f4327",
+            "sha512:6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b",
+            "sha256:6b86b273",
+            "sha256:6B86B273FF34FCE19D6B804EFF5A3F5747ADA4EAA22F1D49C01E52DDB7875B4B",
+        ] {
+            match digest.parse::<Digest>() {
+                Ok(digest) => assert!(persistent_cache_task_id_from_digest(&digest).is_err()),
+                Err(_) => continue,
+            }
+        }
+    }
+
     #[test]
     fn should_generate_peer_id() {
         let test_cases = vec![
@@ -15,8 +15,9 @@
  */
 
 use crate::grpc::{manager::ManagerClient, scheduler::SchedulerClient};
+use crate::metrics::{download_traffic_total, upload_tcp_connection_count, upload_traffic_total};
 use crate::shutdown;
-use dragonfly_api::common::v2::{Build, Cpu, Disk, Host, Memory, Network};
+use dragonfly_api::common::v2::{Build, Cpu, CpuTimes, Disk, Host, Memory, Network};
 use dragonfly_api::manager::v2::{DeleteSeedPeerRequest, SourceType, UpdateSeedPeerRequest};
 use dragonfly_api::scheduler::v2::{AnnounceHostRequest, DeleteHostRequest};
 use dragonfly_client_config::{
@@ -24,8 +25,11 @@ use dragonfly_client_config::{
     CARGO_PKG_RUSTC_VERSION, CARGO_PKG_VERSION, GIT_COMMIT_SHORT_HASH,
 };
 use dragonfly_client_core::error::{ErrorType, OrErr};
-use dragonfly_client_core::Result;
+use dragonfly_client_core::{Error, Result};
 use std::env;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use sysinfo::System;
@@ -73,20 +77,37 @@ impl ManagerAnnouncer {
 
         // If the seed peer is enabled, we should announce the seed peer to the manager.
         if self.config.seed_peer.enable {
-            // Register the seed peer to the manager.
-            self.manager_client
-                .update_seed_peer(UpdateSeedPeerRequest {
-                    source_type: SourceType::SeedPeerSource.into(),
-                    hostname: self.config.host.hostname.clone(),
-                    r#type: self.config.seed_peer.kind.to_string(),
-                    idc: self.config.host.idc.clone(),
-                    location: self.config.host.location.clone(),
-                    ip: self.config.host.ip.unwrap().to_string(),
-                    port: self.config.upload.server.port as i32,
-                    download_port: self.config.upload.server.port as i32,
-                    seed_peer_cluster_id: self.config.seed_peer.cluster_id,
-                })
-                .await?;
+            // Register the seed peer to the manager, if the manager is unavailable, retry
+            // to register the seed peer until the manager is recovered.
+            let mut interval = tokio::time::interval(self.config.seed_peer.keepalive_interval);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        match self
+                            .manager_client
+                            .update_seed_peer(UpdateSeedPeerRequest {
+                                source_type: SourceType::SeedPeerSource.into(),
+                                hostname: self.config.host.hostname.clone(),
+                                r#type: self.config.seed_peer.kind.to_string(),
+                                idc: self.config.host.idc.clone(),
+                                location: self.config.host.location.clone(),
+                                ip: self.config.host.ip.unwrap().to_string(),
+                                port: self.config.upload.server.port as i32,
+                                download_port: self.config.upload.server.port as i32,
+                                seed_peer_cluster_id: self.config.seed_peer.cluster_id,
+                            })
+                            .await
+                        {
+                            Ok(_) => break,
+                            Err(err) => error!("register seed peer to manager failed: {}", err),
+                        }
+                    }
+                    _ = shutdown.recv() => {
+                        info!("announce to manager shutting down");
+                        return Ok(());
+                    }
+                }
+            }
 
             // Announce to scheduler shutting down with signals.
             shutdown.recv().await;
@@ -125,6 +146,10 @@ pub struct SchedulerAnnouncer {
     // system is the system information.
     system: Arc<Mutex<System>>,
 
+    /// traffic is the total download and upload traffic at the last announcement, it is used
+    /// to calculate the download and upload rate in the announce interval.
+    traffic: Mutex<(u64, u64)>,
+
     /// shutdown is used to shutdown the announcer.
     shutdown: shutdown::Shutdown,
 
@@ -148,6 +173,7 @@ impl SchedulerAnnouncer {
             host_id,
             scheduler_client,
             system: Arc::new(Mutex::new(System::new_all())),
+            traffic: Mutex::new((download_traffic_total(), upload_traffic_total())),
             shutdown,
             _shutdown_complete: shutdown_complete_tx,
         };
@@ -155,7 +181,7 @@ impl SchedulerAnnouncer {
         // Initialize the scheduler announcer.
         announcer
             .scheduler_client
-            .init_announce_host(announcer.make_announce_host_request(Duration::ZERO)?)
+            .init_announce_host(announcer.make_announce_host_request(Duration::ZERO).await?)
             .await?;
         Ok(announcer)
     }
@@ -171,7 +197,7 @@ impl SchedulerAnnouncer {
         loop {
             tokio::select! {
                 _ = interval.tick() => {
-                    let request = match self.make_announce_host_request(interval.period()) {
+                    let request = match self.make_announce_host_request(interval.period()).await {
                         Ok(request) => request,
                         Err(err) => {
                             error!("make announce host request failed: {}", err);
@@ -200,7 +226,7 @@ impl SchedulerAnnouncer {
 
     /// make_announce_host_request makes the announce host request.
     #[instrument(skip_all)]
-    fn make_announce_host_request(&self, interval: Duration) -> Result<AnnounceHostRequest> {
+    async fn make_announce_host_request(&self, interval: Duration) -> Result<AnnounceHostRequest> {
         // If the seed peer is enabled, we should announce the seed peer to the scheduler.
         let host_type = if self.config.seed_peer.enable {
             self.config.seed_peer.kind
@@ -208,6 +234,9 @@ impl SchedulerAnnouncer {
             HostType::Normal
         };
 
+        // Get the scheduler cluster id from the dynamic configuration.
+        let scheduler_cluster_id = self.scheduler_client.scheduler_cluster_id().await;
+
         // Refresh the system information.
         let mut sys = self.system.lock().unwrap();
         sys.refresh_all();
@@ -217,43 +246,35 @@ impl SchedulerAnnouncer {
 
         // Get the cpu information.
         let cpu = Cpu {
-            logical_count: sys.physical_core_count().unwrap_or_default() as u32,
+            logical_count: sys.cpus().len() as u32,
             physical_count: sys.physical_core_count().unwrap_or_default() as u32,
             percent: sys.global_cpu_usage() as f64,
             process_percent: process.cpu_usage() as f64,
-
-            // TODO: Get the cpu times.
-            times: None,
+            times: cpu_times(),
         };
 
         // Get the memory information.
+        let total_memory = sys.total_memory().max(1) as f64;
         let memory = Memory {
             total: sys.total_memory(),
             available: sys.available_memory(),
             used: sys.used_memory(),
-            used_percent: (sys.used_memory() / sys.total_memory()) as f64,
-            process_used_percent: (process.memory() / sys.total_memory()) as f64,
+            used_percent: sys.used_memory() as f64 / total_memory * 100.0,
+            process_used_percent: process.memory() as f64 / total_memory * 100.0,
             free: sys.free_memory(),
         };
 
-        // Get the network information.
+        // Get the network information, the download and upload rate are calculated by the
+        // piece traffic in the announce interval.
+        let (download_rate, upload_rate) = self.traffic_rate(interval);
         let network = Network {
-            // TODO: Get the count of the tcp connection.
-            tcp_connection_count: 0,
-
-            // TODO: Get the count of the upload tcp connection.
-            upload_tcp_connection_count: 0,
+            tcp_connection_count: tcp_connection_count(),
+            upload_tcp_connection_count: upload_tcp_connection_count(),
             idc: self.config.host.idc.clone(),
             location: self.config.host.location.clone(),
-
-            // TODO: Get the network download rate, refer to
-            // https://docs.rs/sysinfo/latest/sysinfo/struct.NetworkData.html#method.received.
-            download_rate: 0,
+            download_rate,
             download_rate_limit: self.config.download.rate_limit.as_u64(),
-
-            // TODO: Get the network download rate, refer to
-            // https://docs.rs/sysinfo/latest/sysinfo/struct.NetworkData.html#method.transmitted
-            upload_rate: 0,
+            upload_rate,
             upload_rate_limit: self.config.upload.rate_limit.as_u64(),
         };
 
@@ -272,6 +293,16 @@ impl SchedulerAnnouncer {
             read_bandwidth = disk_usage.read_bytes / interval.as_secs();
         };
 
+        let (inodes_total, inodes_free) = disk_inodes(self.config.storage.dir.as_path())
+            .inspect_err(|err| error!("get disk inodes failed: {}", err))
+            .unwrap_or_default();
+        let inodes_used = inodes_total.saturating_sub(inodes_free);
+        let inodes_used_percent = if inodes_total > 0 {
+            inodes_used as f64 / inodes_total as f64 * 100.0
+        } else {
+            0.0
+        };
+
         let disk = Disk {
             total: total_space,
             free: available_space,
@@ -279,12 +310,10 @@ impl SchedulerAnnouncer {
             used_percent,
             write_bandwidth,
             read_bandwidth,
-
-            // TODO: Get the disk inodes information.
-            inodes_total: 0,
-            inodes_used: 0,
-            inodes_free: 0,
-            inodes_used_percent: 0.0,
+            inodes_total,
+            inodes_used,
+            inodes_free,
+            inodes_used_percent,
         };
 
         // Get the build information.
@@ -315,8 +344,7 @@ impl SchedulerAnnouncer {
             disk: Some(disk),
             build: Some(build),
 
-            // TODO: Get scheduler cluster id from dynconfig.
-            scheduler_cluster_id: 0,
+            scheduler_cluster_id,
             disable_shared: self.config.upload.disable_shared,
         };
 
@@ -328,4 +356,143 @@ impl SchedulerAnnouncer {
             ),
         })
     }
+
+    /// traffic_rate returns the download and upload rate in bytes per second, which are
+    /// calculated by the piece traffic since the last announcement.
+    fn traffic_rate(&self, interval: Duration) -> (u64, u64) {
+        let download_traffic = download_traffic_total();
+        let upload_traffic = upload_traffic_total();
+
+        let mut traffic = self.traffic.lock().unwrap();
+        let (last_download_traffic, last_upload_traffic) = *traffic;
+        *traffic = (download_traffic, upload_traffic);
+
+        if interval.as_secs() == 0 {
+            return (0, 0);
+        }
+
+        // The traffic counters may be reset by the metrics server, so the rate is
+        // zero if the counter is decreased.
+        (
+            download_traffic.saturating_sub(last_download_traffic) / interval.as_secs(),
+            upload_traffic.saturating_sub(last_upload_traffic) / interval.as_secs(),
+        )
+    }
+}
+
+/// PROC_STAT_PATH is the path of the kernel statistics.
+const PROC_STAT_PATH: &str = "/proc/stat";
+
+/// PROC_NET_TCP_PATHS are the paths of the tcp socket tables.
+const PROC_NET_TCP_PATHS: [&str; 2] = ["/proc/net/tcp", "/proc/net/tcp6"];
+
+/// TCP_ESTABLISHED is the state of the established tcp connection in the tcp socket table.
+const TCP_ESTABLISHED: &str = "01";
+
+/// cpu_times returns the cpu times of the host in seconds, it returns None if the cpu
+/// times is not supported.
+fn cpu_times() -> Option<CpuTimes> {
+    let content = std::fs::read_to_string(PROC_STAT_PATH).ok()?;
+
+    // SAFETY: sysconf has no side effect.
+    let clock_ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if clock_ticks <= 0 {
+        return None;
+    }
+
+    parse_cpu_times(&content, clock_ticks as f64)
+}
+
+/// parse_cpu_times parses the cpu times from the content of /proc/stat, the times in the
+/// content are in clock ticks.
+fn parse_cpu_times(content: &str, clock_ticks: f64) -> Option<CpuTimes> {
+    let line = content.lines().find(|line| line.starts_with("cpu "))?;
+    let mut times = line
+        .split_whitespace()
+        .skip(1)
+        .map(|value| value.parse::<f64>().map(|value| value / clock_ticks));
+
+    let mut next = || times.next().unwrap_or(Ok(0.0)).ok();
+    Some(CpuTimes {
+        user: next()?,
+        nice: next()?,
+        system: next()?,
+        idle: next()?,
+        iowait: next()?,
+        irq: next()?,
+        softirq: next()?,
+        steal: next()?,
+        guest: next()?,
+        guest_nice: next()?,
+    })
+}
+
+/// tcp_connection_count returns the count of the established tcp connections of the host.
+fn tcp_connection_count() -> u64 {
+    PROC_NET_TCP_PATHS
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .map(|content| parse_tcp_connection_count(&content))
+        .sum()
+}
+
+/// parse_tcp_connection_count parses the count of the established tcp connections from the
+/// content of the tcp socket table, the first line is the header.
+fn parse_tcp_connection_count(content: &str) -> u64 {
+    content
+        .lines()
+        .skip(1)
+        .filter(|line| line.split_whitespace().nth(3) == Some(TCP_ESTABLISHED))
+        .count() as u64
+}
+
+/// disk_inodes returns the total and free inodes of the filesystem of the path.
+fn disk_inodes(path: &Path) -> Result<(u64, u64)> {
+    let path = CString::new(path.as_os_str().as_bytes()).map_err(|err| {
+        error!("invalid path {:?}: {}", path, err);
+        Error::InvalidParameter
+    })?;
+
+    // SAFETY: The path is a valid c string and the stats is initialized by statvfs.
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    // The types of the inode counts are different on the platforms.
+    #[allow(clippy::unnecessary_cast)]
+    let inodes = (stats.f_files as u64, stats.f_ffree as u64);
+    Ok(inodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_cpu_times() {
+        let content = "cpu  400 100 200 1000 50 10 20 30 0 0\ncpu0 200 50 100 500 25 5 10 15 0 0\n";
+        let times = parse_cpu_times(content, 100.0).unwrap();
+        assert_eq!(times.user, 4.0);
+        assert_eq!(times.nice, 1.0);
+        assert_eq!(times.system, 2.0);
+        assert_eq!(times.idle, 10.0);
+        assert_eq!(times.iowait, 0.5);
+        assert_eq!(times.steal, 0.3);
+
+        // Test the missing fields of the old kernel are zero.
+        let times = parse_cpu_times("cpu  400 100 200 1000\n", 100.0).unwrap();
+        assert_eq!(times.idle, 10.0);
+        assert_eq!(times.guest_nice, 0.0);
+
+        assert!(parse_cpu_times("intr 1 2 3\n", 100.0).is_none());
+        assert!(parse_cpu_times("cpu  invalid\n", 100.0).is_none());
+    }
+
+    #[test]
+    fn should_parse_tcp_connection_count() {
+        let content = "  sl  local_address rem_address   st tx_queue rx_queue\n   0: 00000000:FA3 00000000:0000 0A 00000000:00000000\n   1: 0100007F:FA3 0100007F:D2F0 01 00000000:00000000\n   2: 0100007F:D2F0 0100007F:FA3 01 00000000:00000000\n";
+        assert_eq!(parse_tcp_connection_count(content), 2);
+        assert_eq!(parse_tcp_connection_count(""), 0);
+    }
 }
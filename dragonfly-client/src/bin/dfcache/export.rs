@@ -19,32 +19,49 @@ use dragonfly_api::dfdaemon::v2::{
     download_persistent_cache_task_response, DownloadPersistentCacheTaskRequest,
 };
 use dragonfly_api::errordetails::v2::Backend;
+use dragonfly_client::grpc::dfdaemon_admin::DfdaemonAdminClient;
+use dragonfly_client::ordered_writer::{OrderedWriter, MAX_PENDING_PIECES_SIZE};
+use dragonfly_client_api::admin::v1::{ListPersistentCacheTasksRequest, TaskFilter};
 use dragonfly_client_core::{
     error::{ErrorType, OrErr},
     Error, Result,
 };
+use dragonfly_client_util::{
+    digest::{calculate_file_hash, Digest},
+    id_generator::persistent_cache_task_id_from_digest,
+};
 use indicatif::{ProgressBar, ProgressState, ProgressStyle};
 use path_absolutize::*;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{cmp::min, fmt::Write};
 use termion::{color, style};
-use tokio::fs::{self, OpenOptions};
-use tokio::io::{AsyncSeekExt, AsyncWriteExt, SeekFrom};
+use tokio::fs::{self, File, OpenOptions};
 use tracing::{debug, error, info};
 
+use dragonfly_client::report::{ErrorClass, Event, OutputFormat, PieceStats, ResultReport};
+
 use super::*;
 
 /// ExportCommand is the subcommand of export.
 #[derive(Debug, Clone, Parser)]
 pub struct ExportCommand {
-    #[arg(help = "Specify the persistent cache task ID to export")]
-    id: String,
+    #[arg(
+        required_unless_present = "digest",
+        help = "Specify the persistent cache task ID to export"
+    )]
+    id: Option<String>,
+
+    #[arg(
+        long = "digest",
+        help = "Specify the content digest of the file, e.g. sha256:6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b, the exported file is verified against it before it is renamed to the output path. If the digest is not specified, the exported file is verified against the digest recorded in the persistent cache task by dfdaemon if any. If the ID is not specified, the persistent cache task is exported by the digest, it only works when dfdaemon generates the persistent cache task ID by the content digest of the same algorithm, refer to persistentCacheTask.idAlgorithm in the dfdaemon config"
+    )]
+    digest: Option<String>,
 
     #[arg(
         long = "transfer-from-dfdaemon",
         default_value_t = false,
-        help = "Specify whether to transfer the content of downloading file from dfdaemon's unix domain socket. If it is true, dfcache will call dfdaemon to download the file, and dfdaemon will return the content of downloading file to dfcache via unix domain socket, and dfcache will copy the content to the output path. If it is false, dfdaemon will download the file and hardlink or copy the file to the output path."
+        help = "Specify whether to transfer the content of downloading file from dfdaemon's unix domain socket. If it is true, dfcache will call dfdaemon to download the file, and dfdaemon will return the content of downloading file to dfcache via unix domain socket, and dfcache will copy the content to the output path. If it is false, dfdaemon will download the file and hardlink or copy the file to the output path. If it is true and the export is interrupted, the partial output is kept beside the output path and the rerun resumes from its end."
     )]
     transfer_from_dfdaemon: bool,
 
@@ -85,6 +102,14 @@ pub struct ExportCommand {
     )]
     endpoint: PathBuf,
 
+    #[arg(
+        long = "output-format",
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Specify the output format. If it is json, dfcache prints the result as a JSON line to stdout instead of the progress and the colored message"
+    )]
+    output_format: OutputFormat,
+
     #[arg(
         short = 'l',
         long,
@@ -133,9 +158,18 @@ impl ExportCommand {
             self.verbose,
         );
 
+        // Record the start time.
+        let start_time = Instant::now();
+
         // Validate the command line arguments.
         if let Err(err) = self.validate_args() {
-            println!(
+            if self.output_format == OutputFormat::Json {
+                ResultReport::failed(ErrorClass::InvalidArgument, &err, start_time.elapsed())
+                    .print();
+                std::process::exit(ErrorClass::InvalidArgument.exit_code());
+            }
+
+            eprintln!(
                 "{}{}{}Validating Failed!{}",
                 color::Fg(color::Red),
                 style::Italic,
@@ -143,7 +177,7 @@ impl ExportCommand {
                 style::Reset
             );
 
-            println!(
+            eprintln!(
                 "{}{}{}****************************************{}",
                 color::Fg(color::Black),
                 style::Italic,
@@ -151,7 +185,7 @@ impl ExportCommand {
                 style::Reset
             );
 
-            println!(
+            eprintln!(
                 "{}{}{}Message:{} {}",
                 color::Fg(color::Cyan),
                 style::Italic,
@@ -160,7 +194,7 @@ impl ExportCommand {
                 err,
             );
 
-            println!(
+            eprintln!(
                 "{}{}{}****************************************{}",
                 color::Fg(color::Black),
                 style::Italic,
@@ -168,7 +202,7 @@ impl ExportCommand {
                 style::Reset
             );
 
-            std::process::exit(1);
+            std::process::exit(ErrorClass::InvalidArgument.exit_code());
         }
 
         // Get dfdaemon download client.
@@ -176,7 +210,13 @@ impl ExportCommand {
             match get_dfdaemon_download_client(self.endpoint.to_path_buf()).await {
                 Ok(client) => client,
                 Err(err) => {
-                    println!(
+                    if self.output_format == OutputFormat::Json {
+                        ResultReport::failed(ErrorClass::Connection, &err, start_time.elapsed())
+                            .print();
+                        std::process::exit(ErrorClass::Connection.exit_code());
+                    }
+
+                    eprintln!(
                         "{}{}{}Connect Dfdaemon Failed!{}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -184,7 +224,7 @@ impl ExportCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -192,7 +232,7 @@ impl ExportCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
                         color::Fg(color::Cyan),
                         style::Italic,
@@ -202,7 +242,7 @@ impl ExportCommand {
                         self.endpoint.to_string_lossy(),
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -210,17 +250,26 @@ impl ExportCommand {
                         style::Reset
                     );
 
-                    std::process::exit(1);
+                    std::process::exit(ErrorClass::Connection.exit_code());
                 }
             };
 
         // Run export command.
         if let Err(err) = self.run(dfdaemon_download_client).await {
+            let class = ErrorClass::from_error(&err);
+            if self.output_format == OutputFormat::Json {
+                let mut report = ResultReport::failed(class, &err, start_time.elapsed());
+                report.task_id = self.task_id().ok();
+                report.path = Some(self.output.to_string_lossy().to_string());
+                report.print();
+                std::process::exit(class.exit_code());
+            }
+
             match err {
                 Error::TonicStatus(status) => {
                     let details = status.details();
                     if let Ok(backend_err) = serde_json::from_slice::<Backend>(details) {
-                        println!(
+                        eprintln!(
                             "{}{}{}Exporting Failed!{}",
                             color::Fg(color::Red),
                             style::Italic,
@@ -228,7 +277,7 @@ impl ExportCommand {
                             style::Reset
                         );
 
-                        println!(
+                        eprintln!(
                             "{}{}{}****************************************{}",
                             color::Fg(color::Black),
                             style::Italic,
@@ -237,7 +286,7 @@ impl ExportCommand {
                         );
 
                         if let Some(status_code) = backend_err.status_code {
-                            println!(
+                            eprintln!(
                                 "{}{}{}Bad Status Code:{} {}",
                                 color::Fg(color::Red),
                                 style::Italic,
@@ -247,7 +296,7 @@ impl ExportCommand {
                             );
                         }
 
-                        println!(
+                        eprintln!(
                             "{}{}{}Message:{} {}",
                             color::Fg(color::Cyan),
                             style::Italic,
@@ -257,7 +306,7 @@ impl ExportCommand {
                         );
 
                         if !backend_err.header.is_empty() {
-                            println!(
+                            eprintln!(
                                 "{}{}{}Header:{}",
                                 color::Fg(color::Cyan),
                                 style::Italic,
@@ -265,11 +314,11 @@ impl ExportCommand {
                                 style::Reset
                             );
                             for (key, value) in backend_err.header.iter() {
-                                println!("  [{}]: {}", key.as_str(), value.as_str());
+                                eprintln!("  [{}]: {}", key.as_str(), value.as_str());
                             }
                         }
 
-                        println!(
+                        eprintln!(
                             "{}{}{}****************************************{}",
                             color::Fg(color::Black),
                             style::Italic,
@@ -277,7 +326,7 @@ impl ExportCommand {
                             style::Reset
                         );
                     } else {
-                        println!(
+                        eprintln!(
                             "{}{}{}Exporting Failed!{}",
                             color::Fg(color::Red),
                             style::Italic,
@@ -285,7 +334,7 @@ impl ExportCommand {
                             style::Reset
                         );
 
-                        println!(
+                        eprintln!(
                             "{}{}{}*********************************{}",
                             color::Fg(color::Black),
                             style::Italic,
@@ -293,7 +342,7 @@ impl ExportCommand {
                             style::Reset
                         );
 
-                        println!(
+                        eprintln!(
                             "{}{}{}Bad Code:{} {}",
                             color::Fg(color::Red),
                             style::Italic,
@@ -302,7 +351,7 @@ impl ExportCommand {
                             status.code()
                         );
 
-                        println!(
+                        eprintln!(
                             "{}{}{}Message:{} {}",
                             color::Fg(color::Cyan),
                             style::Italic,
@@ -312,7 +361,7 @@ impl ExportCommand {
                         );
 
                         if !status.details().is_empty() {
-                            println!(
+                            eprintln!(
                                 "{}{}{}Details:{} {}",
                                 color::Fg(color::Cyan),
                                 style::Italic,
@@ -322,7 +371,7 @@ impl ExportCommand {
                             );
                         }
 
-                        println!(
+                        eprintln!(
                             "{}{}{}*********************************{}",
                             color::Fg(color::Black),
                             style::Italic,
@@ -332,7 +381,7 @@ impl ExportCommand {
                     }
                 }
                 Error::BackendError(err) => {
-                    println!(
+                    eprintln!(
                         "{}{}{}Exporting Failed!{}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -340,7 +389,7 @@ impl ExportCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -348,7 +397,7 @@ impl ExportCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Message:{} {}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -358,7 +407,7 @@ impl ExportCommand {
                     );
 
                     if err.header.is_some() {
-                        println!(
+                        eprintln!(
                             "{}{}{}Header:{}",
                             color::Fg(color::Cyan),
                             style::Italic,
@@ -366,11 +415,11 @@ impl ExportCommand {
                             style::Reset
                         );
                         for (key, value) in err.header.unwrap_or_default().iter() {
-                            println!("  [{}]: {}", key.as_str(), value.to_str().unwrap());
+                            eprintln!("  [{}]: {}", key.as_str(), value.to_str().unwrap());
                         }
                     }
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -379,7 +428,7 @@ impl ExportCommand {
                     );
                 }
                 err => {
-                    println!(
+                    eprintln!(
                         "{}{}{}Exporting Failed!{}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -387,7 +436,7 @@ impl ExportCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -395,7 +444,7 @@ impl ExportCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Message:{} {}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -404,7 +453,7 @@ impl ExportCommand {
                         err
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -414,7 +463,7 @@ impl ExportCommand {
                 }
             }
 
-            std::process::exit(1);
+            std::process::exit(class.exit_code());
         }
 
         Ok(())
@@ -422,21 +471,43 @@ impl ExportCommand {
 
     /// run runs the export command.
     async fn run(&self, dfdaemon_download_client: DfdaemonDownloadClient) -> Result<()> {
+        // Record the start time.
+        let start_time = Instant::now();
+        let json = self.output_format == OutputFormat::Json;
+        let task_id = self.task_id()?;
+        let expected_digest = self
+            .digest
+            .as_deref()
+            .map(|digest| digest.parse::<Digest>().map_err(Error::ValidationError))
+            .transpose()?;
+
+        // The file is exported to the partial path first, and it is renamed to the output path
+        // after it is verified, so the output path never has an incomplete file.
+        let absolute_path = Path::new(&self.output).absolutize()?.to_path_buf();
+        let partial_path = partial_path(&absolute_path, &task_id);
+        info!("export file to: {}", absolute_path.to_string_lossy());
+
         // Dfcache needs to notify dfdaemon to transfer the piece content of downloading file via unix domain socket
         // when the `transfer_from_dfdaemon` is true. Otherwise, dfdaemon will download the file and hardlink or
-        // copy the file to the output path.
+        // copy the file to the partial path.
         let (output_path, need_piece_content) = if self.transfer_from_dfdaemon {
             (None, true)
         } else {
-            let absolute_path = Path::new(&self.output).absolutize()?;
-            info!("export file to: {}", absolute_path.to_string_lossy());
-            (Some(absolute_path.to_string_lossy().to_string()), false)
+            // Remove the stale partial file, because dfdaemon can not hardlink to the existing path.
+            if let Err(err) = fs::remove_file(&partial_path).await {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    error!("remove {:?} failed: {}", partial_path, err);
+                    return Err(err.into());
+                }
+            }
+
+            (Some(partial_path.to_string_lossy().to_string()), false)
         };
 
         // Create dfdaemon client.
         let response = dfdaemon_download_client
             .download_persistent_cache_task(DownloadPersistentCacheTaskRequest {
-                task_id: self.id.clone(),
+                task_id: task_id.clone(),
                 // When scheduler triggers the export task, it will set true. If the export task is
                 // triggered by the user, it will set false.
                 persistent: false,
@@ -454,10 +525,10 @@ impl ExportCommand {
                 error!("download persistent cache task failed: {}", err);
             })?;
 
-        // If transfer_from_dfdaemon is true, then dfcache needs to create the output file and write the
-        // piece content to the output file.
-        let mut f = if self.transfer_from_dfdaemon {
-            if let Some(parent) = self.output.parent() {
+        // If transfer_from_dfdaemon is true, then dfcache needs to write the piece content to the
+        // partial file, the partial file of the interrupted export is reused.
+        let mut partial_output = if self.transfer_from_dfdaemon {
+            if let Some(parent) = absolute_path.parent() {
                 if !parent.exists() {
                     fs::create_dir_all(parent).await.inspect_err(|err| {
                         error!("failed to create directory {:?}: {}", parent, err);
@@ -465,23 +536,27 @@ impl ExportCommand {
                 }
             }
 
-            let f = OpenOptions::new()
-                .create_new(true)
-                .write(true)
-                .mode(dfcache::DEFAULT_OUTPUT_FILE_MODE)
-                .open(&self.output)
-                .await
-                .inspect_err(|err| {
-                    error!("open file {:?} failed: {}", self.output, err);
-                })?;
+            let partial_output =
+                open_partial_output(&partial_path, MAX_PENDING_PIECES_SIZE).await?;
+            if partial_output.offset() > 0 {
+                info!(
+                    "resume export from {} bytes of {:?}",
+                    partial_output.offset(),
+                    partial_path
+                );
+            }
 
-            Some(f)
+            Some(partial_output)
         } else {
             None
         };
 
-        // Initialize progress bar.
-        let pb = ProgressBar::new(0);
+        // Initialize progress bar, it is hidden in the json format.
+        let pb = if json {
+            ProgressBar::hidden()
+        } else {
+            ProgressBar::new(0)
+        };
         pb.set_style(
             ProgressStyle::with_template(
                 "[{elapsed_precise}] [{wide_bar}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
@@ -495,6 +570,8 @@ impl ExportCommand {
 
         //  Download file.
         let mut downloaded = 0;
+        let mut content_length = None;
+        let mut pieces = PieceStats::default();
         let mut out_stream = response.into_inner();
         while let Some(message) = out_stream.message().await.inspect_err(|err| {
             error!("get message failed: {}", err);
@@ -504,42 +581,158 @@ impl ExportCommand {
                     response,
                 )) => {
                     pb.set_length(response.content_length);
+                    content_length = Some(response.content_length);
+
+                    // The partial file longer than the content is not produced by the task,
+                    // export it from the beginning.
+                    if let Some(partial_output) = &mut partial_output {
+                        if partial_output.offset() > response.content_length {
+                            partial_output.get_mut().set_len(0).await?;
+                            partial_output.reset();
+                        }
+                    }
+
+                    if json {
+                        Event::Started {
+                            task_id: message.task_id,
+                            peer_id: message.peer_id,
+                            content_length: response.content_length,
+                        }
+                        .print();
+                    }
                 }
                 Some(download_persistent_cache_task_response::Response::DownloadPieceFinishedResponse(
                     response,
                 )) => {
                     let piece = response.piece.ok_or(Error::InvalidParameter)?;
+                    pieces.record(&piece);
 
-                    // Dfcache needs to write the piece content to the output file.
-                    if let Some(f) = &mut f {
-                        f.seek(SeekFrom::Start(piece.offset))
-                            .await
-                            .inspect_err(|err| {
-                                error!("seek {:?} failed: {}", self.output, err);
-                            })?;
-
+                    // Dfcache needs to write the piece content to the partial file.
+                    if let Some(partial_output) = &mut partial_output {
                         let content = piece.content.ok_or(Error::InvalidParameter)?;
-                        f.write_all(&content).await.inspect_err(|err| {
-                            error!("write {:?} failed: {}", self.output, err);
-                        })?;
-
-                        debug!("copy piece {} to {:?} success", piece.number, self.output);
+                        partial_output.write(piece.offset, content).await?;
+                        debug!("copy piece {} to {:?} success", piece.number, partial_path);
                     };
 
                     downloaded += piece.length;
                     let position = min(downloaded + piece.length, pb.length().unwrap_or(0));
                     pb.set_position(position);
+
+                    if json {
+                        Event::Progress {
+                            task_id: task_id.clone(),
+                            piece_number: piece.number,
+                            downloaded_length: downloaded,
+                            content_length: pb.length().unwrap_or(0),
+                        }
+                        .print();
+                    }
                 }
                 None => {}
             }
         }
 
+        if let Some(partial_output) = partial_output {
+            partial_output
+                .finish(Some(content_length.ok_or(Error::InvalidParameter)?))
+                .await?
+                .sync_all()
+                .await?;
+        }
+
+        // Verify the partial file against the specified digest, or the digest recorded in the
+        // persistent cache task by default. The mismatched partial file is removed, because it
+        // can not be resumed.
+        let expected_digest = match expected_digest {
+            Some(expected_digest) => Some(expected_digest),
+            None => self.recorded_digest(&task_id).await?,
+        };
+
+        if let Some(expected_digest) = expected_digest {
+            if let Err(err) = verify_digest(&partial_path, &expected_digest).await {
+                fs::remove_file(&partial_path)
+                    .await
+                    .unwrap_or_else(|err| error!("remove {:?} failed: {}", partial_path, err));
+                return Err(err);
+            }
+        }
+
+        fs::rename(&partial_path, &absolute_path)
+            .await
+            .inspect_err(|err| {
+                error!(
+                    "rename {:?} to {:?} failed: {}",
+                    partial_path, absolute_path, err
+                );
+            })?;
+
         pb.finish_with_message("downloaded");
+        if json {
+            let mut report = ResultReport::succeeded(start_time.elapsed());
+            report.task_id = Some(task_id);
+            report.path = Some(self.output.to_string_lossy().to_string());
+            report.content_length = content_length;
+            report.pieces = Some(pieces);
+            report.print();
+        }
+
         Ok(())
     }
 
+    /// recorded_digest returns the content digest recorded in the persistent cache task by
+    /// dfdaemon, it is none if the id of the persistent cache task is not generated by the
+    /// content digest.
+    async fn recorded_digest(&self, task_id: &str) -> Result<Option<Digest>> {
+        let dfdaemon_admin_client = DfdaemonAdminClient::new_unix(self.endpoint.clone()).await?;
+        let response = dfdaemon_admin_client
+            .list_persistent_cache_tasks(ListPersistentCacheTasksRequest {
+                filter: Some(TaskFilter {
+                    task_id: Some(task_id.to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .await?;
+
+        response
+            .tasks
+            .into_iter()
+            .next()
+            .and_then(|task| task.digest)
+            .map(|digest| digest.parse::<Digest>().map_err(Error::ValidationError))
+            .transpose()
+    }
+
+    /// task_id returns the persistent cache task id to export, it is the hex encoded digest if
+    /// the task is exported by the content digest.
+    fn task_id(&self) -> Result<String> {
+        if let Some(id) = self.id.as_ref() {
+            return Ok(id.clone());
+        }
+
+        let digest = self
+            .digest
+            .as_deref()
+            .ok_or(Error::InvalidParameter)?
+            .parse::<Digest>()
+            .map_err(Error::ValidationError)?;
+        persistent_cache_task_id_from_digest(&digest)
+    }
+
     /// validate_args validates the command line arguments.
     fn validate_args(&self) -> Result<()> {
+        // The verbose logs are printed to stdout, which breaks the json output.
+        if self.verbose && self.output_format == OutputFormat::Json {
+            return Err(Error::ValidationError(
+                "verbose is not supported when the output format is json".to_string(),
+            ));
+        }
+
+        self.task_id()?;
+        if let Some(digest) = self.digest.as_deref() {
+            digest.parse::<Digest>().map_err(Error::ValidationError)?;
+        }
+
         let absolute_path = Path::new(&self.output).absolutize()?;
         match absolute_path.parent() {
             Some(parent_path) => {
@@ -568,3 +761,134 @@ impl ExportCommand {
         Ok(())
     }
 }
+
+/// PARTIAL_FILE_SUFFIX is the suffix of the partial file of the export.
+const PARTIAL_FILE_SUFFIX: &str = "dfcache-partial";
+
+/// partial_path returns the path of the partial file of the export, it is a hidden file in the
+/// directory of the output path, and it is named by the task id to avoid resuming the partial
+/// file of the other task.
+fn partial_path(output: &Path, task_id: &str) -> PathBuf {
+    let file_name = output
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    output.with_file_name(format!(
+        ".{}.{}.{}",
+        file_name,
+        task_id.chars().take(16).collect::<String>(),
+        PARTIAL_FILE_SUFFIX
+    ))
+}
+
+/// verify_digest verifies the content digest of the file.
+async fn verify_digest(path: &Path, expected: &Digest) -> Result<()> {
+    let algorithm = expected.algorithm();
+    let file_path = path.to_path_buf();
+    let actual = tokio::task::spawn_blocking(move || calculate_file_hash(algorithm, &file_path))
+        .await
+        .or_err(ErrorType::AsyncRuntimeError)??;
+
+    if !actual.encoded().eq_ignore_ascii_case(expected.encoded()) {
+        error!(
+            "digest of {:?} mismatch, expected: {}, actual: {}",
+            path, expected, actual
+        );
+        return Err(Error::DigestMismatch(
+            expected.to_string(),
+            actual.to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// open_partial_output opens the partial file of the export in append mode, the pieces are
+/// written in order, so the partial file is always a prefix of the content and the content
+/// written by the interrupted export is reused.
+async fn open_partial_output(path: &Path, max_pending_size: u64) -> Result<OrderedWriter<File>> {
+    let f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(dfcache::DEFAULT_OUTPUT_FILE_MODE)
+        .open(path)
+        .await
+        .inspect_err(|err| {
+            error!("open file {:?} failed: {}", path, err);
+        })?;
+
+    let offset = f.metadata().await?.len();
+    Ok(OrderedWriter::with_offset(f, offset, max_pending_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn should_get_partial_path() {
+        assert_eq!(
+            partial_path(
+                Path::new("/tmp/data/file.bin"),
+                "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b"
+            ),
+            PathBuf::from("/tmp/data/.file.bin.6b86b273ff34fce1.dfcache-partial")
+        );
+    }
+
+    #[tokio::test]
+    async fn should_resume_partial_output() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("partial");
+
+        // The interrupted export writes the first piece, the third piece is buffered.
+        let mut partial_output = open_partial_output(&path, MAX_PENDING_PIECES_SIZE)
+            .await
+            .unwrap();
+        partial_output.write(0, b"aaaa".to_vec()).await.unwrap();
+        partial_output.write(8, b"cc".to_vec()).await.unwrap();
+        assert_eq!(partial_output.offset(), 4);
+        drop(partial_output);
+
+        // The rerun skips the written piece and writes the others in order.
+        let mut partial_output = open_partial_output(&path, MAX_PENDING_PIECES_SIZE)
+            .await
+            .unwrap();
+        assert_eq!(partial_output.offset(), 4);
+        partial_output.write(8, b"cc".to_vec()).await.unwrap();
+        partial_output.write(0, b"xxxx".to_vec()).await.unwrap();
+        partial_output.write(4, b"bbbb".to_vec()).await.unwrap();
+        partial_output.finish(Some(10)).await.unwrap();
+        assert_eq!(fs::read(&path).await.unwrap(), b"aaaabbbbcc");
+
+        // The missing piece fails the finish.
+        let mut partial_output =
+            open_partial_output(&dir.path().join("missing"), MAX_PENDING_PIECES_SIZE)
+                .await
+                .unwrap();
+        partial_output.write(4, b"bbbb".to_vec()).await.unwrap();
+        assert!(partial_output.finish(Some(8)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn should_verify_digest() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file");
+        fs::write(&path, "1").await.unwrap();
+
+        let digest = "sha256:6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b"
+            .parse::<Digest>()
+            .unwrap();
+        assert!(verify_digest(&path, &digest).await.is_ok());
+
+        let digest = "sha256:0000000000000000000000000000000000000000000000000000000000000000"
+            .parse::<Digest>()
+            .unwrap();
+        assert!(matches!(
+            verify_digest(&path, &digest).await,
+            Err(Error::DigestMismatch(_, _))
+        ));
+    }
+}
@@ -15,6 +15,7 @@
  */
 
 use clap::Parser;
+use dragonfly_api::common::v2::PersistentCacheTask;
 use dragonfly_api::dfdaemon::v2::UploadPersistentCacheTaskRequest;
 use dragonfly_client_config::dfcache::default_dfcache_persistent_replica_count;
 use dragonfly_client_core::{
@@ -23,10 +24,16 @@ use dragonfly_client_core::{
 };
 use indicatif::{ProgressBar, ProgressStyle};
 use path_absolutize::*;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use termion::{color, style};
-use tracing::info;
+use tokio::fs;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use dragonfly_client::report::{ErrorClass, OutputFormat, ResultReport};
+use dragonfly_client::walker::get_files;
 
 use super::*;
 
@@ -42,10 +49,18 @@ pub struct ImportCommand {
     #[arg(
         long = "id",
         required = false,
-        help = "Specify the id of the persistent cache task, its length must be 64 bytes. If id is none, dfdaemon will generate the new task id based on the file content, tag and application by wyhash algorithm."
+        help = "Specify the id of the persistent cache task, its length must be 64 bytes. If id is none, dfdaemon will generate the new task id based on the file content, tag and application by wyhash algorithm, or use the content digest of the file as the task id if persistentCacheTask.idAlgorithm is sha256 or blake3 in the dfdaemon config. In recursive mode, it is the id of the index task."
     )]
     id: Option<String>,
 
+    #[arg(
+        short = 'r',
+        long = "recursive",
+        default_value_t = false,
+        help = "Specify whether to import the directory recursively. Every file in the directory is imported as a persistent cache task, and an index task mapping the relative paths of the files to the task IDs is imported at last, its ID is returned"
+    )]
+    recursive: bool,
+
     #[arg(
         long = "persistent-replica-count",
         default_value_t = default_dfcache_persistent_replica_count(),
@@ -91,6 +106,14 @@ pub struct ImportCommand {
     )]
     endpoint: PathBuf,
 
+    #[arg(
+        long = "output-format",
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Specify the output format. If it is json, dfcache prints the result as a JSON line to stdout instead of the progress and the colored message"
+    )]
+    output_format: OutputFormat,
+
     #[arg(
         short = 'l',
         long,
@@ -139,9 +162,18 @@ impl ImportCommand {
             self.verbose,
         );
 
+        // Record the start time.
+        let start_time = Instant::now();
+
         // Validate the command line arguments.
         if let Err(err) = self.validate_args() {
-            println!(
+            if self.output_format == OutputFormat::Json {
+                ResultReport::failed(ErrorClass::InvalidArgument, &err, start_time.elapsed())
+                    .print();
+                std::process::exit(ErrorClass::InvalidArgument.exit_code());
+            }
+
+            eprintln!(
                 "{}{}{}Validating Failed!{}",
                 color::Fg(color::Red),
                 style::Italic,
@@ -149,7 +181,7 @@ impl ImportCommand {
                 style::Reset
             );
 
-            println!(
+            eprintln!(
                 "{}{}{}****************************************{}",
                 color::Fg(color::Black),
                 style::Italic,
@@ -157,7 +189,7 @@ impl ImportCommand {
                 style::Reset
             );
 
-            println!(
+            eprintln!(
                 "{}{}{}Message:{} {}",
                 color::Fg(color::Cyan),
                 style::Italic,
@@ -166,7 +198,7 @@ impl ImportCommand {
                 err,
             );
 
-            println!(
+            eprintln!(
                 "{}{}{}****************************************{}",
                 color::Fg(color::Black),
                 style::Italic,
@@ -174,7 +206,7 @@ impl ImportCommand {
                 style::Reset
             );
 
-            std::process::exit(1);
+            std::process::exit(ErrorClass::InvalidArgument.exit_code());
         }
 
         // Get dfdaemon download client.
@@ -182,7 +214,13 @@ impl ImportCommand {
             match get_dfdaemon_download_client(self.endpoint.to_path_buf()).await {
                 Ok(client) => client,
                 Err(err) => {
-                    println!(
+                    if self.output_format == OutputFormat::Json {
+                        ResultReport::failed(ErrorClass::Connection, &err, start_time.elapsed())
+                            .print();
+                        std::process::exit(ErrorClass::Connection.exit_code());
+                    }
+
+                    eprintln!(
                         "{}{}{}Connect Dfdaemon Failed!{}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -190,7 +228,7 @@ impl ImportCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -198,7 +236,7 @@ impl ImportCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
                         color::Fg(color::Cyan),
                         style::Italic,
@@ -208,7 +246,7 @@ impl ImportCommand {
                         self.endpoint.to_string_lossy(),
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -216,15 +254,29 @@ impl ImportCommand {
                         style::Reset
                     );
 
-                    std::process::exit(1);
+                    std::process::exit(ErrorClass::Connection.exit_code());
                 }
             };
 
         // Run import sub command.
-        if let Err(err) = self.run(dfdaemon_download_client).await {
+        let result = if self.recursive {
+            self.run_recursive(dfdaemon_download_client).await
+        } else {
+            self.run(dfdaemon_download_client).await
+        };
+
+        if let Err(err) = result {
+            let class = ErrorClass::from_error(&err);
+            if self.output_format == OutputFormat::Json {
+                let mut report = ResultReport::failed(class, &err, start_time.elapsed());
+                report.path = Some(self.path.to_string_lossy().to_string());
+                report.print();
+                std::process::exit(class.exit_code());
+            }
+
             match err {
                 Error::TonicStatus(status) => {
-                    println!(
+                    eprintln!(
                         "{}{}{}Importing Failed!{}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -232,7 +284,7 @@ impl ImportCommand {
                         style::Reset,
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}*********************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -240,7 +292,7 @@ impl ImportCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Bad Code:{} {}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -249,7 +301,7 @@ impl ImportCommand {
                         status.code()
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Message:{} {}",
                         color::Fg(color::Cyan),
                         style::Italic,
@@ -258,7 +310,7 @@ impl ImportCommand {
                         status.message()
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Details:{} {}",
                         color::Fg(color::Cyan),
                         style::Italic,
@@ -267,7 +319,7 @@ impl ImportCommand {
                         std::str::from_utf8(status.details()).unwrap()
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}*********************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -276,7 +328,7 @@ impl ImportCommand {
                     );
                 }
                 err => {
-                    println!(
+                    eprintln!(
                         "{}{}{}Importing Failed!{}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -284,7 +336,7 @@ impl ImportCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -292,7 +344,7 @@ impl ImportCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Message:{} {}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -301,7 +353,7 @@ impl ImportCommand {
                         err
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -311,7 +363,7 @@ impl ImportCommand {
                 }
             }
 
-            std::process::exit(1);
+            std::process::exit(class.exit_code());
         }
 
         Ok(())
@@ -322,7 +374,15 @@ impl ImportCommand {
         let absolute_path = Path::new(&self.path).absolutize()?;
         info!("import file: {}", absolute_path.to_string_lossy());
 
-        let pb = ProgressBar::new_spinner();
+        // Record the start time.
+        let start_time = Instant::now();
+
+        // Hide the spinner in the json format.
+        let pb = if self.output_format == OutputFormat::Json {
+            ProgressBar::hidden()
+        } else {
+            ProgressBar::new_spinner()
+        };
         pb.enable_steady_tick(DEFAULT_PROGRESS_BAR_STEADY_TICK_INTERVAL);
         pb.set_style(
             ProgressStyle::with_template("{spinner:.blue} {msg}")
@@ -331,10 +391,128 @@ impl ImportCommand {
         );
         pb.set_message("Importing...");
 
-        let persistent_cache_task = dfdaemon_download_client
+        let persistent_cache_task = self
+            .import_file(&dfdaemon_download_client, &absolute_path, self.id.clone())
+            .await?;
+
+        pb.finish_with_message(format!("Done: {}", persistent_cache_task.id));
+        if self.output_format == OutputFormat::Json {
+            let mut report = ResultReport::succeeded(start_time.elapsed());
+            report.task_id = Some(persistent_cache_task.id);
+            report.path = Some(absolute_path.to_string_lossy().to_string());
+            report.content_length = Some(persistent_cache_task.content_length);
+            report.print();
+        }
+
+        Ok(())
+    }
+
+    /// run_recursive runs the import sub command for the directory, the files in the directory
+    /// are imported one by one, and then the index of the directory is imported as the index task.
+    async fn run_recursive(&self, dfdaemon_download_client: DfdaemonDownloadClient) -> Result<()> {
+        let root_dir = Path::new(&self.path).absolutize()?.to_path_buf();
+        info!("import directory: {}", root_dir.to_string_lossy());
+
+        // Record the start time.
+        let start_time = Instant::now();
+
+        let files = get_files(&root_dir).await?;
+        if files.is_empty() {
+            return Err(Error::ValidationError(format!(
+                "directory {} has no file to import",
+                root_dir.display()
+            )));
+        }
+
+        // Hide the spinner in the json format.
+        let pb = if self.output_format == OutputFormat::Json {
+            ProgressBar::hidden()
+        } else {
+            ProgressBar::new_spinner()
+        };
+        pb.enable_steady_tick(DEFAULT_PROGRESS_BAR_STEADY_TICK_INTERVAL);
+        pb.set_style(
+            ProgressStyle::with_template("{spinner:.blue} {msg}")
+                .unwrap()
+                .tick_strings(&["⣾", "⣽", "⣻", "⢿", "⡿", "⣟", "⣯", "⣷"]),
+        );
+
+        let mut index = Index::default();
+        let mut report = ResultReport::succeeded(Duration::ZERO);
+        for (i, path) in files.iter().enumerate() {
+            let relative_path = path
+                .strip_prefix(&root_dir)
+                .or_err(ErrorType::ParseError)?
+                .to_string_lossy()
+                .to_string();
+            pb.set_message(format!(
+                "Importing {} ({}/{})...",
+                relative_path,
+                i + 1,
+                files.len()
+            ));
+
+            let file_start_time = Instant::now();
+            let persistent_cache_task = self
+                .import_file(&dfdaemon_download_client, path, None)
+                .await
+                .inspect_err(|err| {
+                    error!("import file {} failed: {}", path.display(), err);
+                })?;
+
+            let mut file_report = ResultReport::succeeded(file_start_time.elapsed());
+            file_report.task_id = Some(persistent_cache_task.id.clone());
+            file_report.path = Some(path.to_string_lossy().to_string());
+            file_report.content_length = Some(persistent_cache_task.content_length);
+            report.files.push(file_report);
+
+            index.files.push(IndexEntry {
+                path: relative_path,
+                task_id: persistent_cache_task.id,
+                content_length: persistent_cache_task.content_length,
+            });
+        }
+
+        // Import the index by a temporary file, it is removed after importing.
+        pb.set_message("Importing index...");
+        let index_path = write_index(&root_dir, &index).await?;
+
+        let result = self
+            .import_file(&dfdaemon_download_client, &index_path, self.id.clone())
+            .await;
+        fs::remove_file(&index_path).await.unwrap_or_else(|err| {
+            error!("remove index {} failed: {}", index_path.display(), err);
+        });
+        let index_task = result?;
+
+        pb.finish_with_message(format!(
+            "Done: {} ({} files)",
+            index_task.id,
+            index.files.len()
+        ));
+        if self.output_format == OutputFormat::Json {
+            report.cost_ms = start_time.elapsed().as_millis() as u64;
+            report.task_id = Some(index_task.id);
+            report.path = Some(root_dir.to_string_lossy().to_string());
+            report.content_length =
+                Some(index.files.iter().map(|entry| entry.content_length).sum());
+            report.print();
+        }
+
+        Ok(())
+    }
+
+    /// import_file imports the file as a persistent cache task.
+    async fn import_file(
+        &self,
+        dfdaemon_download_client: &DfdaemonDownloadClient,
+        path: &Path,
+        task_id: Option<String>,
+    ) -> Result<PersistentCacheTask> {
+        dfdaemon_download_client
             .upload_persistent_cache_task(UploadPersistentCacheTaskRequest {
-                task_id: self.id.clone(),
-                path: absolute_path.to_string_lossy().to_string(),
+                task_id,
+                path: path.to_string_lossy().to_string(),
                 persistent_replica_count: self.persistent_replica_count,
                 tag: self.tag.clone(),
                 application: self.application.clone(),
@@ -346,14 +524,18 @@ impl ImportCommand {
                         .or_err(ErrorType::ParseError)?,
                 ),
             })
-            .await?;
-
-        pb.finish_with_message(format!("Done: {}", persistent_cache_task.id));
-        Ok(())
+            .await
     }
 
     /// validate_args validates the command line arguments.
     fn validate_args(&self) -> Result<()> {
+        // The verbose logs are printed to stdout, which breaks the json output.
+        if self.verbose && self.output_format == OutputFormat::Json {
+            return Err(Error::ValidationError(
+                "verbose is not supported when the output format is json".to_string(),
+            ));
+        }
+
         if self.ttl < Duration::from_secs(5 * 60)
             || self.ttl > Duration::from_secs(7 * 24 * 60 * 60)
         {
@@ -372,9 +554,16 @@ impl ImportCommand {
             }
         }
 
-        if self.path.is_dir() {
+        if self.recursive && !self.path.is_dir() {
             return Err(Error::ValidationError(format!(
-                "path {} is a directory",
+                "path {} is not a directory",
+                self.path.display()
+            )));
+        }
+
+        if !self.recursive && self.path.is_dir() {
+            return Err(Error::ValidationError(format!(
+                "path {} is a directory, please use --recursive to import it",
                 self.path.display()
             )));
         }
@@ -389,3 +578,104 @@ impl ImportCommand {
         Ok(())
     }
 }
+
+/// write_index writes the index to the temporary file and returns its path. The dfdaemon reads
+/// the file by the path, so the index is written in the imported directory, which the dfdaemon
+/// can read. If the directory is read-only, the index is written in the temporary directory of
+/// the system instead.
+async fn write_index(root_dir: &Path, index: &Index) -> Result<PathBuf> {
+    let content = serde_json::to_vec(index).or_err(ErrorType::SerializeError)?;
+    let file_name = format!(".dfcache-index-{}.json", Uuid::new_v4());
+
+    let index_path = root_dir.join(&file_name);
+    match fs::write(&index_path, &content).await {
+        Ok(_) => return Ok(index_path),
+        Err(err) => {
+            warn!(
+                "write index {} failed, fallback to the temporary directory: {}",
+                index_path.display(),
+                err
+            );
+        }
+    }
+
+    let index_path = std::env::temp_dir().join(&file_name);
+    fs::write(&index_path, &content).await.inspect_err(|err| {
+        error!("write index {} failed: {}", index_path.display(), err);
+    })?;
+
+    Ok(index_path)
+}
+
+/// Index is the index of the imported directory, it is imported as the index task and maps the
+/// relative paths of the files to their persistent cache task ids.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Index {
+    /// files are the imported files ordered by the relative path.
+    pub files: Vec<IndexEntry>,
+}
+
+/// IndexEntry is the imported file in the index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexEntry {
+    /// path is the path of the file relative to the imported directory.
+    pub path: String,
+
+    /// task_id is the id of the persistent cache task of the file.
+    pub task_id: String,
+
+    /// content_length is the content length of the file.
+    pub content_length: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn should_serialize_index() {
+        let index = Index {
+            files: vec![IndexEntry {
+                path: "a/b".to_string(),
+                task_id: "task-id".to_string(),
+                content_length: 1024,
+            }],
+        };
+
+        let content = serde_json::to_string(&index).unwrap();
+        assert_eq!(
+            content,
+            r#"{"files":[{"path":"a/b","taskId":"task-id","contentLength":1024}]}"#
+        );
+        assert_eq!(serde_json::from_str::<Index>(&content).unwrap(), index);
+    }
+
+    #[tokio::test]
+    async fn should_write_index_in_directory() {
+        let dir = tempdir().unwrap();
+        let index = Index {
+            files: vec![IndexEntry {
+                path: "a".to_string(),
+                task_id: "task-id".to_string(),
+                content_length: 1,
+            }],
+        };
+
+        let index_path = write_index(dir.path(), &index).await.unwrap();
+        assert_eq!(index_path.parent(), Some(dir.path()));
+        assert_eq!(
+            serde_json::from_slice::<Index>(&fs::read(&index_path).await.unwrap()).unwrap(),
+            index
+        );
+
+        // The index is written in the temporary directory if the directory is not writable.
+        let index_path = write_index(&dir.path().join("missing"), &index)
+            .await
+            .unwrap();
+        assert_eq!(index_path.parent(), Some(std::env::temp_dir().as_path()));
+        fs::remove_file(&index_path).await.unwrap();
+    }
+}
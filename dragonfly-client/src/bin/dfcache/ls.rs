@@ -0,0 +1,413 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use chrono::{DateTime, Local};
+use clap::Parser;
+use dragonfly_api::dfdaemon::v2::StatPersistentCacheTaskRequest;
+use dragonfly_client::grpc::dfdaemon_admin::DfdaemonAdminClient;
+use dragonfly_client_api::admin::v1::{
+    ListPersistentCacheTasksRequest, PersistentCacheTask, TaskState,
+};
+use dragonfly_client_core::{
+    error::{ErrorType, OrErr},
+    Error, Result,
+};
+use humantime::format_duration;
+use std::time::{Duration, Instant};
+use tabled::{
+    settings::{object::Rows, Alignment, Modify, Style},
+    Table, Tabled,
+};
+use termion::{color, style};
+use tracing::warn;
+
+use dragonfly_client::report::{ErrorClass, OutputFormat, ResultReport};
+
+use super::*;
+
+/// ListCommand is the subcommand of ls.
+#[derive(Debug, Clone, Parser)]
+pub struct ListCommand {
+    #[arg(
+        long = "no-replica",
+        default_value_t = false,
+        help = "Specify whether to skip querying the replica count of the persistent cache tasks from the scheduler"
+    )]
+    no_replica: bool,
+
+    #[arg(
+        short = 'e',
+        long = "endpoint",
+        default_value_os_t = dfdaemon::default_download_unix_socket_path(),
+        help = "Endpoint of dfdaemon's GRPC server"
+    )]
+    endpoint: PathBuf,
+
+    #[arg(
+        long = "output-format",
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Specify the output format. If it is json, dfcache prints the result as a JSON line to stdout instead of the table"
+    )]
+    output_format: OutputFormat,
+
+    #[arg(
+        short = 'l',
+        long,
+        default_value = "info",
+        help = "Specify the logging level [trace, debug, info, warn, error]"
+    )]
+    log_level: Level,
+
+    #[arg(
+        long,
+        default_value_os_t = dfcache::default_dfcache_log_dir(),
+        help = "Specify the log directory"
+    )]
+    log_dir: PathBuf,
+
+    #[arg(
+        long,
+        default_value_t = 6,
+        help = "Specify the max number of log files"
+    )]
+    log_max_files: usize,
+
+    #[arg(
+        long = "verbose",
+        default_value_t = false,
+        help = "Specify whether to print log"
+    )]
+    verbose: bool,
+}
+
+/// Implement the execute for ListCommand.
+impl ListCommand {
+    /// execute executes the ls command.
+    pub async fn execute(&self) -> Result<()> {
+        // Parse command line arguments.
+        Args::parse();
+
+        // Initialize tracing.
+        let _guards = init_tracing(
+            dfcache::NAME,
+            self.log_dir.clone(),
+            self.log_level,
+            self.log_max_files,
+            None,
+            false,
+            self.verbose,
+        );
+
+        // Record the start time.
+        let start_time = Instant::now();
+
+        // The verbose logs are printed to stdout, which breaks the json output.
+        if self.verbose && self.output_format == OutputFormat::Json {
+            let err = Error::ValidationError(
+                "verbose is not supported when the output format is json".to_string(),
+            );
+            ResultReport::failed(ErrorClass::InvalidArgument, &err, start_time.elapsed()).print();
+            std::process::exit(ErrorClass::InvalidArgument.exit_code());
+        }
+
+        // Get dfdaemon download client and admin client.
+        let clients = match get_dfdaemon_download_client(self.endpoint.to_path_buf()).await {
+            Ok(dfdaemon_download_client) => {
+                DfdaemonAdminClient::new_unix(self.endpoint.to_path_buf())
+                    .await
+                    .map(|dfdaemon_admin_client| (dfdaemon_download_client, dfdaemon_admin_client))
+            }
+            Err(err) => Err(err),
+        };
+
+        let (dfdaemon_download_client, dfdaemon_admin_client) = match clients {
+            Ok(clients) => clients,
+            Err(err) => {
+                if self.output_format == OutputFormat::Json {
+                    ResultReport::failed(ErrorClass::Connection, &err, start_time.elapsed())
+                        .print();
+                    std::process::exit(ErrorClass::Connection.exit_code());
+                }
+
+                eprintln!(
+                    "{}{}{}Connect Dfdaemon Failed!{}",
+                    color::Fg(color::Red),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                eprintln!(
+                    "{}{}{}****************************************{}",
+                    color::Fg(color::Black),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                eprintln!(
+                    "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
+                    color::Fg(color::Cyan),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset,
+                    err,
+                    self.endpoint.to_string_lossy(),
+                );
+
+                eprintln!(
+                    "{}{}{}****************************************{}",
+                    color::Fg(color::Black),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                std::process::exit(ErrorClass::Connection.exit_code());
+            }
+        };
+
+        // Run ls sub command.
+        if let Err(err) = self
+            .run(dfdaemon_download_client, dfdaemon_admin_client)
+            .await
+        {
+            let class = ErrorClass::from_error(&err);
+            if self.output_format == OutputFormat::Json {
+                ResultReport::failed(class, &err, start_time.elapsed()).print();
+                std::process::exit(class.exit_code());
+            }
+
+            eprintln!(
+                "{}{}{}Listing Failed!{}",
+                color::Fg(color::Red),
+                style::Italic,
+                style::Bold,
+                style::Reset
+            );
+
+            eprintln!(
+                "{}{}{}****************************************{}",
+                color::Fg(color::Black),
+                style::Italic,
+                style::Bold,
+                style::Reset
+            );
+
+            eprintln!(
+                "{}{}{}Message:{} {}",
+                color::Fg(color::Red),
+                style::Italic,
+                style::Bold,
+                style::Reset,
+                err
+            );
+
+            eprintln!(
+                "{}{}{}****************************************{}",
+                color::Fg(color::Black),
+                style::Italic,
+                style::Bold,
+                style::Reset
+            );
+
+            std::process::exit(class.exit_code());
+        }
+
+        Ok(())
+    }
+
+    /// run runs the ls command.
+    async fn run(
+        &self,
+        dfdaemon_download_client: DfdaemonDownloadClient,
+        dfdaemon_admin_client: DfdaemonAdminClient,
+    ) -> Result<()> {
+        // Record the start time.
+        let start_time = Instant::now();
+
+        // List all the persistent cache tasks in the local storage page by page.
+        let mut tasks = Vec::new();
+        let mut page_token = String::new();
+        loop {
+            let response = dfdaemon_admin_client
+                .list_persistent_cache_tasks(ListPersistentCacheTasksRequest {
+                    page_token,
+                    ..Default::default()
+                })
+                .await?;
+
+            tasks.extend(response.tasks);
+            if response.next_page_token.is_empty() {
+                break;
+            }
+
+            page_token = response.next_page_token;
+        }
+
+        // The replica count is maintained by the scheduler, it is unknown if the scheduler
+        // is unavailable, e.g. dfdaemon runs in standalone mode.
+        let mut replica_counts = Vec::with_capacity(tasks.len());
+        for task in tasks.iter() {
+            if self.no_replica {
+                replica_counts.push(None);
+                continue;
+            }
+
+            match dfdaemon_download_client
+                .stat_persistent_cache_task(StatPersistentCacheTaskRequest {
+                    task_id: task.id.clone(),
+                })
+                .await
+            {
+                Ok(response) => replica_counts.push(Some((
+                    response.current_persistent_replica_count,
+                    response.persistent_replica_count,
+                ))),
+                Err(err) => {
+                    warn!("stat persistent cache task {} failed: {}", task.id, err);
+                    replica_counts.push(None);
+                }
+            }
+        }
+
+        // Print the tasks as a JSON line in the json format.
+        if self.output_format == OutputFormat::Json {
+            let mut report = ResultReport::succeeded(start_time.elapsed());
+            report.content_length = Some(tasks.iter().map(|task| task.content_length).sum());
+            for (task, replica_count) in tasks.iter().zip(replica_counts) {
+                let mut file = ResultReport::succeeded(Duration::ZERO);
+                file.task_id = Some(task.id.clone());
+                file.content_length = Some(task.content_length);
+                file.task = Some(serde_json::json!({
+                    "id": task.id,
+                    "state": state_name(task),
+                    "persistent": task.persistent,
+                    "ttlSeconds": ttl(task)?.as_secs(),
+                    "expired": task.expired,
+                    "pieceLength": task.piece_length,
+                    "contentLength": task.content_length,
+                    "digest": task.digest,
+                    "currentPersistentReplicaCount": replica_count.map(|(current, _)| current),
+                    "persistentReplicaCount": replica_count.map(|(_, desired)| desired),
+                    "createdAt": task.created_at.as_ref().map(|created_at| created_at.seconds),
+                }));
+                report.files.push(file);
+            }
+
+            report.print();
+            return Ok(());
+        }
+
+        // Define the table struct for printing.
+        #[derive(Debug, Default, Tabled)]
+        #[tabled(rename_all = "UPPERCASE")]
+        struct TableTask {
+            id: String,
+            state: String,
+            persistent: bool,
+            #[tabled(rename = "REPLICA COUNT")]
+            replica_count: String,
+            #[tabled(rename = "CONTENT LENGTH")]
+            content_length: String,
+            ttl: String,
+            #[tabled(rename = "CREATED")]
+            created_at: String,
+        }
+
+        let mut table_tasks = Vec::with_capacity(tasks.len());
+        for (task, replica_count) in tasks.iter().zip(replica_counts) {
+            let mut table_task = TableTask {
+                id: task.id.clone(),
+                state: state_name(task).to_string(),
+                persistent: task.persistent,
+                // Print the replica count as current/desired.
+                replica_count: match replica_count {
+                    Some((current, desired)) => format!("{}/{}", current, desired),
+                    None => "-".to_string(),
+                },
+                // Convert content_length to human readable format.
+                content_length: bytesize::to_string(task.content_length, true),
+                // Convert ttl to human readable format.
+                ttl: format_duration(ttl(task)?).to_string(),
+                ..Default::default()
+            };
+
+            // Convert created_at to human readable format.
+            if let Some(created_at) = task.created_at.as_ref() {
+                if let Some(date_time) =
+                    DateTime::from_timestamp(created_at.seconds, created_at.nanos as u32)
+                {
+                    table_task.created_at = date_time
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string();
+                }
+            }
+
+            table_tasks.push(table_task);
+        }
+
+        // Create a table and print it.
+        let mut table = Table::new(table_tasks);
+        table
+            .with(Style::blank())
+            .with(Modify::new(Rows::first()).with(Alignment::center()));
+        println!("{table}");
+
+        Ok(())
+    }
+}
+
+/// state_name returns the printable state of the persistent cache task, the expired task is
+/// printed as expired regardless of its state.
+fn state_name(task: &PersistentCacheTask) -> &'static str {
+    if task.expired {
+        return "Expired";
+    }
+
+    match task.state() {
+        TaskState::Running => "Running",
+        TaskState::Succeeded => "Succeeded",
+        TaskState::Failed => "Failed",
+        TaskState::Unspecified => "Unknown",
+    }
+}
+
+/// ttl returns the ttl of the persistent cache task.
+fn ttl(task: &PersistentCacheTask) -> Result<Duration> {
+    Duration::try_from(task.ttl.clone().ok_or(Error::InvalidParameter)?)
+        .or_err(ErrorType::ParseError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_get_state_name() {
+        let mut task = PersistentCacheTask::default();
+        task.set_state(TaskState::Succeeded);
+        assert_eq!(state_name(&task), "Succeeded");
+
+        task.set_state(TaskState::Running);
+        assert_eq!(state_name(&task), "Running");
+
+        task.expired = true;
+        assert_eq!(state_name(&task), "Expired");
+    }
+}
@@ -17,6 +17,7 @@
 use clap::{Parser, Subcommand};
 use dragonfly_client::grpc::dfdaemon_download::DfdaemonDownloadClient;
 use dragonfly_client::grpc::health::HealthClient;
+use dragonfly_client::report::EXIT_CODES_HELP;
 use dragonfly_client::tracing::init_tracing;
 use dragonfly_client_config::VersionValueParser;
 use dragonfly_client_config::{dfcache, dfdaemon};
@@ -26,6 +27,8 @@ use tracing::Level;
 
 pub mod export;
 pub mod import;
+pub mod ls;
+pub mod rm;
 pub mod stat;
 
 #[derive(Debug, Parser)]
@@ -36,6 +39,7 @@ pub mod stat;
     about = "dfcache is a cache command line based on P2P technology in Dragonfly.",
     long_about = "A cache command line based on P2P technology in Dragonfly that can import file and export file in P2P network, \
     and it can copy multiple replicas during import. P2P cache is effectively used for fast read and write cache.",
+    after_long_help = EXIT_CODES_HELP,
     disable_version_flag = true
 )]
 struct Args {
@@ -82,6 +86,24 @@ pub enum Command {
         long_about = "Stat a file in Dragonfly P2P network by task ID. If stat successfully, it will return the file information."
     )]
     Stat(stat::StatCommand),
+
+    #[command(
+        name = "ls",
+        author,
+        version,
+        about = "List the files cached in the local dfdaemon",
+        long_about = "List the persistent cache tasks stored in the local dfdaemon with their state, persistence, replica count, content length and ttl."
+    )]
+    Ls(ls::ListCommand),
+
+    #[command(
+        name = "rm",
+        author,
+        version,
+        about = "Remove a file from Dragonfly P2P network",
+        long_about = "Remove a file from Dragonfly P2P network by task ID. It deletes the persistent cache task in the local dfdaemon and its replicas in the other peers."
+    )]
+    Rm(rm::RemoveCommand),
 }
 
 /// Implement the execute for Command.
@@ -92,6 +114,8 @@ impl Command {
             Self::Import(cmd) => cmd.execute().await,
             Self::Export(cmd) => cmd.execute().await,
             Self::Stat(cmd) => cmd.execute().await,
+            Self::Ls(cmd) => cmd.execute().await,
+            Self::Rm(cmd) => cmd.execute().await,
         }
     }
 }
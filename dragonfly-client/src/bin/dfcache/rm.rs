@@ -0,0 +1,287 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use clap::Parser;
+use dragonfly_client::grpc::dfdaemon_admin::DfdaemonAdminClient;
+use dragonfly_client_api::admin::v1::DeletePersistentCacheTaskRequest;
+use dragonfly_client_core::{Error, Result};
+use std::time::Instant;
+use termion::{color, style};
+
+use dragonfly_client::report::{ErrorClass, OutputFormat, ResultReport};
+
+use super::*;
+
+/// RemoveCommand is the subcommand of rm.
+#[derive(Debug, Clone, Parser)]
+pub struct RemoveCommand {
+    #[arg(help = "Specify the persistent cache task ID to remove")]
+    id: String,
+
+    #[arg(
+        short = 'e',
+        long = "endpoint",
+        default_value_os_t = dfdaemon::default_download_unix_socket_path(),
+        help = "Endpoint of dfdaemon's GRPC server"
+    )]
+    endpoint: PathBuf,
+
+    #[arg(
+        long = "output-format",
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Specify the output format. If it is json, dfcache prints the result as a JSON line to stdout instead of the colored message"
+    )]
+    output_format: OutputFormat,
+
+    #[arg(
+        short = 'l',
+        long,
+        default_value = "info",
+        help = "Specify the logging level [trace, debug, info, warn, error]"
+    )]
+    log_level: Level,
+
+    #[arg(
+        long,
+        default_value_os_t = dfcache::default_dfcache_log_dir(),
+        help = "Specify the log directory"
+    )]
+    log_dir: PathBuf,
+
+    #[arg(
+        long,
+        default_value_t = 6,
+        help = "Specify the max number of log files"
+    )]
+    log_max_files: usize,
+
+    #[arg(
+        long = "verbose",
+        default_value_t = false,
+        help = "Specify whether to print log"
+    )]
+    verbose: bool,
+}
+
+/// Implement the execute for RemoveCommand.
+impl RemoveCommand {
+    /// execute executes the rm command.
+    pub async fn execute(&self) -> Result<()> {
+        // Parse command line arguments.
+        Args::parse();
+
+        // Initialize tracing.
+        let _guards = init_tracing(
+            dfcache::NAME,
+            self.log_dir.clone(),
+            self.log_level,
+            self.log_max_files,
+            None,
+            false,
+            self.verbose,
+        );
+
+        // Record the start time.
+        let start_time = Instant::now();
+
+        // The verbose logs are printed to stdout, which breaks the json output.
+        if self.verbose && self.output_format == OutputFormat::Json {
+            let err = Error::ValidationError(
+                "verbose is not supported when the output format is json".to_string(),
+            );
+            ResultReport::failed(ErrorClass::InvalidArgument, &err, start_time.elapsed()).print();
+            std::process::exit(ErrorClass::InvalidArgument.exit_code());
+        }
+
+        // Get dfdaemon admin client, the dfdaemon's health is checked by the download client.
+        let clients = match get_dfdaemon_download_client(self.endpoint.to_path_buf()).await {
+            Ok(_) => DfdaemonAdminClient::new_unix(self.endpoint.to_path_buf()).await,
+            Err(err) => Err(err),
+        };
+
+        let dfdaemon_admin_client = match clients {
+            Ok(client) => client,
+            Err(err) => {
+                if self.output_format == OutputFormat::Json {
+                    ResultReport::failed(ErrorClass::Connection, &err, start_time.elapsed())
+                        .print();
+                    std::process::exit(ErrorClass::Connection.exit_code());
+                }
+
+                eprintln!(
+                    "{}{}{}Connect Dfdaemon Failed!{}",
+                    color::Fg(color::Red),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                eprintln!(
+                    "{}{}{}****************************************{}",
+                    color::Fg(color::Black),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                eprintln!(
+                    "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
+                    color::Fg(color::Cyan),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset,
+                    err,
+                    self.endpoint.to_string_lossy(),
+                );
+
+                eprintln!(
+                    "{}{}{}****************************************{}",
+                    color::Fg(color::Black),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                std::process::exit(ErrorClass::Connection.exit_code());
+            }
+        };
+
+        // Run rm sub command.
+        if let Err(err) = self.run(dfdaemon_admin_client).await {
+            let class = ErrorClass::from_error(&err);
+            if self.output_format == OutputFormat::Json {
+                let mut report = ResultReport::failed(class, &err, start_time.elapsed());
+                report.task_id = Some(self.id.clone());
+                report.print();
+                std::process::exit(class.exit_code());
+            }
+
+            match err {
+                Error::TonicStatus(status) => {
+                    eprintln!(
+                        "{}{}{}Removing Failed!{}",
+                        color::Fg(color::Red),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset,
+                    );
+
+                    eprintln!(
+                        "{}{}{}*********************************{}",
+                        color::Fg(color::Black),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset
+                    );
+
+                    eprintln!(
+                        "{}{}{}Bad Code:{} {}",
+                        color::Fg(color::Red),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset,
+                        status.code()
+                    );
+
+                    eprintln!(
+                        "{}{}{}Message:{} {}",
+                        color::Fg(color::Cyan),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset,
+                        status.message()
+                    );
+
+                    eprintln!(
+                        "{}{}{}*********************************{}",
+                        color::Fg(color::Black),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset
+                    );
+                }
+                err => {
+                    eprintln!(
+                        "{}{}{}Removing Failed!{}",
+                        color::Fg(color::Red),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset
+                    );
+
+                    eprintln!(
+                        "{}{}{}****************************************{}",
+                        color::Fg(color::Black),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset
+                    );
+
+                    eprintln!(
+                        "{}{}{}Message:{} {}",
+                        color::Fg(color::Red),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset,
+                        err
+                    );
+
+                    eprintln!(
+                        "{}{}{}****************************************{}",
+                        color::Fg(color::Black),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset
+                    );
+                }
+            }
+
+            std::process::exit(class.exit_code());
+        }
+
+        Ok(())
+    }
+
+    /// run runs the rm command.
+    async fn run(&self, dfdaemon_admin_client: DfdaemonAdminClient) -> Result<()> {
+        // Record the start time.
+        let start_time = Instant::now();
+        dfdaemon_admin_client
+            .delete_persistent_cache_task(DeletePersistentCacheTaskRequest {
+                task_id: self.id.clone(),
+            })
+            .await?;
+
+        if self.output_format == OutputFormat::Json {
+            let mut report = ResultReport::succeeded(start_time.elapsed());
+            report.task_id = Some(self.id.clone());
+            report.print();
+            return Ok(());
+        }
+
+        println!(
+            "{}{}{}Removed:{} {}",
+            color::Fg(color::Green),
+            style::Italic,
+            style::Bold,
+            style::Reset,
+            self.id,
+        );
+
+        Ok(())
+    }
+}
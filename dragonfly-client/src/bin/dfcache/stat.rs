@@ -22,13 +22,15 @@ use dragonfly_client_core::{
     Error, Result,
 };
 use humantime::format_duration;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tabled::{
     settings::{object::Rows, Alignment, Modify, Style},
     Table, Tabled,
 };
 use termion::{color, style};
 
+use dragonfly_client::report::{ErrorClass, OutputFormat, ResultReport};
+
 use super::*;
 
 /// StatCommand is the subcommand of stat.
@@ -45,6 +47,14 @@ pub struct StatCommand {
     )]
     endpoint: PathBuf,
 
+    #[arg(
+        long = "output-format",
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Specify the output format. If it is json, dfcache prints the result as a JSON line to stdout instead of the progress and the colored message"
+    )]
+    output_format: OutputFormat,
+
     #[arg(
         short = 'l',
         long,
@@ -93,12 +103,30 @@ impl StatCommand {
             self.verbose,
         );
 
+        // Record the start time.
+        let start_time = Instant::now();
+
+        // The verbose logs are printed to stdout, which breaks the json output.
+        if self.verbose && self.output_format == OutputFormat::Json {
+            let err = Error::ValidationError(
+                "verbose is not supported when the output format is json".to_string(),
+            );
+            ResultReport::failed(ErrorClass::InvalidArgument, &err, start_time.elapsed()).print();
+            std::process::exit(ErrorClass::InvalidArgument.exit_code());
+        }
+
         // Get dfdaemon download client.
         let dfdaemon_download_client =
             match get_dfdaemon_download_client(self.endpoint.to_path_buf()).await {
                 Ok(client) => client,
                 Err(err) => {
-                    println!(
+                    if self.output_format == OutputFormat::Json {
+                        ResultReport::failed(ErrorClass::Connection, &err, start_time.elapsed())
+                            .print();
+                        std::process::exit(ErrorClass::Connection.exit_code());
+                    }
+
+                    eprintln!(
                         "{}{}{}Connect Dfdaemon Failed!{}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -106,7 +134,7 @@ impl StatCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -114,7 +142,7 @@ impl StatCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
                         color::Fg(color::Cyan),
                         style::Italic,
@@ -124,7 +152,7 @@ impl StatCommand {
                         self.endpoint.to_string_lossy(),
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -132,15 +160,23 @@ impl StatCommand {
                         style::Reset
                     );
 
-                    std::process::exit(1);
+                    std::process::exit(ErrorClass::Connection.exit_code());
                 }
             };
 
         // Run stat sub command.
         if let Err(err) = self.run(dfdaemon_download_client).await {
+            let class = ErrorClass::from_error(&err);
+            if self.output_format == OutputFormat::Json {
+                let mut report = ResultReport::failed(class, &err, start_time.elapsed());
+                report.task_id = Some(self.id.clone());
+                report.print();
+                std::process::exit(class.exit_code());
+            }
+
             match err {
                 Error::TonicStatus(status) => {
-                    println!(
+                    eprintln!(
                         "{}{}{}Stating Failed!{}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -148,7 +184,7 @@ impl StatCommand {
                         style::Reset,
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}*********************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -156,7 +192,7 @@ impl StatCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Bad Code:{} {}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -165,7 +201,7 @@ impl StatCommand {
                         status.code()
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Message:{} {}",
                         color::Fg(color::Cyan),
                         style::Italic,
@@ -174,7 +210,7 @@ impl StatCommand {
                         status.message()
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Details:{} {}",
                         color::Fg(color::Cyan),
                         style::Italic,
@@ -183,7 +219,7 @@ impl StatCommand {
                         std::str::from_utf8(status.details()).unwrap()
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}*********************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -192,7 +228,7 @@ impl StatCommand {
                     );
                 }
                 err => {
-                    println!(
+                    eprintln!(
                         "{}{}{}Stating Failed!{}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -200,7 +236,7 @@ impl StatCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -208,7 +244,7 @@ impl StatCommand {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Message:{} {}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -217,7 +253,7 @@ impl StatCommand {
                         err
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -227,7 +263,7 @@ impl StatCommand {
                 }
             }
 
-            std::process::exit(1);
+            std::process::exit(class.exit_code());
         }
 
         Ok(())
@@ -235,12 +271,24 @@ impl StatCommand {
 
     /// run runs the stat command.
     async fn run(&self, dfdaemon_download_client: DfdaemonDownloadClient) -> Result<()> {
+        // Record the start time.
+        let start_time = Instant::now();
         let task = dfdaemon_download_client
             .stat_persistent_cache_task(StatPersistentCacheTaskRequest {
                 task_id: self.id.clone(),
             })
             .await?;
 
+        // Print the task as a JSON line in the json format.
+        if self.output_format == OutputFormat::Json {
+            let mut report = ResultReport::succeeded(start_time.elapsed());
+            report.task_id = Some(task.id.clone());
+            report.content_length = Some(task.content_length);
+            report.task = Some(serde_json::to_value(&task).or_err(ErrorType::SerializeError)?);
+            report.print();
+            return Ok(());
+        }
+
         // Define the table struct for printing.
         #[derive(Debug, Default, Tabled)]
         #[tabled(rename_all = "UPPERCASE")]
@@ -14,17 +14,19 @@
  * limitations under the License.
  */
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use dragonfly_api::dfdaemon::v2::DownloadTaskRequest;
 use dragonfly_client::announcer::{ManagerAnnouncer, SchedulerAnnouncer};
 use dragonfly_client::dynconfig::Dynconfig;
 use dragonfly_client::gc::GC;
 use dragonfly_client::grpc::{
     dfdaemon_download::DfdaemonDownloadServer, dfdaemon_upload::DfdaemonUploadServer,
-    manager::ManagerClient, scheduler::SchedulerClient,
+    manager::ManagerClient, resume_task, scheduler::SchedulerClient,
 };
 use dragonfly_client::health::Health;
 use dragonfly_client::metrics::Metrics;
 use dragonfly_client::proxy::Proxy;
+use dragonfly_client::reloader::Reloader;
 use dragonfly_client::resource::{persistent_cache_task::PersistentCacheTask, task::Task};
 use dragonfly_client::shutdown;
 use dragonfly_client::stats::Stats;
@@ -39,7 +41,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use termion::{color, style};
 use tokio::sync::mpsc;
-use tokio::sync::Barrier;
+use tokio::sync::{watch, Barrier};
 use tracing::{error, info, Level};
 
 #[cfg(not(target_env = "msvc"))]
@@ -65,11 +67,20 @@ struct Args {
     #[arg(
         short = 'c',
         long = "config",
+        global = true,
         default_value_os_t = dfdaemon::default_dfdaemon_config_path(),
         help = "Specify config file to use")
     ]
     config: PathBuf,
 
+    #[arg(
+        long = "set",
+        value_name = "KEY=VALUE",
+        global = true,
+        help = "Override the config by key=value, such as upload.rateLimit=1GiB. It can be specified multiple times, and it takes precedence over the config file and the DFDAEMON_* environment variables"
+    )]
+    set: Vec<String>,
+
     #[arg(
         short = 'l',
         long,
@@ -108,6 +119,51 @@ struct Args {
         value_parser = VersionValueParser
     )]
     version: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum Command {
+    #[command(
+        name = "config",
+        about = "Inspect and validate the config of dfdaemon",
+        long_about = "Inspect and validate the config of dfdaemon, the effective config is merged from the defaults, the config file, the DFDAEMON_* environment variables and the --set overrides."
+    )]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum ConfigCommand {
+    #[command(
+        name = "print",
+        about = "Print the effective config",
+        long_about = "Print the effective config in yaml, the secrets such as passwords and tokens are redacted."
+    )]
+    Print,
+
+    #[command(
+        name = "validate",
+        about = "Validate the config file",
+        long_about = "Validate the config file without starting dfdaemon, it checks the fields and the cross-field constraints, such as the gc thresholds, the TLS files and the server ports. The DFDAEMON_ environment variables are not applied, so the file is validated as written. It exits with non-zero code if the config is invalid."
+    )]
+    Validate {
+        #[arg(
+            help = "Specify the config file to validate, it is the config file of --config if not set"
+        )]
+        path: Option<PathBuf>,
+    },
+
+    #[command(
+        name = "schema",
+        about = "Print the JSON schema of the config",
+        long_about = "Print the JSON schema of the config, which is derived from the config types and can be used to lint the config files."
+    )]
+    Schema,
 }
 
 #[tokio::main]
@@ -115,8 +171,51 @@ async fn main() -> Result<(), anyhow::Error> {
     // Parse command line arguments.
     let args = Args::parse();
 
+    // Execute the config subcommands which do not require a valid config.
+    match &args.command {
+        Some(Command::Config {
+            command: ConfigCommand::Validate { path },
+        }) => {
+            // Validate the config file as written, the environment variables of the runner
+            // are not applied, only the explicit overrides of the command line are applied.
+            let path = path.as_ref().unwrap_or(&args.config);
+            if let Err(err) = dfdaemon::Config::load_without_envs(path, &args.set).await {
+                println!(
+                    "{}{}Config {} is invalid: {}{}",
+                    color::Fg(color::Red),
+                    style::Bold,
+                    path.display(),
+                    err,
+                    style::Reset
+                );
+                std::process::exit(1);
+            }
+
+            if args.set.is_empty() {
+                println!("Config {} is valid", path.display());
+            } else {
+                println!(
+                    "Config {} is valid with the --set overrides applied",
+                    path.display()
+                );
+            }
+
+            return Ok(());
+        }
+        Some(Command::Config {
+            command: ConfigCommand::Schema,
+        }) => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&dfdaemon::Config::schema())?
+            );
+            return Ok(());
+        }
+        _ => {}
+    }
+
     // Load config.
-    let config = match dfdaemon::Config::load(&args.config).await {
+    let config = match dfdaemon::Config::load_with_overrides(&args.config, &args.set).await {
         Ok(config) => config,
         Err(err) => {
             println!(
@@ -142,13 +241,22 @@ async fn main() -> Result<(), anyhow::Error> {
         }
     };
 
+    // Execute the config subcommand which prints the loaded config.
+    if let Some(Command::Config {
+        command: ConfigCommand::Print,
+    }) = args.command
+    {
+        print!("{}", config.to_redacted_yaml()?);
+        return Ok(());
+    }
+
     let config = Arc::new(config);
 
     // Initialize tracing.
     let _guards = init_tracing(
         dfdaemon::NAME,
         args.log_dir.clone(),
-        args.log_level,
+        config.log.level.unwrap_or(args.log_level),
         args.log_max_files,
         config.tracing.addr.to_owned(),
         config.tracing.flamegraph,
@@ -171,39 +279,55 @@ async fn main() -> Result<(), anyhow::Error> {
     );
     let id_generator = Arc::new(id_generator);
 
-    // Initialize manager client.
-    let manager_client = ManagerClient::new(config.clone(), config.manager.addr.clone())
-        .await
-        .inspect_err(|err| {
-            error!("initialize manager client failed: {}", err);
-        })?;
-    let manager_client = Arc::new(manager_client);
+    // Initialize manager client, the manager is not used in standalone mode.
+    let manager_client = if config.standalone.enable {
+        info!("dfdaemon runs in standalone mode");
+        None
+    } else {
+        let manager_client = ManagerClient::new(config.clone(), config.manager.get_addrs())
+            .await
+            .inspect_err(|err| {
+                error!("initialize manager client failed: {}", err);
+            })?;
+        Some(Arc::new(manager_client))
+    };
 
     // Initialize channel for graceful shutdown.
     let shutdown = shutdown::Shutdown::default();
     let (shutdown_complete_tx, mut shutdown_complete_rx) = mpsc::unbounded_channel();
 
-    // Initialize dynconfig server.
-    let dynconfig = Dynconfig::new(
-        config.clone(),
-        manager_client.clone(),
-        shutdown.clone(),
-        shutdown_complete_tx.clone(),
-    )
-    .await
-    .inspect_err(|err| {
-        error!("initialize dynconfig server failed: {}", err);
-    })?;
-    let dynconfig = Arc::new(dynconfig);
-
-    // Initialize scheduler client.
-    let scheduler_client = SchedulerClient::new(config.clone(), dynconfig.clone())
-        .await
-        .inspect_err(|err| {
-            error!("initialize scheduler client failed: {}", err);
-        })?;
+    // Initialize dynconfig server, the dynconfig is not used in standalone mode.
+    let dynconfig = match manager_client.clone() {
+        Some(manager_client) => {
+            let dynconfig = Dynconfig::new(
+                config.clone(),
+                manager_client,
+                shutdown.clone(),
+                shutdown_complete_tx.clone(),
+            )
+            .await
+            .inspect_err(|err| {
+                error!("initialize dynconfig server failed: {}", err);
+            })?;
+            Some(Arc::new(dynconfig))
+        }
+        None => None,
+    };
+
+    // Initialize scheduler client, all the requests to the scheduler fail in standalone mode.
+    let scheduler_client = match dynconfig.clone() {
+        Some(dynconfig) => SchedulerClient::new(config.clone(), dynconfig)
+            .await
+            .inspect_err(|err| {
+                error!("initialize scheduler client failed: {}", err);
+            })?,
+        None => SchedulerClient::new_standalone(config.clone()),
+    };
     let scheduler_client = Arc::new(scheduler_client);
 
+    // Initialize channel for the reloaded configuration.
+    let (config_tx, config_rx) = watch::channel(config.clone());
+
     let backend_factory = BackendFactory::new(Some(config.server.plugin_dir.as_path()))
         .inspect_err(|err| {
             error!("initialize backend factory failed: {}", err);
@@ -213,6 +337,7 @@ async fn main() -> Result<(), anyhow::Error> {
     // Initialize task manager.
     let task = Task::new(
         config.clone(),
+        config_rx.clone(),
         id_generator.clone(),
         storage.clone(),
         scheduler_client.clone(),
@@ -223,6 +348,7 @@ async fn main() -> Result<(), anyhow::Error> {
     // Initialize persistent cache task manager.
     let persistent_cache_task = PersistentCacheTask::new(
         config.clone(),
+        config_rx.clone(),
         id_generator.clone(),
         storage.clone(),
         scheduler_client.clone(),
@@ -254,31 +380,39 @@ async fn main() -> Result<(), anyhow::Error> {
     // Initialize proxy server.
     let proxy = Proxy::new(
         config.clone(),
+        config_rx.clone(),
         task.clone(),
         shutdown.clone(),
         shutdown_complete_tx.clone(),
     );
 
-    // Initialize manager announcer.
-    let manager_announcer = ManagerAnnouncer::new(
-        config.clone(),
-        manager_client.clone(),
-        shutdown.clone(),
-        shutdown_complete_tx.clone(),
-    );
-
-    // Initialize scheduler announcer.
-    let scheduler_announcer = SchedulerAnnouncer::new(
-        config.clone(),
-        id_generator.host_id(),
-        scheduler_client.clone(),
-        shutdown.clone(),
-        shutdown_complete_tx.clone(),
-    )
-    .await
-    .inspect_err(|err| {
-        error!("initialize scheduler announcer failed: {}", err);
-    })?;
+    // Initialize manager announcer, the host is not announced in standalone mode.
+    let manager_announcer = manager_client.clone().map(|manager_client| {
+        ManagerAnnouncer::new(
+            config.clone(),
+            manager_client,
+            shutdown.clone(),
+            shutdown_complete_tx.clone(),
+        )
+    });
+
+    // Initialize scheduler announcer, the host is not announced in standalone mode.
+    let scheduler_announcer = if scheduler_client.is_standalone() {
+        None
+    } else {
+        let scheduler_announcer = SchedulerAnnouncer::new(
+            config.clone(),
+            id_generator.host_id(),
+            scheduler_client.clone(),
+            shutdown.clone(),
+            shutdown_complete_tx.clone(),
+        )
+        .await
+        .inspect_err(|err| {
+            error!("initialize scheduler announcer failed: {}", err);
+        })?;
+        Some(scheduler_announcer)
+    };
 
     // Initialize upload grpc server.
     let mut dfdaemon_upload_grpc = DfdaemonUploadServer::new(
@@ -293,6 +427,7 @@ async fn main() -> Result<(), anyhow::Error> {
     // Initialize download grpc server.
     let mut dfdaemon_download_grpc = DfdaemonDownloadServer::new(
         config.download.server.socket_path.clone(),
+        storage.clone(),
         task.clone(),
         persistent_cache_task.clone(),
         shutdown.clone(),
@@ -302,6 +437,7 @@ async fn main() -> Result<(), anyhow::Error> {
     // Initialize garbage collector.
     let gc = GC::new(
         config.clone(),
+        config_rx,
         id_generator.host_id(),
         storage.clone(),
         scheduler_client.clone(),
@@ -309,15 +445,78 @@ async fn main() -> Result<(), anyhow::Error> {
         shutdown_complete_tx.clone(),
     );
 
+    // Initialize config reloader.
+    let reloader = Reloader::new(
+        args.config.clone(),
+        args.set.clone(),
+        config_tx,
+        args.log_level,
+        vec![task.piece.clone(), persistent_cache_task.piece.clone()],
+        shutdown.clone(),
+        shutdown_complete_tx.clone(),
+    );
+
     // Log dfdaemon started pid.
     info!("dfdaemon started at pid {}", std::process::id());
 
-    // grpc server started barrier.
-    let grpc_server_started_barrier = Arc::new(Barrier::new(3));
+    // grpc server started barrier, if resume unfinished tasks is enabled, the resumer
+    // also waits for the grpc servers to be started.
+    let grpc_server_started_barrier =
+        Arc::new(Barrier::new(if config.download.resume_unfinished_tasks {
+            4
+        } else {
+            3
+        }));
+
+    // Resume the unfinished tasks when the grpc servers are started.
+    if config.download.resume_unfinished_tasks {
+        let task = task.clone();
+        let socket_path = config.download.server.socket_path.clone();
+        let barrier = grpc_server_started_barrier.clone();
+        let mut shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = barrier.wait() => {}
+                _ = shutdown.recv() => return,
+            }
+
+            // Get the downloads of the unfinished tasks and release the task manager,
+            // so it will not block the shutdown.
+            let downloads = task.resumable_downloads();
+            drop(task);
+            let downloads = match downloads {
+                Ok(downloads) => downloads,
+                Err(err) => {
+                    error!("get resumable downloads failed: {}", err);
+                    return;
+                }
+            };
+
+            info!("resume {} unfinished tasks", downloads.len());
+            for download in downloads {
+                let socket_path = socket_path.clone();
+                tokio::spawn(async move {
+                    resume_task(
+                        socket_path,
+                        DownloadTaskRequest {
+                            download: Some(download),
+                        },
+                    )
+                    .await
+                    .unwrap_or_else(|err| error!("resume task failed: {}", err));
+                });
+            }
+        });
+    }
 
     // Wait for servers to exit or shutdown signal.
     tokio::select! {
-        _ = tokio::spawn(async move { dynconfig.run().await }) => {
+        _ = tokio::spawn(async move {
+            match dynconfig {
+                Some(dynconfig) => dynconfig.run().await,
+                None => std::future::pending().await,
+            }
+        }) => {
             info!("dynconfig manager exited");
         },
 
@@ -333,11 +532,21 @@ async fn main() -> Result<(), anyhow::Error> {
             info!("stats server exited");
         },
 
-        _ = tokio::spawn(async move { manager_announcer.run().await.unwrap_or_else(|err| error!("announcer manager failed: {}", err))} ) => {
+        _ = tokio::spawn(async move {
+            match manager_announcer {
+                Some(manager_announcer) => manager_announcer.run().await.unwrap_or_else(|err| error!("announcer manager failed: {}", err)),
+                None => std::future::pending().await,
+            }
+        }) => {
             info!("announcer manager exited");
         },
 
-        _ = tokio::spawn(async move { scheduler_announcer.run().await }) => {
+        _ = tokio::spawn(async move {
+            match scheduler_announcer {
+                Some(scheduler_announcer) => scheduler_announcer.run().await,
+                None => std::future::pending().await,
+            }
+        }) => {
             info!("announcer scheduler exited");
         },
 
@@ -345,6 +554,10 @@ async fn main() -> Result<(), anyhow::Error> {
             info!("garbage collector exited");
         },
 
+        _ = tokio::spawn(async move { reloader.run().await.unwrap_or_else(|err| error!("config reloader failed: {}", err)) }) => {
+            info!("config reloader exited");
+        },
+
         _ = {
             let barrier = grpc_server_started_barrier.clone();
             tokio::spawn(async move {
@@ -24,21 +24,35 @@ use dragonfly_client::metrics::{
     collect_backend_request_failure_metrics, collect_backend_request_finished_metrics,
     collect_backend_request_started_metrics,
 };
+use dragonfly_client::ordered_writer::{OrderedWriter, MAX_PENDING_PIECES_SIZE};
+use dragonfly_client::report::{
+    ErrorClass, Event, OutputFormat, PieceStats, ResultReport, EXIT_CODES_HELP,
+};
 use dragonfly_client::tracing::init_tracing;
-use dragonfly_client_backend::{hdfs, object_storage, BackendFactory, DirEntry, HeadRequest};
+use dragonfly_client::walker::get_files;
+use dragonfly_client_backend::{
+    hdfs, http as http_backend, object_storage, BackendFactory, DirEntry, HeadRequest,
+};
 use dragonfly_client_config::VersionValueParser;
 use dragonfly_client_config::{self, dfdaemon, dfget};
 use dragonfly_client_core::error::{BackendError, ErrorType, OrErr};
 use dragonfly_client_core::{Error, Result};
+use dragonfly_client_util::digest::{calculate_file_hash, Digest};
 use dragonfly_client_util::http::{header_vec_to_hashmap, header_vec_to_headermap};
-use indicatif::{MultiProgress, ProgressBar, ProgressState, ProgressStyle};
+use glob::Pattern;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
 use path_absolutize::*;
 use percent_encoding::percent_decode_str;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{cmp::min, fmt::Write};
+use tabled::{
+    settings::{object::Rows, Alignment, Modify, Style},
+    Table, Tabled,
+};
 use termion::{color, style};
 use tokio::fs::{self, OpenOptions};
 use tokio::io::{AsyncSeekExt, AsyncWriteExt, SeekFrom};
@@ -48,6 +62,11 @@ use tracing::{debug, error, info, warn, Level};
 use url::Url;
 use uuid::Uuid;
 
+pub mod manifest;
+
+/// STDOUT_OUTPUT is the output path to stream the content of downloading file to stdout.
+const STDOUT_OUTPUT: &str = "-";
+
 const LONG_ABOUT: &str = r#"
 A download command line based on P2P technology in Dragonfly that can download resources of different protocols.
 
@@ -77,6 +96,21 @@ Examples:
 
   # Download a file from Tencent Cloud Object Storage Service(COS).
   $ dfget cos://<bucket>/<path> -O /tmp/file.txt --storage-access-key-id=<access_key_id> --storage-access-key-secret=<access_key_secret> --storage-endpoint=<endpoint>
+
+  # Stream a file to stdout and pipe it into the other command.
+  $ dfget https://<host>:<port>/<path>.tar.zst -O - | zstd -d | tar -x -C /tmp/dir
+
+  # Download the safetensors files in the directory, skip the files already downloaded and delete the local files removed remotely.
+  $ dfget s3://<bucket>/<path>/ -O /tmp/dir/ --include='*.safetensors' --sync --delete --storage-access-key-id=<access_key_id> --storage-access-key-secret=<access_key_secret>
+
+  # Download the directory of the HTTP file server recursively by parsing its autoindex page, e.g. nginx autoindex.
+  $ dfget https://<host>:<port>/<path>/ -O /tmp/dir/ --recursive
+
+  # Download the files listed in the manifest, one `<url> <output> [digest]` per line.
+  $ dfget --input-file /tmp/list.txt --max-concurrent-requests=20
+
+  # Download the files listed in the JSON or YAML manifest with the url, output, digest, header, tag and application fields.
+  $ dfget --input-file /tmp/list.yaml
 "#;
 
 #[derive(Debug, Parser, Clone)]
@@ -86,11 +120,15 @@ Examples:
     version,
     about = "dfget is a download command line based on P2P technology",
     long_about = LONG_ABOUT,
+    after_long_help = EXIT_CODES_HELP,
     disable_version_flag = true,
 )]
 struct Args {
-    #[arg(help = "Specify the URL to download")]
-    url: Url,
+    #[arg(
+        required_unless_present = "input_file",
+        help = "Specify the URL to download"
+    )]
+    url: Option<Url>,
 
     #[arg(
         long = "transfer-from-dfdaemon",
@@ -102,9 +140,26 @@ struct Args {
     #[arg(
         short = 'O',
         long = "output",
-        help = "Specify the output path of downloading file"
+        required_unless_present = "input_file",
+        help = "Specify the output path of downloading file. If it is -, the content of downloading file is streamed to stdout in order and the progress is printed to stderr"
     )]
-    output: PathBuf,
+    output: Option<PathBuf>,
+
+    #[arg(
+        short = 'i',
+        long = "input-file",
+        conflicts_with_all = ["url", "output"],
+        help = "Specify the manifest of the files to download in batch. The plain text manifest lists one file per line as `<url> <output> [digest]`, and the manifest with the .json, .yaml or .yml extension is a list of items with the url, output, digest, header, tag and application fields. The fields of the item override the command line arguments, and the headers of the item are appended to the --header arguments"
+    )]
+    input_file: Option<PathBuf>,
+
+    #[arg(
+        long = "skip-existing",
+        default_value_t = false,
+        requires = "input_file",
+        help = "Specify whether to skip the outputs which already exist when downloading the files in the manifest, so the manifest can be retried after the partial failure. The existing outputs with the digest in the manifest are always skipped if the digest matches"
+    )]
+    skip_existing: bool,
 
     #[arg(
         short = 'e',
@@ -174,6 +229,43 @@ struct Args {
     )]
     disable_back_to_source: bool,
 
+    #[arg(
+        short = 'r',
+        long = "recursive",
+        default_value_t = false,
+        help = "Specify whether to download the directory of the HTTP file server recursively when the URL ends with '/'. The entries are listed by parsing the directory index, including the HTML and JSON formats of nginx and apache autoindex and the S3 compatible ListBucketResult. The directory of the object storage and HDFS is always downloaded recursively"
+    )]
+    recursive: bool,
+
+    #[arg(
+        long = "include",
+        required = false,
+        help = "Specify the glob pattern of the files to download when downloading a directory. The pattern matches the path relative to the directory and all files are included if it is not specified, e.g. --include='*.safetensors' --include='tokenizer/*'"
+    )]
+    include: Option<Vec<String>>,
+
+    #[arg(
+        long = "exclude",
+        required = false,
+        help = "Specify the glob pattern of the files not to download when downloading a directory. The pattern matches the path relative to the directory and takes precedence over --include, e.g. --exclude='*.tmp'"
+    )]
+    exclude: Option<Vec<String>>,
+
+    #[arg(
+        long = "sync",
+        default_value_t = false,
+        help = "Specify whether to sync the directory incrementally when downloading a directory. The files already present in the output directory with the same content length are skipped, and the files with the different content length are downloaded again. The files are downloaded to the temporary files and renamed after the downloading succeeds, so the interrupted downloading never leaves the partial files"
+    )]
+    sync: bool,
+
+    #[arg(
+        long = "delete",
+        default_value_t = false,
+        requires = "sync",
+        help = "Specify whether to delete the local files in the output directory which are no longer present in the directory when syncing a directory. The files filtered by --include and --exclude are kept"
+    )]
+    delete: bool,
+
     #[arg(long, help = "Specify the region for the Object Storage Service")]
     storage_region: Option<String>,
 
@@ -220,17 +312,33 @@ struct Args {
     #[arg(
         long,
         default_value_t = 10,
-        help = "Specify the max count of file to download when downloading a directory. If the actual file count is greater than this value, the downloading will be rejected"
+        help = "Specify the max count of file to download when downloading a directory. If the actual file count to download after filtering and syncing is greater than this value, the downloading will be rejected"
     )]
     max_files: usize,
 
     #[arg(
         long,
         default_value_t = 5,
-        help = "Specify the max count of concurrent download files when downloading a directory"
+        help = "Specify the max count of concurrent download files when downloading a directory or the files in the manifest"
     )]
     max_concurrent_requests: usize,
 
+    #[arg(
+        long = "output-format",
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Specify the output format. If it is json, dfget prints the started, progress and result events as JSON lines to stdout instead of the progress bar, and the result contains the task ID, content length, cost, the pieces downloaded from the parents and the source, and the error with the backend status code"
+    )]
+    output_format: OutputFormat,
+
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        default_value_t = false,
+        help = "Specify whether to hide the progress bar"
+    )]
+    quiet: bool,
+
     #[arg(
         short = 'l',
         long,
@@ -287,9 +395,18 @@ async fn main() -> anyhow::Result<()> {
         args.verbose,
     );
 
+    // Record the start time.
+    let start_time = Instant::now();
+    let output_format = args.output_format;
+
     // Validate command line arguments.
     if let Err(err) = validate_args(&args) {
-        println!(
+        if output_format == OutputFormat::Json {
+            ResultReport::failed(ErrorClass::InvalidArgument, &err, start_time.elapsed()).print();
+            std::process::exit(ErrorClass::InvalidArgument.exit_code());
+        }
+
+        eprintln!(
             "{}{}{}Validating Failed!{}",
             color::Fg(color::Red),
             style::Italic,
@@ -297,7 +414,7 @@ async fn main() -> anyhow::Result<()> {
             style::Reset
         );
 
-        println!(
+        eprintln!(
             "{}{}{}****************************************{}",
             color::Fg(color::Black),
             style::Italic,
@@ -305,7 +422,7 @@ async fn main() -> anyhow::Result<()> {
             style::Reset
         );
 
-        println!(
+        eprintln!(
             "{}{}{}Message:{} {}",
             color::Fg(color::Cyan),
             style::Italic,
@@ -314,7 +431,7 @@ async fn main() -> anyhow::Result<()> {
             err,
         );
 
-        println!(
+        eprintln!(
             "{}{}{}****************************************{}",
             color::Fg(color::Black),
             style::Italic,
@@ -322,59 +439,80 @@ async fn main() -> anyhow::Result<()> {
             style::Reset
         );
 
-        std::process::exit(1);
+        std::process::exit(ErrorClass::InvalidArgument.exit_code());
     }
 
     // Get dfdaemon download client.
-    let dfdaemon_download_client =
-        match get_dfdaemon_download_client(args.endpoint.to_path_buf()).await {
-            Ok(client) => client,
-            Err(err) => {
-                println!(
-                    "{}{}{}Connect Dfdaemon Failed!{}",
-                    color::Fg(color::Red),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
+    let dfdaemon_download_client = match get_dfdaemon_download_client(args.endpoint.to_path_buf())
+        .await
+    {
+        Ok(client) => client,
+        Err(err) => {
+            if output_format == OutputFormat::Json {
+                ResultReport::failed(ErrorClass::Connection, &err, start_time.elapsed()).print();
+                std::process::exit(ErrorClass::Connection.exit_code());
+            }
 
-                println!(
-                    "{}{}{}****************************************{}",
-                    color::Fg(color::Black),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
+            eprintln!(
+                "{}{}{}Connect Dfdaemon Failed!{}",
+                color::Fg(color::Red),
+                style::Italic,
+                style::Bold,
+                style::Reset
+            );
 
-                println!(
-                    "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
-                    color::Fg(color::Cyan),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset,
-                    err,
-                    args.endpoint.to_string_lossy(),
-                );
+            eprintln!(
+                "{}{}{}****************************************{}",
+                color::Fg(color::Black),
+                style::Italic,
+                style::Bold,
+                style::Reset
+            );
 
-                println!(
-                    "{}{}{}****************************************{}",
-                    color::Fg(color::Black),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
+            eprintln!(
+                "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
+                color::Fg(color::Cyan),
+                style::Italic,
+                style::Bold,
+                style::Reset,
+                err,
+                args.endpoint.to_string_lossy(),
+            );
 
-                std::process::exit(1);
-            }
-        };
+            eprintln!(
+                "{}{}{}****************************************{}",
+                color::Fg(color::Black),
+                style::Italic,
+                style::Bold,
+                style::Reset
+            );
+
+            std::process::exit(ErrorClass::Connection.exit_code());
+        }
+    };
 
     // Run dfget command.
+    let (url, output) = (args.url.clone(), args.output.clone());
     if let Err(err) = run(args, dfdaemon_download_client).await {
+        let class = ErrorClass::from_error(&err);
+        if output_format == OutputFormat::Json {
+            // The result of downloading in batch or directory mode has been printed with the
+            // results of the files.
+            if !matches!(err, Error::DownloadFilesFailed(_, _)) {
+                let mut report = ResultReport::failed(class, &err, start_time.elapsed());
+                report.url = url.map(|url| url.to_string());
+                report.path = output.map(|output| output.to_string_lossy().to_string());
+                report.print();
+            }
+
+            std::process::exit(class.exit_code());
+        }
+
         match err {
             Error::TonicStatus(status) => {
                 let details = status.details();
                 if let Ok(backend_err) = serde_json::from_slice::<Backend>(details) {
-                    println!(
+                    eprintln!(
                         "{}{}{}Downloading Failed!{}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -382,7 +520,7 @@ async fn main() -> anyhow::Result<()> {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -391,7 +529,7 @@ async fn main() -> anyhow::Result<()> {
                     );
 
                     if let Some(status_code) = backend_err.status_code {
-                        println!(
+                        eprintln!(
                             "{}{}{}Bad Status Code:{} {}",
                             color::Fg(color::Red),
                             style::Italic,
@@ -401,7 +539,7 @@ async fn main() -> anyhow::Result<()> {
                         );
                     }
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Message:{} {}",
                         color::Fg(color::Cyan),
                         style::Italic,
@@ -411,7 +549,7 @@ async fn main() -> anyhow::Result<()> {
                     );
 
                     if !backend_err.header.is_empty() {
-                        println!(
+                        eprintln!(
                             "{}{}{}Header:{}",
                             color::Fg(color::Cyan),
                             style::Italic,
@@ -419,11 +557,11 @@ async fn main() -> anyhow::Result<()> {
                             style::Reset
                         );
                         for (key, value) in backend_err.header.iter() {
-                            println!("  [{}]: {}", key.as_str(), value.as_str());
+                            eprintln!("  [{}]: {}", key.as_str(), value.as_str());
                         }
                     }
 
-                    println!(
+                    eprintln!(
                         "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -431,7 +569,7 @@ async fn main() -> anyhow::Result<()> {
                         style::Reset
                     );
                 } else {
-                    println!(
+                    eprintln!(
                         "{}{}{}Downloading Failed!{}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -439,7 +577,7 @@ async fn main() -> anyhow::Result<()> {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}*********************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -447,7 +585,7 @@ async fn main() -> anyhow::Result<()> {
                         style::Reset
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Bad Code:{} {}",
                         color::Fg(color::Red),
                         style::Italic,
@@ -456,7 +594,7 @@ async fn main() -> anyhow::Result<()> {
                         status.code()
                     );
 
-                    println!(
+                    eprintln!(
                         "{}{}{}Message:{} {}",
                         color::Fg(color::Cyan),
                         style::Italic,
@@ -466,7 +604,7 @@ async fn main() -> anyhow::Result<()> {
                     );
 
                     if !status.details().is_empty() {
-                        println!(
+                        eprintln!(
                             "{}{}{}Details:{} {}",
                             color::Fg(color::Cyan),
                             style::Italic,
@@ -476,7 +614,7 @@ async fn main() -> anyhow::Result<()> {
                         );
                     }
 
-                    println!(
+                    eprintln!(
                         "{}{}{}*********************************{}",
                         color::Fg(color::Black),
                         style::Italic,
@@ -486,7 +624,7 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
             Error::BackendError(err) => {
-                println!(
+                eprintln!(
                     "{}{}{}Downloading Failed!{}",
                     color::Fg(color::Red),
                     style::Italic,
@@ -494,7 +632,7 @@ async fn main() -> anyhow::Result<()> {
                     style::Reset
                 );
 
-                println!(
+                eprintln!(
                     "{}{}{}****************************************{}",
                     color::Fg(color::Black),
                     style::Italic,
@@ -502,7 +640,7 @@ async fn main() -> anyhow::Result<()> {
                     style::Reset
                 );
 
-                println!(
+                eprintln!(
                     "{}{}{}Message:{} {}",
                     color::Fg(color::Red),
                     style::Italic,
@@ -512,7 +650,7 @@ async fn main() -> anyhow::Result<()> {
                 );
 
                 if err.header.is_some() {
-                    println!(
+                    eprintln!(
                         "{}{}{}Header:{}",
                         color::Fg(color::Cyan),
                         style::Italic,
@@ -520,11 +658,11 @@ async fn main() -> anyhow::Result<()> {
                         style::Reset
                     );
                     for (key, value) in err.header.unwrap_or_default().iter() {
-                        println!("  [{}]: {}", key.as_str(), value.to_str().unwrap());
+                        eprintln!("  [{}]: {}", key.as_str(), value.to_str().unwrap());
                     }
                 }
 
-                println!(
+                eprintln!(
                     "{}{}{}****************************************{}",
                     color::Fg(color::Black),
                     style::Italic,
@@ -533,7 +671,7 @@ async fn main() -> anyhow::Result<()> {
                 );
             }
             err => {
-                println!(
+                eprintln!(
                     "{}{}{}Downloading Failed!{}",
                     color::Fg(color::Red),
                     style::Italic,
@@ -541,7 +679,7 @@ async fn main() -> anyhow::Result<()> {
                     style::Reset
                 );
 
-                println!(
+                eprintln!(
                     "{}{}{}****************************************{}",
                     color::Fg(color::Black),
                     style::Italic,
@@ -549,7 +687,7 @@ async fn main() -> anyhow::Result<()> {
                     style::Reset
                 );
 
-                println!(
+                eprintln!(
                     "{}{}{}Message:{} {}",
                     color::Fg(color::Red),
                     style::Italic,
@@ -558,7 +696,7 @@ async fn main() -> anyhow::Result<()> {
                     err
                 );
 
-                println!(
+                eprintln!(
                     "{}{}{}****************************************{}",
                     color::Fg(color::Black),
                     style::Italic,
@@ -568,7 +706,7 @@ async fn main() -> anyhow::Result<()> {
             }
         }
 
-        std::process::exit(1);
+        std::process::exit(class.exit_code());
     }
 
     Ok(())
@@ -576,26 +714,73 @@ async fn main() -> anyhow::Result<()> {
 
 /// run runs the dfget command.
 async fn run(mut args: Args, dfdaemon_download_client: DfdaemonDownloadClient) -> Result<()> {
-    // Get the absolute path of the output file.
-    args.output = Path::new(&args.output).absolutize()?.into();
-    info!("download file to: {}", args.output.to_string_lossy());
+    // If the input file is specified, then download all files in the manifest.
+    if let Some(input_file) = args.input_file.clone() {
+        return download_batch(args, input_file, dfdaemon_download_client).await;
+    }
+
+    let (Some(url), Some(output)) = (args.url.clone(), args.output.as_ref()) else {
+        return Err(Error::InvalidParameter);
+    };
+
+    // Get the absolute path of the output file, stdout has no path to absolutize.
+    let output: PathBuf = if is_stdout(output) {
+        output.clone()
+    } else {
+        output.absolutize()?.into()
+    };
+    info!("download file to: {}", output.to_string_lossy());
+    args.output = Some(output);
 
     // If the path has end with '/' and the scheme supports directory download,
     // then download all files in the directory. Otherwise, download the single file.
-    let scheme = args.url.scheme();
-    if args.url.path().ends_with('/') {
+    let scheme = url.scheme();
+    if url.path().ends_with('/') {
         if !BackendFactory::supported_download_directory(scheme) {
             return Err(Error::Unsupported(format!("{} download directory", scheme)));
         };
 
+        // The url ending with '/' is a normal page of the http server, so listing the
+        // directory index is opt-in.
+        if (scheme == http_backend::HTTP_SCHEME || scheme == http_backend::HTTPS_SCHEME)
+            && !args.recursive
+        {
+            return Err(Error::Unsupported(format!(
+                "{} download directory without --recursive",
+                scheme
+            )));
+        }
+
         return download_dir(args, dfdaemon_download_client).await;
     };
 
-    download(args, ProgressBar::new(0), dfdaemon_download_client).await
+    let output_format = args.output_format;
+    let progress_bar = if args.quiet || output_format == OutputFormat::Json {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(0)
+    };
+
+    let report = download(args, progress_bar, dfdaemon_download_client).await?;
+    if output_format == OutputFormat::Json {
+        report.print();
+    }
+
+    Ok(())
 }
 
 /// download_dir downloads all files in the directory.
 async fn download_dir(args: Args, download_client: DfdaemonDownloadClient) -> Result<()> {
+    let (Some(url), Some(output)) = (args.url.clone(), args.output.clone()) else {
+        return Err(Error::InvalidParameter);
+    };
+
+    // Initialize the filter of the entries by the include and exclude patterns.
+    let filter = EntryFilter::new(
+        &args.include.clone().unwrap_or_default(),
+        &args.exclude.clone().unwrap_or_default(),
+    )?;
+
     // Initialize the object storage config and the hdfs config.
     let object_storage = Some(ObjectStorage {
         access_key_id: args.storage_access_key_id.clone(),
@@ -614,72 +799,252 @@ async fn download_dir(args: Args, download_client: DfdaemonDownloadClient) -> Re
     // Get all entries in the directory. If the directory is empty, then return directly.
     let entries = get_entries(args.clone(), object_storage, hdfs).await?;
     if entries.is_empty() {
-        warn!("directory {} is empty", args.url);
+        warn!("directory {} is empty", url);
         return Ok(());
     };
 
-    // If the actual file count is greater than the max_files, then reject the downloading.
-    let count = entries.iter().filter(|entry| !entry.is_dir).count();
-    if count > args.max_files {
-        return Err(Error::MaxDownloadFilesExceeded(count));
+    // Record the start time.
+    let start_time = Instant::now();
+    let mut summary = DownloadSummary::new(args.output_format);
+
+    // Iterate all entries in the directory and collect the files to download.
+    let mut remote_files = HashSet::new();
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry_url: Url = entry.url.parse().or_err(ErrorType::ParseError)?;
+        let is_dir = entry.is_dir;
+        let content_length = entry.content_length as u64;
+        let entry_output = make_output_by_entry(url.clone(), &output, entry)?;
+
+        // If entry is a directory, then create the output directory only when the entries are
+        // not filtered, the parent directories of the filtered files are created when
+        // downloading.
+        if is_dir {
+            if filter.is_empty() {
+                fs::create_dir_all(&entry_output).await.inspect_err(|err| {
+                    error!("create {} failed: {}", entry_output.to_string_lossy(), err);
+                })?;
+            }
+
+            continue;
+        }
+
+        remote_files.insert(entry_output.clone());
+        if !filter.matches(&output, &entry_output) {
+            debug!("skip {} filtered by patterns", entry_url);
+            continue;
+        }
+
+        // In the sync mode, skip the file already present in the output directory with the
+        // same content length, and remove the stale file to redownload it.
+        if args.sync {
+            if let Ok(metadata) = fs::metadata(&entry_output).await {
+                if metadata.is_file() && metadata.len() == content_length {
+                    debug!("skip {} already synced", entry_url);
+                    summary.skipped += 1;
+                    continue;
+                }
+
+                if let Err(err) = fs::remove_file(&entry_output).await {
+                    error!("remove {} failed: {}", entry_output.to_string_lossy(), err);
+                    summary.fail(
+                        entry_url.to_string(),
+                        entry_output,
+                        err.into(),
+                        Duration::ZERO,
+                    );
+                    continue;
+                }
+            }
+        }
+
+        let mut entry_args = args.clone();
+        entry_args.url = Some(entry_url);
+        entry_args.output = Some(entry_output);
+        files.push(entry_args);
     }
 
-    // Initialize the multi progress bar.
-    let multi_progress_bar = MultiProgress::new();
+    // If the actual file count to download is greater than the max_files, then reject the
+    // downloading.
+    if files.len() > args.max_files {
+        return Err(Error::MaxDownloadFilesExceeded(files.len()));
+    }
 
-    // Initialize the join set.
-    let mut join_set = JoinSet::new();
-    let semaphore = Arc::new(Semaphore::new(args.max_concurrent_requests));
+    // In the sync mode, delete the local files which are no longer present in the directory,
+    // the files filtered by patterns are kept.
+    if args.sync && args.delete {
+        for local_file in get_files(&output).await? {
+            if remote_files.contains(&local_file) || !filter.matches(&output, &local_file) {
+                continue;
+            }
 
-    // Iterate all entries in the directory.
-    for entry in entries {
-        let entry_url: Url = entry.url.parse().or_err(ErrorType::ParseError)?;
+            match fs::remove_file(&local_file).await {
+                Ok(_) => {
+                    info!("delete {}", local_file.to_string_lossy());
+                    summary.deleted += 1;
+                }
+                Err(err) => {
+                    error!("delete {} failed: {}", local_file.to_string_lossy(), err);
+                    summary.fail(String::new(), local_file, err.into(), Duration::ZERO);
+                }
+            }
+        }
+    }
 
-        // If entry is a directory, then create the output directory. If entry is a file,
-        // then download the file to the output directory.
-        if entry.is_dir {
-            let output_dir = make_output_by_entry(args.url.clone(), &args.output, entry)?;
-            fs::create_dir_all(&output_dir).await.inspect_err(|err| {
-                error!("create {} failed: {}", output_dir.to_string_lossy(), err);
-            })?;
-        } else {
-            let mut entry_args = args.clone();
-            entry_args.output = make_output_by_entry(args.url.clone(), &args.output, entry)?;
-            entry_args.url = entry_url;
-
-            let progress_bar = multi_progress_bar.add(ProgressBar::new(0));
-            async fn download_entry(
-                args: Args,
-                progress_bar: ProgressBar,
-                download_client: DfdaemonDownloadClient,
-                semaphore: Arc<Semaphore>,
-            ) -> Result<()> {
-                // Limit the concurrent download tasks.
-                let _permit = semaphore.acquire().await.unwrap();
-                download(args, progress_bar, download_client).await
+    download_files(
+        files,
+        args.max_concurrent_requests,
+        args.quiet || args.output_format == OutputFormat::Json,
+        download_client,
+        &mut summary,
+    )
+    .await?;
+
+    summary.finish(start_time.elapsed())
+}
+
+/// download_batch downloads all files in the manifest concurrently and prints the summary of
+/// the downloading. It returns an error if any file failed to download.
+async fn download_batch(
+    args: Args,
+    input_file: PathBuf,
+    download_client: DfdaemonDownloadClient,
+) -> Result<()> {
+    let items = manifest::load(&input_file).await?;
+    info!(
+        "download {} files in manifest {}",
+        items.len(),
+        input_file.to_string_lossy()
+    );
+
+    // Record the start time.
+    let start_time = Instant::now();
+    let mut summary = DownloadSummary::new(args.output_format);
+
+    // Iterate all items in the manifest, the fields of the item override the command line
+    // arguments.
+    let mut files = Vec::with_capacity(items.len());
+    for item in items {
+        let mut item_args = args.clone();
+        item_args.input_file = None;
+        item_args.url = Some(item.url);
+        item_args.output = Some(item.output);
+        if let Some(digest) = item.digest {
+            item_args.digest = digest;
+        }
+
+        if let Some(tag) = item.tag {
+            item_args.tag = tag;
+        }
+
+        if let Some(application) = item.application {
+            item_args.application = application;
+        }
+
+        if let Some(header) = item.header {
+            item_args.header.get_or_insert_with(Vec::new).extend(header);
+        }
+
+        // Skip the output downloaded by the previous run, so the manifest can be retried
+        // after the partial failure.
+        if let Some(output) = item_args.output.as_deref().filter(|output| output.exists()) {
+            if is_downloaded(output, &item_args.digest, args.skip_existing).await {
+                debug!("skip {} already downloaded", output.to_string_lossy());
+                summary.skipped += 1;
+                continue;
             }
+        }
 
-            join_set.spawn(download_entry(
-                entry_args,
-                progress_bar,
-                download_client.clone(),
-                semaphore.clone(),
-            ));
+        files.push(item_args);
+    }
+
+    download_files(
+        files,
+        args.max_concurrent_requests,
+        args.quiet || args.output_format == OutputFormat::Json,
+        download_client,
+        &mut summary,
+    )
+    .await?;
+
+    summary.finish(start_time.elapsed())
+}
+
+/// download_files downloads the files concurrently with the multi progress bar, the failed
+/// files are recorded in the summary rather than aborting the other downloads.
+async fn download_files(
+    files: Vec<Args>,
+    max_concurrent_requests: usize,
+    hide_progress: bool,
+    download_client: DfdaemonDownloadClient,
+    summary: &mut DownloadSummary,
+) -> Result<()> {
+    // Initialize the multi progress bar, it is hidden in the quiet mode or the json format.
+    let multi_progress_bar = if hide_progress {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    } else {
+        MultiProgress::new()
+    };
+
+    // Initialize the join set.
+    let mut join_set = JoinSet::new();
+    let semaphore = Arc::new(Semaphore::new(max_concurrent_requests));
+
+    for args in files {
+        let progress_bar = multi_progress_bar.add(ProgressBar::new(0));
+        async fn download_file(
+            args: Args,
+            progress_bar: ProgressBar,
+            download_client: DfdaemonDownloadClient,
+            semaphore: Arc<Semaphore>,
+        ) -> (Args, Duration, Result<ResultReport>) {
+            // Limit the concurrent download tasks.
+            let _permit = semaphore.acquire().await.unwrap();
+
+            let start_time = Instant::now();
+            let result = match prepare_output(args.output.as_deref()).await {
+                Ok(_) => download(args.clone(), progress_bar.clone(), download_client).await,
+                Err(err) => Err(err),
+            };
+
+            if result.is_err() {
+                progress_bar.abandon();
+            }
+
+            (args, start_time.elapsed(), result)
         }
+
+        join_set.spawn(download_file(
+            args,
+            progress_bar,
+            download_client.clone(),
+            semaphore.clone(),
+        ));
     }
 
-    // Wait for all download tasks finished.
-    while let Some(message) = join_set
+    // Wait for all download tasks finished and collect the failed files.
+    while let Some((args, cost, result)) = join_set
         .join_next()
         .await
         .transpose()
         .or_err(ErrorType::AsyncRuntimeError)?
     {
-        match message {
-            Ok(_) => continue,
+        let url = args.url.map(|url| url.to_string()).unwrap_or_default();
+        let output = args.output.unwrap_or_default();
+        match result {
+            Ok(report) => {
+                summary.succeeded += 1;
+                summary.files.push(report);
+            }
             Err(err) => {
-                error!("download entry failed: {}", err);
-                return Err(err);
+                error!(
+                    "download {} to {} failed: {}",
+                    url,
+                    output.to_string_lossy(),
+                    err
+                );
+
+                summary.fail(url, output, err, cost);
             }
         }
     }
@@ -687,14 +1052,351 @@ async fn download_dir(args: Args, download_client: DfdaemonDownloadClient) -> Re
     Ok(())
 }
 
-/// download downloads the single file.
+/// is_downloaded returns whether the existing output is downloaded by the previous run. If the
+/// digest is specified, the output is downloaded only if the digest of the output matches,
+/// otherwise it is regarded as downloaded if skip the existing outputs.
+async fn is_downloaded(output: &Path, digest: &str, skip_existing: bool) -> bool {
+    if digest.is_empty() {
+        return skip_existing;
+    }
+
+    let Ok(expected) = digest.parse::<Digest>() else {
+        return false;
+    };
+
+    // Calculate the digest of the output in the blocking thread, because the hashing is cpu bound.
+    let algorithm = expected.algorithm();
+    let output = output.to_path_buf();
+    match tokio::task::spawn_blocking(move || calculate_file_hash(algorithm, &output)).await {
+        Ok(Ok(actual)) => actual.encoded().eq_ignore_ascii_case(expected.encoded()),
+        Ok(Err(err)) => {
+            warn!("calculate digest failed: {}", err);
+            false
+        }
+        Err(err) => {
+            warn!("calculate digest failed: {}", err);
+            false
+        }
+    }
+}
+
+/// prepare_output creates the parent directory of the output path, and refuses to overwrite
+/// the existing file.
+async fn prepare_output(output: Option<&Path>) -> Result<()> {
+    let output = output.ok_or(Error::InvalidParameter)?;
+    if output.exists() {
+        return Err(Error::ValidationError(format!(
+            "output path {} is already exist",
+            output.to_string_lossy()
+        )));
+    }
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).await.inspect_err(|err| {
+            error!("create {} failed: {}", parent.to_string_lossy(), err);
+        })?;
+    }
+
+    Ok(())
+}
+
+/// DownloadSummary is the summary of downloading multiple files.
+#[derive(Debug, Default)]
+struct DownloadSummary {
+    /// output_format is the format to print the summary.
+    output_format: OutputFormat,
+
+    /// succeeded is the count of the downloaded files.
+    succeeded: usize,
+
+    /// skipped is the count of the files skipped in the sync mode or already downloaded in
+    /// the batch mode.
+    skipped: usize,
+
+    /// deleted is the count of the local files deleted in the sync mode.
+    deleted: usize,
+
+    /// failures are the url, the output path and the error of the failed files.
+    failures: Vec<(String, PathBuf, Error)>,
+
+    /// files are the results of the downloaded and failed files for the json format.
+    files: Vec<ResultReport>,
+}
+
+/// DownloadSummary implements the summary of downloading multiple files.
+impl DownloadSummary {
+    /// new creates a new download summary.
+    fn new(output_format: OutputFormat) -> Self {
+        Self {
+            output_format,
+            ..Default::default()
+        }
+    }
+
+    /// fail records the failed file, the failure is reported in both the text and json format.
+    fn fail(&mut self, url: String, output: PathBuf, err: Error, cost: Duration) {
+        let mut report = ResultReport::failed(ErrorClass::from_error(&err), &err, cost);
+        report.url = (!url.is_empty()).then(|| url.clone());
+        report.path = Some(output.to_string_lossy().to_string());
+        self.files.push(report);
+        self.failures.push((url, output, err));
+    }
+
+    /// finish prints the summary and the failed files, it returns an error if any file failed.
+    fn finish(mut self, elapsed: Duration) -> Result<()> {
+        if self.output_format == OutputFormat::Json {
+            return self.finish_json(elapsed);
+        }
+
+        println!(
+            "{}{}{}Downloading Summary:{} {} succeeded, {} skipped, {} deleted, {} failed in {:.1}s",
+            color::Fg(color::Cyan),
+            style::Italic,
+            style::Bold,
+            style::Reset,
+            self.succeeded,
+            self.skipped,
+            self.deleted,
+            self.failures.len(),
+            elapsed.as_secs_f64()
+        );
+
+        if self.failures.is_empty() {
+            return Ok(());
+        }
+
+        // Define the table struct for printing.
+        #[derive(Debug, Default, Tabled)]
+        #[tabled(rename_all = "UPPERCASE")]
+        struct TableFailure {
+            url: String,
+            output: String,
+            message: String,
+        }
+
+        self.failures
+            .sort_by(|(_, output, _), (_, other, _)| output.cmp(other));
+        let table_failures = self
+            .failures
+            .iter()
+            .map(|(url, output, err)| TableFailure {
+                url: url.clone(),
+                output: output.to_string_lossy().to_string(),
+                // Print the message of the grpc status rather than the details.
+                message: match err {
+                    Error::TonicStatus(status) => status.message().to_string(),
+                    err => err.to_string(),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let mut table = Table::new(table_failures);
+        table
+            .with(Style::blank())
+            .with(Modify::new(Rows::first()).with(Alignment::center()));
+        println!("{table}");
+
+        Err(Error::DownloadFilesFailed(
+            self.failures.len(),
+            self.succeeded + self.failures.len(),
+        ))
+    }
+
+    /// finish_json prints the result with the results of the files as a JSON line, it returns
+    /// an error if any file failed.
+    fn finish_json(mut self, elapsed: Duration) -> Result<()> {
+        self.files.sort_by(|a, b| a.path.cmp(&b.path));
+        if self.failures.is_empty() {
+            let mut report = ResultReport::succeeded(elapsed);
+            report.files = self.files;
+            report.print();
+            return Ok(());
+        }
+
+        let err =
+            Error::DownloadFilesFailed(self.failures.len(), self.succeeded + self.failures.len());
+
+        let mut report = ResultReport::failed(ErrorClass::PartialFailure, &err, elapsed);
+        report.files = self.files;
+        report.print();
+        Err(err)
+    }
+}
+
+/// EntryFilter filters the files in the directory by the include and exclude glob patterns,
+/// the patterns match the path relative to the directory.
+#[derive(Debug, Default)]
+struct EntryFilter {
+    /// include are the patterns of the files to download, all files are included if it is empty.
+    include: Vec<Pattern>,
+
+    /// exclude are the patterns of the files not to download.
+    exclude: Vec<Pattern>,
+}
+
+/// EntryFilter implements the filter of the files in the directory.
+impl EntryFilter {
+    /// new creates a new entry filter by the glob patterns.
+    fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        let parse = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    Pattern::new(pattern).map_err(|err| {
+                        Error::ValidationError(format!("invalid glob pattern {}: {}", pattern, err))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+
+        Ok(Self {
+            include: parse(include)?,
+            exclude: parse(exclude)?,
+        })
+    }
+
+    /// is_empty returns whether the filter has no patterns.
+    fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// matches returns whether the file under the root directory is included by the filter.
+    fn matches(&self, root_dir: &Path, path: &Path) -> bool {
+        let relative_path = path.strip_prefix(root_dir).unwrap_or(path);
+        let relative_path = relative_path.to_string_lossy();
+        if self
+            .exclude
+            .iter()
+            .any(|pattern| pattern.matches(&relative_path))
+        {
+            return false;
+        }
+
+        self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| pattern.matches(&relative_path))
+    }
+}
+
+/// download downloads the single file. The file is downloaded to the temporary file next to
+/// the output and renamed to the output after the downloading succeeds, so the interrupted
+/// downloading never leaves the partial output, which may be regarded as synced by the
+/// content length in the sync mode. The output created during downloading is not overwritten.
 async fn download(
+    mut args: Args,
+    progress_bar: ProgressBar,
+    download_client: DfdaemonDownloadClient,
+) -> Result<ResultReport> {
+    let Some(output) = args.output.clone() else {
+        return Err(Error::InvalidParameter);
+    };
+
+    if is_stdout(&output) {
+        return download_to(args, progress_bar, download_client).await;
+    }
+
+    // Remove the temporary file left by the previous interrupted downloading.
+    let temp_output = get_temp_output(&output);
+    if let Err(err) = fs::remove_file(&temp_output).await {
+        if err.kind() != std::io::ErrorKind::NotFound {
+            error!("remove {:?} failed: {}", temp_output, err);
+            return Err(err.into());
+        }
+    }
+
+    args.output = Some(temp_output.clone());
+    let result = match download_to(args, progress_bar, download_client).await {
+        Ok(report) => rename_output(&temp_output, &output).await.map(|_| report),
+        Err(err) => Err(err),
+    };
+
+    match result {
+        Ok(mut report) => {
+            report.path = Some(output.to_string_lossy().to_string());
+            Ok(report)
+        }
+        Err(err) => {
+            if let Err(err) = fs::remove_file(&temp_output).await {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    warn!("remove {:?} failed: {}", temp_output, err);
+                }
+            }
+
+            Err(err)
+        }
+    }
+}
+
+/// rename_output renames the temporary file to the output without overwriting the output
+/// created by the others after the validation. The temporary file is hard linked to the
+/// output, which fails if the output exists, and then removed. If the file system does not
+/// support the hard link, the output is checked before renaming.
+async fn rename_output(temp_output: &Path, output: &Path) -> Result<()> {
+    match fs::hard_link(temp_output, output).await {
+        Ok(_) => {
+            if let Err(err) = fs::remove_file(temp_output).await {
+                warn!("remove {:?} failed: {}", temp_output, err);
+            }
+
+            Ok(())
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+            error!("output {:?} is created during downloading", output);
+            Err(Error::ValidationError(format!(
+                "output path {} is already exist",
+                output.to_string_lossy()
+            )))
+        }
+        Err(err) => {
+            warn!(
+                "hard link {:?} to {:?} failed, fallback to rename: {}",
+                temp_output, output, err
+            );
+
+            if fs::try_exists(output).await? {
+                error!("output {:?} is created during downloading", output);
+                return Err(Error::ValidationError(format!(
+                    "output path {} is already exist",
+                    output.to_string_lossy()
+                )));
+            }
+
+            fs::rename(temp_output, output).await.inspect_err(|err| {
+                error!("rename {:?} to {:?} failed: {}", temp_output, output, err);
+            })?;
+
+            Ok(())
+        }
+    }
+}
+
+/// get_temp_output gets the temporary file next to the output for downloading.
+fn get_temp_output(output: &Path) -> PathBuf {
+    let file_name = output
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    output.with_file_name(format!(".{}.dfget", file_name))
+}
+
+/// download_to downloads the single file to the output.
+async fn download_to(
     args: Args,
     progress_bar: ProgressBar,
     download_client: DfdaemonDownloadClient,
-) -> Result<()> {
+) -> Result<ResultReport> {
+    let (Some(url), Some(output)) = (args.url.clone(), args.output.clone()) else {
+        return Err(Error::InvalidParameter);
+    };
+
+    // Record the start time.
+    let start_time = Instant::now();
+    let json = args.output_format == OutputFormat::Json;
+
     // Only initialize object storage when the scheme is an object storage protocol.
-    let object_storage = match object_storage::Scheme::from_str(args.url.scheme()) {
+    let object_storage = match object_storage::Scheme::from_str(url.scheme()) {
         Ok(_) => Some(ObjectStorage {
             access_key_id: args.storage_access_key_id.clone(),
             access_key_secret: args.storage_access_key_secret.clone(),
@@ -708,7 +1410,7 @@ async fn download(
     };
 
     // Only initialize HDFS when the scheme is HDFS protocol.
-    let hdfs = match args.url.scheme() {
+    let hdfs = match url.scheme() {
         hdfs::HDFS_SCHEME => Some(Hdfs {
             delegation_token: args.hdfs_delegation_token.clone(),
         }),
@@ -723,17 +1425,19 @@ async fn download(
     // Dfget needs to notify dfdaemon to transfer the piece content of downloading file via unix domain socket
     // when the `transfer_from_dfdaemon` is true. Otherwise, dfdaemon will download the file and hardlink or
     // copy the file to the output path.
-    let (output_path, need_piece_content) = if args.transfer_from_dfdaemon {
+    // Streaming to stdout always needs the piece content.
+    let to_stdout = is_stdout(&output);
+    let (output_path, need_piece_content) = if args.transfer_from_dfdaemon || to_stdout {
         (None, true)
     } else {
-        (Some(args.output.to_string_lossy().to_string()), false)
+        (Some(output.to_string_lossy().to_string()), false)
     };
 
     // Create dfdaemon client.
     let response = download_client
         .download_task(DownloadTaskRequest {
             download: Some(Download {
-                url: args.url.to_string(),
+                url: url.to_string(),
                 digest: Some(args.digest),
                 // NOTE: Dfget does not support range download.
                 range: None,
@@ -767,8 +1471,8 @@ async fn download(
 
     // If transfer_from_dfdaemon is true, then dfget needs to create the output file and write the
     // piece content to the output file.
-    let mut f = if args.transfer_from_dfdaemon {
-        if let Some(parent) = args.output.parent() {
+    let mut f = if args.transfer_from_dfdaemon && !to_stdout {
+        if let Some(parent) = output.parent() {
             if !parent.exists() {
                 fs::create_dir_all(parent).await.inspect_err(|err| {
                     error!("failed to create directory {:?}: {}", parent, err);
@@ -780,10 +1484,10 @@ async fn download(
             .create_new(true)
             .write(true)
             .mode(dfget::DEFAULT_OUTPUT_FILE_MODE)
-            .open(&args.output)
+            .open(&output)
             .await
             .inspect_err(|err| {
-                error!("open file {:?} failed: {}", args.output, err);
+                error!("open file {:?} failed: {}", output, err);
             })?;
 
         Some(f)
@@ -791,8 +1495,12 @@ async fn download(
         None
     };
 
+    // If the output is stdout, then dfget writes the piece content to stdout in order.
+    let mut stdout_writer =
+        to_stdout.then(|| OrderedWriter::new(tokio::io::stdout(), MAX_PENDING_PIECES_SIZE));
+
     // Get actual path rather than percentage encoded path as download path.
-    let download_path = percent_decode_str(args.url.path()).decode_utf8_lossy();
+    let download_path = percent_decode_str(url.path()).decode_utf8_lossy();
     progress_bar.set_style(
         ProgressStyle::with_template(
             "{msg:.bold}\n[{elapsed_precise}] [{bar:60.green/red}] {percent:3}% ({bytes_per_sec:.red}, {eta:.cyan})",
@@ -807,31 +1515,58 @@ async fn download(
 
     // Download file.
     let mut downloaded = 0;
+    let mut task_id = String::new();
+    let mut content_length = None;
+    let mut pieces = PieceStats::default();
     let mut out_stream = response.into_inner();
     while let Some(message) = out_stream.message().await.inspect_err(|err| {
         error!("get message failed: {}", err);
     })? {
+        if task_id.is_empty() {
+            task_id.clone_from(&message.task_id);
+        }
+
         match message.response {
             Some(download_task_response::Response::DownloadTaskStartedResponse(response)) => {
                 progress_bar.set_length(response.content_length);
+                content_length = Some(response.content_length);
+                if json {
+                    Event::Started {
+                        task_id: message.task_id,
+                        peer_id: message.peer_id,
+                        content_length: response.content_length,
+                    }
+                    .print();
+                }
             }
             Some(download_task_response::Response::DownloadPieceFinishedResponse(response)) => {
                 let piece = response.piece.ok_or(Error::InvalidParameter)?;
+                pieces.record(&piece);
 
                 // Dfget needs to write the piece content to the output file.
                 if let Some(f) = &mut f {
                     f.seek(SeekFrom::Start(piece.offset))
                         .await
                         .inspect_err(|err| {
-                            error!("seek {:?} failed: {}", args.output, err);
+                            error!("seek {:?} failed: {}", output, err);
                         })?;
 
                     let content = piece.content.ok_or(Error::InvalidParameter)?;
                     f.write_all(&content).await.inspect_err(|err| {
-                        error!("write {:?} failed: {}", args.output, err);
+                        error!("write {:?} failed: {}", output, err);
                     })?;
 
-                    debug!("copy piece {} to {:?} success", piece.number, args.output);
+                    debug!("copy piece {} to {:?} success", piece.number, output);
+                } else if let Some(stdout_writer) = &mut stdout_writer {
+                    let content = piece.content.ok_or(Error::InvalidParameter)?;
+                    stdout_writer
+                        .write(piece.offset, content)
+                        .await
+                        .inspect_err(|err| {
+                            error!("write piece {} to stdout failed: {}", piece.number, err);
+                        })?;
+
+                    debug!("write piece {} to stdout success", piece.number);
                 }
 
                 downloaded += piece.length;
@@ -840,13 +1575,45 @@ async fn download(
                     progress_bar.length().unwrap_or(0),
                 );
                 progress_bar.set_position(position);
+
+                if json {
+                    Event::Progress {
+                        task_id: task_id.clone(),
+                        piece_number: piece.number,
+                        downloaded_length: downloaded,
+                        content_length: progress_bar.length().unwrap_or(0),
+                    }
+                    .print();
+                }
             }
             None => {}
         }
     }
 
+    // Flush the remaining content to stdout and check all pieces are written.
+    if let Some(stdout_writer) = stdout_writer {
+        stdout_writer
+            .finish(progress_bar.length())
+            .await
+            .inspect_err(|err| {
+                error!("write to stdout failed: {}", err);
+            })?;
+    }
+
     progress_bar.finish();
-    Ok(())
+
+    let mut report = ResultReport::succeeded(start_time.elapsed());
+    report.task_id = Some(task_id);
+    report.url = Some(url.to_string());
+    report.path = Some(output.to_string_lossy().to_string());
+    report.content_length = content_length;
+    report.pieces = Some(pieces);
+    Ok(report)
+}
+
+/// is_stdout returns whether the output path is stdout.
+fn is_stdout(output: &Path) -> bool {
+    output == Path::new(STDOUT_OUTPUT)
 }
 
 /// get_entries gets all entries in the directory.
@@ -855,9 +1622,11 @@ async fn get_entries(
     object_storage: Option<ObjectStorage>,
     hdfs: Option<Hdfs>,
 ) -> Result<Vec<DirEntry>> {
+    let url = args.url.ok_or(Error::InvalidParameter)?;
+
     // Initialize backend factory and build backend.
     let backend_factory = BackendFactory::new(None)?;
-    let backend = backend_factory.build(args.url.as_str())?;
+    let backend = backend_factory.build(url.as_str())?;
 
     // Collect backend request started metrics.
     collect_backend_request_started_metrics(backend.scheme().as_str(), http::Method::HEAD.as_str());
@@ -868,7 +1637,7 @@ async fn get_entries(
         .head(HeadRequest {
             // NOTE: Mock a task id for head request.
             task_id: Uuid::new_v4().to_string(),
-            url: args.url.to_string(),
+            url: url.to_string(),
             http_header: Some(header_vec_to_headermap(
                 args.header.clone().unwrap_or_default(),
             )?),
@@ -876,6 +1645,7 @@ async fn get_entries(
             client_cert: None,
             object_storage,
             hdfs,
+            recursive: args.recursive,
         })
         .await
         .inspect_err(|_err| {
@@ -942,18 +1712,74 @@ async fn get_dfdaemon_download_client(endpoint: PathBuf) -> Result<DfdaemonDownl
 
 /// validate_args validates the command line arguments.
 fn validate_args(args: &Args) -> Result<()> {
+    // The verbose logs are printed to stdout, which breaks the json output.
+    if args.verbose && args.output_format == OutputFormat::Json {
+        return Err(Error::ValidationError(
+            "verbose is not supported when the output format is json".to_string(),
+        ));
+    }
+
+    // If the input file is specified, the files in the manifest are validated when the
+    // manifest is loaded.
+    if let Some(input_file) = &args.input_file {
+        if !input_file.is_file() {
+            return Err(Error::ValidationError(format!(
+                "input file {} is not a file",
+                input_file.to_string_lossy()
+            )));
+        }
+
+        return Ok(());
+    }
+
+    let (Some(url), Some(output)) = (&args.url, &args.output) else {
+        return Err(Error::ValidationError(
+            "url and output path are required".to_string(),
+        ));
+    };
+
+    // If the output is stdout, only the single file can be streamed and the logs should not be
+    // mixed into the content.
+    if is_stdout(output) {
+        if url.path().ends_with('/') {
+            return Err(Error::ValidationError(
+                "output path - is not supported when downloading a directory".to_string(),
+            ));
+        }
+
+        if args.verbose {
+            return Err(Error::ValidationError(
+                "verbose is not supported when the output path is -".to_string(),
+            ));
+        }
+
+        if args.output_format == OutputFormat::Json {
+            return Err(Error::ValidationError(
+                "json output format is not supported when the output path is -".to_string(),
+            ));
+        }
+
+        return Ok(());
+    }
+
+    // The glob patterns of the include and exclude should be valid.
+    EntryFilter::new(
+        &args.include.clone().unwrap_or_default(),
+        &args.exclude.clone().unwrap_or_default(),
+    )?;
+
     // If the URL is a directory, the output path should be a directory.
-    if args.url.path().ends_with('/') && !args.output.is_dir() {
+    if url.path().ends_with('/') && !output.is_dir() {
         return Err(Error::ValidationError(format!(
             "output path {} is not a directory",
-            args.output.to_string_lossy()
+            output.to_string_lossy()
         )));
     }
 
     // If the URL is a file, the output path should be a file and the parent directory should
     // exist.
-    if !args.url.path().ends_with('/') {
-        let absolute_path = Path::new(&args.output).absolutize()?;
+    if !url.path().ends_with('/') {
+        let absolute_path = output.absolutize()?;
         match absolute_path.parent() {
             Some(parent_path) => {
                 if !parent_path.is_dir() {
@@ -966,7 +1792,7 @@ fn validate_args(args: &Args) -> Result<()> {
             None => {
                 return Err(Error::ValidationError(format!(
                     "output path {} is not exist",
-                    args.output.to_string_lossy()
+                    output.to_string_lossy()
                 )));
             }
         }
@@ -974,7 +1800,7 @@ fn validate_args(args: &Args) -> Result<()> {
         if absolute_path.exists() {
             return Err(Error::ValidationError(format!(
                 "output path {} is already exist",
-                args.output.to_string_lossy()
+                output.to_string_lossy()
             )));
         }
     }
@@ -1084,6 +1910,211 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_validate_args_with_input_file() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let input_file_path = tempdir.path().join("list.txt");
+        std::fs::write(&input_file_path, "http://test.local/test.txt /tmp/test.txt").unwrap();
+
+        let args = Args::parse_from(vec![
+            "dfget",
+            "--input-file",
+            input_file_path.as_os_str().to_str().unwrap(),
+        ]);
+        assert!(args.url.is_none());
+        assert!(args.output.is_none());
+        assert!(validate_args(&args).is_ok());
+
+        let non_exist_file_path = tempdir.path().join("non_exist.txt");
+        let args = Args::parse_from(vec![
+            "dfget",
+            "--input-file",
+            non_exist_file_path.as_os_str().to_str().unwrap(),
+        ]);
+        assert_eq!(
+            validate_args(&args).unwrap_err().to_string(),
+            Error::ValidationError(format!(
+                "input file {} is not a file",
+                non_exist_file_path.display()
+            ))
+            .to_string()
+        );
+
+        // The input file conflicts with the url and the output.
+        assert!(Args::try_parse_from(vec![
+            "dfget",
+            "http://test.local/test.txt",
+            "--input-file",
+            input_file_path.as_os_str().to_str().unwrap(),
+        ])
+        .is_err());
+
+        // The url and the output are required without the input file.
+        assert!(Args::try_parse_from(vec!["dfget", "http://test.local/test.txt"]).is_err());
+    }
+
+    #[test]
+    fn should_filter_entries_by_patterns() {
+        let root_dir = Path::new("/tmp/model");
+        let filter = EntryFilter::new(
+            &["*.safetensors".to_string(), "tokenizer/*".to_string()],
+            &["*.tmp".to_string(), "tokenizer/vocab.txt".to_string()],
+        )
+        .unwrap();
+        assert!(!filter.is_empty());
+
+        let test_cases = vec![
+            ("model-00001.safetensors", true),
+            ("shards/model-00002.safetensors", true),
+            ("tokenizer/config.json", true),
+            ("tokenizer/vocab.txt", false),
+            ("tokenizer/cache.tmp", false),
+            ("README.md", false),
+        ];
+
+        for (path, expected) in test_cases {
+            assert_eq!(
+                filter.matches(root_dir, &root_dir.join(path)),
+                expected,
+                "{}",
+                path
+            );
+        }
+
+        // All files are included without patterns.
+        let filter = EntryFilter::new(&[], &[]).unwrap();
+        assert!(filter.is_empty());
+        assert!(filter.matches(root_dir, &root_dir.join("README.md")));
+
+        // Invalid pattern.
+        assert!(EntryFilter::new(&["[".to_string()], &[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn should_check_downloaded_output() {
+        let tempdir = tempdir().unwrap();
+        let output = tempdir.path().join("test.txt");
+        std::fs::write(&output, "test").unwrap();
+
+        let digest =
+            calculate_file_hash(dragonfly_client_util::digest::Algorithm::Sha256, &output).unwrap();
+        assert!(is_downloaded(&output, &digest.to_string(), false).await);
+        assert!(
+            !is_downloaded(
+                &output,
+                "sha256:0000000000000000000000000000000000000000000000000000000000000000",
+                true
+            )
+            .await
+        );
+        assert!(is_downloaded(&output, "", true).await);
+        assert!(!is_downloaded(&output, "", false).await);
+    }
+
+    #[tokio::test]
+    async fn should_rename_output_without_overwriting() {
+        let tempdir = tempdir().unwrap();
+        let output = tempdir.path().join("test.txt");
+        let temp_output = get_temp_output(&output);
+
+        fs::write(&temp_output, "downloaded").await.unwrap();
+        rename_output(&temp_output, &output).await.unwrap();
+        assert_eq!(fs::read_to_string(&output).await.unwrap(), "downloaded");
+        assert!(!temp_output.exists());
+
+        // The output created during downloading is not overwritten.
+        fs::write(&temp_output, "overwritten").await.unwrap();
+        assert!(rename_output(&temp_output, &output).await.is_err());
+        assert_eq!(fs::read_to_string(&output).await.unwrap(), "downloaded");
+    }
+
+    #[test]
+    fn should_get_temp_output() {
+        assert_eq!(
+            get_temp_output(Path::new("/tmp/dir/test.txt")),
+            PathBuf::from("/tmp/dir/.test.txt.dfget")
+        );
+    }
+
+    #[test]
+    fn should_require_sync_when_delete() {
+        assert!(Args::try_parse_from(vec![
+            "dfget",
+            "http://test.local/test-dir/",
+            "--output",
+            "/tmp/test-dir",
+            "--delete",
+        ])
+        .is_err());
+
+        let args = Args::try_parse_from(vec![
+            "dfget",
+            "http://test.local/test-dir/",
+            "--output",
+            "/tmp/test-dir",
+            "--include",
+            "*.txt",
+            "--sync",
+            "--delete",
+        ])
+        .unwrap();
+        assert!(args.sync && args.delete);
+        assert_eq!(args.include, Some(vec!["*.txt".to_string()]));
+    }
+
+    #[test]
+    fn should_validate_args_with_stdout_output() {
+        let args = Args::parse_from(vec!["dfget", "http://test.local/test.txt", "-O", "-"]);
+        assert!(validate_args(&args).is_ok());
+
+        let test_cases = vec![
+            (
+                Args::parse_from(vec!["dfget", "http://test.local/test-dir/", "-O", "-"]),
+                "output path - is not supported when downloading a directory",
+            ),
+            (
+                Args::parse_from(vec![
+                    "dfget",
+                    "http://test.local/test.txt",
+                    "-O",
+                    "-",
+                    "--verbose",
+                ]),
+                "verbose is not supported when the output path is -",
+            ),
+            (
+                Args::parse_from(vec![
+                    "dfget",
+                    "http://test.local/test.txt",
+                    "-O",
+                    "/tmp/test.txt",
+                    "--output-format",
+                    "json",
+                    "--verbose",
+                ]),
+                "verbose is not supported when the output format is json",
+            ),
+            (
+                Args::parse_from(vec![
+                    "dfget",
+                    "http://test.local/test.txt",
+                    "-O",
+                    "-",
+                    "--output-format",
+                    "json",
+                ]),
+                "json output format is not supported when the output path is -",
+            ),
+        ];
+
+        for (args, error_message) in test_cases {
+            assert_eq!(
+                validate_args(&args).unwrap_err().to_string(),
+                Error::ValidationError(error_message.to_string()).to_string()
+            );
+        }
+    }
+
     #[test]
     fn should_make_output_by_entry() {
         let url = Url::parse("http://example.com/root/").unwrap();
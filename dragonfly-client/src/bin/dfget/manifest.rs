@@ -0,0 +1,306 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_core::{
+    error::{ErrorType, OrErr},
+    Error, Result,
+};
+use path_absolutize::*;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::error;
+use url::Url;
+
+/// Item is the file to download in the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    /// url is the url of the file to download.
+    pub url: Url,
+
+    /// output is the absolute output path of the file.
+    pub output: PathBuf,
+
+    /// digest is the digest to verify the integrity of the file, e.g. md5:86d3f3a95c324c9479bd8986968f4327.
+    pub digest: Option<String>,
+
+    /// header is the headers for downloading the file, e.g. `Content-Type: application/json`.
+    pub header: Option<Vec<String>>,
+
+    /// tag is the tag of the file, the same url with different tags are different tasks.
+    pub tag: Option<String>,
+
+    /// application is the caller application of the file.
+    pub application: Option<String>,
+}
+
+/// RawItem is the item deserialized from the JSON or YAML manifest.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawItem {
+    /// url is the url of the file to download.
+    url: String,
+
+    /// output is the output path of the file.
+    output: PathBuf,
+
+    /// digest is the digest to verify the integrity of the file.
+    #[serde(default)]
+    digest: Option<String>,
+
+    /// header is the headers for downloading the file.
+    #[serde(default)]
+    header: Option<Vec<String>>,
+
+    /// tag is the tag of the file.
+    #[serde(default)]
+    tag: Option<String>,
+
+    /// application is the caller application of the file.
+    #[serde(default)]
+    application: Option<String>,
+}
+
+/// Format is the format of the manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// Text lists one file per line as `<url> <output> [digest]`.
+    Text,
+
+    /// Json is a JSON array of the items.
+    Json,
+
+    /// Yaml is a YAML sequence of the items.
+    Yaml,
+}
+
+impl Format {
+    /// from_path gets the format of the manifest by the file extension.
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Format::Json,
+            Some("yaml") | Some("yml") => Format::Yaml,
+            _ => Format::Text,
+        }
+    }
+}
+
+/// load loads the items from the manifest file, the format of the manifest is determined by
+/// the file extension, `.json` for JSON, `.yaml` and `.yml` for YAML, and the others for the
+/// plain text.
+pub async fn load(path: &Path) -> Result<Vec<Item>> {
+    let content = fs::read_to_string(path).await.inspect_err(|err| {
+        error!("read manifest {} failed: {}", path.display(), err);
+    })?;
+
+    let items = parse(&content, Format::from_path(path))?;
+    if items.is_empty() {
+        return Err(Error::ValidationError(format!(
+            "manifest {} has no file to download",
+            path.display()
+        )));
+    }
+
+    Ok(items)
+}
+
+/// parse parses and validates the items from the content of the manifest.
+fn parse(content: &str, format: Format) -> Result<Vec<Item>> {
+    let raw_items = match format {
+        Format::Text => parse_text(content)?,
+        Format::Json => serde_json::from_str(content).or_err(ErrorType::ParseError)?,
+        Format::Yaml => serde_yaml::from_str(content).or_err(ErrorType::ParseError)?,
+    };
+
+    let mut outputs = HashSet::with_capacity(raw_items.len());
+    let mut items = Vec::with_capacity(raw_items.len());
+    for (index, raw_item) in raw_items.into_iter().enumerate() {
+        let url = Url::parse(&raw_item.url).map_err(|err| {
+            Error::ValidationError(format!(
+                "item {} has invalid url {}: {}",
+                index + 1,
+                raw_item.url,
+                err
+            ))
+        })?;
+
+        // Batch mode downloads the single files, the directory should be downloaded by the
+        // url with the trailing slash directly.
+        if url.path().ends_with('/') {
+            return Err(Error::ValidationError(format!(
+                "item {} url {} is a directory",
+                index + 1,
+                url
+            )));
+        }
+
+        let output: PathBuf = raw_item.output.absolutize()?.into();
+        if !outputs.insert(output.clone()) {
+            return Err(Error::ValidationError(format!(
+                "item {} output path {} is duplicated",
+                index + 1,
+                output.display()
+            )));
+        }
+
+        items.push(Item {
+            url,
+            output,
+            digest: raw_item.digest.filter(|digest| !digest.is_empty()),
+            header: raw_item.header,
+            tag: raw_item.tag,
+            application: raw_item.application,
+        });
+    }
+
+    Ok(items)
+}
+
+/// parse_text parses the plain text manifest, the empty lines and the lines starting with `#`
+/// are ignored.
+fn parse_text(content: &str) -> Result<Vec<RawItem>> {
+    let mut raw_items = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [url, output] => raw_items.push(RawItem {
+                url: url.to_string(),
+                output: PathBuf::from(output),
+                ..Default::default()
+            }),
+            [url, output, digest] => raw_items.push(RawItem {
+                url: url.to_string(),
+                output: PathBuf::from(output),
+                digest: Some(digest.to_string()),
+                ..Default::default()
+            }),
+            _ => {
+                return Err(Error::ValidationError(format!(
+                    "line {} should be in the format of `<url> <output> [digest]`",
+                    index + 1
+                )))
+            }
+        }
+    }
+
+    Ok(raw_items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_text_manifest() {
+        let content = r#"
+# Shards of the dataset.
+http://example.com/shard-0 /tmp/shard-0
+http://example.com/shard-1   /tmp/shard-1   sha256:6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b
+"#;
+
+        let items = parse(content, Format::Text).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].url.as_str(), "http://example.com/shard-0");
+        assert_eq!(items[0].output, PathBuf::from("/tmp/shard-0"));
+        assert_eq!(items[0].digest, None);
+        assert_eq!(
+            items[1].digest.as_deref(),
+            Some("sha256:6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b")
+        );
+    }
+
+    #[test]
+    fn should_parse_json_and_yaml_manifest() {
+        let json = r#"[
+            {
+                "url": "http://example.com/shard-0",
+                "output": "/tmp/shard-0",
+                "digest": "md5:86d3f3a95c324c9479bd8986968f4327",
+                "header": ["Authorization: Bearer token"],
+                "tag": "v1",
+                "application": "trainer"
+            },
+            {"url": "s3://bucket/shard-1", "output": "/tmp/shard-1"}
+        ]"#;
+
+        let yaml = r#"
+- url: http://example.com/shard-0
+  output: /tmp/shard-0
+  digest: md5:86d3f3a95c324c9479bd8986968f4327
+  header:
+    - "Authorization: Bearer token"
+  tag: v1
+  application: trainer
+- url: s3://bucket/shard-1
+  output: /tmp/shard-1
+"#;
+
+        let json_items = parse(json, Format::Json).unwrap();
+        let yaml_items = parse(yaml, Format::Yaml).unwrap();
+        assert_eq!(json_items, yaml_items);
+        assert_eq!(json_items.len(), 2);
+        assert_eq!(
+            json_items[0].header,
+            Some(vec!["Authorization: Bearer token".to_string()])
+        );
+        assert_eq!(json_items[0].tag.as_deref(), Some("v1"));
+        assert_eq!(json_items[0].application.as_deref(), Some("trainer"));
+        assert_eq!(json_items[1].url.scheme(), "s3");
+        assert_eq!(json_items[1].header, None);
+    }
+
+    #[test]
+    fn should_return_error_when_manifest_is_invalid() {
+        let test_cases = vec![
+            ("http://example.com/shard-0", Format::Text),
+            (
+                "http://example.com/shard-0 /tmp/a md5:1 extra",
+                Format::Text,
+            ),
+            ("invalid-url /tmp/shard-0", Format::Text),
+            ("http://example.com/dir/ /tmp/dir", Format::Text),
+            (
+                "http://example.com/shard-0 /tmp/shard\nhttp://example.com/shard-1 /tmp/shard",
+                Format::Text,
+            ),
+            (r#"[{"url": "http://example.com/shard-0"}]"#, Format::Json),
+            (
+                r#"[{"url": "http://example.com/a", "output": "/tmp/a", "unknown": 1}]"#,
+                Format::Json,
+            ),
+            ("url: http://example.com/shard-0", Format::Yaml),
+        ];
+
+        for (content, format) in test_cases {
+            assert!(parse(content, format).is_err(), "{}", content);
+        }
+    }
+
+    #[test]
+    fn should_get_format_from_path() {
+        assert_eq!(Format::from_path(Path::new("list.json")), Format::Json);
+        assert_eq!(Format::from_path(Path::new("list.yaml")), Format::Yaml);
+        assert_eq!(Format::from_path(Path::new("list.yml")), Format::Yaml);
+        assert_eq!(Format::from_path(Path::new("list.txt")), Format::Text);
+        assert_eq!(Format::from_path(Path::new("list")), Format::Text);
+    }
+}
@@ -21,13 +21,23 @@ use dragonfly_api::manager::v2::{
     ListSchedulersRequest, ListSchedulersResponse, Scheduler, SourceType,
 };
 use dragonfly_client_config::{dfdaemon::Config, CARGO_PKG_VERSION, GIT_COMMIT_SHORT_HASH};
-use dragonfly_client_core::{Error, Result};
+use dragonfly_client_core::{
+    error::{ErrorType, OrErr},
+    Error, Result,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::fs;
 use tokio::sync::{mpsc, Mutex, RwLock};
 use tonic_health::pb::health_check_response::ServingStatus;
-use tracing::{error, info, instrument};
+use tracing::{error, info, instrument, warn};
 use url::Url;
 
+/// DEFAULT_CACHE_FILE_NAME is the default file name of the cached dynamic configuration
+/// in the cache directory.
+const DEFAULT_CACHE_FILE_NAME: &str = "dynconfig.json";
+
 /// Data is the dynamic configuration of the dfdaemon.
 #[derive(Default)]
 pub struct Data {
@@ -41,6 +51,70 @@ pub struct Data {
     pub available_scheduler_cluster_id: Option<u64>,
 }
 
+/// CachedScheduler is the scheduler stored in the cache file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct CachedScheduler {
+    /// id is the id of the scheduler.
+    id: u64,
+
+    /// hostname is the hostname of the scheduler.
+    hostname: String,
+
+    /// idc is the idc of the scheduler.
+    idc: Option<String>,
+
+    /// location is the location of the scheduler.
+    location: Option<String>,
+
+    /// ip is the ip of the scheduler.
+    ip: String,
+
+    /// port is the port of the scheduler.
+    port: i32,
+
+    /// scheduler_cluster_id is the id of the scheduler cluster.
+    scheduler_cluster_id: u64,
+}
+
+/// CachedScheduler implements From<&Scheduler>.
+impl From<&Scheduler> for CachedScheduler {
+    fn from(scheduler: &Scheduler) -> Self {
+        CachedScheduler {
+            id: scheduler.id,
+            hostname: scheduler.hostname.clone(),
+            idc: scheduler.idc.clone(),
+            location: scheduler.location.clone(),
+            ip: scheduler.ip.clone(),
+            port: scheduler.port,
+            scheduler_cluster_id: scheduler.scheduler_cluster_id,
+        }
+    }
+}
+
+/// Scheduler implements From<CachedScheduler>.
+impl From<CachedScheduler> for Scheduler {
+    fn from(scheduler: CachedScheduler) -> Self {
+        Scheduler {
+            id: scheduler.id,
+            hostname: scheduler.hostname,
+            idc: scheduler.idc,
+            location: scheduler.location,
+            ip: scheduler.ip,
+            port: scheduler.port,
+            scheduler_cluster_id: scheduler.scheduler_cluster_id,
+            ..Default::default()
+        }
+    }
+}
+
+/// Cache is the dynamic configuration stored in the cache file, it is used when
+/// the manager is unavailable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Cache {
+    /// schedulers is the last schedulers listed from the manager.
+    schedulers: Vec<CachedScheduler>,
+}
+
 /// Dynconfig supports dynamic configuration of the client.
 pub struct Dynconfig {
     /// data is the dynamic configuration of the dfdaemon.
@@ -52,6 +126,9 @@ pub struct Dynconfig {
     /// manager_client is the grpc client of the manager.
     manager_client: Arc<ManagerClient>,
 
+    /// cache_path is the path of the cache file of the dynamic configuration.
+    cache_path: PathBuf,
+
     /// mutex is used to protect refresh.
     mutex: Mutex<()>,
 
@@ -73,10 +150,12 @@ impl Dynconfig {
         shutdown_complete_tx: mpsc::UnboundedSender<()>,
     ) -> Result<Self> {
         // Create a new Dynconfig.
+        let cache_path = config.server.cache_dir.join(DEFAULT_CACHE_FILE_NAME);
         let dc = Dynconfig {
             config,
             data: RwLock::new(Data::default()),
             manager_client,
+            cache_path,
             mutex: Mutex::new(()),
             shutdown,
             _shutdown_complete: shutdown_complete_tx,
@@ -120,8 +199,17 @@ impl Dynconfig {
             return Ok(());
         };
 
-        // refresh the schedulers.
-        let schedulers = self.list_schedulers().await?;
+        // refresh the schedulers, if the manager is unavailable, use the fallback schedulers.
+        let (schedulers, is_fallback) = match self.list_schedulers().await {
+            Ok(schedulers) => (schedulers, false),
+            Err(err) => {
+                warn!(
+                    "list schedulers from manager failed, use the fallback: {}",
+                    err
+                );
+                (self.list_fallback_schedulers().await.ok_or(err)?, true)
+            }
+        };
 
         // Get the available schedulers.
         let available_schedulers = self
@@ -133,6 +221,23 @@ impl Dynconfig {
             return Err(Error::AvailableSchedulersNotFound);
         }
 
+        // Store the schedulers listed from the manager to the cache file, they will be
+        // used when the manager is unavailable.
+        if !is_fallback {
+            store_cache(
+                self.cache_path.as_path(),
+                &Cache {
+                    schedulers: schedulers
+                        .schedulers
+                        .iter()
+                        .map(CachedScheduler::from)
+                        .collect(),
+                },
+            )
+            .await
+            .unwrap_or_else(|err| error!("store dynconfig cache failed: {}", err));
+        }
+
         // Get the data with write lock.
         let mut data = self.data.write().await;
         data.schedulers = schedulers;
@@ -167,6 +272,42 @@ impl Dynconfig {
             .await
     }
 
+    /// list_fallback_schedulers lists the schedulers from the cache file, if the cache file is not
+    /// found, the static schedulers in the configuration are used.
+    #[instrument(skip_all)]
+    async fn list_fallback_schedulers(&self) -> Option<ListSchedulersResponse> {
+        match load_cache(self.cache_path.as_path()).await {
+            Ok(Some(cache)) if !cache.schedulers.is_empty() => {
+                info!("use the cached schedulers in {:?}", self.cache_path);
+                return Some(ListSchedulersResponse {
+                    schedulers: cache.schedulers.into_iter().map(Scheduler::from).collect(),
+                });
+            }
+            Ok(_) => {}
+            Err(err) => error!("load dynconfig cache failed: {}", err),
+        }
+
+        if self.config.scheduler.addrs.is_empty() {
+            return None;
+        }
+
+        info!("use the static schedulers in configuration");
+        Some(ListSchedulersResponse {
+            schedulers: self
+                .config
+                .scheduler
+                .addrs
+                .iter()
+                .map(|addr| Scheduler {
+                    hostname: addr.ip().to_string(),
+                    ip: addr.ip().to_string(),
+                    port: addr.port() as i32,
+                    ..Default::default()
+                })
+                .collect(),
+        })
+    }
+
     /// get_available_schedulers gets the available schedulers.
     #[instrument(skip_all)]
     async fn get_available_schedulers(&self, schedulers: &[Scheduler]) -> Result<Vec<Scheduler>> {
@@ -227,3 +368,62 @@ impl Dynconfig {
         Ok(available_schedulers)
     }
 }
+
+/// load_cache loads the dynamic configuration from the cache file, it returns none
+/// if the cache file is not found.
+async fn load_cache(path: &Path) -> Result<Option<Cache>> {
+    let content = match fs::read(path).await {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    let cache = serde_json::from_slice(&content).or_err(ErrorType::SerializeError)?;
+    Ok(Some(cache))
+}
+
+/// store_cache stores the dynamic configuration to the cache file. The cache is written to
+/// a temporary file and renamed, so the cache file will not be corrupted by the interrupted writes.
+async fn store_cache(path: &Path, cache: &Cache) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let content = serde_json::to_vec(cache).or_err(ErrorType::SerializeError)?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, content).await?;
+    fs::rename(&temp_path, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn should_store_and_load_cache() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("dynconfig").join(DEFAULT_CACHE_FILE_NAME);
+
+        // Test the cache file is not found.
+        assert!(load_cache(path.as_path()).await.unwrap().is_none());
+
+        let scheduler = Scheduler {
+            id: 1,
+            hostname: "scheduler".to_string(),
+            ip: "127.0.0.1".to_string(),
+            port: 8002,
+            scheduler_cluster_id: 1,
+            ..Default::default()
+        };
+        let cache = Cache {
+            schedulers: vec![CachedScheduler::from(&scheduler)],
+        };
+        store_cache(path.as_path(), &cache).await.unwrap();
+
+        let loaded = load_cache(path.as_path()).await.unwrap().unwrap();
+        assert_eq!(loaded, cache);
+        assert_eq!(Scheduler::from(loaded.schedulers[0].clone()), scheduler);
+    }
+}
@@ -18,12 +18,12 @@ use crate::grpc::scheduler::SchedulerClient;
 use crate::shutdown;
 use chrono::Utc;
 use dragonfly_api::scheduler::v2::DeleteTaskRequest;
-use dragonfly_client_config::dfdaemon::Config;
+use dragonfly_client_config::dfdaemon::{Config, Policy};
 use dragonfly_client_core::Result;
 use dragonfly_client_storage::{metadata, Storage};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use tracing::{error, info, instrument};
 
 // DOWNLOAD_TASK_TIMEOUT is the timeout of downloading the task. If the task download timeout, the
@@ -35,6 +35,9 @@ pub struct GC {
     /// config is the configuration of the dfdaemon.
     config: Arc<Config>,
 
+    /// config_rx receives the reloaded configuration of the dfdaemon.
+    config_rx: watch::Receiver<Arc<Config>>,
+
     /// host_id is the id of the host.
     host_id: String,
 
@@ -56,6 +59,7 @@ impl GC {
     #[instrument(skip_all)]
     pub fn new(
         config: Arc<Config>,
+        config_rx: watch::Receiver<Arc<Config>>,
         host_id: String,
         storage: Arc<Storage>,
         scheduler_client: Arc<SchedulerClient>,
@@ -64,6 +68,7 @@ impl GC {
     ) -> Self {
         GC {
             config,
+            config_rx,
             host_id,
             storage,
             scheduler_client,
@@ -112,13 +117,19 @@ impl GC {
         }
     }
 
+    /// policy returns the gc policy of the reloaded configuration.
+    fn policy(&self) -> Policy {
+        self.config_rx.borrow().gc.policy.clone()
+    }
+
     /// evict_task_by_ttl evicts the task by ttl.
     #[instrument(skip_all)]
     async fn evict_task_by_ttl(&self) -> Result<()> {
         info!("start to evict by task ttl");
+        let policy = self.policy();
         for task in self.storage.get_tasks()? {
             // If the task is expired and not uploading, evict the task.
-            if task.is_expired(self.config.gc.policy.task_ttl) {
+            if task.is_expired(policy.task_ttl) {
                 self.storage.delete_task(&task.id).await;
                 info!("evict task {}", task.id);
 
@@ -133,21 +144,21 @@ impl GC {
     /// evict_task_by_disk_usage evicts the task by disk usage.
     #[instrument(skip_all)]
     async fn evict_task_by_disk_usage(&self) -> Result<()> {
+        let policy = self.policy();
         let available_space = self.storage.available_space()?;
         let total_space = self.storage.total_space()?;
 
         // Calculate the usage percent.
         let usage_percent = (100 - available_space * 100 / total_space) as u8;
-        if usage_percent >= self.config.gc.policy.dist_high_threshold_percent {
+        if usage_percent >= policy.dist_high_threshold_percent {
             info!(
                 "start to evict task by disk usage, disk usage {}% is higher than high threshold {}%",
-                usage_percent, self.config.gc.policy.dist_high_threshold_percent
+                usage_percent, policy.dist_high_threshold_percent
             );
 
             // Calculate the need evict space.
             let need_evict_space = total_space as f64
-                * ((usage_percent - self.config.gc.policy.dist_low_threshold_percent) as f64
-                    / 100.0);
+                * ((usage_percent - policy.dist_low_threshold_percent) as f64 / 100.0);
 
             // Evict the task by the need evict space.
             if let Err(err) = self.evict_task_space(need_evict_space as u64).await {
@@ -172,10 +183,19 @@ impl GC {
             }
 
             // If the task has downloaded finished, task has the content length, evicted space is the
-            // content length. If the task has started and did not download the data, and content
-            // length is 0, evicted space is 0.
+            // stored length of the pieces, which is counted by the compressed length of the compressed
+            // pieces and skips the blocks shared with other pieces. It is decided by the stored pieces
+            // instead of the current config, because the deduplication may be toggled after the pieces
+            // are stored. If the task has started and did not download the data, and content length
+            // is 0, evicted space is 0.
             let task_space = match task.content_length() {
-                Some(content_length) => content_length,
+                Some(_) => match self.storage.get_task_stored_length(&task) {
+                    Ok(stored_length) => stored_length,
+                    Err(err) => {
+                        error!("get stored length of task {} failed: {}", task.id, err);
+                        continue;
+                    }
+                },
                 None => {
                     // If the task has no content length, skip it.
                     if !task.is_failed() {
@@ -218,6 +238,11 @@ impl GC {
     /// delete_task_from_scheduler deletes the task from the scheduler.
     #[instrument(skip_all)]
     async fn delete_task_from_scheduler(&self, task: metadata::Task) {
+        // The task is only stored in the local in standalone mode.
+        if self.scheduler_client.is_standalone() {
+            return;
+        }
+
         self.scheduler_client
             .delete_task(DeleteTaskRequest {
                 host_id: self.host_id.clone(),
@@ -247,21 +272,21 @@ impl GC {
     /// evict_persistent_cache_task_by_disk_usage evicts the persistent cache task by disk usage.
     #[instrument(skip_all)]
     async fn evict_persistent_cache_task_by_disk_usage(&self) -> Result<()> {
+        let policy = self.policy();
         let available_space = self.storage.available_space()?;
         let total_space = self.storage.total_space()?;
 
         // Calculate the usage percent.
         let usage_percent = (100 - available_space * 100 / total_space) as u8;
-        if usage_percent >= self.config.gc.policy.dist_high_threshold_percent {
+        if usage_percent >= policy.dist_high_threshold_percent {
             info!(
                 "start to evict persistent cache task by disk usage, disk usage {}% is higher than high threshold {}%",
-                usage_percent, self.config.gc.policy.dist_high_threshold_percent
+                usage_percent, policy.dist_high_threshold_percent
             );
 
             // Calculate the need evict space.
             let need_evict_space = total_space as f64
-                * ((usage_percent - self.config.gc.policy.dist_low_threshold_percent) as f64
-                    / 100.0);
+                * ((usage_percent - policy.dist_low_threshold_percent) as f64 / 100.0);
 
             // Evict the persistent cache task by the need evict space.
             if let Err(err) = self
@@ -0,0 +1,858 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::gc::DOWNLOAD_TASK_TIMEOUT;
+use crate::metrics::{collect_delete_task_failure_metrics, collect_delete_task_started_metrics};
+use crate::resource::{persistent_cache_task, task};
+use chrono::{NaiveDateTime, Utc};
+use dragonfly_api::common::v2::TaskType;
+use dragonfly_api::scheduler::v2::{
+    DeletePersistentCacheTaskRequest as SchedulerDeletePersistentCacheTaskRequest,
+    DeleteTaskRequest,
+};
+use dragonfly_client_api::admin::v1::{
+    dfdaemon_admin_client::DfdaemonAdminClient as DfdaemonAdminGRPCClient,
+    dfdaemon_admin_server::DfdaemonAdmin, DeletePersistentCacheTaskRequest,
+    EvictPersistentCacheTasksRequest, EvictPersistentCacheTasksResponse, EvictTasksRequest,
+    EvictTasksResponse, ListPersistentCacheTasksRequest, ListPersistentCacheTasksResponse,
+    ListTaskPiecesRequest, ListTaskPiecesResponse, ListTasksRequest, ListTasksResponse,
+    PersistentCacheTask, Piece, Task, TaskFilter, TaskState,
+};
+use dragonfly_client_core::{
+    error::{ErrorType, OrErr},
+    Result as ClientResult,
+};
+use dragonfly_client_storage::{metadata, Storage};
+use hyper_util::rt::TokioIo;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UnixStream;
+use tonic::service::interceptor::InterceptedService;
+use tonic::{
+    transport::{Channel, Endpoint, Uri},
+    Request, Response, Status,
+};
+use tower::service_fn;
+use tracing::{error, info, instrument, Span};
+
+use super::interceptor::TracingInterceptor;
+
+/// DEFAULT_PAGE_SIZE is the default number of the tasks in a page.
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// MAX_PAGE_SIZE is the max number of the tasks in a page.
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// DfdaemonAdminServerHandler is the handler of the dfdaemon admin grpc service, it is served
+/// by the unix domain socket of the download server.
+pub struct DfdaemonAdminServerHandler {
+    /// storage is the local storage.
+    storage: Arc<Storage>,
+
+    /// task is the task manager.
+    task: Arc<task::Task>,
+
+    /// persistent_cache_task is the persistent cache task manager.
+    persistent_cache_task: Arc<persistent_cache_task::PersistentCacheTask>,
+}
+
+/// DfdaemonAdminServerHandler implements the handler of the dfdaemon admin grpc service.
+impl DfdaemonAdminServerHandler {
+    /// new creates a new DfdaemonAdminServerHandler.
+    pub fn new(
+        storage: Arc<Storage>,
+        task: Arc<task::Task>,
+        persistent_cache_task: Arc<persistent_cache_task::PersistentCacheTask>,
+    ) -> Self {
+        Self {
+            storage,
+            task,
+            persistent_cache_task,
+        }
+    }
+
+    /// get_tasks returns the tasks matched by the filter ordered by the task id.
+    fn get_tasks(&self, filter: &Filter) -> Result<Vec<metadata::Task>, Status> {
+        let mut tasks = self.storage.get_tasks().map_err(|err| {
+            error!("get tasks: {}", err);
+            Status::internal(err.to_string())
+        })?;
+
+        tasks.retain(|task| filter.matches_task(task));
+        tasks.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(tasks)
+    }
+
+    /// get_persistent_cache_tasks returns the persistent cache tasks matched by the filter
+    /// ordered by the task id.
+    fn get_persistent_cache_tasks(
+        &self,
+        filter: &Filter,
+    ) -> Result<Vec<metadata::PersistentCacheTask>, Status> {
+        let mut tasks = self.storage.get_persistent_cache_tasks().map_err(|err| {
+            error!("get persistent cache tasks: {}", err);
+            Status::internal(err.to_string())
+        })?;
+
+        tasks.retain(|task| filter.matches_persistent_cache_task(task));
+        tasks.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(tasks)
+    }
+}
+
+/// DfdaemonAdminServerHandler implements the dfdaemon admin grpc service.
+#[tonic::async_trait]
+impl DfdaemonAdmin for DfdaemonAdminServerHandler {
+    /// list_tasks lists the tasks in the local storage.
+    #[instrument(skip_all)]
+    async fn list_tasks(
+        &self,
+        request: Request<ListTasksRequest>,
+    ) -> Result<Response<ListTasksResponse>, Status> {
+        let request = request.into_inner();
+        let filter = Filter::new(request.filter)?;
+        let tasks = self.get_tasks(&filter)?;
+
+        let total_count = tasks.len() as u64;
+        let total_content_length = tasks.iter().filter_map(|task| task.content_length()).sum();
+        let (tasks, next_page_token) = paginate(
+            tasks,
+            |task| task.id.as_str(),
+            request.page_size,
+            &request.page_token,
+        );
+
+        Ok(Response::new(ListTasksResponse {
+            tasks: tasks.iter().map(task_to_proto).collect(),
+            next_page_token,
+            total_count,
+            total_content_length,
+        }))
+    }
+
+    /// list_persistent_cache_tasks lists the persistent cache tasks in the local storage.
+    #[instrument(skip_all)]
+    async fn list_persistent_cache_tasks(
+        &self,
+        request: Request<ListPersistentCacheTasksRequest>,
+    ) -> Result<Response<ListPersistentCacheTasksResponse>, Status> {
+        let request = request.into_inner();
+        let filter = Filter::new(request.filter)?;
+        let tasks = self.get_persistent_cache_tasks(&filter)?;
+
+        let total_count = tasks.len() as u64;
+        let total_content_length = tasks.iter().map(|task| task.content_length()).sum();
+        let (tasks, next_page_token) = paginate(
+            tasks,
+            |task| task.id.as_str(),
+            request.page_size,
+            &request.page_token,
+        );
+
+        Ok(Response::new(ListPersistentCacheTasksResponse {
+            tasks: tasks.iter().map(persistent_cache_task_to_proto).collect(),
+            next_page_token,
+            total_count,
+            total_content_length,
+        }))
+    }
+
+    /// list_task_pieces lists the pieces of the task in the local storage.
+    #[instrument(skip_all, fields(task_id))]
+    async fn list_task_pieces(
+        &self,
+        request: Request<ListTaskPiecesRequest>,
+    ) -> Result<Response<ListTaskPiecesResponse>, Status> {
+        let task_id = request.into_inner().task_id;
+        tracing::Span::current().record("task_id", task_id.as_str());
+
+        let exists = self
+            .storage
+            .get_task(&task_id)
+            .map(|task| task.is_some())
+            .and_then(|exists| {
+                if exists {
+                    return Ok(true);
+                }
+
+                self.storage
+                    .get_persistent_cache_task(&task_id)
+                    .map(|task| task.is_some())
+            })
+            .map_err(|err| {
+                error!("get task: {}", err);
+                Status::internal(err.to_string())
+            })?;
+
+        if !exists {
+            return Err(Status::not_found(format!("task {} not found", task_id)));
+        }
+
+        let mut pieces = self.storage.get_pieces(&task_id).map_err(|err| {
+            error!("get pieces: {}", err);
+            Status::internal(err.to_string())
+        })?;
+        pieces.sort_by_key(|piece| piece.number);
+
+        let finished_piece_count = pieces.iter().filter(|piece| piece.is_finished()).count() as u32;
+        let stored_length = pieces.iter().map(|piece| piece.stored_length()).sum();
+        Ok(Response::new(ListTaskPiecesResponse {
+            pieces: pieces.iter().map(piece_to_proto).collect(),
+            finished_piece_count,
+            stored_length,
+        }))
+    }
+
+    /// evict_tasks evicts the tasks matched by the filter, the downloading and uploading
+    /// tasks are skipped.
+    #[instrument(skip_all)]
+    async fn evict_tasks(
+        &self,
+        request: Request<EvictTasksRequest>,
+    ) -> Result<Response<EvictTasksResponse>, Status> {
+        let request = request.into_inner();
+        let filter = Filter::new(request.filter)?;
+        let host_id = self.task.id_generator.host_id();
+
+        let mut response = EvictTasksResponse::default();
+        for task in self.get_tasks(&filter)? {
+            if task.is_uploading()
+                || is_running(task.is_finished(), task.is_failed(), task.created_at)
+            {
+                response.skipped_task_ids.push(task.id);
+                continue;
+            }
+
+            if !request.dry_run {
+                self.storage.delete_task(&task.id).await;
+
+                // The task is only stored in the local in standalone mode.
+                if !self.task.scheduler_client.is_standalone() {
+                    self.task
+                        .scheduler_client
+                        .delete_task(DeleteTaskRequest {
+                            host_id: host_id.clone(),
+                            task_id: task.id.clone(),
+                        })
+                        .await
+                        .unwrap_or_else(|err| {
+                            error!("delete task {} from scheduler failed: {}", task.id, err);
+                        });
+                }
+            }
+
+            response.evicted_content_length += task.content_length().unwrap_or_default();
+            response.task_ids.push(task.id);
+        }
+
+        info!(
+            "evict {} tasks, skip {} tasks, dry run: {}",
+            response.task_ids.len(),
+            response.skipped_task_ids.len(),
+            request.dry_run
+        );
+        Ok(Response::new(response))
+    }
+
+    /// evict_persistent_cache_tasks evicts the persistent cache tasks matched by the filter,
+    /// the downloading and uploading persistent cache tasks are skipped.
+    #[instrument(skip_all)]
+    async fn evict_persistent_cache_tasks(
+        &self,
+        request: Request<EvictPersistentCacheTasksRequest>,
+    ) -> Result<Response<EvictPersistentCacheTasksResponse>, Status> {
+        let request = request.into_inner();
+        let filter = Filter::new(request.filter)?;
+        let host_id = self.persistent_cache_task.id_generator.host_id();
+
+        let mut response = EvictPersistentCacheTasksResponse::default();
+        for task in self.get_persistent_cache_tasks(&filter)? {
+            if task.is_uploading()
+                || is_running(task.is_finished(), task.is_failed(), task.created_at)
+            {
+                response.skipped_task_ids.push(task.id);
+                continue;
+            }
+
+            if !request.dry_run {
+                self.persistent_cache_task.delete(&task.id).await;
+
+                // The persistent cache task is only stored in the local in standalone mode.
+                if !self.persistent_cache_task.scheduler_client.is_standalone() {
+                    self.persistent_cache_task
+                        .scheduler_client
+                        .delete_persistent_cache_task(SchedulerDeletePersistentCacheTaskRequest {
+                            host_id: host_id.clone(),
+                            task_id: task.id.clone(),
+                        })
+                        .await
+                        .unwrap_or_else(|err| {
+                            error!(
+                                "delete persistent cache task {} from scheduler failed: {}",
+                                task.id, err
+                            );
+                        });
+                }
+            }
+
+            response.evicted_content_length += task.content_length();
+            response.task_ids.push(task.id);
+        }
+
+        info!(
+            "evict {} persistent cache tasks, skip {} persistent cache tasks, dry run: {}",
+            response.task_ids.len(),
+            response.skipped_task_ids.len(),
+            request.dry_run
+        );
+        Ok(Response::new(response))
+    }
+
+    /// delete_persistent_cache_task deletes the persistent cache task in the local storage and
+    /// tells the scheduler to delete its replicas in the other peers. It is served by the unix
+    /// domain socket only, the upload server deletes the persistent cache task in the local.
+    #[instrument(skip_all, fields(host_id, task_id))]
+    async fn delete_persistent_cache_task(
+        &self,
+        request: Request<DeletePersistentCacheTaskRequest>,
+    ) -> Result<Response<()>, Status> {
+        // Clone the request.
+        let request = request.into_inner();
+
+        // Generate the host id.
+        let host_id = self.persistent_cache_task.id_generator.host_id();
+
+        // Get the task id from the request.
+        let task_id = request.task_id;
+
+        // Span record the host id and task id.
+        Span::current().record("host_id", host_id.as_str());
+        Span::current().record("task_id", task_id.as_str());
+        info!("delete persistent cache task in admin server");
+
+        // Collect the delete task started metrics.
+        collect_delete_task_started_metrics(TaskType::PersistentCache as i32);
+
+        let task = self
+            .persistent_cache_task
+            .get(task_id.as_str())
+            .map_err(|err| {
+                // Collect the delete task failure metrics.
+                collect_delete_task_failure_metrics(TaskType::PersistentCache as i32);
+
+                error!("get persistent cache task: {}", err);
+                Status::internal(err.to_string())
+            })?;
+
+        // The uploading persistent cache task is being downloaded by the other peers, deleting
+        // it will break their downloads.
+        if let Some(task) = task.as_ref() {
+            if task.is_uploading() {
+                // Collect the delete task failure metrics.
+                collect_delete_task_failure_metrics(TaskType::PersistentCache as i32);
+
+                error!("persistent cache task is uploading");
+                return Err(Status::failed_precondition(format!(
+                    "persistent cache task {} is uploading",
+                    task_id
+                )));
+            }
+        }
+
+        // The persistent cache task is only stored in the local in standalone mode, otherwise
+        // the scheduler deletes its replicas in the other peers.
+        let scheduler_client = &self.persistent_cache_task.scheduler_client;
+        if scheduler_client.is_standalone() {
+            if task.is_none() {
+                // Collect the delete task failure metrics.
+                collect_delete_task_failure_metrics(TaskType::PersistentCache as i32);
+
+                error!("persistent cache task not found");
+                return Err(Status::not_found(format!(
+                    "persistent cache task {} not found",
+                    task_id
+                )));
+            }
+        } else {
+            scheduler_client
+                .delete_persistent_cache_task(SchedulerDeletePersistentCacheTaskRequest {
+                    host_id,
+                    task_id: task_id.clone(),
+                })
+                .await
+                .map_err(|err| {
+                    // Collect the delete task failure metrics.
+                    collect_delete_task_failure_metrics(TaskType::PersistentCache as i32);
+
+                    error!("delete persistent cache task from scheduler: {}", err);
+                    Status::internal(err.to_string())
+                })?;
+        }
+
+        self.persistent_cache_task.delete(task_id.as_str()).await;
+        Ok(Response::new(()))
+    }
+}
+
+/// DfdaemonAdminClient is a wrapper of DfdaemonAdminGRPCClient.
+#[derive(Clone)]
+pub struct DfdaemonAdminClient {
+    /// client is the grpc client of the dfdaemon admin.
+    pub client: DfdaemonAdminGRPCClient<InterceptedService<Channel, TracingInterceptor>>,
+}
+
+/// DfdaemonAdminClient implements the grpc client of the dfdaemon admin.
+impl DfdaemonAdminClient {
+    /// new_unix creates a new DfdaemonAdminClient with unix domain socket.
+    #[instrument(skip_all)]
+    pub async fn new_unix(socket_path: PathBuf) -> ClientResult<Self> {
+        // Ignore the uri because it is not used.
+        let channel = Endpoint::try_from("http://[::]:50051")
+            .unwrap()
+            .buffer_size(super::BUFFER_SIZE)
+            .connect_timeout(super::CONNECT_TIMEOUT)
+            .connect_with_connector(service_fn(move |_: Uri| {
+                let socket_path = socket_path.clone();
+                async move {
+                    Ok::<_, std::io::Error>(TokioIo::new(
+                        UnixStream::connect(socket_path.clone()).await?,
+                    ))
+                }
+            }))
+            .await
+            .inspect_err(|err| {
+                error!("connect failed: {}", err);
+            })
+            .or_err(ErrorType::ConnectError)?;
+
+        let client = DfdaemonAdminGRPCClient::with_interceptor(channel, TracingInterceptor)
+            .max_decoding_message_size(usize::MAX)
+            .max_encoding_message_size(usize::MAX);
+        Ok(Self { client })
+    }
+
+    /// list_tasks lists the tasks in the dfdaemon.
+    #[instrument(skip_all)]
+    pub async fn list_tasks(&self, request: ListTasksRequest) -> ClientResult<ListTasksResponse> {
+        let request = Self::make_request(request);
+        let response = self.client.clone().list_tasks(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// list_persistent_cache_tasks lists the persistent cache tasks in the dfdaemon.
+    #[instrument(skip_all)]
+    pub async fn list_persistent_cache_tasks(
+        &self,
+        request: ListPersistentCacheTasksRequest,
+    ) -> ClientResult<ListPersistentCacheTasksResponse> {
+        let request = Self::make_request(request);
+        let response = self
+            .client
+            .clone()
+            .list_persistent_cache_tasks(request)
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    /// list_task_pieces lists the pieces of the task in the dfdaemon.
+    #[instrument(skip_all)]
+    pub async fn list_task_pieces(
+        &self,
+        request: ListTaskPiecesRequest,
+    ) -> ClientResult<ListTaskPiecesResponse> {
+        let request = Self::make_request(request);
+        let response = self.client.clone().list_task_pieces(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// evict_tasks evicts the tasks matched by the filter in the dfdaemon, it has no timeout
+    /// because evicting a large number of tasks may take a long time.
+    #[instrument(skip_all)]
+    pub async fn evict_tasks(
+        &self,
+        request: EvictTasksRequest,
+    ) -> ClientResult<EvictTasksResponse> {
+        let response = self.client.clone().evict_tasks(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// evict_persistent_cache_tasks evicts the persistent cache tasks matched by the filter in
+    /// the dfdaemon, it has no timeout because evicting a large number of persistent cache tasks
+    /// may take a long time.
+    #[instrument(skip_all)]
+    pub async fn evict_persistent_cache_tasks(
+        &self,
+        request: EvictPersistentCacheTasksRequest,
+    ) -> ClientResult<EvictPersistentCacheTasksResponse> {
+        let response = self
+            .client
+            .clone()
+            .evict_persistent_cache_tasks(request)
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    /// delete_persistent_cache_task deletes the persistent cache task in the dfdaemon and its
+    /// replicas in the other peers.
+    #[instrument(skip_all)]
+    pub async fn delete_persistent_cache_task(
+        &self,
+        request: DeletePersistentCacheTaskRequest,
+    ) -> ClientResult<()> {
+        let request = Self::make_request(request);
+        self.client
+            .clone()
+            .delete_persistent_cache_task(request)
+            .await?;
+        Ok(())
+    }
+
+    /// make_request creates a new request with timeout.
+    #[instrument(skip_all)]
+    fn make_request<T>(request: T) -> tonic::Request<T> {
+        let mut request = tonic::Request::new(request);
+        request.set_timeout(super::REQUEST_TIMEOUT);
+        request
+    }
+}
+
+/// Filter is the parsed filter of the tasks, the unset fields match all tasks.
+#[derive(Debug, Default)]
+struct Filter {
+    /// state is the state of the tasks.
+    state: TaskState,
+
+    /// application is the application of the tasks.
+    application: Option<String>,
+
+    /// tag is the tag of the tasks.
+    tag: Option<String>,
+
+    /// url_prefix is the prefix of the download url of the tasks.
+    url_prefix: Option<String>,
+
+    /// min_content_length is the min content length of the tasks.
+    min_content_length: Option<u64>,
+
+    /// max_content_length is the max content length of the tasks.
+    max_content_length: Option<u64>,
+
+    /// min_age is the min age of the tasks.
+    min_age: Option<Duration>,
+
+    /// max_age is the max age of the tasks.
+    max_age: Option<Duration>,
+
+    /// task_id is the id of the task.
+    task_id: Option<String>,
+}
+
+/// Filter implements the filter of the tasks.
+impl Filter {
+    /// new parses the filter of the request.
+    fn new(filter: Option<TaskFilter>) -> Result<Self, Status> {
+        let Some(filter) = filter else {
+            return Ok(Self::default());
+        };
+
+        let parse_duration = |duration: Option<prost_wkt_types::Duration>| {
+            duration
+                .map(Duration::try_from)
+                .transpose()
+                .map_err(|_| Status::invalid_argument("invalid age in filter"))
+        };
+
+        Ok(Self {
+            state: filter.state(),
+            application: filter.application,
+            tag: filter.tag,
+            url_prefix: filter.url_prefix,
+            min_content_length: filter.min_content_length,
+            max_content_length: filter.max_content_length,
+            min_age: parse_duration(filter.min_age)?,
+            max_age: parse_duration(filter.max_age)?,
+            task_id: filter.task_id,
+        })
+    }
+
+    /// matches_task returns whether the task is matched by the filter.
+    fn matches_task(&self, task: &metadata::Task) -> bool {
+        let download_request = task.download_request.as_ref();
+        let matches_optional = |expected: &Option<String>, actual: Option<&str>| {
+            expected
+                .as_deref()
+                .is_none_or(|expected| actual == Some(expected))
+        };
+
+        matches_optional(&self.task_id, Some(task.id.as_str()))
+            && matches_optional(
+                &self.application,
+                download_request.and_then(|request| request.application.as_deref()),
+            )
+            && matches_optional(
+                &self.tag,
+                download_request.and_then(|request| request.tag.as_deref()),
+            )
+            && self.url_prefix.as_deref().is_none_or(|url_prefix| {
+                download_request.is_some_and(|request| request.url.starts_with(url_prefix))
+            })
+            && self.matches(
+                task_state(task.is_finished(), task.is_failed()),
+                task.content_length(),
+                task.created_at,
+            )
+    }
+
+    /// matches_persistent_cache_task returns whether the persistent cache task is matched by
+    /// the filter, the application, tag and url prefix are ignored.
+    fn matches_persistent_cache_task(&self, task: &metadata::PersistentCacheTask) -> bool {
+        self.task_id
+            .as_deref()
+            .is_none_or(|task_id| task.id == task_id)
+            && self.matches(
+                task_state(task.is_finished(), task.is_failed()),
+                Some(task.content_length()),
+                task.created_at,
+            )
+    }
+
+    /// matches returns whether the state, content length and age are matched by the filter.
+    fn matches(
+        &self,
+        state: TaskState,
+        content_length: Option<u64>,
+        created_at: NaiveDateTime,
+    ) -> bool {
+        if self.state != TaskState::Unspecified && self.state != state {
+            return false;
+        }
+
+        // The task without content length is not matched by the content length range.
+        if self.min_content_length.is_some() || self.max_content_length.is_some() {
+            let Some(content_length) = content_length else {
+                return false;
+            };
+
+            if self
+                .min_content_length
+                .is_some_and(|min| content_length < min)
+                || self
+                    .max_content_length
+                    .is_some_and(|max| content_length > max)
+            {
+                return false;
+            }
+        }
+
+        let age = (Utc::now().naive_utc() - created_at)
+            .to_std()
+            .unwrap_or_default();
+        !(self.min_age.is_some_and(|min| age < min) || self.max_age.is_some_and(|max| age > max))
+    }
+}
+
+/// task_state returns the state of the task.
+fn task_state(finished: bool, failed: bool) -> TaskState {
+    if finished {
+        TaskState::Succeeded
+    } else if failed {
+        TaskState::Failed
+    } else {
+        TaskState::Running
+    }
+}
+
+/// is_running returns whether the task is downloading, the task which is not finished after
+/// the download timeout is considered as stuck and can be evicted, refer to the gc.
+fn is_running(finished: bool, failed: bool, created_at: NaiveDateTime) -> bool {
+    !finished && !failed && created_at + DOWNLOAD_TASK_TIMEOUT > Utc::now().naive_utc()
+}
+
+/// paginate returns the page of the items ordered by the id after the page token, and the
+/// page token of the next page which is empty if there is no more items.
+fn paginate<T>(
+    items: Vec<T>,
+    id: impl Fn(&T) -> &str,
+    page_size: u32,
+    page_token: &str,
+) -> (Vec<T>, String) {
+    let page_size = match page_size as usize {
+        0 => DEFAULT_PAGE_SIZE,
+        page_size => page_size.min(MAX_PAGE_SIZE),
+    };
+
+    let mut items = items
+        .into_iter()
+        .skip_while(|item| !page_token.is_empty() && id(item) <= page_token)
+        .take(page_size + 1)
+        .collect::<Vec<_>>();
+
+    if items.len() <= page_size {
+        return (items, String::new());
+    }
+
+    items.truncate(page_size);
+    let next_page_token = items.last().map(|item| id(item).to_string()).unwrap();
+    (items, next_page_token)
+}
+
+/// task_to_proto converts the task metadata to the task of the admin service.
+fn task_to_proto(task: &metadata::Task) -> Task {
+    let download_request = task.download_request.as_ref();
+    let mut proto = Task {
+        id: task.id.clone(),
+        url: download_request.map(|request| request.url.clone()),
+        application: download_request.and_then(|request| request.application.clone()),
+        tag: download_request.and_then(|request| request.tag.clone()),
+        piece_length: task.piece_length(),
+        content_length: task.content_length(),
+        uploading: task.is_uploading(),
+        uploaded_count: task.uploaded_count,
+        prefetched: task.is_prefetched(),
+        compressed: task.is_compressed(),
+        created_at: Some(prost_wkt_types::Timestamp::from(task.created_at)),
+        updated_at: Some(prost_wkt_types::Timestamp::from(task.updated_at)),
+        finished_at: task.finished_at.map(prost_wkt_types::Timestamp::from),
+        failed_at: task.failed_at.map(prost_wkt_types::Timestamp::from),
+        ..Default::default()
+    };
+
+    proto.set_state(task_state(task.is_finished(), task.is_failed()));
+    proto
+}
+
+/// persistent_cache_task_to_proto converts the persistent cache task metadata to the
+/// persistent cache task of the admin service.
+fn persistent_cache_task_to_proto(task: &metadata::PersistentCacheTask) -> PersistentCacheTask {
+    let mut proto = PersistentCacheTask {
+        id: task.id.clone(),
+        persistent: task.is_persistent(),
+        ttl: prost_wkt_types::Duration::try_from(task.ttl).ok(),
+        expired: task.is_expired(),
+        piece_length: task.piece_length(),
+        content_length: task.content_length(),
+        uploading: task.is_uploading(),
+        uploaded_count: task.uploaded_count,
+        created_at: Some(prost_wkt_types::Timestamp::from(task.created_at)),
+        updated_at: Some(prost_wkt_types::Timestamp::from(task.updated_at)),
+        finished_at: task.finished_at.map(prost_wkt_types::Timestamp::from),
+        failed_at: task.failed_at.map(prost_wkt_types::Timestamp::from),
+        digest: task.digest.clone(),
+        ..Default::default()
+    };
+
+    proto.set_state(task_state(task.is_finished(), task.is_failed()));
+    proto
+}
+
+/// piece_to_proto converts the piece metadata to the piece of the admin service.
+fn piece_to_proto(piece: &metadata::Piece) -> Piece {
+    Piece {
+        number: piece.number,
+        offset: piece.offset,
+        length: piece.length,
+        digest: piece.digest.clone(),
+        parent_id: piece.parent_id.clone(),
+        stored_length: piece.stored_length(),
+        finished: piece.is_finished(),
+        uploaded_count: piece.uploaded_count,
+        created_at: Some(prost_wkt_types::Timestamp::from(piece.created_at)),
+        finished_at: piece.finished_at.map(prost_wkt_types::Timestamp::from),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_paginate_by_page_token() {
+        let items = vec!["a", "b", "c", "d", "e"];
+
+        let (page, next_page_token) = paginate(items.clone(), |item| *item, 2, "");
+        assert_eq!(page, vec!["a", "b"]);
+        assert_eq!(next_page_token, "b");
+
+        let (page, next_page_token) = paginate(items.clone(), |item| *item, 2, &next_page_token);
+        assert_eq!(page, vec!["c", "d"]);
+        assert_eq!(next_page_token, "d");
+
+        let (page, next_page_token) = paginate(items.clone(), |item| *item, 2, &next_page_token);
+        assert_eq!(page, vec!["e"]);
+        assert!(next_page_token.is_empty());
+
+        // Test the default page size is used if the page size is 0.
+        let (page, next_page_token) = paginate(items, |item| *item, 0, "");
+        assert_eq!(page.len(), 5);
+        assert!(next_page_token.is_empty());
+    }
+
+    #[test]
+    fn should_match_task_by_filter() {
+        let task = metadata::Task {
+            id: "task".to_string(),
+            content_length: Some(1024),
+            created_at: Utc::now().naive_utc() - chrono::Duration::hours(2),
+            finished_at: Some(Utc::now().naive_utc()),
+            download_request: Some(metadata::DownloadRequest {
+                url: "https://example.com/models/model.bin".to_string(),
+                application: Some("inference".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(Filter::default().matches_task(&task));
+
+        let filter = Filter {
+            state: TaskState::Succeeded,
+            application: Some("inference".to_string()),
+            url_prefix: Some("https://example.com/models/".to_string()),
+            min_content_length: Some(1024),
+            min_age: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        };
+        assert!(filter.matches_task(&task));
+
+        let filters = vec![
+            Filter {
+                state: TaskState::Failed,
+                ..Default::default()
+            },
+            Filter {
+                tag: Some("tag".to_string()),
+                ..Default::default()
+            },
+            Filter {
+                url_prefix: Some("https://example.com/images/".to_string()),
+                ..Default::default()
+            },
+            Filter {
+                max_content_length: Some(1023),
+                ..Default::default()
+            },
+            Filter {
+                max_age: Some(Duration::from_secs(3600)),
+                ..Default::default()
+            },
+        ];
+
+        for filter in filters {
+            assert!(!filter.matches_task(&task), "{:?}", filter);
+        }
+    }
+}
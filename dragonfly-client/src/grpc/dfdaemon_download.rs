@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use crate::grpc::dfdaemon_admin::DfdaemonAdminServerHandler;
 use crate::metrics::{
     collect_delete_host_failure_metrics, collect_delete_host_started_metrics,
     collect_delete_task_failure_metrics, collect_delete_task_started_metrics,
@@ -36,10 +37,12 @@ use dragonfly_api::dfdaemon::v2::{
 };
 use dragonfly_api::errordetails::v2::Backend;
 use dragonfly_api::scheduler::v2::DeleteHostRequest as SchedulerDeleteHostRequest;
+use dragonfly_client_api::admin::v1::dfdaemon_admin_server::DfdaemonAdminServer as DfdaemonAdminGRPCServer;
 use dragonfly_client_core::{
     error::{ErrorType, OrErr},
     Error as ClientError, Result as ClientResult,
 };
+use dragonfly_client_storage::Storage;
 use dragonfly_client_util::http::{get_range, hashmap_to_headermap, headermap_to_hashmap};
 use hyper_util::rt::TokioIo;
 use std::path::{Path, PathBuf};
@@ -56,7 +59,7 @@ use tonic::{
     Code, Request, Response, Status,
 };
 use tower::service_fn;
-use tracing::{error, info, instrument, Instrument, Span};
+use tracing::{error, info, instrument, warn, Instrument, Span};
 
 use super::interceptor::TracingInterceptor;
 
@@ -68,6 +71,9 @@ pub struct DfdaemonDownloadServer {
     /// service is the grpc service of the dfdaemon.
     service: DfdaemonDownloadGRPCServer<DfdaemonDownloadServerHandler>,
 
+    /// admin_service is the grpc service to inspect and evict the cached tasks.
+    admin_service: DfdaemonAdminGRPCServer<DfdaemonAdminServerHandler>,
+
     /// shutdown is used to shutdown the grpc server.
     shutdown: shutdown::Shutdown,
 
@@ -81,11 +87,21 @@ impl DfdaemonDownloadServer {
     #[instrument(skip_all)]
     pub fn new(
         socket_path: PathBuf,
+        storage: Arc<Storage>,
         task: Arc<task::Task>,
         persistent_cache_task: Arc<persistent_cache_task::PersistentCacheTask>,
         shutdown: shutdown::Shutdown,
         shutdown_complete_tx: mpsc::UnboundedSender<()>,
     ) -> Self {
+        // Initialize the admin grpc service.
+        let admin_service = DfdaemonAdminGRPCServer::new(DfdaemonAdminServerHandler::new(
+            storage,
+            task.clone(),
+            persistent_cache_task.clone(),
+        ))
+        .max_decoding_message_size(usize::MAX)
+        .max_encoding_message_size(usize::MAX);
+
         // Initialize the grpc service.
         let service = DfdaemonDownloadGRPCServer::new(DfdaemonDownloadServerHandler {
             socket_path: socket_path.clone(),
@@ -98,6 +114,7 @@ impl DfdaemonDownloadServer {
         Self {
             socket_path,
             service,
+            admin_service,
             shutdown,
             _shutdown_complete: shutdown_complete_tx,
         }
@@ -109,6 +126,7 @@ impl DfdaemonDownloadServer {
         // Register the reflection service.
         let reflection = tonic_reflection::server::Builder::configure()
             .register_encoded_file_descriptor_set(dragonfly_api::FILE_DESCRIPTOR_SET)
+            .register_encoded_file_descriptor_set(dragonfly_client_api::FILE_DESCRIPTOR_SET)
             .build_v1()?;
 
         // Clone the shutdown channel.
@@ -121,6 +139,9 @@ impl DfdaemonDownloadServer {
         health_reporter
             .set_serving::<DfdaemonDownloadGRPCServer<DfdaemonDownloadServerHandler>>()
             .await;
+        health_reporter
+            .set_serving::<DfdaemonAdminGRPCServer<DfdaemonAdminServerHandler>>()
+            .await;
 
         // Start download grpc server with unix domain socket.
         fs::create_dir_all(self.socket_path.parent().unwrap()).await?;
@@ -140,6 +161,7 @@ impl DfdaemonDownloadServer {
             .add_service(reflection.clone())
             .add_service(health_service)
             .add_service(self.service.clone())
+            .add_service(self.admin_service.clone())
             .serve_with_incoming_shutdown(uds_stream, async move {
                 // When the grpc server is started, notify the barrier. If the shutdown signal is received
                 // before barrier is waited successfully, the server will shutdown immediately.
@@ -660,6 +682,11 @@ impl DfdaemonDownload for DfdaemonDownloadServerHandler {
         // Collect the delete host started metrics.
         collect_delete_host_started_metrics();
 
+        // The host is not announced to the scheduler in standalone mode.
+        if self.task.scheduler_client.is_standalone() {
+            return Ok(Response::new(()));
+        }
+
         self.task
             .scheduler_client
             .delete_host(SchedulerDeleteHostRequest { host_id })
@@ -880,17 +907,13 @@ impl DfdaemonDownload for DfdaemonDownloadServerHandler {
         let path = Path::new(request.path.as_str());
         info!("upload persistent cache task {:?}", request);
 
-        // Generate the task id.
-        let task_id = match request.task_id.as_deref() {
-            Some(task_id) => task_id.to_string(),
+        // Generate the task id, the content digest is returned if the task id is generated
+        // by the content digest.
+        let (task_id, digest) = match request.task_id.as_deref() {
+            Some(task_id) => (task_id.to_string(), None),
             None => self
-                .task
-                .id_generator
-                .persistent_cache_task_id(
-                    &path.to_path_buf(),
-                    request.tag.as_deref(),
-                    request.application.as_deref(),
-                )
+                .persistent_cache_task
+                .id(path, request.tag.as_deref(), request.application.as_deref())
                 .map_err(|err| {
                     error!("generate persistent cache task id: {}", err);
                     Status::invalid_argument(err.to_string())
@@ -910,6 +933,32 @@ impl DfdaemonDownload for DfdaemonDownloadServerHandler {
         Span::current().record("peer_id", peer_id.as_str());
         info!("upload persistent cache task in download server");
 
+        // The persistent cache task with the same content digest has been imported, e.g. the
+        // same file is imported with another tag, return it instead of importing it again.
+        if digest.is_some() {
+            match self.persistent_cache_task.get(task_id.as_str()) {
+                Ok(Some(task)) if task.is_finished() && !task.is_expired() => {
+                    match self
+                        .persistent_cache_task
+                        .stat(task_id.as_str(), host_id.as_str())
+                        .await
+                    {
+                        Ok(task) => {
+                            info!("persistent cache task has been imported");
+                            return Ok(Response::new(task));
+                        }
+                        Err(err) => {
+                            warn!("stat imported persistent cache task: {}", err);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    error!("get persistent cache task: {}", err);
+                }
+            }
+        }
+
         // Collect upload task started metrics.
         collect_upload_task_started_metrics(
             TaskType::PersistentCache as i32,
@@ -925,6 +974,7 @@ impl DfdaemonDownload for DfdaemonDownloadServerHandler {
                 host_id.as_str(),
                 peer_id.as_str(),
                 path.to_path_buf(),
+                digest.map(|digest| digest.to_string()),
                 request.clone(),
             )
             .await
@@ -21,6 +21,7 @@ use crate::metrics::{
     collect_stat_task_started_metrics, collect_update_task_failure_metrics,
     collect_update_task_started_metrics, collect_upload_piece_failure_metrics,
     collect_upload_piece_finished_metrics, collect_upload_piece_started_metrics,
+    collect_upload_tcp_connection_closed_metrics, collect_upload_tcp_connection_opened_metrics,
 };
 use crate::resource::{persistent_cache_task, task};
 use crate::shutdown;
@@ -44,21 +45,29 @@ use dragonfly_client_core::{
 use dragonfly_client_util::http::{get_range, hashmap_to_headermap, headermap_to_hashmap};
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 use tokio::sync::Barrier;
 use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
 use tonic::service::interceptor::InterceptedService;
 use tonic::{
-    transport::{Channel, Server},
+    transport::{
+        server::{Connected, TcpConnectInfo, TcpIncoming},
+        Channel, Server,
+    },
     Code, Request, Response, Status,
 };
 use tracing::{error, info, instrument, Instrument, Span};
 use url::Url;
 
 use super::interceptor::TracingInterceptor;
+use super::pool::{ChannelKey, ChannelPool};
 
 /// DfdaemonUploadServer is the grpc server of the upload.
 pub struct DfdaemonUploadServer {
@@ -135,18 +144,25 @@ impl DfdaemonUploadServer {
             server_builder = server_builder.tls_config(server_tls_config)?;
         }
 
+        // Track the live tcp connections of the upload grpc server, the count is announced
+        // to the scheduler as the upload tcp connection count of the host.
+        let incoming = TcpIncoming::new(self.addr, true, Some(super::TCP_KEEPALIVE))
+            .map_err(|err| {
+                error!("bind upload grpc server on {} failed: {}", self.addr, err);
+                ClientError::Unknown(err.to_string())
+            })?
+            .map(|stream| stream.map(TrackedTcpStream::new));
+
         let server = server_builder
-            .tcp_nodelay(true)
             .max_frame_size(super::MAX_FRAME_SIZE)
             .initial_connection_window_size(super::INITIAL_WINDOW_SIZE)
             .initial_stream_window_size(super::INITIAL_WINDOW_SIZE)
-            .tcp_keepalive(Some(super::TCP_KEEPALIVE))
             .http2_keepalive_interval(Some(super::HTTP2_KEEP_ALIVE_INTERVAL))
             .http2_keepalive_timeout(Some(super::HTTP2_KEEP_ALIVE_TIMEOUT))
             .add_service(reflection.clone())
             .add_service(health_service)
             .add_service(self.service.clone())
-            .serve_with_shutdown(self.addr, async move {
+            .serve_with_incoming_shutdown(incoming, async move {
                 // When the grpc server is started, notify the barrier. If the shutdown signal is received
                 // before barrier is waited successfully, the server will shutdown immediately.
                 tokio::select! {
@@ -172,6 +188,80 @@ impl DfdaemonUploadServer {
     }
 }
 
+/// TrackedTcpStream is the tcp stream of the upload grpc server, it is counted in the
+/// upload tcp connection metrics until it is dropped.
+struct TrackedTcpStream {
+    /// inner is the accepted tcp stream.
+    inner: TcpStream,
+}
+
+/// TrackedTcpStream implements the tracked tcp stream.
+impl TrackedTcpStream {
+    /// new creates a new TrackedTcpStream and counts the opened connection.
+    fn new(inner: TcpStream) -> Self {
+        collect_upload_tcp_connection_opened_metrics();
+        Self { inner }
+    }
+}
+
+/// TrackedTcpStream implements Drop to count the closed connection.
+impl Drop for TrackedTcpStream {
+    fn drop(&mut self) {
+        collect_upload_tcp_connection_closed_metrics();
+    }
+}
+
+/// TrackedTcpStream implements Connected to provide the connection info of the tcp stream.
+impl Connected for TrackedTcpStream {
+    type ConnectInfo = TcpConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.inner.connect_info()
+    }
+}
+
+/// TrackedTcpStream implements AsyncRead by the inner tcp stream.
+impl AsyncRead for TrackedTcpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+    }
+}
+
+/// TrackedTcpStream implements AsyncWrite by the inner tcp stream.
+impl AsyncWrite for TrackedTcpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
 /// DfdaemonUploadServerHandler is the handler of the dfdaemon upload grpc service.
 pub struct DfdaemonUploadServerHandler {
     /// socket_path is the path of the unix domain socket.
@@ -1155,7 +1245,7 @@ impl DfdaemonUpload for DfdaemonUploadServerHandler {
         Ok(Response::new(task))
     }
 
-    /// delete_persistent_cache_task deletes the persistent cache task.
+    /// delete_persistent_cache_task deletes the persistent cache task in the local storage.
     #[instrument(skip_all, fields(host_id, task_id))]
     async fn delete_persistent_cache_task(
         &self,
@@ -1177,6 +1267,33 @@ impl DfdaemonUpload for DfdaemonUploadServerHandler {
 
         // Collect the delete task started metrics.
         collect_delete_task_started_metrics(TaskType::PersistentCache as i32);
+
+        let task = self
+            .persistent_cache_task
+            .get(task_id.as_str())
+            .map_err(|err| {
+                // Collect the delete task failure metrics.
+                collect_delete_task_failure_metrics(TaskType::PersistentCache as i32);
+
+                error!("get persistent cache task: {}", err);
+                Status::internal(err.to_string())
+            })?;
+
+        // The uploading persistent cache task is being downloaded by the other peers, deleting
+        // it will break their downloads.
+        if let Some(task) = task.as_ref() {
+            if task.is_uploading() {
+                // Collect the delete task failure metrics.
+                collect_delete_task_failure_metrics(TaskType::PersistentCache as i32);
+
+                error!("persistent cache task is uploading");
+                return Err(Status::failed_precondition(format!(
+                    "persistent cache task {} is uploading",
+                    task_id
+                )));
+            }
+        }
+
         self.persistent_cache_task.delete(task_id.as_str()).await;
         Ok(Response::new(()))
     }
@@ -1434,6 +1551,41 @@ impl DfdaemonUploadClient {
     /// new creates a new DfdaemonUploadClient.
     #[instrument(skip_all)]
     pub async fn new(config: Arc<Config>, addr: String) -> ClientResult<Self> {
+        let channel = Self::connect(config, addr).await?;
+        Ok(Self::from_channel(channel))
+    }
+
+    /// new_with_pool creates a new DfdaemonUploadClient by the pooled channel, if the channel
+    /// to the address does not exist in the pool, it connects and pools the channel.
+    #[instrument(skip_all)]
+    pub async fn new_with_pool(
+        config: Arc<Config>,
+        pool: Arc<ChannelPool>,
+        addr: String,
+    ) -> ClientResult<Self> {
+        let key = Self::channel_key(&config, addr.clone());
+        let channel = pool
+            .get_or_connect(key, Self::connect(config, addr))
+            .await?;
+        Ok(Self::from_channel(channel))
+    }
+
+    /// channel_key returns the key of the pooled channel to the address.
+    pub fn channel_key(config: &Config, addr: String) -> ChannelKey {
+        ChannelKey::new(addr, config.upload.client.cert.clone())
+    }
+
+    /// from_channel creates a new DfdaemonUploadClient by the channel.
+    fn from_channel(channel: Channel) -> Self {
+        let client = DfdaemonUploadGRPCClient::with_interceptor(channel, TracingInterceptor)
+            .max_decoding_message_size(usize::MAX)
+            .max_encoding_message_size(usize::MAX);
+        Self { client }
+    }
+
+    /// connect establishes the channel to the address.
+    #[instrument(skip_all)]
+    async fn connect(config: Arc<Config>, addr: String) -> ClientResult<Channel> {
         let domain_name = Url::parse(addr.as_str())?
             .host_str()
             .ok_or_else(|| {
@@ -1481,10 +1633,7 @@ impl DfdaemonUploadClient {
                 .or_err(ErrorType::ConnectError)?,
         };
 
-        let client = DfdaemonUploadGRPCClient::with_interceptor(channel, TracingInterceptor)
-            .max_decoding_message_size(usize::MAX)
-            .max_encoding_message_size(usize::MAX);
-        Ok(Self { client })
+        Ok(channel)
     }
 
     /// download_task downloads the task.
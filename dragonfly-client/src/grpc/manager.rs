@@ -15,6 +15,7 @@
  */
 
 use crate::grpc::health::HealthClient;
+use crate::metrics::{collect_active_manager_metrics, collect_manager_failover_metrics};
 use dragonfly_api::manager::v2::{
     manager_client::ManagerClient as ManagerGRPCClient, DeleteSeedPeerRequest,
     ListSchedulersRequest, ListSchedulersResponse, SeedPeer, UpdateSeedPeerRequest,
@@ -25,84 +26,95 @@ use dragonfly_client_core::{
     Error, Result,
 };
 use std::sync::Arc;
-use tonic::{service::interceptor::InterceptedService, transport::Channel};
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+use tonic::{
+    service::interceptor::InterceptedService,
+    transport::{Channel, ClientTlsConfig, Endpoint},
+    Code,
+};
 use tonic_health::pb::health_check_response::ServingStatus;
-use tracing::{error, instrument, warn};
+use tracing::{error, info, instrument, warn};
 use url::Url;
 
 use super::interceptor::TracingInterceptor;
 
-/// ManagerClient is a wrapper of ManagerGRPCClient.
+/// MAX_FAILOVER_ATTEMPTS is the max number of attempts to fail over to a healthy manager.
+const MAX_FAILOVER_ATTEMPTS: u32 = 3;
+
+/// INITIAL_FAILOVER_BACKOFF is the initial backoff between the failover attempts, and it is
+/// doubled after each attempt.
+const INITIAL_FAILOVER_BACKOFF: Duration = Duration::from_millis(500);
+
+/// GRPCClient is the grpc client of the manager with the tracing interceptor.
+type GRPCClient = ManagerGRPCClient<InterceptedService<Channel, TracingInterceptor>>;
+
+/// ManagerEndpoint is the endpoint of a manager.
+struct ManagerEndpoint {
+    /// addr is the address of the manager.
+    addr: String,
+
+    /// endpoint is the grpc endpoint of the manager.
+    endpoint: Endpoint,
+
+    /// client_tls_config is the client tls config of the manager.
+    client_tls_config: Option<ClientTlsConfig>,
+}
+
+/// ManagerClient is a wrapper of ManagerGRPCClient, it connects to one of the healthy managers
+/// and fails over to the next healthy manager if the active manager is unavailable.
 #[derive(Clone)]
 pub struct ManagerClient {
-    /// client is the grpc client of the manager.
-    pub client: ManagerGRPCClient<InterceptedService<Channel, TracingInterceptor>>,
+    /// endpoints is the endpoints of the managers.
+    endpoints: Arc<Vec<ManagerEndpoint>>,
+
+    /// active is the index of the active manager in the endpoints and its grpc client.
+    active: Arc<RwLock<(usize, GRPCClient)>>,
+
+    /// failover_mutex is used to make sure only one failover is running at a time.
+    failover_mutex: Arc<Mutex<()>>,
 }
 
 /// ManagerClient implements the grpc client of the manager.
 impl ManagerClient {
     /// new creates a new ManagerClient.
     #[instrument(skip_all)]
-    pub async fn new(config: Arc<Config>, addr: String) -> Result<Self> {
-        let domain_name = Url::parse(addr.as_str())?
-            .host_str()
-            .ok_or_else(|| {
-                error!("invalid address: {}", addr);
-                Error::InvalidParameter
-            })?
-            .to_string();
-
-        let client_tls_config = config
-            .manager
-            .load_client_tls_config(domain_name.as_str())
-            .await?;
+    pub async fn new(config: Arc<Config>, addrs: Vec<String>) -> Result<Self> {
+        if addrs.is_empty() {
+            error!("manager addresses are empty");
+            return Err(Error::InvalidParameter);
+        }
 
-        let health_client = HealthClient::new(addr.as_str(), client_tls_config.clone()).await?;
-        match health_client.check().await {
-            Ok(resp) => {
-                if resp.status != ServingStatus::Serving as i32 {
-                    return Err(Error::AvailableManagerNotFound);
-                }
-            }
-            Err(err) => return Err(err),
+        let mut endpoints = Vec::with_capacity(addrs.len());
+        for addr in addrs {
+            endpoints.push(Self::make_endpoint(config.clone(), addr).await?);
         }
 
-        let channel = match client_tls_config {
-            Some(client_tls_config) => Channel::from_shared(addr.clone())
-                .map_err(|_| Error::InvalidURI(addr.clone()))?
-                .tls_config(client_tls_config)?
-                .buffer_size(super::BUFFER_SIZE)
-                .connect_timeout(super::CONNECT_TIMEOUT)
-                .timeout(super::REQUEST_TIMEOUT)
-                .tcp_keepalive(Some(super::TCP_KEEPALIVE))
-                .http2_keep_alive_interval(super::HTTP2_KEEP_ALIVE_INTERVAL)
-                .keep_alive_timeout(super::HTTP2_KEEP_ALIVE_TIMEOUT)
-                .connect()
-                .await
-                .inspect_err(|err| {
-                    error!("connect to {} failed: {}", addr.to_string(), err);
-                })
-                .or_err(ErrorType::ConnectError)?,
-            None => Channel::from_shared(addr.clone())
-                .map_err(|_| Error::InvalidURI(addr.clone()))?
-                .buffer_size(super::BUFFER_SIZE)
-                .connect_timeout(super::CONNECT_TIMEOUT)
-                .timeout(super::REQUEST_TIMEOUT)
-                .tcp_keepalive(Some(super::TCP_KEEPALIVE))
-                .http2_keep_alive_interval(super::HTTP2_KEEP_ALIVE_INTERVAL)
-                .keep_alive_timeout(super::HTTP2_KEEP_ALIVE_TIMEOUT)
-                .connect()
-                .await
-                .inspect_err(|err| {
-                    error!("connect to {} failed: {}", addr.to_string(), err);
-                })
-                .or_err(ErrorType::ConnectError)?,
+        // If all the managers are unavailable, connect to the first manager lazily, so the
+        // dfdaemon can start with the fallback schedulers and fail over when the managers
+        // are recovered.
+        let (index, channel) = match Self::connect_healthy(&endpoints, 0).await {
+            Some((index, channel)) => (index, channel),
+            None => {
+                warn!("all the managers are unavailable, connect lazily");
+                (0, endpoints[0].endpoint.connect_lazy())
+            }
         };
 
-        let client = ManagerGRPCClient::with_interceptor(channel, TracingInterceptor)
-            .max_decoding_message_size(usize::MAX)
-            .max_encoding_message_size(usize::MAX);
-        Ok(Self { client })
+        info!("active manager is {}", endpoints[index].addr);
+        collect_active_manager_metrics(
+            &endpoints
+                .iter()
+                .map(|endpoint| endpoint.addr.clone())
+                .collect::<Vec<String>>(),
+            endpoints[index].addr.as_str(),
+        );
+
+        Ok(Self {
+            endpoints: Arc::new(endpoints),
+            active: Arc::new(RwLock::new((index, Self::make_client(channel)))),
+            failover_mutex: Arc::new(Mutex::new(())),
+        })
     }
 
     /// list_schedulers lists all schedulers that best match the client.
@@ -111,27 +123,218 @@ impl ManagerClient {
         &self,
         request: ListSchedulersRequest,
     ) -> Result<ListSchedulersResponse> {
-        let request = Self::make_request(request);
-        let response = self.client.clone().list_schedulers(request).await?;
-        Ok(response.into_inner())
+        let (index, mut client) = self.client().await;
+        match client
+            .list_schedulers(Self::make_request(request.clone()))
+            .await
+        {
+            Ok(response) => Ok(response.into_inner()),
+            Err(status) if Self::is_unavailable(&status) => {
+                let response = self
+                    .failover(index)
+                    .await?
+                    .list_schedulers(Self::make_request(request))
+                    .await?;
+                Ok(response.into_inner())
+            }
+            Err(status) => Err(status.into()),
+        }
     }
 
     /// update_seed_peer updates the seed peer information.
     #[instrument(skip_all)]
     pub async fn update_seed_peer(&self, request: UpdateSeedPeerRequest) -> Result<SeedPeer> {
-        let request = Self::make_request(request);
-        let response = self.client.clone().update_seed_peer(request).await?;
-        Ok(response.into_inner())
+        let (index, mut client) = self.client().await;
+        match client
+            .update_seed_peer(Self::make_request(request.clone()))
+            .await
+        {
+            Ok(response) => Ok(response.into_inner()),
+            Err(status) if Self::is_unavailable(&status) => {
+                let response = self
+                    .failover(index)
+                    .await?
+                    .update_seed_peer(Self::make_request(request))
+                    .await?;
+                Ok(response.into_inner())
+            }
+            Err(status) => Err(status.into()),
+        }
     }
 
     /// delete_seed_peer deletes the seed peer information.
     #[instrument(skip_all)]
     pub async fn delete_seed_peer(&self, request: DeleteSeedPeerRequest) -> Result<()> {
-        let request = Self::make_request(request);
-        self.client.clone().delete_seed_peer(request).await?;
+        let (index, mut client) = self.client().await;
+        match client
+            .delete_seed_peer(Self::make_request(request.clone()))
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(status) if Self::is_unavailable(&status) => {
+                self.failover(index)
+                    .await?
+                    .delete_seed_peer(Self::make_request(request))
+                    .await?;
+                Ok(())
+            }
+            Err(status) => Err(status.into()),
+        }
+    }
+
+    /// client returns the index of the active manager and its grpc client.
+    async fn client(&self) -> (usize, GRPCClient) {
+        let active = self.active.read().await;
+        (active.0, active.1.clone())
+    }
+
+    /// failover fails over to the next healthy manager when the manager of the failed index is
+    /// unavailable, the managers are retried with backoff if no healthy manager is found.
+    #[instrument(skip(self))]
+    async fn failover(&self, failed_index: usize) -> Result<GRPCClient> {
+        // Only one failover can be running at a time.
+        let _guard = self.failover_mutex.lock().await;
+
+        // If the active manager has been changed by the other failover, use it directly.
+        let (index, client) = self.client().await;
+        if index != failed_index {
+            return Ok(client);
+        }
+
+        let mut backoff = INITIAL_FAILOVER_BACKOFF;
+        for attempt in 1..=MAX_FAILOVER_ATTEMPTS {
+            if let Some((index, channel)) =
+                Self::connect_healthy(&self.endpoints, (failed_index + 1) % self.endpoints.len())
+                    .await
+            {
+                let addr = self.endpoints[index].addr.as_str();
+                if index != failed_index {
+                    info!(
+                        "fail over manager from {} to {}",
+                        self.endpoints[failed_index].addr, addr
+                    );
+                    collect_manager_failover_metrics();
+                }
+
+                collect_active_manager_metrics(
+                    &self
+                        .endpoints
+                        .iter()
+                        .map(|endpoint| endpoint.addr.clone())
+                        .collect::<Vec<String>>(),
+                    addr,
+                );
+
+                let client = Self::make_client(channel);
+                *self.active.write().await = (index, client.clone());
+                return Ok(client);
+            }
+
+            if attempt < MAX_FAILOVER_ATTEMPTS {
+                warn!(
+                    "no healthy manager found in attempt {}, retry after {:?}",
+                    attempt, backoff
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+
+        error!("no healthy manager found");
+        Err(Error::AvailableManagerNotFound)
+    }
+
+    /// connect_healthy connects to the first healthy manager from the start index in turn, and
+    /// returns the index of the manager and the connected channel.
+    async fn connect_healthy(
+        endpoints: &[ManagerEndpoint],
+        start: usize,
+    ) -> Option<(usize, Channel)> {
+        for i in 0..endpoints.len() {
+            let index = (start + i) % endpoints.len();
+            let endpoint = &endpoints[index];
+            if let Err(err) =
+                Self::check_health(endpoint.addr.as_str(), endpoint.client_tls_config.clone()).await
+            {
+                warn!("manager {} is unavailable: {}", endpoint.addr, err);
+                continue;
+            }
+
+            match endpoint
+                .endpoint
+                .connect()
+                .await
+                .or_err(ErrorType::ConnectError)
+            {
+                Ok(channel) => return Some((index, channel)),
+                Err(err) => error!("connect to {} failed: {}", endpoint.addr, err),
+            }
+        }
+
+        None
+    }
+
+    /// check_health checks whether the manager is serving.
+    #[instrument(skip_all)]
+    async fn check_health(addr: &str, client_tls_config: Option<ClientTlsConfig>) -> Result<()> {
+        let health_client = HealthClient::new(addr, client_tls_config).await?;
+        let resp = health_client.check().await?;
+        if resp.status != ServingStatus::Serving as i32 {
+            return Err(Error::AvailableManagerNotFound);
+        }
+
         Ok(())
     }
 
+    /// is_unavailable returns whether the status indicates the manager is unavailable.
+    fn is_unavailable(status: &tonic::Status) -> bool {
+        matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded)
+    }
+
+    /// make_endpoint creates the grpc endpoint of the manager.
+    async fn make_endpoint(config: Arc<Config>, addr: String) -> Result<ManagerEndpoint> {
+        let domain_name = Url::parse(addr.as_str())?
+            .host_str()
+            .ok_or_else(|| {
+                error!("invalid address: {}", addr);
+                Error::InvalidParameter
+            })?
+            .to_string();
+
+        let client_tls_config = config
+            .manager
+            .load_client_tls_config(domain_name.as_str())
+            .await?;
+
+        let endpoint = match client_tls_config.clone() {
+            Some(client_tls_config) => Channel::from_shared(addr.clone())
+                .map_err(|_| Error::InvalidURI(addr.clone()))?
+                .tls_config(client_tls_config)?,
+            None => {
+                Channel::from_shared(addr.clone()).map_err(|_| Error::InvalidURI(addr.clone()))?
+            }
+        }
+        .buffer_size(super::BUFFER_SIZE)
+        .connect_timeout(super::CONNECT_TIMEOUT)
+        .timeout(super::REQUEST_TIMEOUT)
+        .tcp_keepalive(Some(super::TCP_KEEPALIVE))
+        .http2_keep_alive_interval(super::HTTP2_KEEP_ALIVE_INTERVAL)
+        .keep_alive_timeout(super::HTTP2_KEEP_ALIVE_TIMEOUT);
+
+        Ok(ManagerEndpoint {
+            addr,
+            endpoint,
+            client_tls_config,
+        })
+    }
+
+    /// make_client creates the grpc client of the manager by the channel.
+    fn make_client(channel: Channel) -> GRPCClient {
+        ManagerGRPCClient::with_interceptor(channel, TracingInterceptor)
+            .max_decoding_message_size(usize::MAX)
+            .max_encoding_message_size(usize::MAX)
+    }
+
     /// make_request creates a new request with timeout.
     #[instrument(skip_all)]
     fn make_request<T>(request: T) -> tonic::Request<T> {
@@ -150,11 +353,17 @@ mod tests {
     #[tokio::test]
     async fn invalid_uri_should_fail() {
         let addr = "htt:/xxx".to_string();
-        let result = ManagerClient::new(Arc::new(Config::default()), addr).await;
+        let result = ManagerClient::new(Arc::new(Config::default()), vec![addr]).await;
         assert!(result.is_err());
         match result {
             Err(e) => assert_eq!(e.to_string(), "invalid parameter"),
             _ => panic!("unexpected error"),
         }
     }
+
+    #[tokio::test]
+    async fn empty_addrs_should_fail() {
+        let result = ManagerClient::new(Arc::new(Config::default()), Vec::new()).await;
+        assert!(result.is_err());
+    }
 }
@@ -24,11 +24,13 @@ use std::time::Duration;
 use tonic::Request;
 use tracing::{error, info, instrument, Instrument};
 
+pub mod dfdaemon_admin;
 pub mod dfdaemon_download;
 pub mod dfdaemon_upload;
 pub mod health;
 pub mod interceptor;
 pub mod manager;
+pub mod pool;
 pub mod scheduler;
 
 /// CONNECT_TIMEOUT is the timeout for GRPC connection.
@@ -138,3 +140,36 @@ pub async fn prefetch_task(
 
     Ok(())
 }
+
+/// resume_task resumes the unfinished task by the persisted download request, the finished
+/// pieces of the task will be reused.
+#[instrument(skip_all)]
+pub async fn resume_task(socket_path: PathBuf, request: DownloadTaskRequest) -> ClientResult<()> {
+    // Initialize the dfdaemon download client.
+    let dfdaemon_download_client =
+        dfdaemon_download::DfdaemonDownloadClient::new_unix(socket_path.clone()).await?;
+
+    // Download task by dfdaemon download client.
+    let response = dfdaemon_download_client
+        .download_task(request)
+        .await
+        .inspect_err(|err| {
+            error!("resume task failed: {}", err);
+        })?;
+
+    // Wait for the download task to finish.
+    let mut out_stream = response.into_inner();
+    loop {
+        match out_stream.message().await {
+            Ok(Some(_)) => info!("resume piece finished"),
+            Ok(None) => {
+                info!("resume task finished");
+                return Ok(());
+            }
+            Err(err) => {
+                error!("resume piece failed: {}", err);
+                return Err(ClientError::TonicStatus(err));
+            }
+        }
+    }
+}
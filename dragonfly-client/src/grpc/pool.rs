@@ -0,0 +1,254 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_core::Result as ClientResult;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tonic::transport::Channel;
+use tracing::debug;
+
+/// ChannelKey is the key of the pooled channel, the channels to the same address with
+/// different TLS identities are pooled separately.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChannelKey {
+    /// addr is the address of the remote server.
+    pub addr: String,
+
+    /// identity is the client cert path of the TLS identity, it is None if the channel
+    /// is not secured by mutual TLS.
+    pub identity: Option<PathBuf>,
+}
+
+/// ChannelKey implements the key of the pooled channel.
+impl ChannelKey {
+    /// new creates a new ChannelKey.
+    pub fn new(addr: String, identity: Option<PathBuf>) -> Self {
+        Self { addr, identity }
+    }
+}
+
+/// PooledChannel is the channel in the pool with the last used time.
+struct PooledChannel {
+    /// channel is the established grpc channel.
+    channel: Channel,
+
+    /// last_used_at is the last time the channel is used.
+    last_used_at: Instant,
+}
+
+/// ChannelPool is the pool of the established grpc channels, the channels are reused by
+/// the requests to the same address. The idle channels are evicted after the idle timeout,
+/// and the least recently used channel is evicted if the pool is full.
+pub struct ChannelPool {
+    /// capacity is the max number of the pooled channels.
+    capacity: usize,
+
+    /// idle_timeout is the timeout of the idle channel.
+    idle_timeout: Duration,
+
+    /// channels is the pooled channels.
+    channels: Mutex<HashMap<ChannelKey, PooledChannel>>,
+}
+
+/// ChannelPool implements the pool of the grpc channels.
+impl ChannelPool {
+    /// new creates a new ChannelPool.
+    pub fn new(capacity: usize, idle_timeout: Duration) -> Self {
+        Self {
+            capacity,
+            idle_timeout,
+            channels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// get returns the pooled channel by the key, and refreshes the last used time.
+    pub fn get(&self, key: &ChannelKey) -> Option<Channel> {
+        let mut channels = self.channels.lock().unwrap();
+        match channels.get_mut(key) {
+            Some(pooled) if pooled.last_used_at.elapsed() < self.idle_timeout => {
+                pooled.last_used_at = Instant::now();
+                Some(pooled.channel.clone())
+            }
+            Some(_) => {
+                debug!("evict idle channel {:?}", key);
+                channels.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// get_or_connect returns the pooled channel by the key, if the channel does not exist,
+    /// it connects by the connect future and pools the established channel.
+    pub async fn get_or_connect<F>(&self, key: ChannelKey, connect: F) -> ClientResult<Channel>
+    where
+        F: Future<Output = ClientResult<Channel>>,
+    {
+        if let Some(channel) = self.get(&key) {
+            return Ok(channel);
+        }
+
+        // Connect without holding the lock, if the channel is pooled by the concurrent
+        // request, the pooled channel is replaced and it is still usable by its holders.
+        let channel = connect.await?;
+        self.insert(key, channel.clone());
+        Ok(channel)
+    }
+
+    /// insert pools the channel, the idle channels are evicted first, and the least recently
+    /// used channel is evicted if the pool is still full.
+    fn insert(&self, key: ChannelKey, channel: Channel) {
+        let mut channels = self.channels.lock().unwrap();
+        let now = Instant::now();
+        channels.retain(|_, pooled| now.duration_since(pooled.last_used_at) < self.idle_timeout);
+
+        while !channels.contains_key(&key) && channels.len() >= self.capacity {
+            let Some(lru_key) = channels
+                .iter()
+                .min_by_key(|(_, pooled)| pooled.last_used_at)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+
+            debug!("evict least recently used channel {:?}", lru_key);
+            channels.remove(&lru_key);
+        }
+
+        channels.insert(
+            key,
+            PooledChannel {
+                channel,
+                last_used_at: now,
+            },
+        );
+    }
+
+    /// remove evicts the channel by the key, it is called when the channel is broken.
+    pub fn remove(&self, key: &ChannelKey) {
+        if self.channels.lock().unwrap().remove(key).is_some() {
+            debug!("evict channel {:?}", key);
+        }
+    }
+
+    /// remove_addr evicts the channels to the address with all TLS identities.
+    pub fn remove_addr(&self, addr: &str) {
+        self.channels
+            .lock()
+            .unwrap()
+            .retain(|key, _| key.addr != addr);
+    }
+
+    /// len returns the number of the pooled channels.
+    pub fn len(&self) -> usize {
+        self.channels.lock().unwrap().len()
+    }
+
+    /// is_empty returns whether the pool is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::transport::Endpoint;
+
+    fn lazy_channel(addr: &'static str) -> ClientResult<Channel> {
+        Ok(Endpoint::from_static(addr).connect_lazy())
+    }
+
+    #[tokio::test]
+    async fn should_reuse_channel() {
+        let pool = ChannelPool::new(2, Duration::from_secs(60));
+        let key = ChannelKey::new("http://127.0.0.1:4000".to_string(), None);
+
+        pool.get_or_connect(key.clone(), async { lazy_channel("http://127.0.0.1:4000") })
+            .await
+            .unwrap();
+        assert_eq!(pool.len(), 1);
+
+        // Test the pooled channel is reused without connecting.
+        pool.get_or_connect(key.clone(), async { panic!("should not connect") })
+            .await
+            .unwrap();
+        assert_eq!(pool.len(), 1);
+
+        // Test the same address with different identity is pooled separately.
+        let key_with_identity = ChannelKey::new(
+            "http://127.0.0.1:4000".to_string(),
+            Some(PathBuf::from("/etc/ssl/client.crt")),
+        );
+        pool.get_or_connect(key_with_identity, async {
+            lazy_channel("http://127.0.0.1:4000")
+        })
+        .await
+        .unwrap();
+        assert_eq!(pool.len(), 2);
+
+        pool.remove_addr("http://127.0.0.1:4000");
+        assert!(pool.is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_evict_least_recently_used_channel() {
+        let pool = ChannelPool::new(2, Duration::from_secs(60));
+        let key_0 = ChannelKey::new("http://127.0.0.1:4000".to_string(), None);
+        let key_1 = ChannelKey::new("http://127.0.0.1:4001".to_string(), None);
+        let key_2 = ChannelKey::new("http://127.0.0.1:4002".to_string(), None);
+
+        pool.get_or_connect(key_0.clone(), async {
+            lazy_channel("http://127.0.0.1:4000")
+        })
+        .await
+        .unwrap();
+        pool.get_or_connect(key_1.clone(), async {
+            lazy_channel("http://127.0.0.1:4001")
+        })
+        .await
+        .unwrap();
+        assert!(pool.get(&key_0).is_some());
+
+        pool.get_or_connect(key_2.clone(), async {
+            lazy_channel("http://127.0.0.1:4002")
+        })
+        .await
+        .unwrap();
+        assert_eq!(pool.len(), 2);
+        assert!(pool.get(&key_1).is_none());
+        assert!(pool.get(&key_0).is_some());
+
+        pool.remove(&key_2);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_evict_idle_channel() {
+        let pool = ChannelPool::new(2, Duration::from_millis(10));
+        let key = ChannelKey::new("http://127.0.0.1:4000".to_string(), None);
+
+        pool.get_or_connect(key.clone(), async { lazy_channel("http://127.0.0.1:4000") })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(pool.get(&key).is_none());
+        assert!(pool.is_empty());
+    }
+}
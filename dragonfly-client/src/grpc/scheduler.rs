@@ -30,9 +30,11 @@ use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::error::{ErrorType, OrErr};
 use dragonfly_client_core::{Error, Result};
 use hashring::HashRing;
+use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tokio::task::JoinSet;
 use tonic::service::interceptor::InterceptedService;
@@ -41,6 +43,15 @@ use tracing::{error, info, instrument, Instrument};
 use url::Url;
 
 use super::interceptor::TracingInterceptor;
+use super::pool::{ChannelKey, ChannelPool};
+
+/// CIRCUIT_BREAKER_FAILURE_THRESHOLD is the number of the consecutive connection failures
+/// to open the circuit breaker of the scheduler.
+const CIRCUIT_BREAKER_FAILURE_THRESHOLD: u32 = 3;
+
+/// CIRCUIT_BREAKER_OPEN_DURATION is the duration of the circuit breaker keeps open, the
+/// scheduler is skipped in the duration.
+const CIRCUIT_BREAKER_OPEN_DURATION: Duration = Duration::from_secs(30);
 
 /// VNode is the virtual node of the hashring.
 #[derive(Debug, Copy, Clone, Hash, PartialEq)]
@@ -57,14 +68,52 @@ impl std::fmt::Display for VNode {
     }
 }
 
+/// CircuitBreaker is the circuit breaker of a scheduler. It is opened when the scheduler fails
+/// to be connected consecutively, and the scheduler is skipped until the open duration elapsed.
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    /// failures is the number of the consecutive connection failures.
+    failures: u32,
+
+    /// opened_at is the time when the circuit breaker is opened.
+    opened_at: Option<Instant>,
+}
+
+/// CircuitBreaker implements the circuit breaker.
+impl CircuitBreaker {
+    /// is_allowed returns whether the scheduler is allowed to be connected. The circuit breaker
+    /// is half-open after the open duration, so the scheduler can be tried again.
+    fn is_allowed(&self) -> bool {
+        match self.opened_at {
+            Some(opened_at) => opened_at.elapsed() >= CIRCUIT_BREAKER_OPEN_DURATION,
+            None => true,
+        }
+    }
+
+    /// record_success closes the circuit breaker.
+    fn record_success(&mut self) {
+        self.failures = 0;
+        self.opened_at = None;
+    }
+
+    /// record_failure records the connection failure, and opens the circuit breaker if the
+    /// consecutive failures reach the threshold.
+    fn record_failure(&mut self) {
+        self.failures += 1;
+        if self.failures >= CIRCUIT_BREAKER_FAILURE_THRESHOLD {
+            self.opened_at = Some(Instant::now());
+        }
+    }
+}
+
 /// SchedulerClient is a wrapper of SchedulerGRPCClient.
 #[derive(Clone)]
 pub struct SchedulerClient {
     /// config is the configuration of the dfdaemon.
     config: Arc<Config>,
 
-    /// dynconfig is the dynamic configuration of the dfdaemon.
-    dynconfig: Arc<Dynconfig>,
+    /// dynconfig is the dynamic configuration of the dfdaemon, it is none in standalone mode.
+    dynconfig: Option<Arc<Dynconfig>>,
 
     /// available_schedulers is the available schedulers.
     available_schedulers: Arc<RwLock<Vec<Scheduler>>>,
@@ -74,6 +123,12 @@ pub struct SchedulerClient {
 
     /// hashring is the hashring of the scheduler.
     hashring: Arc<RwLock<HashRing<VNode>>>,
+
+    /// circuit_breakers is the circuit breakers of the schedulers.
+    circuit_breakers: Arc<Mutex<HashMap<SocketAddr, CircuitBreaker>>>,
+
+    /// pool is the pool of the grpc channels to the schedulers.
+    pool: Arc<ChannelPool>,
 }
 
 /// SchedulerClient implements the grpc client of the scheduler.
@@ -82,17 +137,58 @@ impl SchedulerClient {
     #[instrument(skip_all)]
     pub async fn new(config: Arc<Config>, dynconfig: Arc<Dynconfig>) -> Result<Self> {
         let client = Self {
-            config,
-            dynconfig,
+            dynconfig: Some(dynconfig),
             available_schedulers: Arc::new(RwLock::new(Vec::new())),
             available_scheduler_addrs: Arc::new(RwLock::new(Vec::new())),
             hashring: Arc::new(RwLock::new(HashRing::new())),
+            circuit_breakers: Arc::new(Mutex::new(HashMap::new())),
+            pool: Arc::new(ChannelPool::new(
+                config.download.parent_selector.capacity,
+                config.network.connection_pool.idle_timeout,
+            )),
+            config,
         };
 
         client.refresh_available_scheduler_addrs().await?;
         Ok(client)
     }
 
+    /// new_standalone creates a new SchedulerClient in standalone mode, there are no
+    /// available schedulers, so all the requests to the scheduler will fail.
+    pub fn new_standalone(config: Arc<Config>) -> Self {
+        Self {
+            dynconfig: None,
+            available_schedulers: Arc::new(RwLock::new(Vec::new())),
+            available_scheduler_addrs: Arc::new(RwLock::new(Vec::new())),
+            hashring: Arc::new(RwLock::new(HashRing::new())),
+            circuit_breakers: Arc::new(Mutex::new(HashMap::new())),
+            pool: Arc::new(ChannelPool::new(
+                config.download.parent_selector.capacity,
+                config.network.connection_pool.idle_timeout,
+            )),
+            config,
+        }
+    }
+
+    /// is_standalone returns whether the client is in standalone mode.
+    pub fn is_standalone(&self) -> bool {
+        self.dynconfig.is_none()
+    }
+
+    /// scheduler_cluster_id returns the id of the available scheduler cluster, it returns 0
+    /// if the available scheduler cluster is unknown.
+    pub async fn scheduler_cluster_id(&self) -> u64 {
+        match self.dynconfig.as_ref() {
+            Some(dynconfig) => dynconfig
+                .data
+                .read()
+                .await
+                .available_scheduler_cluster_id
+                .unwrap_or_default(),
+            None => 0,
+        }
+    }
+
     /// announce_peer announces the peer to the scheduler.
     #[instrument(skip_all)]
     pub async fn announce_peer(
@@ -101,11 +197,11 @@ impl SchedulerClient {
         peer_id: &str,
         request: impl tonic::IntoStreamingRequest<Message = AnnouncePeerRequest>,
     ) -> Result<tonic::Response<tonic::codec::Streaming<AnnouncePeerResponse>>> {
-        let response = self
-            .client(task_id, Some(peer_id))
-            .await?
+        let (addr, mut client) = self.client(task_id, Some(peer_id)).await?;
+        let response = client
             .announce_peer(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(response)
     }
 
@@ -114,11 +210,11 @@ impl SchedulerClient {
     pub async fn stat_peer(&self, request: StatPeerRequest) -> Result<Peer> {
         let task_id = request.task_id.clone();
         let request = Self::make_request(request);
-        let response = self
-            .client(task_id.as_str(), None)
-            .await?
+        let (addr, mut client) = self.client(task_id.as_str(), None).await?;
+        let response = client
             .stat_peer(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(response.into_inner())
     }
 
@@ -127,10 +223,11 @@ impl SchedulerClient {
     pub async fn delete_peer(&self, request: DeletePeerRequest) -> Result<()> {
         let task_id = request.task_id.clone();
         let request = Self::make_request(request);
-        self.client(task_id.as_str(), None)
-            .await?
+        let (addr, mut client) = self.client(task_id.as_str(), None).await?;
+        client
             .delete_peer(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(())
     }
 
@@ -139,11 +236,11 @@ impl SchedulerClient {
     pub async fn stat_task(&self, request: StatTaskRequest) -> Result<Task> {
         let task_id = request.task_id.clone();
         let request = Self::make_request(request);
-        let response = self
-            .client(task_id.as_str(), None)
-            .await?
+        let (addr, mut client) = self.client(task_id.as_str(), None).await?;
+        let response = client
             .stat_task(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(response.into_inner())
     }
 
@@ -152,10 +249,11 @@ impl SchedulerClient {
     pub async fn delete_task(&self, request: DeleteTaskRequest) -> Result<()> {
         let task_id = request.task_id.clone();
         let request = Self::make_request(request);
-        self.client(task_id.as_str(), None)
-            .await?
+        let (addr, mut client) = self.client(task_id.as_str(), None).await?;
+        client
             .delete_task(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(())
     }
 
@@ -335,11 +433,11 @@ impl SchedulerClient {
         peer_id: &str,
         request: impl tonic::IntoStreamingRequest<Message = AnnouncePersistentCachePeerRequest>,
     ) -> Result<tonic::Response<tonic::codec::Streaming<AnnouncePersistentCachePeerResponse>>> {
-        let response = self
-            .client(task_id, Some(peer_id))
-            .await?
+        let (addr, mut client) = self.client(task_id, Some(peer_id)).await?;
+        let response = client
             .announce_persistent_cache_peer(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(response)
     }
 
@@ -351,11 +449,11 @@ impl SchedulerClient {
     ) -> Result<PersistentCachePeer> {
         let task_id = request.task_id.clone();
         let request = Self::make_request(request);
-        let response = self
-            .client(task_id.as_str(), None)
-            .await?
+        let (addr, mut client) = self.client(task_id.as_str(), None).await?;
+        let response = client
             .stat_persistent_cache_peer(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(response.into_inner())
     }
 
@@ -367,10 +465,11 @@ impl SchedulerClient {
     ) -> Result<()> {
         let task_id = request.task_id.clone();
         let request = Self::make_request(request);
-        self.client(task_id.as_str(), None)
-            .await?
+        let (addr, mut client) = self.client(task_id.as_str(), None).await?;
+        client
             .delete_persistent_cache_peer(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(())
     }
 
@@ -382,10 +481,11 @@ impl SchedulerClient {
     ) -> Result<()> {
         let task_id = request.task_id.clone();
         let request = Self::make_request(request);
-        self.client(task_id.as_str(), None)
-            .await?
+        let (addr, mut client) = self.client(task_id.as_str(), None).await?;
+        client
             .upload_persistent_cache_task_started(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(())
     }
 
@@ -397,11 +497,11 @@ impl SchedulerClient {
     ) -> Result<PersistentCacheTask> {
         let task_id = request.task_id.clone();
         let request = Self::make_request(request);
-        let response = self
-            .client(task_id.as_str(), None)
-            .await?
+        let (addr, mut client) = self.client(task_id.as_str(), None).await?;
+        let response = client
             .upload_persistent_cache_task_finished(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(response.into_inner())
     }
 
@@ -413,10 +513,11 @@ impl SchedulerClient {
     ) -> Result<()> {
         let task_id = request.task_id.clone();
         let request = Self::make_request(request);
-        self.client(task_id.as_str(), None)
-            .await?
+        let (addr, mut client) = self.client(task_id.as_str(), None).await?;
+        client
             .upload_persistent_cache_task_failed(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(())
     }
 
@@ -428,11 +529,11 @@ impl SchedulerClient {
     ) -> Result<PersistentCacheTask> {
         let task_id = request.task_id.clone();
         let request = Self::make_request(request);
-        let response = self
-            .client(task_id.as_str(), None)
-            .await?
+        let (addr, mut client) = self.client(task_id.as_str(), None).await?;
+        let response = client
             .stat_persistent_cache_task(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(response.into_inner())
     }
 
@@ -444,31 +545,106 @@ impl SchedulerClient {
     ) -> Result<()> {
         let task_id = request.task_id.clone();
         let request = Self::make_request(request);
-        self.client(task_id.as_str(), None)
-            .await?
+        let (addr, mut client) = self.client(task_id.as_str(), None).await?;
+        client
             .delete_persistent_cache_task(request)
-            .await?;
+            .await
+            .inspect_err(|status| self.evict_if_unavailable(addr, status))?;
         Ok(())
     }
 
-    /// client gets the grpc client of the scheduler.
+    /// client gets the grpc client of the scheduler. The scheduler is picked by the hashring with
+    /// the task id, if the scheduler can not be connected, the next schedulers on the ring are
+    /// tried in turn, and the schedulers with the open circuit breaker are skipped.
     #[instrument(skip(self))]
     async fn client(
         &self,
         task_id: &str,
         peer_id: Option<&str>,
-    ) -> Result<SchedulerGRPCClient<InterceptedService<Channel, TracingInterceptor>>> {
+    ) -> Result<(
+        SocketAddr,
+        SchedulerGRPCClient<InterceptedService<Channel, TracingInterceptor>>,
+    )> {
         // Update scheduler addresses of the client.
         self.update_available_scheduler_addrs().await?;
 
-        // Get the scheduler address from the hashring.
-        let addrs = self.hashring.read().await;
-        let addr = *addrs
-            .get(&task_id[0..5].to_string())
-            .ok_or_else(|| Error::HashRing(task_id.to_string()))?;
-        drop(addrs);
-        info!("picked {:?}", addr);
+        // Get the scheduler addresses from the hashring, the first one is the owner of the
+        // task, and the others are the next schedulers on the ring. The replicas exclude the
+        // owner, so the owner is not returned twice.
+        let hashring = self.hashring.read().await;
+        let addrs = hashring
+            .get_with_replicas(&task_id[0..5].to_string(), hashring.len().saturating_sub(1))
+            .ok_or_else(|| Error::HashRing(task_id.to_string()))?
+            .into_iter()
+            .map(|vnode| vnode.addr)
+            .collect::<Vec<SocketAddr>>();
+        drop(hashring);
+
+        // Skip the schedulers with the open circuit breaker, if all the circuit breakers
+        // are open, try all the schedulers.
+        let candidate_addrs = {
+            let circuit_breakers = self.circuit_breakers.lock().unwrap();
+            addrs
+                .iter()
+                .filter(|addr| {
+                    circuit_breakers
+                        .get(addr)
+                        .map_or(true, |circuit_breaker| circuit_breaker.is_allowed())
+                })
+                .copied()
+                .collect::<Vec<SocketAddr>>()
+        };
+        let candidate_addrs = if candidate_addrs.is_empty() {
+            addrs
+        } else {
+            candidate_addrs
+        };
+
+        let mut last_err = Error::HashRing(task_id.to_string());
+        for addr in candidate_addrs {
+            info!("picked {:?}", addr);
+            match self.connect(addr).await {
+                Ok(client) => {
+                    if let Some(circuit_breaker) =
+                        self.circuit_breakers.lock().unwrap().get_mut(&addr)
+                    {
+                        circuit_breaker.record_success();
+                    }
+
+                    return Ok((addr, client));
+                }
+                Err(err) => {
+                    self.record_failure(addr);
+                    last_err = err;
+                }
+            }
+        }
+
+        Err(last_err)
+    }
+
+    /// connect returns the grpc client of the scheduler by the pooled channel, if the channel
+    /// does not exist in the pool, it connects to the scheduler and pools the channel.
+    #[instrument(skip(self))]
+    async fn connect(
+        &self,
+        addr: SocketAddr,
+    ) -> Result<SchedulerGRPCClient<InterceptedService<Channel, TracingInterceptor>>> {
+        let channel = self
+            .pool
+            .get_or_connect(self.channel_key(addr), self.connect_channel(addr))
+            .await?;
 
+        Ok(
+            SchedulerGRPCClient::with_interceptor(channel, TracingInterceptor)
+                .max_decoding_message_size(usize::MAX)
+                .max_encoding_message_size(usize::MAX),
+        )
+    }
+
+    /// connect_channel connects to the scheduler and returns the grpc channel.
+    #[instrument(skip(self))]
+    async fn connect_channel(&self, addr: SocketAddr) -> Result<Channel> {
         let addr = format!("http://{}", addr);
         let domain_name = Url::parse(addr.as_str())?
             .host_str()
@@ -515,19 +691,72 @@ impl SchedulerClient {
                 .or_err(ErrorType::ConnectError)?,
         };
 
-        Ok(
-            SchedulerGRPCClient::with_interceptor(channel, TracingInterceptor)
-                .max_decoding_message_size(usize::MAX)
-                .max_encoding_message_size(usize::MAX),
+        Ok(channel)
+    }
+
+    /// channel_key returns the key of the pooled channel to the scheduler.
+    fn channel_key(&self, addr: SocketAddr) -> ChannelKey {
+        ChannelKey::new(
+            format!("http://{}", addr),
+            self.config.scheduler.cert.clone(),
         )
     }
 
+    /// evict_if_unavailable evicts the pooled channel of the scheduler if the scheduler is
+    /// unavailable, the next request to the scheduler establishes a new channel. The pooled
+    /// channel to the dead scheduler fails by the unavailable status instead of the connect
+    /// error, so the failure is also recorded by the circuit breaker to fall back to the next
+    /// scheduler on the hashring.
+    fn evict_if_unavailable(&self, addr: SocketAddr, status: &tonic::Status) {
+        if status.code() == tonic::Code::Unavailable {
+            self.pool.remove(&self.channel_key(addr));
+            self.record_failure(addr);
+        }
+    }
+
+    /// record_failure records the failure of the scheduler by the circuit breaker, and
+    /// refreshes the dynamic configuration immediately to evict the unhealthy scheduler
+    /// from the hashring.
+    fn record_failure(&self, addr: SocketAddr) {
+        self.circuit_breakers
+            .lock()
+            .unwrap()
+            .entry(addr)
+            .or_default()
+            .record_failure();
+
+        self.refresh_dynconfig_in_background();
+    }
+
+    /// refresh_dynconfig_in_background refreshes the dynamic configuration in background, the
+    /// refresh is skipped if another refresh is running.
+    fn refresh_dynconfig_in_background(&self) {
+        let Some(dynconfig) = self.dynconfig.clone() else {
+            return;
+        };
+
+        tokio::spawn(
+            async move {
+                dynconfig
+                    .refresh()
+                    .await
+                    .unwrap_or_else(|err| error!("refresh dynconfig failed: {}", err));
+            }
+            .in_current_span(),
+        );
+    }
+
     /// update_available_scheduler_addrs updates the addresses of available schedulers.
     #[instrument(skip(self))]
     async fn update_available_scheduler_addrs(&self) -> Result<()> {
+        // There are no available schedulers in standalone mode.
+        let Some(dynconfig) = self.dynconfig.as_ref() else {
+            return Err(Error::AvailableSchedulersNotFound);
+        };
+
         // Get the endpoints of available schedulers.
         let data_available_schedulers_clone = {
-            let data = self.dynconfig.data.read().await;
+            let data = dynconfig.data.read().await;
             data.available_schedulers.clone()
         };
 
@@ -588,6 +817,7 @@ impl SchedulerClient {
         drop(available_schedulers);
 
         // Update the addresses of available schedulers.
+        let new_available_scheduler_addrs_clone = new_available_scheduler_addrs.clone();
         let mut available_scheduler_addrs = self.available_scheduler_addrs.write().await;
         *available_scheduler_addrs = new_available_scheduler_addrs;
         drop(available_scheduler_addrs);
@@ -597,6 +827,21 @@ impl SchedulerClient {
         *hashring = new_hashring;
         drop(hashring);
 
+        // Remove the circuit breakers and the pooled channels of the schedulers which are
+        // not available.
+        self.circuit_breakers
+            .lock()
+            .unwrap()
+            .retain(|addr, _| new_available_scheduler_addrs_clone.contains(addr));
+        for available_scheduler in available_schedulers_clone.iter() {
+            if let Ok(ip) = IpAddr::from_str(&available_scheduler.ip) {
+                let addr = SocketAddr::new(ip, available_scheduler.port as u16);
+                if !new_available_scheduler_addrs_clone.contains(&addr) {
+                    self.pool.remove_addr(&format!("http://{}", addr));
+                }
+            }
+        }
+
         let available_scheduler_addrs = self.available_scheduler_addrs.read().await;
         info!(
             "refresh available scheduler addresses: {:?}",
@@ -611,8 +856,13 @@ impl SchedulerClient {
     /// refresh_available_scheduler_addrs refreshes addresses of available schedulers.
     #[instrument(skip(self))]
     async fn refresh_available_scheduler_addrs(&self) -> Result<()> {
+        // There are no available schedulers in standalone mode.
+        let Some(dynconfig) = self.dynconfig.as_ref() else {
+            return Err(Error::AvailableSchedulersNotFound);
+        };
+
         // Refresh the dynamic configuration.
-        self.dynconfig.refresh().await?;
+        dynconfig.refresh().await?;
 
         // Update scheduler addresses of the client.
         self.update_available_scheduler_addrs().await
@@ -626,3 +876,30 @@ impl SchedulerClient {
         request
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_open_and_close_circuit_breaker() {
+        let mut circuit_breaker = CircuitBreaker::default();
+        assert!(circuit_breaker.is_allowed());
+
+        for _ in 0..CIRCUIT_BREAKER_FAILURE_THRESHOLD - 1 {
+            circuit_breaker.record_failure();
+            assert!(circuit_breaker.is_allowed());
+        }
+
+        circuit_breaker.record_failure();
+        assert!(!circuit_breaker.is_allowed());
+
+        // Test the circuit breaker is half-open after the open duration.
+        circuit_breaker.opened_at = Some(Instant::now() - CIRCUIT_BREAKER_OPEN_DURATION);
+        assert!(circuit_breaker.is_allowed());
+
+        circuit_breaker.record_success();
+        assert!(circuit_breaker.is_allowed());
+        assert_eq!(circuit_breaker.failures, 0);
+    }
+}
@@ -20,8 +20,12 @@ pub mod gc;
 pub mod grpc;
 pub mod health;
 pub mod metrics;
+pub mod ordered_writer;
 pub mod proxy;
+pub mod reloader;
+pub mod report;
 pub mod resource;
 pub mod shutdown;
 pub mod stats;
 pub mod tracing;
+pub mod walker;
@@ -21,8 +21,8 @@ use dragonfly_client_config::{
 };
 use lazy_static::lazy_static;
 use prometheus::{
-    exponential_buckets, gather, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec,
-    Opts, Registry, TextEncoder,
+    core::Collector, exponential_buckets, gather, Encoder, HistogramOpts, HistogramVec,
+    IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
 };
 use std::net::SocketAddr;
 use std::path::Path;
@@ -52,6 +52,14 @@ lazy_static! {
             &["git_version", "git_commit", "platform", "build_time"]
         ).expect("metric can be created");
 
+    /// CONFIG_VERSION_GAUGE is used to record the version of the active configuration, the version
+    /// is increased when the configuration is reloaded.
+    pub static ref CONFIG_VERSION_GAUGE: IntGaugeVec =
+        IntGaugeVec::new(
+            Opts::new("config_version", "Version of the active configuration.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
+            &["digest"]
+        ).expect("metric can be created");
+
     /// UPLOAD_TASK_COUNT is used to count the number of upload tasks.
     pub static ref UPLOAD_TASK_COUNT: IntCounterVec =
         IntCounterVec::new(
@@ -122,6 +130,13 @@ lazy_static! {
             &[]
         ).expect("metric can be created");
 
+    /// UPLOAD_TCP_CONNECTION_GAUGE is used to gauge the number of the live tcp connections of the upload server.
+    pub static ref UPLOAD_TCP_CONNECTION_GAUGE: IntGaugeVec =
+        IntGaugeVec::new(
+            Opts::new("upload_tcp_connection_total", "Gauge of the number of the live tcp connections of the upload server.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
+            &[]
+        ).expect("metric can be created");
+
     /// DOWNLOAD_TRAFFIC is used to count the download traffic.
     pub static ref DOWNLOAD_TRAFFIC: IntCounterVec =
         IntCounterVec::new(
@@ -276,6 +291,20 @@ lazy_static! {
             Opts::new("disk_read_bytes", "Gauge of the disk read bytes.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
             &[]
         ).expect("metric can be created");
+
+    /// ACTIVE_MANAGER_GAUGE is used to gauge which manager is active, the active manager is 1.
+    pub static ref ACTIVE_MANAGER_GAUGE: IntGaugeVec =
+        IntGaugeVec::new(
+            Opts::new("active_manager", "Gauge of the active manager.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
+            &["addr"]
+        ).expect("metric can be created");
+
+    /// MANAGER_FAILOVER_COUNT is used to count the number of manager failovers.
+    pub static ref MANAGER_FAILOVER_COUNT: IntCounterVec =
+        IntCounterVec::new(
+            Opts::new("manager_failover_total", "Counter of the number of the manager failover.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
+            &[]
+        ).expect("metric can be created");
 }
 
 /// register_custom_metrics registers all custom metrics.
@@ -285,6 +314,10 @@ fn register_custom_metrics() {
         .register(Box::new(VERSION_GAUGE.clone()))
         .expect("metric can be registered");
 
+    REGISTRY
+        .register(Box::new(CONFIG_VERSION_GAUGE.clone()))
+        .expect("metric can be registered");
+
     REGISTRY
         .register(Box::new(DOWNLOAD_TASK_COUNT.clone()))
         .expect("metric can be registered");
@@ -309,6 +342,10 @@ fn register_custom_metrics() {
         .register(Box::new(CONCURRENT_UPLOAD_PIECE_GAUGE.clone()))
         .expect("metric can be registered");
 
+    REGISTRY
+        .register(Box::new(UPLOAD_TCP_CONNECTION_GAUGE.clone()))
+        .expect("metric can be registered");
+
     REGISTRY
         .register(Box::new(DOWNLOAD_TRAFFIC.clone()))
         .expect("metric can be registered");
@@ -398,12 +435,22 @@ fn register_custom_metrics() {
     REGISTRY
         .register(Box::new(DISK_READ_BYTES.clone()))
         .expect("metric can be registered");
+
+    REGISTRY
+        .register(Box::new(ACTIVE_MANAGER_GAUGE.clone()))
+        .expect("metric can be registered");
+
+    REGISTRY
+        .register(Box::new(MANAGER_FAILOVER_COUNT.clone()))
+        .expect("metric can be registered");
 }
 
-/// reset_custom_metrics resets all custom metrics.
+/// reset_custom_metrics resets all custom metrics. UPLOAD_TCP_CONNECTION_GAUGE is not reset,
+/// because it tracks the live connections and is decreased when the connections are closed.
 #[instrument(skip_all)]
 fn reset_custom_metrics() {
     VERSION_GAUGE.reset();
+    CONFIG_VERSION_GAUGE.reset();
     DOWNLOAD_TASK_COUNT.reset();
     DOWNLOAD_TASK_FAILURE_COUNT.reset();
     PREFETCH_TASK_COUNT.reset();
@@ -432,6 +479,8 @@ fn reset_custom_metrics() {
     DISK_USAGE_SPACE.reset();
     DISK_WRITTEN_BYTES.reset();
     DISK_READ_BYTES.reset();
+    ACTIVE_MANAGER_GAUGE.reset();
+    MANAGER_FAILOVER_COUNT.reset();
 }
 
 /// TaskSize represents the size of the task.
@@ -719,6 +768,44 @@ pub fn collect_upload_piece_traffic_metrics(task_type: i32, length: u64) {
         .inc_by(length);
 }
 
+/// collect_upload_tcp_connection_opened_metrics collects the metrics of the upload tcp connection opened.
+pub fn collect_upload_tcp_connection_opened_metrics() {
+    UPLOAD_TCP_CONNECTION_GAUGE.with_label_values(&[]).inc();
+}
+
+/// collect_upload_tcp_connection_closed_metrics collects the metrics of the upload tcp connection closed.
+pub fn collect_upload_tcp_connection_closed_metrics() {
+    UPLOAD_TCP_CONNECTION_GAUGE.with_label_values(&[]).dec();
+}
+
+/// upload_tcp_connection_count returns the number of the live tcp connections of the upload server.
+pub fn upload_tcp_connection_count() -> u64 {
+    UPLOAD_TCP_CONNECTION_GAUGE
+        .with_label_values(&[])
+        .get()
+        .max(0) as u64
+}
+
+/// download_traffic_total returns the total download traffic of all the traffic types and task types.
+pub fn download_traffic_total() -> u64 {
+    sum_counter_vec(&DOWNLOAD_TRAFFIC)
+}
+
+/// upload_traffic_total returns the total upload traffic of all the task types.
+pub fn upload_traffic_total() -> u64 {
+    sum_counter_vec(&UPLOAD_TRAFFIC)
+}
+
+/// sum_counter_vec sums the values of the counters with all the label values.
+fn sum_counter_vec(counter_vec: &IntCounterVec) -> u64 {
+    counter_vec
+        .collect()
+        .iter()
+        .flat_map(|family| family.get_metric())
+        .map(|metric| metric.get_counter().get_value() as u64)
+        .sum()
+}
+
 /// collect_upload_piece_failure_metrics collects the upload piece failure metrics.
 pub fn collect_upload_piece_failure_metrics() {
     CONCURRENT_UPLOAD_PIECE_GAUGE.with_label_values(&[]).dec();
@@ -822,6 +909,29 @@ pub fn collect_delete_host_failure_metrics() {
     DELETE_HOST_FAILURE_COUNT.with_label_values(&[]).inc();
 }
 
+/// collect_active_manager_metrics collects the active manager metrics, the active manager
+/// is set to 1 and the others are set to 0.
+pub fn collect_active_manager_metrics(addrs: &[String], active_addr: &str) {
+    for addr in addrs {
+        ACTIVE_MANAGER_GAUGE
+            .with_label_values(&[addr.as_str()])
+            .set((addr == active_addr) as i64);
+    }
+}
+
+/// collect_manager_failover_metrics collects the manager failover metrics.
+pub fn collect_manager_failover_metrics() {
+    MANAGER_FAILOVER_COUNT.with_label_values(&[]).inc();
+}
+
+/// collect_config_version_metrics collects the version metrics of the active configuration.
+pub fn collect_config_version_metrics(version: u64, digest: &str) {
+    CONFIG_VERSION_GAUGE.reset();
+    CONFIG_VERSION_GAUGE
+        .with_label_values(&[digest])
+        .set(version as i64);
+}
+
 /// collect_disk_metrics collects the disk metrics.
 pub fn collect_disk_metrics(path: &Path, system: &Arc<Mutex<System>>) {
     // Collect disk space metrics.
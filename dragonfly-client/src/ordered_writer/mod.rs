@@ -0,0 +1,284 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_core::{
+    error::{ErrorType, OrErr},
+    Error, Result,
+};
+use std::collections::BTreeMap;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, SeekFrom};
+
+/// MAX_PENDING_PIECES_SIZE is the default max size of the out-of-order pieces buffered in
+/// memory, the pieces exceeding it are spilled to the temporary file.
+pub const MAX_PENDING_PIECES_SIZE: u64 = 128 * 1024 * 1024;
+
+/// OrderedWriter writes the pieces to the writer in order of the offset, the out-of-order
+/// pieces are buffered in memory until the previous pieces are written. The out-of-order
+/// pieces exceeding the max pending size are spilled to the temporary file, so the memory
+/// is bounded when the previous piece is slow to download.
+pub struct OrderedWriter<W> {
+    /// writer is the writer of the content.
+    writer: W,
+
+    /// offset is the length of the content written to the writer.
+    offset: u64,
+
+    /// pending are the out-of-order pieces buffered in memory by the offset.
+    pending: BTreeMap<u64, Vec<u8>>,
+
+    /// pending_size is the total size of the pieces buffered in memory.
+    pending_size: u64,
+
+    /// max_pending_size is the max size of the pieces buffered in memory.
+    max_pending_size: u64,
+
+    /// spill is the temporary file of the spilled pieces, which is created on the first spill.
+    spill: Option<File>,
+
+    /// spilled are the lengths of the out-of-order pieces spilled to the temporary file by
+    /// the offset.
+    spilled: BTreeMap<u64, u64>,
+}
+
+/// OrderedWriter implements the ordered writing of the pieces.
+impl<W: AsyncWrite + Unpin> OrderedWriter<W> {
+    /// new creates a new ordered writer writing the content from the beginning.
+    pub fn new(writer: W, max_pending_size: u64) -> Self {
+        Self::with_offset(writer, 0, max_pending_size)
+    }
+
+    /// with_offset creates a new ordered writer whose writer already has the content before
+    /// the offset, such as the partial file of the interrupted download.
+    pub fn with_offset(writer: W, offset: u64, max_pending_size: u64) -> Self {
+        Self {
+            writer,
+            offset,
+            pending: BTreeMap::new(),
+            pending_size: 0,
+            max_pending_size,
+            spill: None,
+            spilled: BTreeMap::new(),
+        }
+    }
+
+    /// offset returns the length of the content written to the writer.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// get_mut returns the mutable reference of the writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// reset discards the written offset and the buffered pieces to write the content from the
+    /// beginning, the content of the writer needs to be truncated by the caller.
+    pub fn reset(&mut self) {
+        self.offset = 0;
+        self.pending.clear();
+        self.pending_size = 0;
+        self.spilled.clear();
+    }
+
+    /// write writes the piece if it is the next piece, and then writes the buffered pieces
+    /// following it. Otherwise, the piece is buffered in memory or spilled to the temporary
+    /// file. The content of the piece already written is skipped.
+    pub async fn write(&mut self, offset: u64, content: Vec<u8>) -> Result<()> {
+        if offset + content.len() as u64 <= self.offset
+            || self.pending.contains_key(&offset)
+            || self.spilled.contains_key(&offset)
+        {
+            return Ok(());
+        }
+
+        if offset > self.offset {
+            if self.pending_size + content.len() as u64 <= self.max_pending_size {
+                self.pending_size += content.len() as u64;
+                self.pending.insert(offset, content);
+            } else {
+                self.write_spill(offset, &content).await?;
+            }
+
+            return Ok(());
+        }
+
+        self.write_prefix(offset, &content).await?;
+        loop {
+            let next_pending = self.pending.keys().next().copied();
+            let next_spilled = self.spilled.keys().next().copied();
+            let next = match (next_pending, next_spilled) {
+                (Some(pending), Some(spilled)) => pending.min(spilled),
+                (Some(pending), None) => pending,
+                (None, Some(spilled)) => spilled,
+                (None, None) => break,
+            };
+
+            if next > self.offset {
+                break;
+            }
+
+            let content = match self.pending.remove(&next) {
+                Some(content) => {
+                    self.pending_size -= content.len() as u64;
+                    content
+                }
+                None => {
+                    let length = self.spilled.remove(&next).unwrap_or_default();
+                    self.read_spill(next, length).await?
+                }
+            };
+
+            self.write_prefix(next, &content).await?;
+        }
+
+        Ok(())
+    }
+
+    /// write_prefix writes the piece at the offset not after the written length, the content
+    /// already written is skipped.
+    async fn write_prefix(&mut self, offset: u64, content: &[u8]) -> Result<()> {
+        let skip = (self.offset - offset) as usize;
+        if skip < content.len() {
+            self.writer.write_all(&content[skip..]).await?;
+            self.offset += (content.len() - skip) as u64;
+        }
+
+        Ok(())
+    }
+
+    /// write_spill writes the piece to the temporary file at the offset.
+    async fn write_spill(&mut self, offset: u64, content: &[u8]) -> Result<()> {
+        if self.spill.is_none() {
+            let spill = tokio::task::spawn_blocking(tempfile::tempfile)
+                .await
+                .or_err(ErrorType::AsyncRuntimeError)??;
+            self.spill = Some(File::from_std(spill));
+        }
+
+        let spill = self
+            .spill
+            .as_mut()
+            .ok_or_else(|| Error::Unknown("spill file is not created".to_string()))?;
+        spill.seek(SeekFrom::Start(offset)).await?;
+        spill.write_all(content).await?;
+        self.spilled.insert(offset, content.len() as u64);
+        Ok(())
+    }
+
+    /// read_spill reads the piece from the temporary file at the offset.
+    async fn read_spill(&mut self, offset: u64, length: u64) -> Result<Vec<u8>> {
+        let spill = self
+            .spill
+            .as_mut()
+            .ok_or_else(|| Error::Unknown("spill file is not created".to_string()))?;
+
+        let mut content = vec![0; length as usize];
+        spill.seek(SeekFrom::Start(offset)).await?;
+        spill.read_exact(&mut content).await?;
+        Ok(content)
+    }
+
+    /// finish flushes the writer and checks the written length is the content length, the
+    /// writer is returned for the further operations, such as syncing the file.
+    pub async fn finish(mut self, content_length: Option<u64>) -> Result<W> {
+        self.writer.flush().await?;
+        let missing = self.pending.keys().chain(self.spilled.keys()).min();
+        if let Some(offset) = missing {
+            return Err(Error::Unknown(format!(
+                "piece before offset {} is missing, written length is {}",
+                offset, self.offset
+            )));
+        }
+
+        if let Some(content_length) = content_length {
+            if content_length != self.offset {
+                return Err(Error::ContentLengthMismatch(content_length, self.offset));
+            }
+        }
+
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn should_write_pieces_in_order() {
+        let mut writer = OrderedWriter::new(Vec::new(), MAX_PENDING_PIECES_SIZE);
+        writer.write(4, b"efgh".to_vec()).await.unwrap();
+        writer.write(8, b"ij".to_vec()).await.unwrap();
+        assert!(writer.writer.is_empty());
+
+        writer.write(0, b"abcd".to_vec()).await.unwrap();
+        assert_eq!(writer.writer, b"abcdefghij".to_vec());
+        assert!(writer.pending.is_empty());
+
+        // The duplicated piece is ignored.
+        writer.write(4, b"efgh".to_vec()).await.unwrap();
+        assert_eq!(writer.offset(), 10);
+        assert_eq!(writer.finish(Some(10)).await.unwrap(), b"abcdefghij");
+    }
+
+    #[tokio::test]
+    async fn should_write_pieces_after_offset() {
+        // The content before the offset is written by the interrupted download, the overlapped
+        // piece is written from the offset.
+        let mut writer = OrderedWriter::with_offset(b"aaaa".to_vec(), 4, MAX_PENDING_PIECES_SIZE);
+        writer.write(8, b"cc".to_vec()).await.unwrap();
+        writer.write(0, b"xxxx".to_vec()).await.unwrap();
+        writer.write(2, b"xxbbbb".to_vec()).await.unwrap();
+        assert_eq!(writer.finish(Some(10)).await.unwrap(), b"aaaabbbbcc");
+
+        // The reset writer writes the content from the beginning.
+        let mut writer = OrderedWriter::with_offset(Vec::new(), 4, MAX_PENDING_PIECES_SIZE);
+        writer.write(8, b"cc".to_vec()).await.unwrap();
+        writer.reset();
+        writer.write(0, b"aaaa".to_vec()).await.unwrap();
+        assert_eq!(writer.finish(Some(4)).await.unwrap(), b"aaaa");
+    }
+
+    #[tokio::test]
+    async fn should_spill_pieces_exceeding_max_pending_size() {
+        let mut writer = OrderedWriter::new(Vec::new(), 4);
+        writer.write(4, b"efgh".to_vec()).await.unwrap();
+        writer.write(8, b"ij".to_vec()).await.unwrap();
+        assert_eq!(writer.pending_size, 4);
+        assert_eq!(writer.spilled.get(&8), Some(&2));
+
+        writer.write(0, b"abcd".to_vec()).await.unwrap();
+        assert_eq!(writer.writer, b"abcdefghij".to_vec());
+        assert_eq!(writer.pending_size, 0);
+        assert!(writer.spilled.is_empty());
+        assert!(writer.finish(Some(10)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_return_error_when_pieces_are_incomplete() {
+        let mut writer = OrderedWriter::new(Vec::new(), MAX_PENDING_PIECES_SIZE);
+        writer.write(4, b"efgh".to_vec()).await.unwrap();
+        assert!(writer.finish(Some(8)).await.is_err());
+
+        let mut writer = OrderedWriter::new(Vec::new(), MAX_PENDING_PIECES_SIZE);
+        writer.write(0, b"abcd".to_vec()).await.unwrap();
+        assert_eq!(
+            writer.finish(Some(8)).await.unwrap_err().to_string(),
+            Error::ContentLengthMismatch(8, 4).to_string()
+        );
+    }
+}
@@ -58,7 +58,7 @@ use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
-use tokio::sync::{mpsc, Barrier};
+use tokio::sync::{mpsc, watch, Barrier};
 use tokio::time::sleep;
 use tokio_rustls::TlsAcceptor;
 use tokio_util::io::ReaderStream;
@@ -75,6 +75,10 @@ pub struct Proxy {
     /// config is the configuration of the dfdaemon.
     config: Arc<Config>,
 
+    /// config_rx receives the reloaded configuration of the dfdaemon, the requests are
+    /// handled with the reloaded proxy rules.
+    config_rx: watch::Receiver<Arc<Config>>,
+
     /// cache is the cache manager for storing the piece content.
     cache: Arc<cache::Cache>,
 
@@ -104,12 +108,14 @@ impl Proxy {
     #[instrument(skip_all)]
     pub fn new(
         config: Arc<Config>,
+        config_rx: watch::Receiver<Arc<Config>>,
         task: Arc<Task>,
         shutdown: shutdown::Shutdown,
         shutdown_complete_tx: mpsc::UnboundedSender<()>,
     ) -> Self {
         let mut proxy = Self {
             config: config.clone(),
+            config_rx,
             cache: Arc::new(cache::Cache::new(config.proxy.cache_capacity, task.clone()).unwrap()),
             task: task.clone(),
             addr: SocketAddr::new(config.proxy.server.ip.unwrap(), config.proxy.server.port),
@@ -183,7 +189,7 @@ impl Proxy {
                     let io = TokioIo::new(tcp);
                     debug!("accepted connection from {}", remote_address);
 
-                    let config = self.config.clone();
+                    let config_rx = self.config_rx.clone();
                     let cache = self.cache.clone();
                     let task = self.task.clone();
                     let dfdaemon_download_client = dfdaemon_download_client.clone();
@@ -197,7 +203,7 @@ impl Proxy {
                             .title_case_headers(true)
                             .serve_connection(
                                 io,
-                                service_fn(move |request| handler(config.clone(), cache.clone(), task.clone(), request, dfdaemon_download_client.clone(), registry_cert.clone(), server_ca_cert.clone())),
+                                service_fn(move |request| handler(config_rx.borrow().clone(), cache.clone(), task.clone(), request, dfdaemon_download_client.clone(), registry_cert.clone(), server_ca_cert.clone())),
                                 )
                             .with_upgrades()
                             .await
@@ -1100,7 +1106,7 @@ fn make_download_task_request(
             range: None,
             r#type: TaskType::Standard as i32,
             tag: header::get_tag(&header),
-            application: header::get_application(&header),
+            application: header::get_application(&header).or_else(|| rule.application.clone()),
             priority: header::get_priority(&header),
             filtered_query_params: header::get_filtered_query_params(
                 &header,
@@ -0,0 +1,206 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::metrics::collect_config_version_metrics;
+use crate::resource::piece::Piece;
+use crate::shutdown;
+use crate::tracing::reload_log_level;
+use dragonfly_client_config::dfdaemon::Config;
+use dragonfly_client_core::Result;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{mpsc, watch};
+use tracing::{error, info, instrument, Level};
+
+/// Reloader reloads the configuration of the dfdaemon at runtime. The configuration is
+/// reloaded when the SIGHUP signal is received, or the configuration file is changed if the
+/// watch is enabled. Only the reloadable settings are applied, refer to
+/// Config::merge_reloadable, and the configuration is rolled back if it fails to apply.
+pub struct Reloader {
+    /// config_path is the path of the configuration file.
+    config_path: PathBuf,
+
+    /// overrides are the key=value overrides of the command line, they are applied to the
+    /// reloaded configuration as well.
+    overrides: Vec<String>,
+
+    /// config_tx sends the reloaded configuration to the subscribers.
+    config_tx: watch::Sender<Arc<Config>>,
+
+    /// default_log_level is the log level of the command line, it is used if the log level
+    /// is not set in the configuration.
+    default_log_level: Level,
+
+    /// pieces are the piece managers to update the rate limiters.
+    pieces: Vec<Arc<Piece>>,
+
+    /// version is the version of the active configuration.
+    version: AtomicU64,
+
+    /// shutdown is used to shutdown the reloader.
+    shutdown: shutdown::Shutdown,
+
+    /// _shutdown_complete is used to notify the reloader is shutdown.
+    _shutdown_complete: mpsc::UnboundedSender<()>,
+}
+
+/// Reloader implements the configuration reloader of the dfdaemon.
+impl Reloader {
+    /// new creates a new Reloader.
+    #[instrument(skip_all)]
+    pub fn new(
+        config_path: PathBuf,
+        overrides: Vec<String>,
+        config_tx: watch::Sender<Arc<Config>>,
+        default_log_level: Level,
+        pieces: Vec<Arc<Piece>>,
+        shutdown: shutdown::Shutdown,
+        shutdown_complete_tx: mpsc::UnboundedSender<()>,
+    ) -> Self {
+        Self {
+            config_path,
+            overrides,
+            config_tx,
+            default_log_level,
+            pieces,
+            version: AtomicU64::new(1),
+            shutdown,
+            _shutdown_complete: shutdown_complete_tx,
+        }
+    }
+
+    /// run reloads the configuration when the SIGHUP signal is received or the configuration
+    /// file is changed.
+    #[instrument(skip_all)]
+    pub async fn run(&self) -> Result<()> {
+        // Clone the shutdown channel.
+        let mut shutdown = self.shutdown.clone();
+
+        // Record the version of the configuration loaded at startup.
+        let digest = digest(&self.config_path).await.unwrap_or_default();
+        collect_config_version_metrics(self.version.load(Ordering::SeqCst), &digest);
+
+        let mut sighup = signal(SignalKind::hangup())?;
+        let config = self.config_tx.borrow().clone();
+        let mut interval = tokio::time::interval(config.reload.watch_interval);
+        let mut last_modified = modified(&self.config_path).await;
+        loop {
+            tokio::select! {
+                _ = sighup.recv() => {
+                    info!("received SIGHUP, reload config {}", self.config_path.display());
+                    if let Err(err) = self.reload().await {
+                        error!("reload config failed: {}", err);
+                    }
+                }
+                _ = interval.tick(), if config.reload.watch => {
+                    let modified = modified(&self.config_path).await;
+                    if modified.is_none() || modified == last_modified {
+                        continue;
+                    }
+
+                    last_modified = modified;
+                    info!("config {} is changed, reload it", self.config_path.display());
+                    if let Err(err) = self.reload().await {
+                        error!("reload config failed: {}", err);
+                    }
+                }
+                _ = shutdown.recv() => {
+                    // Shutdown the reloader.
+                    info!("reloader shutting down");
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// reload loads and validates the configuration file, and applies the reloadable settings.
+    /// If the configuration is invalid or fails to apply, the active configuration is kept.
+    #[instrument(skip_all)]
+    pub async fn reload(&self) -> Result<()> {
+        let current = self.config_tx.borrow().clone();
+        let new = Config::load_with_overrides(&self.config_path, &self.overrides).await?;
+        let config = Arc::new(current.merge_reloadable(&new));
+
+        // Apply the reloaded configuration, and roll back to the active configuration
+        // if it fails.
+        if let Err(err) = self.apply(&current, &config) {
+            error!(
+                "apply config failed, roll back to the active config: {}",
+                err
+            );
+            self.apply(&config, &current)
+                .unwrap_or_else(|err| error!("roll back config failed: {}", err));
+            return Err(err);
+        }
+
+        // Notify the subscribers to use the reloaded configuration.
+        self.config_tx.send_replace(config);
+
+        let version = self.version.fetch_add(1, Ordering::SeqCst) + 1;
+        let digest = digest(&self.config_path).await.unwrap_or_default();
+        collect_config_version_metrics(version, &digest);
+        info!(
+            "config is reloaded, version: {}, digest: {}",
+            version, digest
+        );
+        Ok(())
+    }
+
+    /// apply applies the changed settings which are not read from the configuration directly.
+    fn apply(&self, current: &Config, config: &Config) -> Result<()> {
+        // Reload the log level.
+        let current_log_level = current.log.level.unwrap_or(self.default_log_level);
+        let log_level = config.log.level.unwrap_or(self.default_log_level);
+        if log_level != current_log_level {
+            reload_log_level(log_level)?;
+        }
+
+        // Reload the rate limiters.
+        if config.download.rate_limit != current.download.rate_limit
+            || config.upload.rate_limit != current.upload.rate_limit
+            || config.proxy.prefetch_rate_limit != current.proxy.prefetch_rate_limit
+            || config.bandwidth_classes != current.bandwidth_classes
+        {
+            for piece in &self.pieces {
+                piece.update_rate_limits(config);
+            }
+
+            info!(
+                "rate limits are reloaded, download: {}, upload: {}, prefetch: {}, bandwidth classes: {}",
+                config.download.rate_limit,
+                config.upload.rate_limit,
+                config.proxy.prefetch_rate_limit,
+                config.bandwidth_classes.len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// modified returns the last modified time of the file.
+async fn modified(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.ok()?.modified().ok()
+}
+
+/// digest returns the digest of the file content.
+async fn digest(path: &Path) -> Result<String> {
+    let content = tokio::fs::read(path).await?;
+    Ok(blake3::hash(&content).to_hex().to_string())
+}
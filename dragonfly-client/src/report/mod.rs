@@ -0,0 +1,511 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_api::common::v2::{Piece, TrafficType};
+use dragonfly_api::errordetails::v2::Backend;
+use dragonfly_client_core::Error;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tonic::Code;
+
+/// EXIT_CODES_HELP is the help of the exit codes for the long about of the command line.
+pub const EXIT_CODES_HELP: &str = r#"Exit Codes:
+  0  Succeeded.
+  1  Unknown error.
+  2  Invalid arguments.
+  3  Failed to connect dfdaemon.
+  4  Backend error, e.g. the source returns the bad status code.
+  5  Verification failed, e.g. the digest or the content length mismatch.
+  6  Timeout.
+  7  Task is not found.
+  8  Some files failed to download in batch or directory mode."#;
+
+/// OutputFormat is the format of the command line output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Text prints the progress bar and the colored message for human.
+    #[default]
+    Text,
+
+    /// Json prints the events as JSON lines to stdout for machine.
+    Json,
+}
+
+/// ErrorClass is the class of the error, each class has a stable exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorClass {
+    /// Unknown is the error not classified.
+    Unknown,
+
+    /// InvalidArgument is the error of the invalid command line arguments.
+    InvalidArgument,
+
+    /// Connection is the error of connecting dfdaemon.
+    Connection,
+
+    /// Backend is the error returned by the source.
+    Backend,
+
+    /// Verification is the error of the digest or the content length mismatch.
+    Verification,
+
+    /// Timeout is the error of the timeout.
+    Timeout,
+
+    /// NotFound is the error of the task not found.
+    NotFound,
+
+    /// PartialFailure is the error of some files failed in batch or directory mode.
+    PartialFailure,
+}
+
+/// ErrorClass implements the classification of the error.
+impl ErrorClass {
+    /// from_error classifies the error of the command.
+    pub fn from_error(err: &Error) -> Self {
+        match err {
+            Error::ValidationError(_)
+            | Error::InvalidParameter
+            | Error::Unsupported(_)
+            | Error::MaxDownloadFilesExceeded(_) => ErrorClass::InvalidArgument,
+            Error::BackendError(_) => ErrorClass::Backend,
+            Error::DigestMismatch(_, _) | Error::ContentLengthMismatch(_, _) => {
+                ErrorClass::Verification
+            }
+            Error::DownloadFilesFailed(_, _) => ErrorClass::PartialFailure,
+            Error::TonicTransportError(_) => ErrorClass::Connection,
+            Error::TonicStatus(status) => {
+                if backend_from_status(status).is_some() {
+                    return ErrorClass::Backend;
+                }
+
+                match status.code() {
+                    Code::InvalidArgument | Code::FailedPrecondition => ErrorClass::InvalidArgument,
+                    Code::DeadlineExceeded => ErrorClass::Timeout,
+                    Code::NotFound => ErrorClass::NotFound,
+                    Code::Unavailable => ErrorClass::Connection,
+                    Code::DataLoss => ErrorClass::Verification,
+                    _ => ErrorClass::Unknown,
+                }
+            }
+            _ => ErrorClass::Unknown,
+        }
+    }
+
+    /// exit_code returns the exit code of the error class.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorClass::Unknown => 1,
+            ErrorClass::InvalidArgument => 2,
+            ErrorClass::Connection => 3,
+            ErrorClass::Backend => 4,
+            ErrorClass::Verification => 5,
+            ErrorClass::Timeout => 6,
+            ErrorClass::NotFound => 7,
+            ErrorClass::PartialFailure => 8,
+        }
+    }
+}
+
+/// ErrorReport is the machine-readable error.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorReport {
+    /// class is the class of the error.
+    pub class: ErrorClass,
+
+    /// message is the message of the error.
+    pub message: String,
+
+    /// code is the grpc code of the error returned by dfdaemon.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+
+    /// status_code is the status code returned by the source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<i32>,
+
+    /// header is the header returned by the source.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub header: HashMap<String, String>,
+}
+
+/// ErrorReport implements the conversion from the error.
+impl ErrorReport {
+    /// new creates a new error report with the error class.
+    pub fn new(class: ErrorClass, err: &Error) -> Self {
+        let mut report = Self {
+            class,
+            message: err.to_string(),
+            code: None,
+            status_code: None,
+            header: HashMap::new(),
+        };
+
+        match err {
+            Error::TonicStatus(status) => {
+                report.code = Some(format!("{:?}", status.code()));
+                report.message = status.message().to_string();
+                if let Some(backend) = backend_from_status(status) {
+                    report.message = backend.message;
+                    report.status_code = backend.status_code;
+                    report.header = backend.header;
+                }
+            }
+            Error::BackendError(err) => {
+                report.message = err.message.clone();
+                report.status_code = err.status_code.map(|code| code.as_u16() as i32);
+                report.header = err
+                    .header
+                    .iter()
+                    .flatten()
+                    .map(|(key, value)| {
+                        (
+                            key.to_string(),
+                            value.to_str().unwrap_or_default().to_string(),
+                        )
+                    })
+                    .collect();
+            }
+            _ => {}
+        }
+
+        report
+    }
+}
+
+/// From implements the conversion from the error with the classified error class.
+impl From<&Error> for ErrorReport {
+    fn from(err: &Error) -> Self {
+        Self::new(ErrorClass::from_error(err), err)
+    }
+}
+
+/// PieceStats is the statistics of the downloaded pieces by the traffic type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PieceStats {
+    /// parent_count is the count of the pieces downloaded from the remote parents.
+    pub parent_count: u64,
+
+    /// parent_length is the length of the pieces downloaded from the remote parents.
+    pub parent_length: u64,
+
+    /// source_count is the count of the pieces downloaded from the source.
+    pub source_count: u64,
+
+    /// source_length is the length of the pieces downloaded from the source.
+    pub source_length: u64,
+
+    /// local_count is the count of the pieces hit in the local cache.
+    pub local_count: u64,
+
+    /// local_length is the length of the pieces hit in the local cache.
+    pub local_length: u64,
+}
+
+/// PieceStats implements the statistics of the pieces.
+impl PieceStats {
+    /// record records the finished piece by its traffic type.
+    pub fn record(&mut self, piece: &Piece) {
+        let traffic_type = piece
+            .traffic_type
+            .and_then(|traffic_type| TrafficType::try_from(traffic_type).ok());
+
+        match traffic_type {
+            Some(TrafficType::BackToSource) => {
+                self.source_count += 1;
+                self.source_length += piece.length;
+            }
+            Some(TrafficType::RemotePeer) => {
+                self.parent_count += 1;
+                self.parent_length += piece.length;
+            }
+            Some(TrafficType::LocalPeer) => {
+                self.local_count += 1;
+                self.local_length += piece.length;
+            }
+            // The piece without the traffic type is downloaded from the parent if it has a
+            // parent, otherwise it is hit in the local cache.
+            None if piece.parent_id.is_some() => {
+                self.parent_count += 1;
+                self.parent_length += piece.length;
+            }
+            None => {
+                self.local_count += 1;
+                self.local_length += piece.length;
+            }
+        }
+    }
+}
+
+/// Event is the machine-readable event printed as a JSON line to stdout.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum Event {
+    /// Started is the event when the task is started to download.
+    #[serde(rename_all = "camelCase")]
+    Started {
+        /// task_id is the id of the task.
+        task_id: String,
+
+        /// peer_id is the id of the peer.
+        peer_id: String,
+
+        /// content_length is the content length of the task.
+        content_length: u64,
+    },
+
+    /// Progress is the event when a piece of the task is downloaded.
+    #[serde(rename_all = "camelCase")]
+    Progress {
+        /// task_id is the id of the task.
+        task_id: String,
+
+        /// piece_number is the number of the downloaded piece.
+        piece_number: u32,
+
+        /// downloaded_length is the downloaded length of the task.
+        downloaded_length: u64,
+
+        /// content_length is the content length of the task.
+        content_length: u64,
+    },
+
+    /// Result is the event when the command is finished.
+    Result(Box<ResultReport>),
+}
+
+/// Event implements the printing of the event.
+impl Event {
+    /// print prints the event as a JSON line to stdout.
+    pub fn print(&self) {
+        match serde_json::to_string(self) {
+            Ok(line) => println!("{}", line),
+            Err(err) => eprintln!("serialize event failed: {}", err),
+        }
+    }
+}
+
+/// ResultReport is the final result of the command.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultReport {
+    /// success indicates whether the command is succeeded.
+    pub success: bool,
+
+    /// task_id is the id of the task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+
+    /// url is the download url of the task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// path is the local path of the imported or exported file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// content_length is the content length of the task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_length: Option<u64>,
+
+    /// cost_ms is the cost of the command in milliseconds.
+    pub cost_ms: u64,
+
+    /// pieces is the statistics of the downloaded pieces.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pieces: Option<PieceStats>,
+
+    /// task is the detail of the task, e.g. the result of the stat command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<serde_json::Value>,
+
+    /// files are the results of the files in batch or directory mode.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<ResultReport>,
+
+    /// error is the error of the failed command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorReport>,
+
+    /// exit_code is the exit code of the command.
+    pub exit_code: i32,
+}
+
+/// ResultReport implements the constructors of the result.
+impl ResultReport {
+    /// succeeded creates a result of the succeeded command.
+    pub fn succeeded(cost: Duration) -> Self {
+        Self {
+            success: true,
+            cost_ms: cost.as_millis() as u64,
+            ..Default::default()
+        }
+    }
+
+    /// failed creates a result of the failed command with the error class.
+    pub fn failed(class: ErrorClass, err: &Error, cost: Duration) -> Self {
+        Self {
+            success: false,
+            cost_ms: cost.as_millis() as u64,
+            error: Some(ErrorReport::new(class, err)),
+            exit_code: class.exit_code(),
+            ..Default::default()
+        }
+    }
+
+    /// print prints the result as the result event.
+    pub fn print(self) {
+        Event::Result(Box::new(self)).print();
+    }
+}
+
+/// backend_from_status parses the backend error details from the grpc status.
+fn backend_from_status(status: &tonic::Status) -> Option<Backend> {
+    serde_json::from_slice::<Backend>(status.details()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dragonfly_client_core::error::BackendError;
+
+    #[test]
+    fn should_classify_errors() {
+        let test_cases = vec![
+            (
+                Error::ValidationError("invalid".to_string()),
+                ErrorClass::InvalidArgument,
+                2,
+            ),
+            (
+                Error::DigestMismatch("a".to_string(), "b".to_string()),
+                ErrorClass::Verification,
+                5,
+            ),
+            (
+                Error::TonicStatus(tonic::Status::deadline_exceeded("timeout")),
+                ErrorClass::Timeout,
+                6,
+            ),
+            (
+                Error::TonicStatus(tonic::Status::not_found("not found")),
+                ErrorClass::NotFound,
+                7,
+            ),
+            (
+                Error::DownloadFilesFailed(1, 2),
+                ErrorClass::PartialFailure,
+                8,
+            ),
+            (
+                Error::Unknown("unknown".to_string()),
+                ErrorClass::Unknown,
+                1,
+            ),
+        ];
+
+        for (err, class, exit_code) in test_cases {
+            assert_eq!(ErrorClass::from_error(&err), class);
+            assert_eq!(class.exit_code(), exit_code);
+        }
+    }
+
+    #[test]
+    fn should_report_backend_error() {
+        let backend = Backend {
+            message: "forbidden".to_string(),
+            header: HashMap::from([("server".to_string(), "nginx".to_string())]),
+            status_code: Some(403),
+        };
+        let status = tonic::Status::with_details(
+            Code::Internal,
+            "download failed",
+            serde_json::to_vec(&backend).unwrap().into(),
+        );
+
+        let report = ErrorReport::from(&Error::TonicStatus(status));
+        assert_eq!(report.class, ErrorClass::Backend);
+        assert_eq!(report.message, "forbidden");
+        assert_eq!(report.code.as_deref(), Some("Internal"));
+        assert_eq!(report.status_code, Some(403));
+        assert_eq!(report.header.get("server").unwrap(), "nginx");
+
+        let report = ErrorReport::from(&Error::BackendError(Box::new(BackendError {
+            message: "not found".to_string(),
+            status_code: Some(reqwest::StatusCode::NOT_FOUND),
+            header: None,
+        })));
+        assert_eq!(report.class, ErrorClass::Backend);
+        assert_eq!(report.status_code, Some(404));
+        assert!(report.header.is_empty());
+    }
+
+    #[test]
+    fn should_record_piece_stats() {
+        let mut stats = PieceStats::default();
+        let pieces = vec![
+            (Some(TrafficType::BackToSource as i32), None, 10),
+            (Some(TrafficType::RemotePeer as i32), Some("parent"), 20),
+            (None, Some("parent"), 30),
+            (Some(TrafficType::LocalPeer as i32), None, 40),
+        ];
+
+        for (traffic_type, parent_id, length) in pieces {
+            stats.record(&Piece {
+                traffic_type,
+                parent_id: parent_id.map(|parent_id| parent_id.to_string()),
+                length,
+                ..Default::default()
+            });
+        }
+
+        assert_eq!(
+            stats,
+            PieceStats {
+                parent_count: 2,
+                parent_length: 50,
+                source_count: 1,
+                source_length: 10,
+                local_count: 1,
+                local_length: 40,
+            }
+        );
+    }
+
+    #[test]
+    fn should_serialize_events() {
+        let event = Event::Started {
+            task_id: "task".to_string(),
+            peer_id: "peer".to_string(),
+            content_length: 1024,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"started","taskId":"task","peerId":"peer","contentLength":1024}"#
+        );
+
+        let mut report = ResultReport::succeeded(Duration::from_millis(1500));
+        report.task_id = Some("task".to_string());
+        assert_eq!(
+            serde_json::to_string(&Event::Result(Box::new(report))).unwrap(),
+            r#"{"event":"result","success":true,"taskId":"task","costMs":1500,"exitCode":0}"#
+        );
+    }
+}
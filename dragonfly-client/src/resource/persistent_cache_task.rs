@@ -43,7 +43,10 @@ use dragonfly_client_core::{
     Result as ClientResult,
 };
 use dragonfly_client_storage::{metadata, Storage};
-use dragonfly_client_util::id_generator::IDGenerator;
+use dragonfly_client_util::{
+    digest::Digest,
+    id_generator::{persistent_cache_task_id_from_digest, IDGenerator},
+};
 use std::path::{Path, PathBuf};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
@@ -54,7 +57,7 @@ use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncSeekExt, BufReader, SeekFrom};
 use tokio::sync::{
     mpsc::{self, Sender},
-    Semaphore,
+    watch, Semaphore,
 };
 use tokio::task::JoinSet;
 use tokio_stream::{wrappers::ReceiverStream, StreamExt};
@@ -68,6 +71,9 @@ pub struct PersistentCacheTask {
     /// config is the configuration of the dfdaemon.
     config: Arc<Config>,
 
+    /// config_rx receives the reloaded configuration of the dfdaemon.
+    config_rx: watch::Receiver<Arc<Config>>,
+
     /// id_generator is the id generator.
     pub id_generator: Arc<IDGenerator>,
 
@@ -87,6 +93,7 @@ impl PersistentCacheTask {
     #[instrument(skip_all)]
     pub fn new(
         config: Arc<Config>,
+        config_rx: watch::Receiver<Arc<Config>>,
         id_generator: Arc<IDGenerator>,
         storage: Arc<Storage>,
         scheduler_client: Arc<SchedulerClient>,
@@ -102,6 +109,7 @@ impl PersistentCacheTask {
 
         Ok(Self {
             config,
+            config_rx,
             id_generator,
             storage,
             scheduler_client,
@@ -115,7 +123,40 @@ impl PersistentCacheTask {
         self.storage.get_persistent_cache_task(task_id)
     }
 
-    /// create_persistent creates a persistent cache task from local.
+    /// id generates the id of the persistent cache task imported from the file. If the id is
+    /// generated by the content digest, the content digest is returned as well.
+    #[instrument(skip_all)]
+    pub fn id(
+        &self,
+        path: &Path,
+        tag: Option<&str>,
+        application: Option<&str>,
+    ) -> ClientResult<(String, Option<Digest>)> {
+        match self
+            .config
+            .persistent_cache_task
+            .id_algorithm
+            .digest_algorithm()
+        {
+            Some(algorithm) => {
+                let digest = self
+                    .id_generator
+                    .persistent_cache_task_digest(path, algorithm)?;
+                Ok((persistent_cache_task_id_from_digest(&digest)?, Some(digest)))
+            }
+            None => Ok((
+                self.id_generator.persistent_cache_task_id(
+                    &path.to_path_buf(),
+                    tag,
+                    application,
+                )?,
+                None,
+            )),
+        }
+    }
+
+    /// create_persistent creates a persistent cache task from local, the digest is recorded in
+    /// the metadata if the task id is generated by the content digest.
     #[instrument(skip_all)]
     pub async fn create_persistent(
         &self,
@@ -123,6 +164,7 @@ impl PersistentCacheTask {
         host_id: &str,
         peer_id: &str,
         path: PathBuf,
+        digest: Option<String>,
         request: UploadPersistentCacheTaskRequest,
     ) -> ClientResult<CommonPersistentCacheTask> {
         // Convert prost_wkt_types::Duration to std::time::Duration.
@@ -178,7 +220,15 @@ impl PersistentCacheTask {
         }
 
         self.storage
-            .create_persistent_cache_task_started(task_id, ttl, piece_length, content_length)
+            .create_persistent_cache_task_started(
+                task_id,
+                ttl,
+                piece_length,
+                content_length,
+                digest,
+                self.piece
+                    .bandwidth_class(request.application.as_deref(), request.tag.as_deref()),
+            )
             .await?;
 
         info!("upload persistent cache task started");
@@ -456,6 +506,11 @@ impl PersistentCacheTask {
             response.piece_length,
             response.content_length,
             created_at.naive_utc(),
+            // The scheduler does not carry the content digest of the persistent cache task, so
+            // the replica records no digest and the export skips the verification of it.
+            None,
+            self.piece
+                .bandwidth_class(response.application.as_deref(), response.tag.as_deref()),
         )
     }
 
@@ -710,9 +765,16 @@ impl PersistentCacheTask {
             })?;
         info!("announced persistent cache peer has been connected");
 
-        let out_stream = response
-            .into_inner()
-            .timeout(self.config.scheduler.schedule_timeout);
+        // Get the scheduler timeouts from the reloaded configuration.
+        let (schedule_timeout, max_schedule_count) = {
+            let config = self.config_rx.borrow();
+            (
+                config.scheduler.schedule_timeout,
+                config.scheduler.max_schedule_count,
+            )
+        };
+
+        let out_stream = response.into_inner().timeout(schedule_timeout);
         tokio::pin!(out_stream);
 
         while let Some(message) = out_stream.try_next().await.inspect_err(|err| {
@@ -720,7 +782,7 @@ impl PersistentCacheTask {
         })? {
             // Check if the schedule count is exceeded.
             schedule_count += 1;
-            if schedule_count >= self.config.scheduler.max_schedule_count {
+            if schedule_count >= max_schedule_count {
                 in_stream_tx
                     .send_timeout(
                         AnnouncePersistentCachePeerRequest {
@@ -974,6 +1036,7 @@ impl PersistentCacheTask {
         // Initialize the piece collector.
         let piece_collector = piece_collector::PersistentCachePieceCollector::new(
             self.config.clone(),
+            self.piece.pool(),
             host_id,
             task_id,
             interested_pieces.clone(),
@@ -1023,6 +1086,7 @@ impl PersistentCacheTask {
                 in_stream_tx: Sender<AnnouncePersistentCachePeerRequest>,
                 interrupt: Arc<AtomicBool>,
                 finished_pieces: Arc<Mutex<Vec<metadata::Piece>>>,
+                bandwidth_class: Option<String>,
             ) -> ClientResult<metadata::Piece> {
                 // Limit the concurrent download count.
                 let _permit = semaphore.acquire().await.unwrap();
@@ -1043,6 +1107,7 @@ impl PersistentCacheTask {
                         length,
                         parent.clone(),
                         false,
+                        bandwidth_class.as_deref(),
                     )
                     .await
                     .map_err(|err| {
@@ -1175,6 +1240,7 @@ impl PersistentCacheTask {
                     in_stream_tx.clone(),
                     interrupt.clone(),
                     finished_pieces.clone(),
+                    task.bandwidth_class.clone(),
                 )
                 .in_current_span(),
             );
@@ -15,22 +15,26 @@
  */
 
 use super::*;
+use crate::grpc::pool::ChannelPool;
 use crate::metrics::{
     collect_backend_request_failure_metrics, collect_backend_request_finished_metrics,
     collect_backend_request_started_metrics, collect_download_piece_traffic_metrics,
     collect_upload_piece_traffic_metrics,
 };
+use crate::resource::priority_scheduler::{PriorityPermit, PriorityScheduler};
 use chrono::Utc;
-use dragonfly_api::common::v2::{Hdfs, ObjectStorage, Range, TrafficType};
+use dragonfly_api::common::v2::{Hdfs, ObjectStorage, Priority, Range, TaskType, TrafficType};
 use dragonfly_client_backend::{BackendFactory, GetRequest};
-use dragonfly_client_config::dfdaemon::Config;
+use dragonfly_client_config::dfdaemon::{BandwidthClass, Config};
 use dragonfly_client_core::{error::BackendError, Error, Result};
 use dragonfly_client_storage::{metadata, Storage};
 use dragonfly_client_util::id_generator::IDGenerator;
 use leaky_bucket::RateLimiter;
+use lru::LruCache;
 use reqwest::header::{self, HeaderMap};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncRead, AsyncReadExt};
 use tracing::{error, info, instrument, Span};
@@ -47,6 +51,51 @@ const MIN_PIECE_LENGTH: u64 = 4 * 1024 * 1024;
 /// MAX_PIECE_LENGTH is the maximum piece length.
 const MAX_PIECE_LENGTH: u64 = 16 * 1024 * 1024;
 
+/// UPLOAD_BANDWIDTH_CLASS_CACHE_CAPACITY is the max number of the uploading tasks whose
+/// bandwidth classes are cached.
+const UPLOAD_BANDWIDTH_CLASS_CACHE_CAPACITY: usize = 1024;
+
+/// new_rate_limiter creates a new rate limiter with the rate in bps(bytes per second).
+fn new_rate_limiter(rate: u64) -> RateLimiter {
+    RateLimiter::builder()
+        .initial(rate as usize)
+        .refill(rate as usize)
+        .max(rate as usize)
+        .interval(Duration::from_secs(1))
+        .fair(false)
+        .build()
+}
+
+/// BandwidthClassRateLimiter is the rate limiters of the bandwidth class.
+struct BandwidthClassRateLimiter {
+    /// class is the configuration of the bandwidth class.
+    class: BandwidthClass,
+
+    /// download_rate_limiter is the rate limiter of the download speed of the class.
+    download_rate_limiter: Arc<RateLimiter>,
+
+    /// upload_rate_limiter is the rate limiter of the upload speed of the class.
+    upload_rate_limiter: Arc<RateLimiter>,
+}
+
+/// new_bandwidth_class_rate_limiters creates the rate limiters of the bandwidth classes.
+fn new_bandwidth_class_rate_limiters(
+    classes: &[BandwidthClass],
+) -> Vec<Arc<BandwidthClassRateLimiter>> {
+    classes
+        .iter()
+        .map(|class| {
+            Arc::new(BandwidthClassRateLimiter {
+                class: class.clone(),
+                download_rate_limiter: Arc::new(new_rate_limiter(
+                    class.download_rate_limit.as_u64(),
+                )),
+                upload_rate_limiter: Arc::new(new_rate_limiter(class.upload_rate_limit.as_u64())),
+            })
+        })
+        .collect()
+}
+
 /// PieceLengthStrategy sets the optimization strategy of piece length.
 pub enum PieceLengthStrategy {
     /// OptimizeByFileLength optimizes the piece length by the file length.
@@ -64,6 +113,9 @@ pub struct Piece {
     /// storage is the local storage.
     storage: Arc<Storage>,
 
+    /// pool is the pool of the grpc channels to the parents.
+    pool: Arc<ChannelPool>,
+
     /// downloader_factory is the piece downloader factory.
     downloader_factory: Arc<piece_downloader::DownloaderFactory>,
 
@@ -71,13 +123,24 @@ pub struct Piece {
     backend_factory: Arc<BackendFactory>,
 
     /// download_rate_limiter is the rate limiter of the download speed in bps(bytes per second).
-    download_rate_limiter: Arc<RateLimiter>,
+    download_rate_limiter: RwLock<Arc<RateLimiter>>,
 
     /// upload_rate_limiter is the rate limiter of the upload speed in bps(bytes per second).
-    upload_rate_limiter: Arc<RateLimiter>,
+    upload_rate_limiter: RwLock<Arc<RateLimiter>>,
 
     /// prefetch_rate_limiter is the rate limiter of the prefetch speed in bps(bytes per second).
-    prefetch_rate_limiter: Arc<RateLimiter>,
+    prefetch_rate_limiter: RwLock<Arc<RateLimiter>>,
+
+    /// priority_scheduler schedules the piece downloads across the tasks by the priority.
+    priority_scheduler: PriorityScheduler,
+
+    /// bandwidth_class_rate_limiters are the rate limiters of the bandwidth classes, they nest
+    /// within the download and upload rate limiters.
+    bandwidth_class_rate_limiters: RwLock<Vec<Arc<BandwidthClassRateLimiter>>>,
+
+    /// upload_bandwidth_classes caches the bandwidth classes of the uploading tasks by the
+    /// task id, so the task metadata is not read for every uploaded piece.
+    upload_bandwidth_classes: Mutex<LruCache<String, Option<String>>>,
 }
 
 /// Piece implements the piece manager.
@@ -90,45 +153,174 @@ impl Piece {
         storage: Arc<Storage>,
         backend_factory: Arc<BackendFactory>,
     ) -> Result<Self> {
+        let pool = Arc::new(ChannelPool::new(
+            config.download.parent_selector.capacity,
+            config.network.connection_pool.idle_timeout,
+        ));
+
         Ok(Self {
             config: config.clone(),
             id_generator,
             storage,
+            pool: pool.clone(),
             downloader_factory: Arc::new(piece_downloader::DownloaderFactory::new(
                 config.storage.server.protocol.as_str(),
                 config.clone(),
+                pool,
             )?),
             backend_factory,
-            download_rate_limiter: Arc::new(
-                RateLimiter::builder()
-                    .initial(config.download.rate_limit.as_u64() as usize)
-                    .refill(config.download.rate_limit.as_u64() as usize)
-                    .max(config.download.rate_limit.as_u64() as usize)
-                    .interval(Duration::from_secs(1))
-                    .fair(false)
-                    .build(),
-            ),
-            upload_rate_limiter: Arc::new(
-                RateLimiter::builder()
-                    .initial(config.upload.rate_limit.as_u64() as usize)
-                    .refill(config.upload.rate_limit.as_u64() as usize)
-                    .max(config.upload.rate_limit.as_u64() as usize)
-                    .interval(Duration::from_secs(1))
-                    .fair(false)
-                    .build(),
-            ),
-            prefetch_rate_limiter: Arc::new(
-                RateLimiter::builder()
-                    .initial(config.proxy.prefetch_rate_limit.as_u64() as usize)
-                    .refill(config.proxy.prefetch_rate_limit.as_u64() as usize)
-                    .max(config.proxy.prefetch_rate_limit.as_u64() as usize)
-                    .interval(Duration::from_secs(1))
-                    .fair(false)
-                    .build(),
-            ),
+            download_rate_limiter: RwLock::new(Arc::new(new_rate_limiter(
+                config.download.rate_limit.as_u64(),
+            ))),
+            upload_rate_limiter: RwLock::new(Arc::new(new_rate_limiter(
+                config.upload.rate_limit.as_u64(),
+            ))),
+            prefetch_rate_limiter: RwLock::new(Arc::new(new_rate_limiter(
+                config.proxy.prefetch_rate_limit.as_u64(),
+            ))),
+            priority_scheduler: PriorityScheduler::new(config.download.priority_scheduler.clone()),
+            bandwidth_class_rate_limiters: RwLock::new(new_bandwidth_class_rate_limiters(
+                &config.bandwidth_classes,
+            )),
+            upload_bandwidth_classes: Mutex::new(LruCache::new(
+                NonZeroUsize::new(UPLOAD_BANDWIDTH_CLASS_CACHE_CAPACITY).unwrap(),
+            )),
         })
     }
 
+    /// update_rate_limits replaces the rate limiters with the rate limits of the reloaded
+    /// configuration, the pieces acquiring the old rate limiters are not affected.
+    pub fn update_rate_limits(&self, config: &Config) {
+        *self.download_rate_limiter.write().unwrap() =
+            Arc::new(new_rate_limiter(config.download.rate_limit.as_u64()));
+        *self.upload_rate_limiter.write().unwrap() =
+            Arc::new(new_rate_limiter(config.upload.rate_limit.as_u64()));
+        *self.prefetch_rate_limiter.write().unwrap() =
+            Arc::new(new_rate_limiter(config.proxy.prefetch_rate_limit.as_u64()));
+        *self.bandwidth_class_rate_limiters.write().unwrap() =
+            new_bandwidth_class_rate_limiters(&config.bandwidth_classes);
+    }
+
+    /// bandwidth_class returns the name of the first bandwidth class matching the application
+    /// and tag of the task.
+    pub fn bandwidth_class(&self, application: Option<&str>, tag: Option<&str>) -> Option<String> {
+        self.bandwidth_class_rate_limiters
+            .read()
+            .unwrap()
+            .iter()
+            .find(|limiter| limiter.class.matches(application, tag))
+            .map(|limiter| limiter.class.name.clone())
+    }
+
+    /// bandwidth_class_rate_limiter returns the rate limiters of the bandwidth class, it is none
+    /// if the task is not in any bandwidth class.
+    fn bandwidth_class_rate_limiter(
+        &self,
+        bandwidth_class: Option<&str>,
+    ) -> Option<Arc<BandwidthClassRateLimiter>> {
+        let bandwidth_class = bandwidth_class?;
+        self.bandwidth_class_rate_limiters
+            .read()
+            .unwrap()
+            .iter()
+            .find(|limiter| limiter.class.name == bandwidth_class)
+            .cloned()
+    }
+
+    /// download_rate_limiter returns the current rate limiter of the download speed.
+    fn download_rate_limiter(&self) -> Arc<RateLimiter> {
+        self.download_rate_limiter.read().unwrap().clone()
+    }
+
+    /// upload_rate_limiter returns the current rate limiter of the upload speed.
+    fn upload_rate_limiter(&self) -> Arc<RateLimiter> {
+        self.upload_rate_limiter.read().unwrap().clone()
+    }
+
+    /// prefetch_rate_limiter returns the current rate limiter of the prefetch speed.
+    fn prefetch_rate_limiter(&self) -> Arc<RateLimiter> {
+        self.prefetch_rate_limiter.read().unwrap().clone()
+    }
+
+    /// acquire_download_rate_limiter acquires the prefetch rate limiter for the prefetch piece,
+    /// otherwise acquires the download rate limiter by the priority of the task.
+    async fn acquire_download_rate_limiter(
+        &self,
+        task_id: &str,
+        length: u64,
+        is_prefetch: bool,
+        priority: Priority,
+        bandwidth_class: Option<&str>,
+    ) -> PriorityPermit<'_> {
+        // Acquire the rate limiter of the bandwidth class first, so the piece waiting for its
+        // class does not hold the global rate limiter.
+        if let Some(limiter) = self.bandwidth_class_rate_limiter(bandwidth_class) {
+            limiter.download_rate_limiter.acquire(length as usize).await;
+        }
+
+        if is_prefetch {
+            return self
+                .priority_scheduler
+                .acquire_prefetch(length, self.prefetch_rate_limiter())
+                .await;
+        }
+
+        self.priority_scheduler
+            .acquire(task_id, priority, length, self.download_rate_limiter())
+            .await
+    }
+
+    /// upload_bandwidth_class returns the bandwidth class of the uploading task. The piece is
+    /// uploaded by the request of the other peer without the task, so the bandwidth class is
+    /// read from the task metadata once and cached by the task id.
+    fn upload_bandwidth_class(&self, task_id: &str, task_type: TaskType) -> Option<String> {
+        if let Some(bandwidth_class) = self.upload_bandwidth_classes.lock().unwrap().get(task_id) {
+            return bandwidth_class.clone();
+        }
+
+        let bandwidth_class = match task_type {
+            TaskType::PersistentCache => self
+                .storage
+                .get_persistent_cache_task(task_id)
+                .map(|task| task.map(|task| task.bandwidth_class)),
+            _ => self
+                .storage
+                .get_task(task_id)
+                .map(|task| task.map(|task| task.bandwidth_class)),
+        };
+
+        match bandwidth_class {
+            Ok(Some(bandwidth_class)) => {
+                self.upload_bandwidth_classes
+                    .lock()
+                    .unwrap()
+                    .put(task_id.to_string(), bandwidth_class.clone());
+                bandwidth_class
+            }
+            Ok(None) => None,
+            Err(err) => {
+                error!("get task {} failed: {}", task_id, err);
+                None
+            }
+        }
+    }
+
+    /// acquire_upload_rate_limiter acquires the upload rate limiter of the bandwidth class of
+    /// the task and then the global upload rate limiter.
+    async fn acquire_upload_rate_limiter(&self, task_id: &str, task_type: TaskType, length: u64) {
+        let bandwidth_class = self.upload_bandwidth_class(task_id, task_type);
+        if let Some(limiter) = self.bandwidth_class_rate_limiter(bandwidth_class.as_deref()) {
+            limiter.upload_rate_limiter.acquire(length as usize).await;
+        }
+
+        self.upload_rate_limiter().acquire(length as usize).await;
+    }
+
+    /// pool returns the pool of the grpc channels to the parents.
+    pub fn pool(&self) -> Arc<ChannelPool> {
+        self.pool.clone()
+    }
+
     /// id generates a new piece id.
     #[inline]
     #[instrument(skip_all)]
@@ -339,7 +531,8 @@ impl Piece {
 
         // Acquire the upload rate limiter.
         if !disable_rate_limit {
-            self.upload_rate_limiter.acquire(length as usize).await;
+            self.acquire_upload_rate_limiter(task_id, TaskType::Standard, length)
+                .await;
         }
 
         // Upload the piece content.
@@ -370,7 +563,8 @@ impl Piece {
 
         // Acquire the upload rate limiter.
         if !disable_rate_limit {
-            self.upload_rate_limiter.acquire(length as usize).await;
+            self.acquire_upload_rate_limiter(task_id, TaskType::Standard, length)
+                .await;
         }
 
         // Upload the piece content.
@@ -403,10 +597,10 @@ impl Piece {
         if !disable_rate_limit {
             if is_prefetch {
                 // Acquire the prefetch rate limiter.
-                self.prefetch_rate_limiter.acquire(length as usize).await;
+                self.prefetch_rate_limiter().acquire(length as usize).await;
             } else {
                 // Acquire the download rate limiter.
-                self.download_rate_limiter.acquire(length as usize).await;
+                self.download_rate_limiter().acquire(length as usize).await;
             }
         }
 
@@ -433,10 +627,10 @@ impl Piece {
         if !disable_rate_limit {
             if is_prefetch {
                 // Acquire the prefetch rate limiter.
-                self.prefetch_rate_limiter.acquire(length as usize).await;
+                self.prefetch_rate_limiter().acquire(length as usize).await;
             } else {
                 // Acquire the download rate limiter.
-                self.download_rate_limiter.acquire(length as usize).await;
+                self.download_rate_limiter().acquire(length as usize).await;
             }
         }
 
@@ -469,17 +663,17 @@ impl Piece {
         length: u64,
         parent: piece_collector::CollectedParent,
         is_prefetch: bool,
+        priority: Priority,
+        bandwidth_class: Option<&str>,
     ) -> Result<metadata::Piece> {
         // Span record the piece_id.
         Span::current().record("piece_id", piece_id);
 
-        if is_prefetch {
-            // Acquire the prefetch rate limiter.
-            self.prefetch_rate_limiter.acquire(length as usize).await;
-        } else {
-            // Acquire the download rate limiter.
-            self.download_rate_limiter.acquire(length as usize).await;
-        }
+        // Acquire the rate limiter by the priority, the permit is held until the piece is
+        // downloaded.
+        let _permit = self
+            .acquire_download_rate_limiter(task_id, length, is_prefetch, priority, bandwidth_class)
+            .await;
 
         // Record the start of downloading piece.
         let piece = self
@@ -566,19 +760,19 @@ impl Piece {
         length: u64,
         request_header: HeaderMap,
         is_prefetch: bool,
+        priority: Priority,
+        bandwidth_class: Option<&str>,
         object_storage: Option<ObjectStorage>,
         hdfs: Option<Hdfs>,
     ) -> Result<metadata::Piece> {
         // Span record the piece_id.
         Span::current().record("piece_id", piece_id);
 
-        if is_prefetch {
-            // Acquire the prefetch rate limiter.
-            self.prefetch_rate_limiter.acquire(length as usize).await;
-        } else {
-            // Acquire the download rate limiter.
-            self.download_rate_limiter.acquire(length as usize).await;
-        }
+        // Acquire the rate limiter by the priority, the permit is held until the piece is
+        // downloaded.
+        let _permit = self
+            .acquire_download_rate_limiter(task_id, length, is_prefetch, priority, bandwidth_class)
+            .await;
 
         // Record the start of downloading piece.
         let piece = self
@@ -754,7 +948,8 @@ impl Piece {
         Span::current().record("piece_id", piece_id);
 
         // Acquire the upload rate limiter.
-        self.upload_rate_limiter.acquire(length as usize).await;
+        self.acquire_upload_rate_limiter(task_id, TaskType::PersistentCache, length)
+            .await;
 
         // Upload the persistent cache piece content.
         self.storage
@@ -786,10 +981,10 @@ impl Piece {
         if !disable_rate_limit {
             if is_prefetch {
                 // Acquire the prefetch rate limiter.
-                self.prefetch_rate_limiter.acquire(length as usize).await;
+                self.prefetch_rate_limiter().acquire(length as usize).await;
             } else {
                 // Acquire the download rate limiter.
-                self.download_rate_limiter.acquire(length as usize).await;
+                self.download_rate_limiter().acquire(length as usize).await;
             }
         }
 
@@ -822,16 +1017,23 @@ impl Piece {
         length: u64,
         parent: piece_collector::CollectedParent,
         is_prefetch: bool,
+        bandwidth_class: Option<&str>,
     ) -> Result<metadata::Piece> {
         // Span record the piece_id.
         Span::current().record("piece_id", piece_id);
 
+        // Acquire the rate limiter of the bandwidth class first, so the piece waiting for its
+        // class does not hold the global rate limiter.
+        if let Some(limiter) = self.bandwidth_class_rate_limiter(bandwidth_class) {
+            limiter.download_rate_limiter.acquire(length as usize).await;
+        }
+
         if is_prefetch {
             // Acquire the prefetch rate limiter.
-            self.prefetch_rate_limiter.acquire(length as usize).await;
+            self.prefetch_rate_limiter().acquire(length as usize).await;
         } else {
             // Acquire the download rate limiter.
-            self.download_rate_limiter.acquire(length as usize).await;
+            self.download_rate_limiter().acquire(length as usize).await;
         }
 
         // Record the start of downloading piece.
@@ -1021,4 +1223,115 @@ mod tests {
             assert_eq!(last_piece.length, expected_last_piece_length);
         }
     }
+
+    #[tokio::test]
+    async fn should_get_bandwidth_class() {
+        let temp_dir = tempdir().unwrap();
+
+        let mut config = Config::default();
+        config.bandwidth_classes = vec![
+            BandwidthClass {
+                name: "ci".to_string(),
+                applications: vec!["ci".to_string()],
+                ..Default::default()
+            },
+            BandwidthClass {
+                name: "images".to_string(),
+                applications: vec!["ci".to_string()],
+                tags: vec!["images".to_string()],
+                ..Default::default()
+            },
+        ];
+        let config = Arc::new(config);
+
+        let id_generator =
+            IDGenerator::new("127.0.0.1".to_string(), "localhost".to_string(), false);
+        let id_generator = Arc::new(id_generator);
+
+        let storage = Storage::new(
+            config.clone(),
+            temp_dir.path(),
+            temp_dir.path().to_path_buf(),
+        )
+        .await
+        .unwrap();
+        let storage = Arc::new(storage);
+
+        let backend_factory = BackendFactory::new(None).unwrap();
+        let backend_factory = Arc::new(backend_factory);
+
+        let piece = Piece::new(
+            config.clone(),
+            id_generator,
+            storage.clone(),
+            backend_factory,
+        )
+        .unwrap();
+
+        assert_eq!(
+            piece.bandwidth_class(Some("ci"), Some("images")),
+            Some("ci".to_string())
+        );
+        assert_eq!(
+            piece.bandwidth_class(None, Some("images")),
+            Some("images".to_string())
+        );
+        assert_eq!(piece.bandwidth_class(Some("other"), None), None);
+
+        // The task started with the bandwidth class uses the rate limiters of the class.
+        let task = storage
+            .download_task_started(
+                "task",
+                None,
+                None,
+                None,
+                None,
+                false,
+                piece.bandwidth_class(Some("ci"), None),
+            )
+            .unwrap();
+        assert_eq!(
+            piece
+                .bandwidth_class_rate_limiter(task.bandwidth_class.as_deref())
+                .map(|limiter| limiter.class.name.clone()),
+            Some("ci".to_string())
+        );
+        assert!(piece
+            .bandwidth_class_rate_limiter(Some("unknown"))
+            .is_none());
+        assert!(piece.bandwidth_class_rate_limiter(None).is_none());
+
+        // The bandwidth class of the uploading task is cached after the first read.
+        assert_eq!(
+            piece.upload_bandwidth_class("task", TaskType::Standard),
+            Some("ci".to_string())
+        );
+        storage.delete_task("task").await;
+        assert_eq!(
+            piece.upload_bandwidth_class("task", TaskType::Standard),
+            Some("ci".to_string())
+        );
+        assert_eq!(
+            piece.upload_bandwidth_class("unknown", TaskType::Standard),
+            None
+        );
+
+        // The persistent cache task started with the bandwidth class uses the rate limiters of
+        // the class as well.
+        storage
+            .create_persistent_cache_task_started(
+                "persistent-cache-task",
+                Duration::from_secs(3600),
+                1024,
+                1024,
+                None,
+                piece.bandwidth_class(None, Some("images")),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            piece.upload_bandwidth_class("persistent-cache-task", TaskType::PersistentCache),
+            Some("images".to_string())
+        );
+    }
 }
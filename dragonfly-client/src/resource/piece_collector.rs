@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use crate::grpc::dfdaemon_upload::DfdaemonUploadClient;
+use crate::grpc::{dfdaemon_upload::DfdaemonUploadClient, pool::ChannelPool};
 use dashmap::DashMap;
 use dragonfly_api::common::v2::Host;
 use dragonfly_api::dfdaemon::v2::{SyncPersistentCachePiecesRequest, SyncPiecesRequest};
@@ -55,6 +55,9 @@ pub struct PieceCollector {
     /// config is the configuration of the dfdaemon.
     config: Arc<Config>,
 
+    /// pool is the pool of the grpc channels to the parents.
+    pool: Arc<ChannelPool>,
+
     /// host_id is the id of the host.
     host_id: String,
 
@@ -77,6 +80,7 @@ impl PieceCollector {
     #[instrument(skip_all)]
     pub fn new(
         config: Arc<Config>,
+        pool: Arc<ChannelPool>,
         host_id: &str,
         task_id: &str,
         interested_pieces: Vec<metadata::Piece>,
@@ -89,6 +93,7 @@ impl PieceCollector {
 
         Self {
             config,
+            pool,
             task_id: task_id.to_string(),
             host_id: host_id.to_string(),
             parents,
@@ -101,6 +106,7 @@ impl PieceCollector {
     #[instrument(skip_all)]
     pub async fn run(&self) -> Receiver<CollectedPiece> {
         let config = self.config.clone();
+        let pool = self.pool.clone();
         let host_id = self.host_id.clone();
         let task_id = self.task_id.clone();
         let parents = self.parents.clone();
@@ -112,6 +118,7 @@ impl PieceCollector {
             async move {
                 Self::collect_from_parents(
                     config,
+                    pool,
                     &host_id,
                     &task_id,
                     parents,
@@ -136,6 +143,7 @@ impl PieceCollector {
     #[instrument(skip_all)]
     async fn collect_from_parents(
         config: Arc<Config>,
+        pool: Arc<ChannelPool>,
         host_id: &str,
         task_id: &str,
         parents: Vec<CollectedParent>,
@@ -150,6 +158,7 @@ impl PieceCollector {
             #[allow(clippy::too_many_arguments)]
             async fn sync_pieces(
                 config: Arc<Config>,
+                pool: Arc<ChannelPool>,
                 host_id: String,
                 task_id: String,
                 parent: CollectedParent,
@@ -167,16 +176,19 @@ impl PieceCollector {
                     Error::InvalidPeer(parent.id.clone())
                 })?;
 
-                // Create a dfdaemon client.
-                let dfdaemon_upload_client =
-                    DfdaemonUploadClient::new(config, format!("http://{}:{}", host.ip, host.port))
-                        .await
-                        .inspect_err(|err| {
-                            error!(
-                                "create dfdaemon upload client from parent {} failed: {}",
-                                parent.id, err
-                            );
-                        })?;
+                // Create a dfdaemon client by the pooled channel.
+                let dfdaemon_upload_client = DfdaemonUploadClient::new_with_pool(
+                    config,
+                    pool,
+                    format!("http://{}:{}", host.ip, host.port),
+                )
+                .await
+                .inspect_err(|err| {
+                    error!(
+                        "create dfdaemon upload client from parent {} failed: {}",
+                        parent.id, err
+                    );
+                })?;
 
                 let response = dfdaemon_upload_client
                     .sync_pieces(SyncPiecesRequest {
@@ -244,6 +256,7 @@ impl PieceCollector {
             join_set.spawn(
                 sync_pieces(
                     config.clone(),
+                    pool.clone(),
                     host_id.to_string(),
                     task_id.to_string(),
                     parent.clone(),
@@ -287,6 +300,9 @@ pub struct PersistentCachePieceCollector {
     /// config is the configuration of the dfdaemon.
     config: Arc<Config>,
 
+    /// pool is the pool of the grpc channels to the parents.
+    pool: Arc<ChannelPool>,
+
     /// host_id is the id of the host.
     host_id: String,
 
@@ -309,6 +325,7 @@ impl PersistentCachePieceCollector {
     #[instrument(skip_all)]
     pub fn new(
         config: Arc<Config>,
+        pool: Arc<ChannelPool>,
         host_id: &str,
         task_id: &str,
         interested_pieces: Vec<metadata::Piece>,
@@ -321,6 +338,7 @@ impl PersistentCachePieceCollector {
 
         Self {
             config,
+            pool,
             task_id: task_id.to_string(),
             host_id: host_id.to_string(),
             parents,
@@ -333,6 +351,7 @@ impl PersistentCachePieceCollector {
     #[instrument(skip_all)]
     pub async fn run(&self) -> Receiver<CollectedPiece> {
         let config = self.config.clone();
+        let pool = self.pool.clone();
         let host_id = self.host_id.clone();
         let task_id = self.task_id.clone();
         let parents = self.parents.clone();
@@ -344,6 +363,7 @@ impl PersistentCachePieceCollector {
             async move {
                 Self::collect_from_parents(
                     config,
+                    pool,
                     &host_id,
                     &task_id,
                     parents,
@@ -368,6 +388,7 @@ impl PersistentCachePieceCollector {
     #[instrument(skip_all)]
     async fn collect_from_parents(
         config: Arc<Config>,
+        pool: Arc<ChannelPool>,
         host_id: &str,
         task_id: &str,
         parents: Vec<CollectedParent>,
@@ -382,6 +403,7 @@ impl PersistentCachePieceCollector {
             #[allow(clippy::too_many_arguments)]
             async fn sync_pieces(
                 config: Arc<Config>,
+                pool: Arc<ChannelPool>,
                 host_id: String,
                 task_id: String,
                 parent: CollectedParent,
@@ -399,16 +421,19 @@ impl PersistentCachePieceCollector {
                     Error::InvalidPeer(parent.id.clone())
                 })?;
 
-                // Create a dfdaemon client.
-                let dfdaemon_upload_client =
-                    DfdaemonUploadClient::new(config, format!("http://{}:{}", host.ip, host.port))
-                        .await
-                        .inspect_err(|err| {
-                            error!(
-                                "create dfdaemon upload client from parent {} failed: {}",
-                                parent.id, err
-                            );
-                        })?;
+                // Create a dfdaemon client by the pooled channel.
+                let dfdaemon_upload_client = DfdaemonUploadClient::new_with_pool(
+                    config,
+                    pool,
+                    format!("http://{}:{}", host.ip, host.port),
+                )
+                .await
+                .inspect_err(|err| {
+                    error!(
+                        "create dfdaemon upload client from parent {} failed: {}",
+                        parent.id, err
+                    );
+                })?;
 
                 let response = dfdaemon_upload_client
                     .sync_persistent_cache_pieces(SyncPersistentCachePiecesRequest {
@@ -482,6 +507,7 @@ impl PersistentCachePieceCollector {
             join_set.spawn(
                 sync_pieces(
                     config.clone(),
+                    pool.clone(),
                     host_id.to_string(),
                     task_id.to_string(),
                     parent.clone(),
@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use crate::grpc::dfdaemon_upload::DfdaemonUploadClient;
+use crate::grpc::{dfdaemon_upload::DfdaemonUploadClient, pool::ChannelPool};
 use dragonfly_api::dfdaemon::v2::{DownloadPersistentCachePieceRequest, DownloadPieceRequest};
 use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::{Error, Result};
@@ -56,9 +56,9 @@ pub struct DownloaderFactory {
 impl DownloaderFactory {
     /// new returns a new DownloadFactory.
     #[instrument(skip_all)]
-    pub fn new(protocol: &str, config: Arc<Config>) -> Result<Self> {
+    pub fn new(protocol: &str, config: Arc<Config>, pool: Arc<ChannelPool>) -> Result<Self> {
         let downloader = match protocol {
-            "grpc" => Arc::new(GRPCDownloader::new(config.clone())),
+            "grpc" => Arc::new(GRPCDownloader::new(config.clone(), pool)),
             _ => {
                 error!("downloader unsupported protocol: {}", protocol);
                 return Err(Error::InvalidParameter);
@@ -79,14 +79,43 @@ impl DownloaderFactory {
 pub struct GRPCDownloader {
     /// config is the configuration of the dfdaemon.
     config: Arc<Config>,
+
+    /// pool is the pool of the grpc channels to the parents.
+    pool: Arc<ChannelPool>,
 }
 
 /// GRPCDownloader implements the downloader with the gRPC protocol.
 impl GRPCDownloader {
     /// new returns a new GRPCDownloader.
     #[instrument(skip_all)]
-    pub fn new(config: Arc<Config>) -> Self {
-        Self { config }
+    pub fn new(config: Arc<Config>, pool: Arc<ChannelPool>) -> Self {
+        Self { config, pool }
+    }
+
+    /// client returns the dfdaemon upload client of the parent by the pooled channel.
+    async fn client(&self, addr: &str) -> Result<DfdaemonUploadClient> {
+        DfdaemonUploadClient::new_with_pool(
+            self.config.clone(),
+            self.pool.clone(),
+            format!("http://{}", addr),
+        )
+        .await
+    }
+
+    /// evict_if_unavailable evicts the pooled channel of the parent if the parent is
+    /// unavailable, the next request to the parent establishes a new channel.
+    fn evict_if_unavailable(&self, addr: &str, err: &Error) {
+        if let Error::TonicStatus(status) = err {
+            if matches!(
+                status.code(),
+                tonic::Code::Unavailable | tonic::Code::Unknown
+            ) {
+                self.pool.remove(&DfdaemonUploadClient::channel_key(
+                    &self.config,
+                    format!("http://{}", addr),
+                ));
+            }
+        }
     }
 }
 
@@ -102,8 +131,7 @@ impl Downloader for GRPCDownloader {
         host_id: &str,
         task_id: &str,
     ) -> Result<(Vec<u8>, u64, String)> {
-        let dfdaemon_upload_client =
-            DfdaemonUploadClient::new(self.config.clone(), format!("http://{}", addr)).await?;
+        let dfdaemon_upload_client = self.client(addr).await?;
 
         let response = dfdaemon_upload_client
             .download_piece(
@@ -114,7 +142,8 @@ impl Downloader for GRPCDownloader {
                 },
                 self.config.download.piece_timeout,
             )
-            .await?;
+            .await
+            .inspect_err(|err| self.evict_if_unavailable(addr, err))?;
 
         let Some(piece) = response.piece else {
             return Err(Error::InvalidParameter);
@@ -137,8 +166,7 @@ impl Downloader for GRPCDownloader {
         host_id: &str,
         task_id: &str,
     ) -> Result<(Vec<u8>, u64, String)> {
-        let dfdaemon_upload_client =
-            DfdaemonUploadClient::new(self.config.clone(), format!("http://{}", addr)).await?;
+        let dfdaemon_upload_client = self.client(addr).await?;
 
         let response = dfdaemon_upload_client
             .download_persistent_cache_piece(
@@ -149,7 +177,8 @@ impl Downloader for GRPCDownloader {
                 },
                 self.config.download.piece_timeout,
             )
-            .await?;
+            .await
+            .inspect_err(|err| self.evict_if_unavailable(addr, err))?;
 
         let Some(piece) = response.piece else {
             return Err(Error::InvalidParameter);
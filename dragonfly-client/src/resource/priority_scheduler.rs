@@ -0,0 +1,412 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_api::common::v2::Priority;
+use dragonfly_client_config::dfdaemon::PriorityScheduler as PrioritySchedulerConfig;
+use leaky_bucket::RateLimiter;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tracing::debug;
+
+/// VIRTUAL_TIME_SCALE scales the virtual time to keep the precision of the integer division by
+/// the weight.
+const VIRTUAL_TIME_SCALE: u128 = 1 << 16;
+
+/// Flow is the state of the task in the weighted fair queuing.
+#[derive(Debug, Default)]
+struct Flow {
+    /// finish is the virtual finish time of the last queued piece of the task.
+    finish: u128,
+
+    /// queued is the count of the queued pieces of the task.
+    queued: usize,
+}
+
+/// State is the shared state of the priority scheduler.
+#[derive(Debug, Default)]
+struct State {
+    /// virtual_time is the virtual finish time of the last granted piece, refer to the
+    /// self-clocked fair queuing.
+    virtual_time: u128,
+
+    /// sequence is the sequence of the queued pieces to break the ties of the finish time.
+    sequence: u64,
+
+    /// flows are the tasks with the queued pieces.
+    flows: HashMap<String, Flow>,
+
+    /// queue are the queued pieces ordered by the virtual finish time.
+    queue: BTreeSet<(u128, u64)>,
+
+    /// granting indicates whether the granted piece is acquiring the rate limiter, the pieces
+    /// acquire the rate limiter one by one to keep the order of the queue.
+    granting: bool,
+
+    /// preempting is the count of the pieces which preempt the download bandwidth, including
+    /// the queued pieces and the downloading pieces.
+    preempting: usize,
+}
+
+/// PriorityScheduler schedules the piece downloads across the tasks by the priority. The pieces
+/// acquire the download rate limiter by weighted fair queuing, and the pieces of the tasks with
+/// the preemption priority make the prefetch pieces and the pieces with the lower priority wait.
+pub struct PriorityScheduler {
+    /// config is the configuration of the priority scheduler.
+    config: PrioritySchedulerConfig,
+
+    /// state is the shared state of the priority scheduler.
+    state: Mutex<State>,
+
+    /// notify wakes up the waiting pieces when the state is changed.
+    notify: Notify,
+}
+
+/// PriorityScheduler implements the weighted fair queuing of the piece downloads.
+impl PriorityScheduler {
+    /// new returns a new PriorityScheduler.
+    pub fn new(config: PrioritySchedulerConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(State::default()),
+            notify: Notify::new(),
+        }
+    }
+
+    /// acquire waits for the turn of the piece and acquires the rate limiter. The returned
+    /// permit should be held until the piece is downloaded, because the pieces with the
+    /// preemption priority preempt the bandwidth until they are downloaded.
+    pub async fn acquire(
+        &self,
+        task_id: &str,
+        priority: Priority,
+        length: u64,
+        rate_limiter: Arc<RateLimiter>,
+    ) -> PriorityPermit<'_> {
+        if !self.config.enable {
+            rate_limiter.acquire(length as usize).await;
+            return PriorityPermit {
+                scheduler: self,
+                preempting: false,
+            };
+        }
+
+        // The pieces with the preemption priority are counted until the permit is dropped, and
+        // the other pieces wait until there is no piece preempting the bandwidth.
+        let permit = if self.is_preempting(priority) {
+            self.state.lock().unwrap().preempting += 1;
+            PriorityPermit {
+                scheduler: self,
+                preempting: true,
+            }
+        } else {
+            self.wait_for_preemption().await;
+            PriorityPermit {
+                scheduler: self,
+                preempting: false,
+            }
+        };
+
+        // Enqueue the piece with the virtual finish time, the task with the larger weight
+        // advances the virtual time slower, so its pieces are granted earlier.
+        let ticket = {
+            let mut state = self.state.lock().unwrap();
+            let virtual_time = state.virtual_time;
+            state.sequence += 1;
+            let sequence = state.sequence;
+
+            let weight = self.weight(priority);
+            let flow = state.flows.entry(task_id.to_string()).or_default();
+            let finish =
+                flow.finish.max(virtual_time) + length as u128 * VIRTUAL_TIME_SCALE / weight;
+            flow.finish = finish;
+            flow.queued += 1;
+
+            state.queue.insert((finish, sequence));
+            (finish, sequence)
+        };
+
+        // Dequeue the piece when it is granted or the download is cancelled.
+        let _queued = QueuedPiece {
+            scheduler: self,
+            task_id,
+            ticket,
+        };
+
+        // Wait for the turn of the piece, it is granted when it is the first piece in the
+        // queue and no piece is acquiring the rate limiter.
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let mut state = self.state.lock().unwrap();
+                if !state.granting && state.queue.first() == Some(&ticket) {
+                    state.granting = true;
+                    state.queue.remove(&ticket);
+                    break;
+                }
+            }
+
+            notified.await;
+        }
+
+        // Release the granting when the rate limiter is acquired or the download is cancelled.
+        let _granting = GrantingPiece {
+            scheduler: self,
+            finish: ticket.0,
+        };
+
+        debug!(
+            "piece of task {} with priority {:?} is granted",
+            task_id, priority
+        );
+        rate_limiter.acquire(length as usize).await;
+        permit
+    }
+
+    /// acquire_prefetch waits until there is no piece preempting the bandwidth and acquires the
+    /// prefetch rate limiter. The prefetch pieces are not queued, because they are limited by
+    /// the prefetch rate limiter.
+    pub async fn acquire_prefetch(
+        &self,
+        length: u64,
+        rate_limiter: Arc<RateLimiter>,
+    ) -> PriorityPermit<'_> {
+        if self.config.enable {
+            self.wait_for_preemption().await;
+        }
+
+        rate_limiter.acquire(length as usize).await;
+        PriorityPermit {
+            scheduler: self,
+            preempting: false,
+        }
+    }
+
+    /// weight returns the weight of the priority, the weight is at least 1.
+    fn weight(&self, priority: Priority) -> u128 {
+        self.config
+            .weights
+            .get(priority as usize)
+            .copied()
+            .unwrap_or(1)
+            .max(1) as u128
+    }
+
+    /// is_preempting returns whether the priority preempts the download bandwidth.
+    fn is_preempting(&self, priority: Priority) -> bool {
+        match self.config.preemption_priority {
+            Some(preemption_priority) => priority as i32 >= preemption_priority as i32,
+            None => false,
+        }
+    }
+
+    /// wait_for_preemption waits until there is no piece preempting the bandwidth.
+    async fn wait_for_preemption(&self) {
+        if self.config.preemption_priority.is_none() {
+            return;
+        }
+
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if self.state.lock().unwrap().preempting == 0 {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+}
+
+/// QueuedPiece removes the piece from the queue when it is dropped.
+struct QueuedPiece<'a> {
+    /// scheduler is the priority scheduler.
+    scheduler: &'a PriorityScheduler,
+
+    /// task_id is the id of the task.
+    task_id: &'a str,
+
+    /// ticket is the virtual finish time and the sequence of the piece.
+    ticket: (u128, u64),
+}
+
+/// QueuedPiece implements Drop.
+impl Drop for QueuedPiece<'_> {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.state.lock().unwrap();
+        state.queue.remove(&self.ticket);
+
+        // Remove the task without the queued pieces, the next piece of the task starts from
+        // the virtual time.
+        if let Some(flow) = state.flows.get_mut(self.task_id) {
+            flow.queued -= 1;
+            if flow.queued == 0 {
+                state.flows.remove(self.task_id);
+            }
+        }
+
+        drop(state);
+        self.scheduler.notify.notify_waiters();
+    }
+}
+
+/// GrantingPiece advances the virtual time and releases the granting when it is dropped.
+struct GrantingPiece<'a> {
+    /// scheduler is the priority scheduler.
+    scheduler: &'a PriorityScheduler,
+
+    /// finish is the virtual finish time of the granted piece.
+    finish: u128,
+}
+
+/// GrantingPiece implements Drop.
+impl Drop for GrantingPiece<'_> {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.state.lock().unwrap();
+        state.granting = false;
+        state.virtual_time = state.virtual_time.max(self.finish);
+
+        drop(state);
+        self.scheduler.notify.notify_waiters();
+    }
+}
+
+/// PriorityPermit is the permit of the piece download, the piece with the preemption priority
+/// preempts the download bandwidth until the permit is dropped.
+pub struct PriorityPermit<'a> {
+    /// scheduler is the priority scheduler.
+    scheduler: &'a PriorityScheduler,
+
+    /// preempting indicates whether the piece preempts the download bandwidth.
+    preempting: bool,
+}
+
+/// PriorityPermit implements Drop.
+impl Drop for PriorityPermit<'_> {
+    fn drop(&mut self) {
+        if !self.preempting {
+            return;
+        }
+
+        self.scheduler.state.lock().unwrap().preempting -= 1;
+        self.scheduler.notify.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// new_rate_limiter creates a rate limiter which refills 1 byte every 10 milliseconds.
+    fn new_rate_limiter() -> Arc<RateLimiter> {
+        Arc::new(
+            RateLimiter::builder()
+                .initial(0)
+                .refill(1)
+                .max(1)
+                .interval(Duration::from_millis(10))
+                .build(),
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_grant_pieces_by_weighted_fair_queuing() {
+        let scheduler = Arc::new(PriorityScheduler::new(PrioritySchedulerConfig {
+            enable: true,
+            ..Default::default()
+        }));
+        let rate_limiter = new_rate_limiter();
+        let granted = Arc::new(Mutex::new(Vec::new()));
+
+        // The low priority task queues its pieces first, the high priority task arrives later
+        // and its pieces are granted before the queued pieces of the low priority task.
+        let mut handles = Vec::new();
+        for (task_id, priority) in [
+            ("low", Priority::Level0),
+            ("low", Priority::Level0),
+            ("low", Priority::Level0),
+            ("high", Priority::Level6),
+            ("high", Priority::Level6),
+        ] {
+            let scheduler = scheduler.clone();
+            let rate_limiter = rate_limiter.clone();
+            let granted = granted.clone();
+            handles.push(tokio::spawn(async move {
+                let _permit = scheduler.acquire(task_id, priority, 1, rate_limiter).await;
+                granted.lock().unwrap().push(task_id);
+            }));
+
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let granted = granted.lock().unwrap().clone();
+        assert_eq!(granted, vec!["low", "high", "high", "low", "low"]);
+        assert!(scheduler.state.lock().unwrap().flows.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_preempt_low_priority_pieces() {
+        let scheduler = Arc::new(PriorityScheduler::new(PrioritySchedulerConfig {
+            enable: true,
+            preemption_priority: Some(Priority::Level5 as u8),
+            ..Default::default()
+        }));
+        let rate_limiter = Arc::new(RateLimiter::builder().initial(1024).max(1024).build());
+
+        // The prefetch piece and the low priority piece wait until the high priority piece is
+        // downloaded.
+        let permit = scheduler
+            .acquire("high", Priority::Level6, 1, rate_limiter.clone())
+            .await;
+
+        let prefetch = tokio::spawn({
+            let scheduler = scheduler.clone();
+            let rate_limiter = rate_limiter.clone();
+            async move {
+                let _permit = scheduler.acquire_prefetch(1, rate_limiter).await;
+            }
+        });
+
+        let low = tokio::spawn({
+            let scheduler = scheduler.clone();
+            let rate_limiter = rate_limiter.clone();
+            async move {
+                let _permit = scheduler
+                    .acquire("low", Priority::Level0, 1, rate_limiter)
+                    .await;
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!prefetch.is_finished());
+        assert!(!low.is_finished());
+
+        drop(permit);
+        prefetch.await.unwrap();
+        low.await.unwrap();
+        assert_eq!(scheduler.state.lock().unwrap().preempting, 0);
+    }
+}
@@ -20,7 +20,8 @@ use crate::metrics::{
     collect_backend_request_started_metrics,
 };
 use dragonfly_api::common::v2::{
-    Download, Hdfs, ObjectStorage, Peer, Piece, Range, Task as CommonTask, TrafficType,
+    Download, Hdfs, Host, ObjectStorage, Peer, Piece, Priority, Range, Task as CommonTask,
+    TrafficType,
 };
 use dragonfly_api::dfdaemon::{
     self,
@@ -57,7 +58,7 @@ use std::time::Instant;
 use tokio::io::AsyncReadExt;
 use tokio::sync::{
     mpsc::{self, Sender},
-    Semaphore,
+    watch, Semaphore,
 };
 use tokio::task::JoinSet;
 use tokio_stream::{wrappers::ReceiverStream, StreamExt};
@@ -66,11 +67,26 @@ use tracing::{debug, error, info, instrument, Instrument};
 
 use super::*;
 
+/// SENSITIVE_HEADERS is the headers which are not persisted in the download request of the task.
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-amz-security-token",
+];
+
+/// SENSITIVE_HEADER_KEYWORDS is the keywords of the header names which are not persisted
+/// in the download request of the task.
+const SENSITIVE_HEADER_KEYWORDS: &[&str] = &["token", "secret", "password", "api-key"];
+
 /// Task represents a task manager.
 pub struct Task {
     /// config is the configuration of the dfdaemon.
     config: Arc<Config>,
 
+    /// config_rx receives the reloaded configuration of the dfdaemon.
+    config_rx: watch::Receiver<Arc<Config>>,
+
     /// id_generator is the id generator.
     pub id_generator: Arc<IDGenerator>,
 
@@ -93,6 +109,7 @@ impl Task {
     #[instrument(skip_all)]
     pub fn new(
         config: Arc<Config>,
+        config_rx: watch::Receiver<Arc<Config>>,
         id_generator: Arc<IDGenerator>,
         storage: Arc<Storage>,
         scheduler_client: Arc<SchedulerClient>,
@@ -108,6 +125,7 @@ impl Task {
 
         Ok(Self {
             config,
+            config_rx,
             id_generator,
             storage: storage.clone(),
             scheduler_client: scheduler_client.clone(),
@@ -128,7 +146,19 @@ impl Task {
         id: &str,
         request: Download,
     ) -> ClientResult<metadata::Task> {
-        let task = self.storage.download_task_started(id, None, None, None)?;
+        let task = self.storage.download_task_started(
+            id,
+            None,
+            None,
+            None,
+            self.make_download_request(&request),
+            self.config
+                .storage
+                .compression
+                .is_matched(request.application.as_deref(), request.url.as_str()),
+            self.piece
+                .bandwidth_class(request.application.as_deref(), request.tag.as_deref()),
+        )?;
         if task.content_length.is_some() && task.piece_length.is_some() {
             return Ok(task);
         }
@@ -164,6 +194,7 @@ impl Task {
                 client_cert: None,
                 object_storage: request.object_storage,
                 hdfs: request.hdfs,
+                recursive: false,
             })
             .await
             .inspect_err(|_err| {
@@ -220,9 +251,101 @@ impl Task {
             Some(piece_length),
             Some(content_length),
             response.http_header,
+            None,
+            false,
+            None,
         )
     }
 
+    /// make_download_request makes the persisted download request by the download, the sensitive
+    /// headers are removed. If the download contains the credentials of the object storage or hdfs,
+    /// the download request will not be persisted.
+    fn make_download_request(&self, request: &Download) -> Option<metadata::DownloadRequest> {
+        if !self.config.download.resume_unfinished_tasks
+            || request.object_storage.is_some()
+            || request.hdfs.is_some()
+        {
+            return None;
+        }
+
+        let request_header = request
+            .request_header
+            .iter()
+            .filter(|(key, _)| {
+                let key = key.to_lowercase();
+                !SENSITIVE_HEADERS.contains(&key.as_str())
+                    && !SENSITIVE_HEADER_KEYWORDS
+                        .iter()
+                        .any(|keyword| key.contains(keyword))
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        Some(metadata::DownloadRequest {
+            url: request.url.clone(),
+            digest: request.digest.clone(),
+            range: request.range.map(|range| (range.start, range.length)),
+            r#type: request.r#type,
+            tag: request.tag.clone(),
+            application: request.application.clone(),
+            priority: request.priority,
+            filtered_query_params: request.filtered_query_params.clone(),
+            request_header,
+            piece_length: request.piece_length,
+            disable_back_to_source: request.disable_back_to_source,
+            need_back_to_source: request.need_back_to_source,
+            is_prefetch: request.is_prefetch,
+        })
+    }
+
+    /// resumable_downloads returns the downloads of the unfinished tasks which can be resumed,
+    /// and removes the unfinished pieces of the tasks, the finished pieces will be reused.
+    #[instrument(skip_all)]
+    pub fn resumable_downloads(&self) -> ClientResult<Vec<Download>> {
+        let mut downloads = Vec::new();
+        for task in self.storage.get_tasks()? {
+            if !task.is_resumable() || task.is_expired(self.config.gc.policy.task_ttl) {
+                continue;
+            }
+
+            // The unfinished pieces are interrupted by the restart, remove them
+            // to download again.
+            for piece in self.storage.get_pieces(task.id.as_str())? {
+                if !piece.is_finished() {
+                    self.storage.download_piece_failed(
+                        self.storage
+                            .piece_id(task.id.as_str(), piece.number)
+                            .as_str(),
+                    )?;
+                }
+            }
+
+            let Some(request) = task.download_request else {
+                continue;
+            };
+
+            info!("task {} can be resumed", task.id);
+            downloads.push(Download {
+                url: request.url,
+                digest: request.digest,
+                range: request.range.map(|(start, length)| Range { start, length }),
+                r#type: request.r#type,
+                tag: request.tag,
+                application: request.application,
+                priority: request.priority,
+                filtered_query_params: request.filtered_query_params,
+                request_header: request.request_header,
+                piece_length: request.piece_length,
+                disable_back_to_source: request.disable_back_to_source,
+                need_back_to_source: request.need_back_to_source,
+                is_prefetch: request.is_prefetch,
+                ..Default::default()
+            });
+        }
+
+        Ok(downloads)
+    }
+
     /// download_finished updates the metadata of the task when the task downloads finished.
     #[instrument(skip_all)]
     pub fn download_finished(&self, id: &str) -> ClientResult<metadata::Task> {
@@ -387,11 +510,23 @@ impl Task {
             info!("all pieces are downloaded from local");
             return Ok(());
         };
-        debug!("download the pieces with scheduler");
 
-        // Download the pieces with scheduler.
-        let finished_pieces = match self
-            .download_partial_with_scheduler(
+        // Download the pieces from the static peers in standalone mode, otherwise
+        // download the pieces with scheduler.
+        let result = if self.scheduler_client.is_standalone() {
+            debug!("download the pieces from static peers");
+            self.download_partial_from_static_peers(
+                task,
+                host_id,
+                peer_id,
+                interested_pieces.clone(),
+                request.clone(),
+                download_progress_tx.clone(),
+            )
+            .await
+        } else {
+            debug!("download the pieces with scheduler");
+            self.download_partial_with_scheduler(
                 task,
                 host_id,
                 peer_id,
@@ -401,7 +536,9 @@ impl Task {
                 download_progress_tx.clone(),
             )
             .await
-        {
+        };
+
+        let finished_pieces = match result {
             Ok(finished_pieces) => finished_pieces,
             Err(err) => {
                 error!("download with scheduler error: {:?}", err);
@@ -450,7 +587,7 @@ impl Task {
 
         // Check if all pieces are downloaded.
         if interested_pieces.is_empty() {
-            info!("all pieces are downloaded with scheduler");
+            info!("all pieces are downloaded with scheduler or static peers");
             return Ok(());
         };
 
@@ -538,9 +675,16 @@ impl Task {
             })?;
         info!("announced peer has been connected");
 
-        let out_stream = response
-            .into_inner()
-            .timeout(self.config.scheduler.schedule_timeout);
+        // Get the scheduler timeouts from the reloaded configuration.
+        let (schedule_timeout, max_schedule_count) = {
+            let config = self.config_rx.borrow();
+            (
+                config.scheduler.schedule_timeout,
+                config.scheduler.max_schedule_count,
+            )
+        };
+
+        let out_stream = response.into_inner().timeout(schedule_timeout);
         tokio::pin!(out_stream);
 
         while let Some(message) = out_stream.try_next().await.inspect_err(|err| {
@@ -548,7 +692,7 @@ impl Task {
         })? {
             // Check if the schedule count is exceeded.
             schedule_count += 1;
-            if schedule_count >= self.config.scheduler.max_schedule_count {
+            if schedule_count >= max_schedule_count {
                 in_stream_tx
                     .send_timeout(
                         AnnouncePeerRequest {
@@ -683,9 +827,10 @@ impl Task {
                             response.candidate_parents.clone(),
                             remaining_interested_pieces.clone(),
                             request.is_prefetch,
+                            request.priority(),
                             request.need_piece_content,
                             download_progress_tx.clone(),
-                            in_stream_tx.clone(),
+                            Some(in_stream_tx.clone()),
                         )
                         .await
                     {
@@ -915,7 +1060,61 @@ impl Task {
         Ok(finished_pieces)
     }
 
+    /// download_partial_from_static_peers downloads a partial task from the static peers in
+    /// standalone mode, the pieces are not reported to the scheduler.
+    #[instrument(skip_all)]
+    async fn download_partial_from_static_peers(
+        &self,
+        task: &metadata::Task,
+        host_id: &str,
+        peer_id: &str,
+        interested_pieces: Vec<metadata::Piece>,
+        request: Download,
+        download_progress_tx: Sender<Result<DownloadTaskResponse, Status>>,
+    ) -> ClientResult<Vec<metadata::Piece>> {
+        // If no static peers, download the pieces back-to-source directly.
+        if self.config.standalone.peers.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Exclude the dfdaemon itself from the static peers.
+        let parents = self
+            .config
+            .standalone
+            .peers
+            .iter()
+            .filter(|addr| {
+                !(Some(addr.ip()) == self.config.host.ip
+                    && addr.port() == self.config.upload.server.port)
+            })
+            .map(|addr| Peer {
+                id: format!("static-peer-{}", addr),
+                host: Some(Host {
+                    ip: addr.ip().to_string(),
+                    port: addr.port() as i32,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .collect::<Vec<Peer>>();
+
+        self.download_partial_with_scheduler_from_parent(
+            task,
+            host_id,
+            peer_id,
+            parents,
+            interested_pieces,
+            request.is_prefetch,
+            request.priority(),
+            request.need_piece_content,
+            download_progress_tx,
+            None,
+        )
+        .await
+    }
+
     /// download_partial_with_scheduler_from_parent downloads a partial task with scheduler from a parent.
+    /// If in_stream_tx is none, the download results of the pieces are not reported to the scheduler.
     #[allow(clippy::too_many_arguments)]
     #[instrument(skip_all)]
     async fn download_partial_with_scheduler_from_parent(
@@ -926,9 +1125,10 @@ impl Task {
         parents: Vec<Peer>,
         interested_pieces: Vec<metadata::Piece>,
         is_prefetch: bool,
+        priority: Priority,
         need_piece_content: bool,
         download_progress_tx: Sender<Result<DownloadTaskResponse, Status>>,
-        in_stream_tx: Sender<AnnouncePeerRequest>,
+        in_stream_tx: Option<Sender<AnnouncePeerRequest>>,
     ) -> ClientResult<Vec<metadata::Piece>> {
         // Get the id of the task.
         let task_id = task.id.as_str();
@@ -936,6 +1136,7 @@ impl Task {
         // Initialize the piece collector.
         let piece_collector = piece_collector::PieceCollector::new(
             self.config.clone(),
+            self.piece.pool(),
             host_id,
             task_id,
             interested_pieces.clone(),
@@ -981,10 +1182,12 @@ impl Task {
                 piece_manager: Arc<piece::Piece>,
                 semaphore: Arc<Semaphore>,
                 download_progress_tx: Sender<Result<DownloadTaskResponse, Status>>,
-                in_stream_tx: Sender<AnnouncePeerRequest>,
+                in_stream_tx: Option<Sender<AnnouncePeerRequest>>,
                 interrupt: Arc<AtomicBool>,
                 finished_pieces: Arc<Mutex<Vec<metadata::Piece>>>,
                 is_prefetch: bool,
+                priority: Priority,
+                bandwidth_class: Option<String>,
                 need_piece_content: bool,
             ) -> ClientResult<metadata::Piece> {
                 // Limit the concurrent piece count.
@@ -1006,6 +1209,8 @@ impl Task {
                         length,
                         parent.clone(),
                         is_prefetch,
+                        priority,
+                        bandwidth_class.as_deref(),
                     )
                     .await
                     .map_err(|err| {
@@ -1061,30 +1266,32 @@ impl Task {
                 }
 
                 // Send the download piece finished request.
-                in_stream_tx
-                    .send_timeout(
-                        AnnouncePeerRequest {
-                            host_id: host_id.to_string(),
-                            task_id: task_id.to_string(),
-                            peer_id: peer_id.to_string(),
-                            request: Some(
-                                announce_peer_request::Request::DownloadPieceFinishedRequest(
-                                    DownloadPieceFinishedRequest {
-                                        piece: Some(piece.clone()),
-                                    },
+                if let Some(in_stream_tx) = in_stream_tx {
+                    in_stream_tx
+                        .send_timeout(
+                            AnnouncePeerRequest {
+                                host_id: host_id.to_string(),
+                                task_id: task_id.to_string(),
+                                peer_id: peer_id.to_string(),
+                                request: Some(
+                                    announce_peer_request::Request::DownloadPieceFinishedRequest(
+                                        DownloadPieceFinishedRequest {
+                                            piece: Some(piece.clone()),
+                                        },
+                                    ),
                                 ),
-                            ),
-                        },
-                        REQUEST_TIMEOUT,
-                    )
-                    .await
-                    .inspect_err(|err| {
-                        error!(
-                            "send DownloadPieceFinishedRequest for piece {} failed: {:?}",
-                            piece_id, err
-                        );
-                        interrupt.store(true, Ordering::SeqCst);
-                    })?;
+                            },
+                            REQUEST_TIMEOUT,
+                        )
+                        .await
+                        .inspect_err(|err| {
+                            error!(
+                                "send DownloadPieceFinishedRequest for piece {} failed: {:?}",
+                                piece_id, err
+                            );
+                            interrupt.store(true, Ordering::SeqCst);
+                        })?;
+                }
 
                 // Send the download progress.
                 download_progress_tx
@@ -1138,6 +1345,8 @@ impl Task {
                     interrupt.clone(),
                     finished_pieces.clone(),
                     is_prefetch,
+                    priority,
+                    task.bandwidth_class.clone(),
                     need_piece_content,
                 )
                 .in_current_span(),
@@ -1156,6 +1365,12 @@ impl Task {
                 Err(Error::DownloadFromParentFailed(err)) => {
                     let (piece_number, parent_id) = (err.piece_number, err.parent_id);
 
+                    // If the scheduler is not used, ignore the failed piece and it will be
+                    // downloaded back-to-source.
+                    let Some(in_stream_tx) = in_stream_tx.as_ref() else {
+                        continue;
+                    };
+
                     // Send the download piece failed request.
                     in_stream_tx
                         .send_timeout(
@@ -1251,6 +1466,8 @@ impl Task {
                 length: u64,
                 request_header: HeaderMap,
                 is_prefetch: bool,
+                priority: Priority,
+                bandwidth_class: Option<String>,
                 need_piece_content: bool,
                 piece_manager: Arc<piece::Piece>,
                 semaphore: Arc<Semaphore>,
@@ -1275,6 +1492,8 @@ impl Task {
                         length,
                         request_header,
                         is_prefetch,
+                        priority,
+                        bandwidth_class.as_deref(),
                         object_storage,
                         hdfs,
                     )
@@ -1378,6 +1597,8 @@ impl Task {
                     interested_piece.length,
                     request_header.clone(),
                     request.is_prefetch,
+                    request.priority(),
+                    task.bandwidth_class.clone(),
                     request.need_piece_content,
                     self.piece.clone(),
                     semaphore.clone(),
@@ -1640,6 +1861,8 @@ impl Task {
                 length: u64,
                 request_header: HeaderMap,
                 is_prefetch: bool,
+                priority: Priority,
+                bandwidth_class: Option<String>,
                 piece_manager: Arc<piece::Piece>,
                 semaphore: Arc<Semaphore>,
                 download_progress_tx: Sender<Result<DownloadTaskResponse, Status>>,
@@ -1662,6 +1885,8 @@ impl Task {
                         length,
                         request_header,
                         is_prefetch,
+                        priority,
+                        bandwidth_class.as_deref(),
                         object_storage,
                         hdfs,
                     )
@@ -1720,6 +1945,8 @@ impl Task {
                     interested_piece.length,
                     request_header.clone(),
                     request.is_prefetch,
+                    request.priority(),
+                    task.bandwidth_class.clone(),
                     self.piece.clone(),
                     semaphore.clone(),
                     download_progress_tx.clone(),
@@ -1791,6 +2018,12 @@ impl Task {
             Some(task) => {
                 self.storage.delete_task(task.id.as_str()).await;
 
+                // The task is only stored in the local in standalone mode.
+                if self.scheduler_client.is_standalone() {
+                    info!("delete task {} from local storage", task.id);
+                    return Ok(());
+                }
+
                 self.scheduler_client
                     .delete_task(DeleteTaskRequest {
                         host_id: host_id.to_string(),
@@ -14,10 +14,12 @@
  * limitations under the License.
  */
 
+use dragonfly_client_core::{Error, Result};
 use opentelemetry::sdk::propagation::TraceContextPropagator;
 use rolling_file::*;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 use tracing::{info, Level};
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_flame::FlameLayer;
@@ -26,9 +28,26 @@ use tracing_subscriber::{
     filter::LevelFilter,
     fmt::{time::ChronoLocal, Layer},
     prelude::*,
-    EnvFilter, Registry,
+    reload, EnvFilter, Registry,
 };
 
+/// LOG_LEVEL_RELOAD_HANDLE is the handle to reload the log level of the global subscriber.
+static LOG_LEVEL_RELOAD_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// reload_log_level reloads the log level of the global subscriber initialized by init_tracing.
+pub fn reload_log_level(log_level: Level) -> Result<()> {
+    let handle = LOG_LEVEL_RELOAD_HANDLE
+        .get()
+        .ok_or_else(|| Error::Unknown("tracing is not initialized".to_string()))?;
+
+    handle
+        .reload(EnvFilter::default().add_directive(log_level.into()))
+        .map_err(|err| Error::Unknown(err.to_string()))?;
+
+    info!("log level is reloaded to {}", log_level);
+    Ok(())
+}
+
 /// init_tracing initializes the tracing system.
 #[allow(clippy::too_many_arguments)]
 pub fn init_tracing(
@@ -89,6 +108,8 @@ pub fn init_tracing(
     // Setup env filter for log level.
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::default().add_directive(log_level.into()));
+    let (env_filter, env_filter_reload_handle) = reload::Layer::new(env_filter);
+    let _ = LOG_LEVEL_RELOAD_HANDLE.set(env_filter_reload_handle);
 
     // Setup flame layer.
     let flame_layer = if flamegraph {
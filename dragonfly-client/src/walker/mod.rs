@@ -0,0 +1,72 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_core::Result;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::error;
+
+/// get_files gets the regular files in the directory recursively ordered by the path, the
+/// symbolic links and the special files are skipped.
+pub async fn get_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = fs::read_dir(&dir).await.inspect_err(|err| {
+            error!("read {} failed: {}", dir.to_string_lossy(), err);
+        })?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                dirs.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn should_get_files_recursively() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).await.unwrap();
+        fs::create_dir_all(dir.path().join("empty")).await.unwrap();
+        fs::write(dir.path().join("c"), "c").await.unwrap();
+        fs::write(dir.path().join("a/b/d"), "d").await.unwrap();
+        fs::write(dir.path().join("a/e"), "e").await.unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path().join("c"), dir.path().join("link")).unwrap();
+
+        let files = get_files(dir.path()).await.unwrap();
+        assert_eq!(
+            files,
+            vec![
+                dir.path().join("a/b/d"),
+                dir.path().join("a/e"),
+                dir.path().join("c"),
+            ]
+        );
+    }
+}